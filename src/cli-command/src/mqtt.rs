@@ -27,9 +27,18 @@
     mqtt_broker_set_auto_subscribe_rule, mqtt_broker_unbind_schema, mqtt_broker_update_connector,
     mqtt_broker_update_schema,
 };
+use grpc_clients::mqtt::admin_ext::call::{
+    mqtt_broker_check_acl, mqtt_broker_clean_session, mqtt_broker_create_psk,
+    mqtt_broker_delete_psk, mqtt_broker_delete_retain_message, mqtt_broker_get_client_detail,
+    mqtt_broker_get_retain_message, mqtt_broker_kick_client, mqtt_broker_list_psk,
+    mqtt_broker_list_retain_message, mqtt_broker_list_session, mqtt_broker_retain_message_stats,
+};
 use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
 use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
 use metadata_struct::mqtt::bridge::connector::MQTTConnector;
+use metadata_struct::mqtt::psk::MqttPsk;
+use metadata_struct::mqtt::session::MqttSession;
 use metadata_struct::mqtt::user::MqttUser;
 use metadata_struct::schema::SchemaData;
 use paho_mqtt::{DisconnectOptionsBuilder, MessageBuilder, Properties, PropertyCode, ReasonCode};
@@ -43,6 +52,12 @@
     MqttListConnectorRequest, MqttListSchemaRequest, MqttUnbindSchemaRequest,
     MqttUpdateConnectorRequest, MqttUpdateSchemaRequest, SetAutoSubscribeRuleRequest,
 };
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttCheckAclRequest, MqttCleanSessionRequest, MqttCreatePskRequest, MqttDeletePskRequest,
+    MqttDeleteRetainMessageRequest, MqttGetClientDetailRequest, MqttGetRetainMessageRequest,
+    MqttKickClientRequest, MqttListPskRequest, MqttListRetainMessageRequest,
+    MqttListSessionRequest, MqttRetainMessageStatsRequest,
+};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -101,6 +116,26 @@ pub enum MqttActionType {
     ListAutoSubscribeRule(ListAutoSubscribeRuleRequest),
     SetAutoSubscribeRule(SetAutoSubscribeRuleRequest),
     DeleteAutoSubscribeRule(DeleteAutoSubscribeRuleRequest),
+
+    // acl
+    CheckAcl(MqttCheckAclRequest),
+
+    // psk
+    ListPsk,
+    CreatePsk(MqttCreatePskRequest),
+    DeletePsk(MqttDeletePskRequest),
+
+    // session
+    ListSession,
+    GetClientDetail(MqttGetClientDetailRequest),
+    KickClient(MqttKickClientRequest),
+    CleanSession(MqttCleanSessionRequest),
+
+    // retain
+    ListRetainMessage(MqttListRetainMessageRequest),
+    GetRetainMessage(MqttGetRetainMessageRequest),
+    DeleteRetainMessage(MqttDeleteRetainMessageRequest),
+    RetainMessageStats(MqttRetainMessageStatsRequest),
 }
 
 pub struct MqttBrokerCommand {}
@@ -223,6 +258,60 @@ pub async fn start(&self, params: MqttCliCommandParam) {
                 self.delete_auto_subscribe_rule(&client_pool, params.clone(), request.clone())
                     .await;
             }
+
+            // acl
+            MqttActionType::CheckAcl(ref request) => {
+                self.check_acl(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+
+            // psk
+            MqttActionType::ListPsk => {
+                self.list_psk(&client_pool, params.clone()).await;
+            }
+            MqttActionType::CreatePsk(ref request) => {
+                self.create_psk(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::DeletePsk(ref request) => {
+                self.delete_psk(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+
+            // session
+            MqttActionType::ListSession => {
+                self.list_session(&client_pool, params.clone()).await;
+            }
+            MqttActionType::GetClientDetail(ref request) => {
+                self.get_client_detail(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::KickClient(ref request) => {
+                self.kick_client(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::CleanSession(ref request) => {
+                self.clean_session(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+
+            // retain
+            MqttActionType::ListRetainMessage(ref request) => {
+                self.list_retain_message(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::GetRetainMessage(ref request) => {
+                self.get_retain_message(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::DeleteRetainMessage(ref request) => {
+                self.delete_retain_message(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::RetainMessageStats(ref request) => {
+                self.retain_message_stats(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
         }
     }
     async fn publish(&self, params: MqttCliCommandParam, args: PublishArgsRequest) {
@@ -971,6 +1060,395 @@ async fn list_auto_subscribe_rule(
             }
         }
     }
+
+    async fn check_acl(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttCheckAclRequest,
+    ) {
+        match mqtt_broker_check_acl(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.add_row(row!["allow", "source", "resource", "topic", "permission"]);
+                if data.rule.is_empty() {
+                    table.add_row(row![data.allow, data.source, "-", "-", "-"]);
+                } else {
+                    match serde_json::from_slice::<MqttAcl>(data.rule.as_slice()) {
+                        Ok(rule) => {
+                            table.add_row(row![
+                                data.allow,
+                                data.source,
+                                format!("{}:{}", rule.resource_type, rule.resource_name),
+                                rule.topic,
+                                format!("{:?}", rule.permission)
+                            ]);
+                        }
+                        Err(e) => {
+                            error_info(e.to_string());
+                            return;
+                        }
+                    }
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker check acl exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_psk(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
+        let request = MqttListPskRequest {};
+        match mqtt_broker_list_psk(client_pool, &grpc_addr(params.server), request).await {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.add_row(row!["identity", "create_time"]);
+                for psk in data.psks {
+                    match serde_json::from_slice::<MqttPsk>(psk.as_slice()) {
+                        Ok(psk) => {
+                            table.add_row(row![psk.identity, psk.create_time]);
+                        }
+                        Err(e) => {
+                            error_info(e.to_string());
+                            return;
+                        }
+                    }
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list psk exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn create_psk(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttCreatePskRequest,
+    ) {
+        match mqtt_broker_create_psk(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Created successfully!")
+            }
+            Err(e) => {
+                println!("MQTT broker create psk exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn delete_psk(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttDeletePskRequest,
+    ) {
+        match mqtt_broker_delete_psk(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Deleted successfully!");
+            }
+            Err(e) => {
+                println!("MQTT broker delete psk exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_session(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
+        let request = MqttListSessionRequest {};
+        match mqtt_broker_list_session(client_pool, &grpc_addr(params.server), request).await {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.add_row(row![
+                    "client_id",
+                    "session_expiry",
+                    "broker_id",
+                    "connection_id",
+                    "create_time"
+                ]);
+                for session in data.sessions {
+                    match serde_json::from_slice::<MqttSession>(session.as_slice()) {
+                        Ok(session) => {
+                            table.add_row(row![
+                                session.client_id,
+                                session.session_expiry,
+                                option_cell(session.broker_id),
+                                option_cell(session.connection_id),
+                                session.create_time
+                            ]);
+                        }
+                        Err(e) => {
+                            error_info(e.to_string());
+                            return;
+                        }
+                    }
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list session exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn get_client_detail(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttGetClientDetailRequest,
+    ) {
+        match mqtt_broker_get_client_detail(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                let detail = match serde_json::from_slice::<serde_json::Value>(&data.detail) {
+                    Ok(detail) => detail,
+                    Err(e) => {
+                        error_info(e.to_string());
+                        return;
+                    }
+                };
+                let subscriptions: Vec<String> = detail["subscriptions"]
+                    .as_array()
+                    .map(|list| {
+                        list.iter()
+                            .map(|sub| sub["path"].as_str().unwrap_or_default().to_string())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                // format table
+                let mut table = Table::new();
+                table.add_row(row!["client_id", json_cell(&detail["client_id"])]);
+                table.add_row(row!["connected", !detail["connection"].is_null()]);
+                table.add_row(row!["addr", json_cell(&detail["network"]["addr"])]);
+                table.add_row(row!["protocol", json_cell(&detail["network"]["protocol"])]);
+                table.add_row(row![
+                    "login_user",
+                    json_cell(&detail["connection"]["login_user"])
+                ]);
+                table.add_row(row![
+                    "session_expiry",
+                    json_cell(&detail["session"]["session_expiry"])
+                ]);
+                table.add_row(row!["subscriptions", subscriptions.join("\n")]);
+                table.add_row(row!["inflight_num", json_cell(&detail["inflight_num"])]);
+                table.add_row(row!["queue_depth", json_cell(&detail["queue_depth"])]);
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker get client detail exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn kick_client(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttKickClientRequest,
+    ) {
+        match mqtt_broker_kick_client(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Kicked successfully!");
+            }
+            Err(e) => {
+                println!("MQTT broker kick client exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn clean_session(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttCleanSessionRequest,
+    ) {
+        match mqtt_broker_clean_session(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Deleted successfully!");
+            }
+            Err(e) => {
+                println!("MQTT broker clean session exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_retain_message(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttListRetainMessageRequest,
+    ) {
+        match mqtt_broker_list_retain_message(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.add_row(row![
+                    "topic_name",
+                    "client_id",
+                    "qos",
+                    "payload_size",
+                    "expired_at",
+                    "create_time"
+                ]);
+                for raw in data.retain_messages {
+                    let raw = match serde_json::from_slice::<serde_json::Value>(&raw) {
+                        Ok(raw) => raw,
+                        Err(e) => {
+                            error_info(e.to_string());
+                            return;
+                        }
+                    };
+                    table.add_row(row![
+                        json_cell(&raw["topic_name"]),
+                        json_cell(&raw["client_id"]),
+                        json_cell(&raw["qos"]),
+                        json_cell(&raw["payload_size"]),
+                        json_cell(&raw["expired_at"]),
+                        json_cell(&raw["create_time"])
+                    ]);
+                }
+                // output cmd
+                table.printstd();
+                println!("total: {}", data.total);
+            }
+            Err(e) => {
+                println!("MQTT broker list retain message exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn get_retain_message(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttGetRetainMessageRequest,
+    ) {
+        match mqtt_broker_get_retain_message(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                let detail = match serde_json::from_slice::<serde_json::Value>(&data.detail) {
+                    Ok(detail) => detail,
+                    Err(e) => {
+                        error_info(e.to_string());
+                        return;
+                    }
+                };
+
+                // format table
+                let mut table = Table::new();
+                for key in [
+                    "topic_name",
+                    "client_id",
+                    "qos",
+                    "payload",
+                    "payload_encoding",
+                    "content_type",
+                    "response_topic",
+                    "user_properties",
+                    "expiry_interval",
+                    "expired_at",
+                    "create_time",
+                ] {
+                    table.add_row(row![key, json_cell(&detail[key])]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker get retain message exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn delete_retain_message(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttDeleteRetainMessageRequest,
+    ) {
+        match mqtt_broker_delete_retain_message(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.add_row(row!["topic_name", "result"]);
+                for result in data.results {
+                    let outcome = if result.error.is_empty() {
+                        "deleted".to_string()
+                    } else {
+                        result.error
+                    };
+                    table.add_row(row![result.topic_name, outcome]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker delete retain message exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn retain_message_stats(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttRetainMessageStatsRequest,
+    ) {
+        match mqtt_broker_retain_message_stats(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.add_row(row!["count", "total_size"]);
+                table.add_row(row![data.count, data.total_size]);
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker retain message stats exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+}
+
+fn option_cell<T: ToString>(value: Option<T>) -> String {
+    value.map_or("-".to_string(), |value| value.to_string())
+}
+
+fn json_cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "-".to_string(),
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
 }
 
 #[cfg(test)]
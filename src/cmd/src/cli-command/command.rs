@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(crate) mod journal;
 pub(crate) mod mqtt;
 
 use clap::{arg, Parser, Subcommand, ValueEnum};
@@ -20,9 +21,11 @@
     PlacementActionType, PlacementCenterCommand, PlacementCliCommandParam,
 };
 use mqtt::admin::{
-    process_auto_subscribe_args, BindSchemaArgs, CreateConnectorArgs, CreateSchemaArgs,
+    process_acl_args, process_auto_subscribe_args, process_psk_args, process_retain_args,
+    process_session_args, BindSchemaArgs, CreateConnectorArgs, CreateSchemaArgs,
     DeleteConnectorArgs, DeleteSchemaArgs, ListBindSchemaArgs, ListConnectorArgs, ListSchemaArgs,
-    MqttAutoSubscribeRuleCommand, UnbindSchemaArgs, UpdateConnectorArgs, UpdateSchemaArgs,
+    MqttAclCommand, MqttAutoSubscribeRuleCommand, MqttPskCommand, MqttRetainCommand,
+    MqttSessionCommand, UnbindSchemaArgs, UpdateConnectorArgs, UpdateSchemaArgs,
 };
 use mqtt::publish::process_subscribe_args;
 use protocol::broker_mqtt::broker_mqtt_admin::{
@@ -37,6 +40,7 @@
     AddLearnerRequest, ChangeMembershipRequest, Node,
 };
 
+use crate::journal::segment::{verify_segment, VerifySegmentArgs};
 use crate::mqtt::admin::{
     process_slow_sub_args, process_user_args, FlappingDetectArgs, MqttUserCommand, SlowSubArgs,
 };
@@ -133,6 +137,18 @@ enum MQTTAction {
     //auto subscribe
     #[clap(name = "auto-subscribe-rule")]
     AutoSubscribeRule(MqttAutoSubscribeRuleCommand),
+
+    // acl
+    Acl(MqttAclCommand),
+
+    // psk
+    Psk(MqttPskCommand),
+
+    // session
+    Session(MqttSessionCommand),
+
+    // retain
+    Retain(MqttRetainCommand),
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -203,8 +219,17 @@ struct JournalArgs {
     #[arg(short, long,default_value_t =String::from("127.0.0.1:1228"))]
     server: String,
 
-    #[arg(short, long,default_value_t =String::from("status"))]
-    action: String,
+    #[clap(subcommand)]
+    action: JournalAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum JournalAction {
+    Status,
+
+    // segment file
+    #[clap(name = "verify-segment")]
+    VerifySegment(VerifySegmentArgs),
 }
 
 #[tokio::main]
@@ -309,6 +334,10 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
                 })
             }
             MQTTAction::AutoSubscribeRule(args) => process_auto_subscribe_args(args),
+            MQTTAction::Acl(args) => process_acl_args(args),
+            MQTTAction::Psk(args) => process_psk_args(args),
+            MQTTAction::Session(args) => process_session_args(args),
+            MQTTAction::Retain(args) => process_retain_args(args),
         },
     };
     cmd.start(params).await;
@@ -340,7 +369,10 @@ async fn handle_placement(args: PlacementArgs, cmd: PlacementCenterCommand) {
     cmd.start(params).await;
 }
 
-// TODO: implement journal engine
 async fn handle_journal(args: JournalArgs) {
-    println!("{:?}", args);
+    match args.action {
+        // TODO: implement journal engine
+        JournalAction::Status => println!("{:?}", args.server),
+        JournalAction::VerifySegment(args) => verify_segment(args).await,
+    }
 }
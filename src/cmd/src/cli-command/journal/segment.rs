@@ -0,0 +1,69 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::process;
+
+use clap::arg;
+use journal_server::scan_segment_file;
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: verify the records of a segment file offline", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct VerifySegmentArgs {
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "path of the segment file, such as <data_path>/<namespace>/<shard>/<segment>.msg"
+    )]
+    pub(crate) file: String,
+}
+
+/// verify the checksum and offset of every record in a segment file, exiting with a non-zero
+/// code if the segment file is damaged
+pub(crate) async fn verify_segment(args: VerifySegmentArgs) {
+    let result = match scan_segment_file(&args.file).await {
+        Ok(result) => result,
+        Err(e) => {
+            println!("Failed to read segment file {}: {}", args.file, e);
+            process::exit(1);
+        }
+    };
+
+    println!("segment file: {}", args.file);
+    println!("file size: {}", result.file_size);
+    println!("valid size: {}", result.valid_size);
+    println!("record num: {}", result.record_num);
+    println!("offset: {} - {}", result.start_offset, result.end_offset);
+    println!(
+        "timestamp: {} - {}",
+        result.start_timestamp, result.end_timestamp
+    );
+
+    if let Some(error) = result.error {
+        println!(
+            "damaged: position {}, {}, {} bytes after the last valid record",
+            error.position,
+            error.reason,
+            result.file_size - result.valid_size
+        );
+        if error.torn_tail {
+            println!("torn tail: the bytes after the last valid record are dropped on startup");
+        } else {
+            println!("corrupted: the Journal Server refuses to start with this segment file");
+        }
+        process::exit(1);
+    }
+    println!("ok");
+}
@@ -27,6 +27,11 @@
 use protocol::broker_mqtt::broker_mqtt_admin::{
     EnableSlowSubscribeRequest, ListSlowSubscribeRequest,
 };
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttCheckAclRequest, MqttCleanSessionRequest, MqttCreatePskRequest, MqttDeletePskRequest,
+    MqttDeleteRetainMessageRequest, MqttGetClientDetailRequest, MqttGetRetainMessageRequest,
+    MqttKickClientRequest, MqttListRetainMessageRequest, MqttRetainMessageStatsRequest,
+};
 
 #[derive(clap::Args, Debug)]
 #[command(author="RobustMQ", about="related operations of mqtt users, such as listing, creating, and deleting ", long_about = None)]
@@ -337,6 +342,266 @@ pub fn process_auto_subscribe_args(args: MqttAutoSubscribeRuleCommand) -> MqttAc
     }
 }
 
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="related operations of mqtt acl, such as checking the acl of a client", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct MqttAclCommand {
+    #[command(subcommand)]
+    pub action: Option<MqttAclActionType>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum MqttAclActionType {
+    Check(CheckAclArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: check whether the acl allows a client to publish or subscribe", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct CheckAclArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) client_id: String,
+
+    #[arg(
+        short,
+        long,
+        default_value = "",
+        help = "the user of the live connection when empty"
+    )]
+    pub(crate) username: String,
+
+    #[arg(
+        short,
+        long,
+        default_value = "",
+        help = "the ip of the live connection when empty"
+    )]
+    pub(crate) ip: String,
+
+    #[arg(short, long, required = true)]
+    pub(crate) topic: String,
+
+    #[arg(short, long, default_value = "Publish")]
+    #[arg(value_parser = ["All", "Subscribe", "Publish", "PubSub", "Retain", "Qos"])]
+    pub(crate) action: String,
+
+    #[arg(short, long, default_value_t = false)]
+    pub(crate) retain: bool,
+}
+
+pub fn process_acl_args(args: MqttAclCommand) -> MqttActionType {
+    match args.action {
+        Some(acl_action) => match acl_action {
+            MqttAclActionType::Check(arg) => MqttActionType::CheckAcl(MqttCheckAclRequest {
+                client_id: arg.client_id,
+                username: arg.username,
+                source_ip_addr: arg.ip,
+                topic: arg.topic,
+                action: arg.action,
+                retain: arg.retain,
+            }),
+        },
+        None => unreachable!(),
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="related operations of the TLS-PSK identities of the tcps listener, such as listing, creating, and deleting", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct MqttPskCommand {
+    #[command(subcommand)]
+    pub action: Option<MqttPskActionType>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum MqttPskActionType {
+    List,
+    Create(CreatePskArgs),
+    Delete(DeletePskArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: create a TLS-PSK identity", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct CreatePskArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) identity: String,
+
+    #[arg(short, long, required = true, help = "the pre-shared key, hex encoded")]
+    pub(crate) psk: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: delete a TLS-PSK identity", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct DeletePskArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) identity: String,
+}
+
+pub fn process_psk_args(args: MqttPskCommand) -> MqttActionType {
+    match args.action {
+        Some(psk_action) => match psk_action {
+            MqttPskActionType::List => MqttActionType::ListPsk,
+            MqttPskActionType::Create(arg) => MqttActionType::CreatePsk(MqttCreatePskRequest {
+                identity: arg.identity,
+                psk: arg.psk,
+            }),
+            MqttPskActionType::Delete(arg) => MqttActionType::DeletePsk(MqttDeletePskRequest {
+                identity: arg.identity,
+            }),
+        },
+        None => unreachable!(),
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="related operations of mqtt sessions and clients, such as listing, inspecting, kicking, and cleaning", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct MqttSessionCommand {
+    #[command(subcommand)]
+    pub action: Option<MqttSessionActionType>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum MqttSessionActionType {
+    List,
+    Detail(ClientDetailArgs),
+    Kick(KickClientArgs),
+    Clean(CleanSessionArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: show the session, connection and subscriptions of a client", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ClientDetailArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) client_id: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: disconnect a client and keep its session", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct KickClientArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) client_id: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: remove the session of a client, disconnecting it first", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct CleanSessionArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) client_id: String,
+}
+
+pub fn process_session_args(args: MqttSessionCommand) -> MqttActionType {
+    match args.action {
+        Some(session_action) => match session_action {
+            MqttSessionActionType::List => MqttActionType::ListSession,
+            MqttSessionActionType::Detail(arg) => {
+                MqttActionType::GetClientDetail(MqttGetClientDetailRequest {
+                    client_id: arg.client_id,
+                })
+            }
+            MqttSessionActionType::Kick(arg) => MqttActionType::KickClient(MqttKickClientRequest {
+                client_id: arg.client_id,
+            }),
+            MqttSessionActionType::Clean(arg) => {
+                MqttActionType::CleanSession(MqttCleanSessionRequest {
+                    client_id: arg.client_id,
+                })
+            }
+        },
+        None => unreachable!(),
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="related operations of mqtt retained messages, such as listing, inspecting, deleting, and counting", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct MqttRetainCommand {
+    #[command(subcommand)]
+    pub action: Option<MqttRetainActionType>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum MqttRetainActionType {
+    List(ListRetainMessageArgs),
+    Detail(RetainMessageDetailArgs),
+    Delete(DeleteRetainMessageArgs),
+    Stats(RetainMessageStatsArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: list a page of the retained messages, without their payload", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ListRetainMessageArgs {
+    // a topic name or a filter with + and #, all retained messages when empty
+    #[arg(short, long, default_value_t = String::new())]
+    pub(crate) topic_filter: String,
+
+    #[arg(short, long, default_value_t = 1)]
+    pub(crate) page: u32,
+
+    #[arg(short = 's', long, default_value_t = 20)]
+    pub(crate) page_size: u32,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: show the retained message of a topic, with its payload", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct RetainMessageDetailArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) topic_name: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: delete the retained messages whose topic matches the filter, # deletes all of them", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct DeleteRetainMessageArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) topic_filter: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: count the retained messages and their payload size", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct RetainMessageStatsArgs {
+    #[arg(short, long, default_value_t = String::new())]
+    pub(crate) topic_filter: String,
+}
+
+pub fn process_retain_args(args: MqttRetainCommand) -> MqttActionType {
+    match args.action {
+        Some(retain_action) => match retain_action {
+            MqttRetainActionType::List(arg) => {
+                MqttActionType::ListRetainMessage(MqttListRetainMessageRequest {
+                    topic_filter: arg.topic_filter,
+                    page: arg.page,
+                    page_size: arg.page_size,
+                })
+            }
+            MqttRetainActionType::Detail(arg) => {
+                MqttActionType::GetRetainMessage(MqttGetRetainMessageRequest {
+                    topic_name: arg.topic_name,
+                })
+            }
+            MqttRetainActionType::Delete(arg) => {
+                MqttActionType::DeleteRetainMessage(MqttDeleteRetainMessageRequest {
+                    topic_filter: arg.topic_filter,
+                })
+            }
+            MqttRetainActionType::Stats(arg) => {
+                MqttActionType::RetainMessageStats(MqttRetainMessageStatsRequest {
+                    topic_filter: arg.topic_filter,
+                })
+            }
+        },
+        None => unreachable!(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
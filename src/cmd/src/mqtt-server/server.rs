@@ -33,5 +33,8 @@ fn main() {
     init_broker_mqtt_conf_by_path(&args.conf);
     init_broker_mqtt_log();
     let (stop_send, _) = broadcast::channel(2);
-    start_mqtt_broker_server(stop_send);
+    if let Err(e) = start_mqtt_broker_server(stop_send) {
+        eprintln!("MQTT Broker failed to start: {}", e);
+        std::process::exit(1);
+    }
 }
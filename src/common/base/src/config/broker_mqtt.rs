@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::OnceLock;
 
 use serde::{Deserialize, Serialize};
@@ -20,7 +21,8 @@
     default_prometheus, override_default_by_env, Auth, Log, Prometheus, Storage, Telemetry,
 };
 use super::default_mqtt::{
-    default_auth, default_grpc_port, default_log, default_mqtt_cluster_dynamic_feature,
+    default_auth, default_grpc_port, default_http_admin, default_http_admin_bind,
+    default_http_admin_port, default_log, default_mqtt_cluster_dynamic_feature,
     default_mqtt_cluster_dynamic_flapping_detect, default_mqtt_cluster_dynamic_network,
     default_mqtt_cluster_dynamic_protocol, default_mqtt_cluster_dynamic_security,
     default_mqtt_cluster_dynamic_slow_sub, default_network, default_network_quic_port,
@@ -56,6 +58,8 @@ pub struct BrokerMqttConfig {
     pub telemetry: Telemetry,
     #[serde(default = "default_prometheus")]
     pub prometheus: Prometheus,
+    #[serde(default = "default_http_admin")]
+    pub http_admin: HttpAdmin,
 
     #[serde(default = "default_mqtt_cluster_dynamic_slow_sub")]
     pub cluster_dynamic_config_slow_sub: MqttClusterDynamicSlowSub,
@@ -96,6 +100,9 @@ pub fn encode(&self) -> Vec<u8> {
 pub struct MqttClusterDynamicConfigSecurity {
     pub is_self_protection_status: bool,
     pub secret_free_login: bool,
+    // Allow or Deny, the permission of an action that no acl rule matches
+    #[serde(default)]
+    pub acl_no_match: ConfigAclPermission,
 }
 
 impl MqttClusterDynamicConfigSecurity {
@@ -113,6 +120,18 @@ pub struct MqttClusterDynamicConfigNetwork {
     pub websockets_max_connection_num: u64,
     pub response_max_try_mut_times: u64,
     pub response_try_mut_sleep_time_ms: u64,
+    // new connections accepted per second by each tcp and tcps listener, 0 means unlimited
+    #[serde(default)]
+    pub max_connection_rate: u64,
+    // PUBLISH packets per second of each client, 0 means unlimited
+    #[serde(default)]
+    pub max_publish_rate: u64,
+    // PUBLISH payload bytes per second of each client, 0 means unlimited
+    #[serde(default)]
+    pub max_publish_bytes_rate: u64,
+    // SUBSCRIBE packets per second of each client, 0 means unlimited
+    #[serde(default)]
+    pub max_subscribe_rate: u64,
 }
 
 // MQTT cluster Feature related dynamic configuration
@@ -123,6 +142,13 @@ pub struct MqttClusterDynamicConfigFeature {
     pub subscription_identifiers_available: ConfigAvailableFlag,
     pub shared_subscription_available: ConfigAvailableFlag,
     pub exclusive_subscription_available: ConfigAvailableFlag,
+    // round_robin, random, hash_clientid, hash_topic, sticky or least_inflight, how the messages
+    // of a shared subscription group are spread over its members
+    #[serde(default)]
+    pub shared_subscription_strategy: ConfigSharedSubscriptionStrategy,
+    // (group_name, strategy), overrides shared_subscription_strategy for a group
+    #[serde(default)]
+    pub shared_subscription_group_strategy: HashMap<String, ConfigSharedSubscriptionStrategy>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -164,6 +190,30 @@ pub enum ConfigAvailableFlag {
     Enable,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, Clone)]
+pub enum ConfigSharedSubscriptionStrategy {
+    #[default]
+    #[serde(rename = "round_robin")]
+    RoundRobin,
+    #[serde(rename = "random")]
+    Random,
+    #[serde(rename = "hash_clientid")]
+    HashClientId,
+    #[serde(rename = "hash_topic")]
+    HashTopic,
+    #[serde(rename = "sticky")]
+    Sticky,
+    #[serde(rename = "least_inflight")]
+    LeastInflight,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, Clone)]
+pub enum ConfigAclPermission {
+    #[default]
+    Allow,
+    Deny,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Network {
     #[serde(default = "default_network_tcp_port")]
@@ -180,6 +230,24 @@ pub struct Network {
     pub tls_cert: String,
     #[serde(default)]
     pub tls_key: String,
+    // CA bundle that verifies client certificates, empty means mutual TLS is disabled
+    #[serde(default)]
+    pub tls_ca: String,
+    // reject TLS clients without a certificate when mutual TLS is enabled
+    #[serde(default)]
+    pub tls_fail_if_no_peer_cert: bool,
+    // cn, dn or san of the client certificate used as the username, empty means disabled
+    #[serde(default)]
+    pub peer_cert_as_username: String,
+    // cn, dn or san of the client certificate used as the client id, empty means disabled
+    #[serde(default)]
+    pub peer_cert_as_clientid: String,
+    // accept TLS-PSK clients on the tcps listener, the PSK identity is used as the username
+    #[serde(default)]
+    pub tls_psk_enable: bool,
+    // OpenSSL cipher list of the PSK cipher suites, empty means the PSK-AES-GCM and PSK-AES-CBC suites
+    #[serde(default)]
+    pub tls_psk_ciphers: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -222,6 +290,25 @@ pub struct OfflineMessage {
     pub max_messages_num: u32,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct HttpAdmin {
+    #[serde(default)]
+    pub enable: bool,
+    // the admin API has full control over the cluster, it only listens on localhost by default
+    #[serde(default = "default_http_admin_bind")]
+    pub bind: String,
+    #[serde(default = "default_http_admin_port")]
+    pub port: u32,
+    // basic auth credentials, basic auth is disabled unless both are set
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    // sent in the X-API-Key header or as a bearer token, empty means API keys are disabled
+    #[serde(default)]
+    pub api_key: String,
+}
+
 static BROKER_MQTT_CONF: OnceLock<BrokerMqttConfig> = OnceLock::new();
 
 pub fn init_broker_mqtt_conf_by_path(config_path: &str) -> &'static BrokerMqttConfig {
@@ -270,6 +357,7 @@ pub fn broker_mqtt_conf() -> &'static BrokerMqttConfig {
 mod tests {
     use super::{
         broker_mqtt_conf, init_broker_mqtt_conf_by_path, override_default_by_env, BrokerMqttConfig,
+        ConfigAclPermission, ConfigSharedSubscriptionStrategy,
     };
     use crate::tools::read_file;
 
@@ -300,6 +388,10 @@ fn config_default_test() {
         assert_eq!(config.network.quic_port, 9083);
         assert!(!config.network.tls_cert.is_empty());
         assert!(!config.network.tls_key.is_empty());
+        assert!(config.network.tls_ca.is_empty());
+        assert!(!config.network.tls_fail_if_no_peer_cert);
+        assert!(config.network.peer_cert_as_username.is_empty());
+        assert!(!config.network.tls_psk_enable);
 
         assert_eq!(config.tcp_thread.accept_thread_num, 1);
         assert_eq!(config.tcp_thread.handler_thread_num, 10);
@@ -310,6 +402,13 @@ fn config_default_test() {
         assert_eq!(config.tcp_thread.lock_max_try_mut_times, 30);
         assert_eq!(config.tcp_thread.lock_try_mut_sleep_time_ms, 50);
 
+        assert!(!config.http_admin.enable);
+        assert_eq!(config.http_admin.bind, "127.0.0.1".to_string());
+        assert_eq!(config.http_admin.port, 8080);
+        assert!(config.http_admin.username.is_empty());
+        assert!(config.http_admin.password.is_empty());
+        assert!(config.http_admin.api_key.is_empty());
+
         assert_eq!(config.system.runtime_worker_threads, 128);
         assert_eq!(config.system.default_user, "admin".to_string());
         assert_eq!(config.system.default_password, "pwd123".to_string());
@@ -331,6 +430,34 @@ fn config_default_test() {
         assert_eq!(config.auth.storage_type, "placement".to_string());
         assert_eq!(config.auth.journal_addr, "".to_string());
         assert_eq!(config.auth.mysql_addr, "".to_string());
+        assert_eq!(config.auth.authn_type, "plaintext".to_string());
+        assert_eq!(config.auth.jwt_from, "password".to_string());
+        assert_eq!(config.auth.http_timeout_ms, 5000);
+        assert!(!config.auth.http_fail_open);
+        assert_eq!(config.auth.password_hash_algorithm, "pbkdf2".to_string());
+        assert_eq!(config.auth.redis_addr, "".to_string());
+        assert_eq!(
+            config.auth.redis_user_key,
+            "mqtt_user:${username}".to_string()
+        );
+
+        assert!(!config.cluster_dynamic_config_security.secret_free_login);
+        assert_eq!(config.cluster_dynamic_config_network.max_connection_rate, 0);
+        assert_eq!(config.cluster_dynamic_config_network.max_publish_rate, 0);
+        assert_eq!(
+            config.cluster_dynamic_config_security.acl_no_match,
+            ConfigAclPermission::Allow
+        );
+        assert_eq!(
+            config
+                .cluster_dynamic_config_feature
+                .shared_subscription_strategy,
+            ConfigSharedSubscriptionStrategy::RoundRobin
+        );
+        assert!(config
+            .cluster_dynamic_config_feature
+            .shared_subscription_group_strategy
+            .is_empty());
     }
 
     #[test]
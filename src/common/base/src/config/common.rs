@@ -27,6 +27,28 @@ pub struct Storage {
     #[serde(default)]
     pub rocksdb_data_path: String,
     pub rocksdb_max_open_files: Option<i32>,
+    #[serde(default)]
+    pub minio_endpoint: String,
+    #[serde(default)]
+    pub minio_access_key_id: String,
+    #[serde(default)]
+    pub minio_secret_access_key: String,
+    #[serde(default)]
+    pub minio_data_dir: String,
+    #[serde(default)]
+    pub minio_bucket: String,
+    #[serde(default)]
+    pub s3_endpoint: String,
+    #[serde(default)]
+    pub s3_region: String,
+    #[serde(default)]
+    pub s3_bucket: String,
+    #[serde(default)]
+    pub s3_root: String,
+    #[serde(default)]
+    pub s3_access_key_id: String,
+    #[serde(default)]
+    pub s3_secret_access_key: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -52,6 +74,63 @@ pub struct Auth {
     pub journal_addr: String,
     #[serde(default)]
     pub mysql_addr: String,
+    // plaintext, jwt or http, empty means plaintext
+    #[serde(default)]
+    pub authn_type: String,
+    // HS256, RS256 or ES256
+    #[serde(default)]
+    pub jwt_algorithm: String,
+    #[serde(default)]
+    pub jwt_secret: String,
+    #[serde(default)]
+    pub jwt_public_key_file: String,
+    #[serde(default)]
+    pub jwt_jwks_file: String,
+    // the CONNECT field carrying the token, password or username, empty means password
+    #[serde(default)]
+    pub jwt_from: String,
+    #[serde(default)]
+    pub jwt_username_claim: String,
+    #[serde(default)]
+    pub jwt_client_id_claim: String,
+    #[serde(default)]
+    pub jwt_acl_claim: String,
+    // accept tokens without an exp claim, they are rejected by default
+    #[serde(default)]
+    pub jwt_exp_optional: bool,
+    #[serde(default)]
+    pub http_auth_url: String,
+    // PUBLISH and SUBSCRIBE are authorized by the HTTP service when set
+    #[serde(default)]
+    pub http_acl_url: String,
+    // 0 means 5000
+    #[serde(default)]
+    pub http_timeout_ms: u64,
+    // 0 disables the cache
+    #[serde(default)]
+    pub http_cache_ttl_sec: u64,
+    // allow the request when the HTTP service fails
+    #[serde(default)]
+    pub http_fail_open: bool,
+    // plain, bcrypt, pbkdf2 or sha256, empty means plain
+    #[serde(default)]
+    pub password_hash_algorithm: String,
+    // required when storage_type is redis, like redis://127.0.0.1:6379/0
+    #[serde(default)]
+    pub redis_addr: String,
+    // the key of the hash of a user, empty means mqtt_user:${username}
+    #[serde(default)]
+    pub redis_user_key: String,
+    // the key of the list of acl of a resource,
+    // empty means mqtt_acl:${resource_type}:${resource_name}
+    #[serde(default)]
+    pub redis_acl_key: String,
+    // the key of a blacklist entry, empty means mqtt_blacklist:${blacklist_type}:${resource_name}
+    #[serde(default)]
+    pub redis_blacklist_key: String,
+    // the key of a TLS-PSK identity, empty means mqtt_psk:${identity}
+    #[serde(default)]
+    pub redis_psk_key: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::common::Log;
-use super::journal_server::{Network, Shard, Storage, System, TcpThread};
+use super::common::{Log, Prometheus};
+use super::journal_server::{Network, Replication, Shard, Storage, System, TcpThread};
 
 pub fn default_network() -> Network {
     Network {
@@ -31,6 +31,10 @@ pub fn default_shard() -> Shard {
         enable_auto_create_shard: default_enable_auto_create_shard(),
         shard_replica_num: default_shard_replica_num(),
         max_segment_size: default_max_segment_size(),
+        fsync_strategy: default_fsync_strategy(),
+        fsync_interval_ms: default_fsync_interval_ms(),
+        fsync_bytes: default_fsync_bytes(),
+        compression: default_compression(),
     }
 }
 
@@ -46,6 +50,47 @@ pub fn default_max_segment_size() -> u32 {
     1073741824
 }
 
+pub fn default_fsync_strategy() -> String {
+    "every_write".to_string()
+}
+
+pub fn default_fsync_interval_ms() -> u64 {
+    1000
+}
+
+pub fn default_fsync_bytes() -> u64 {
+    1048576
+}
+
+pub fn default_compression() -> String {
+    "none".to_string()
+}
+
+pub fn default_replication() -> Replication {
+    Replication {
+        min_insync_replica_num: default_min_insync_replica_num(),
+        replica_lag_time_max_ms: default_replica_lag_time_max_ms(),
+        replica_fetch_interval_ms: default_replica_fetch_interval_ms(),
+        replica_ack_timeout_ms: default_replica_ack_timeout_ms(),
+    }
+}
+
+pub fn default_min_insync_replica_num() -> u32 {
+    1
+}
+
+pub fn default_replica_lag_time_max_ms() -> u64 {
+    10000
+}
+
+pub fn default_replica_fetch_interval_ms() -> u64 {
+    100
+}
+
+pub fn default_replica_ack_timeout_ms() -> u64 {
+    30000
+}
+
 pub fn default_local_ip() -> String {
     "127.0.0.1".to_string()
 }
@@ -90,3 +135,15 @@ pub fn default_log() -> Log {
         log_config: "./config/log4rs.yaml".to_string(),
     }
 }
+
+pub fn default_prometheus() -> Prometheus {
+    Prometheus {
+        port: default_prometheus_port(),
+        ..super::common::default_prometheus()
+    }
+}
+
+// the broker serves its metrics on 9090 and the placement center on 9091
+pub fn default_prometheus_port() -> u32 {
+    9092
+}
@@ -12,8 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+
 use super::broker_mqtt::{
-    ConfigAvailableFlag, MqttClusterDynamicConfigFeature, MqttClusterDynamicConfigNetwork,
+    ConfigAclPermission, ConfigAvailableFlag, ConfigSharedSubscriptionStrategy, HttpAdmin,
+    MqttClusterDynamicConfigFeature, MqttClusterDynamicConfigNetwork,
     MqttClusterDynamicConfigProtocol, MqttClusterDynamicConfigSecurity,
     MqttClusterDynamicFlappingDetect, MqttClusterDynamicSlowSub, Network, OfflineMessage, System,
     TcpThread,
@@ -37,6 +40,12 @@ pub fn default_network() -> Network {
         quic_port: default_network_quic_port(),
         tls_cert: "".to_string(),
         tls_key: "".to_string(),
+        tls_ca: "".to_string(),
+        tls_fail_if_no_peer_cert: false,
+        peer_cert_as_username: "".to_string(),
+        peer_cert_as_clientid: "".to_string(),
+        tls_psk_enable: false,
+        tls_psk_ciphers: "".to_string(),
     }
 }
 pub fn default_network_tcp_port() -> u32 {
@@ -83,6 +92,17 @@ pub fn default_storage() -> Storage {
         mysql_addr: "".to_string(),
         rocksdb_data_path: "".to_string(),
         rocksdb_max_open_files: None,
+        minio_endpoint: "".to_string(),
+        minio_access_key_id: "".to_string(),
+        minio_secret_access_key: "".to_string(),
+        minio_data_dir: "".to_string(),
+        minio_bucket: "".to_string(),
+        s3_endpoint: "".to_string(),
+        s3_region: "".to_string(),
+        s3_bucket: "".to_string(),
+        s3_root: "".to_string(),
+        s3_access_key_id: "".to_string(),
+        s3_secret_access_key: "".to_string(),
     }
 }
 
@@ -106,9 +126,49 @@ pub fn default_auth() -> Auth {
         storage_type: "memory".to_string(),
         journal_addr: "".to_string(),
         mysql_addr: "".to_string(),
+        authn_type: "plaintext".to_string(),
+        jwt_algorithm: "".to_string(),
+        jwt_secret: "".to_string(),
+        jwt_public_key_file: "".to_string(),
+        jwt_jwks_file: "".to_string(),
+        jwt_from: "password".to_string(),
+        jwt_username_claim: "username".to_string(),
+        jwt_client_id_claim: "clientid".to_string(),
+        jwt_acl_claim: "acl".to_string(),
+        jwt_exp_optional: false,
+        http_auth_url: "".to_string(),
+        http_acl_url: "".to_string(),
+        http_timeout_ms: 5000,
+        http_cache_ttl_sec: 60,
+        http_fail_open: false,
+        password_hash_algorithm: "pbkdf2".to_string(),
+        redis_addr: "".to_string(),
+        redis_user_key: "mqtt_user:${username}".to_string(),
+        redis_acl_key: "mqtt_acl:${resource_type}:${resource_name}".to_string(),
+        redis_blacklist_key: "mqtt_blacklist:${blacklist_type}:${resource_name}".to_string(),
+        redis_psk_key: "mqtt_psk:${identity}".to_string(),
+    }
+}
+
+pub fn default_http_admin() -> HttpAdmin {
+    HttpAdmin {
+        enable: false,
+        bind: default_http_admin_bind(),
+        port: default_http_admin_port(),
+        username: "".to_string(),
+        password: "".to_string(),
+        api_key: "".to_string(),
     }
 }
 
+pub fn default_http_admin_bind() -> String {
+    "127.0.0.1".to_string()
+}
+
+pub fn default_http_admin_port() -> u32 {
+    8080
+}
+
 pub fn default_telemetry() -> Telemetry {
     Telemetry {
         enable: false,
@@ -124,6 +184,8 @@ pub fn default_mqtt_cluster_dynamic_feature() -> MqttClusterDynamicConfigFeature
         subscription_identifiers_available: ConfigAvailableFlag::Enable,
         shared_subscription_available: ConfigAvailableFlag::Enable,
         exclusive_subscription_available: ConfigAvailableFlag::Enable,
+        shared_subscription_strategy: ConfigSharedSubscriptionStrategy::RoundRobin,
+        shared_subscription_group_strategy: HashMap::new(),
     }
 }
 
@@ -131,6 +193,7 @@ pub fn default_mqtt_cluster_dynamic_security() -> MqttClusterDynamicConfigSecuri
     MqttClusterDynamicConfigSecurity {
         secret_free_login: false,
         is_self_protection_status: false,
+        acl_no_match: ConfigAclPermission::Allow,
     }
 }
 
@@ -174,5 +237,9 @@ pub fn default_mqtt_cluster_dynamic_network() -> MqttClusterDynamicConfigNetwork
         websockets_max_connection_num: 1000,
         response_max_try_mut_times: 128,
         response_try_mut_sleep_time_ms: 100,
+        max_connection_rate: 0,
+        max_publish_rate: 0,
+        max_publish_bytes_rate: 0,
+        max_subscribe_rate: 0,
     }
 }
@@ -16,11 +16,15 @@
 
 use serde::Deserialize;
 
-use super::common::{default_prometheus, Log, Prometheus};
+use super::common::{Log, Prometheus};
 use super::default_journal_server::{
-    default_enable_auto_create_shard, default_grpc_port, default_local_ip, default_log,
-    default_max_segment_size, default_network, default_network_tcp_port, default_network_tcps_port,
-    default_shard, default_shard_replica_num, default_storage, default_system, default_tcp_thread,
+    default_compression, default_enable_auto_create_shard, default_fsync_bytes,
+    default_fsync_interval_ms, default_fsync_strategy, default_grpc_port, default_local_ip,
+    default_log, default_max_segment_size, default_min_insync_replica_num, default_network,
+    default_network_tcp_port, default_network_tcps_port, default_prometheus,
+    default_replica_ack_timeout_ms, default_replica_fetch_interval_ms,
+    default_replica_lag_time_max_ms, default_replication, default_shard, default_shard_replica_num,
+    default_storage, default_system, default_tcp_thread,
 };
 use crate::tools::{read_file, try_create_fold};
 
@@ -34,6 +38,8 @@ pub struct JournalServerConfig {
     pub network: Network,
     #[serde(default = "default_shard")]
     pub shard: Shard,
+    #[serde(default = "default_replication")]
+    pub replication: Replication,
     #[serde(default = "default_system")]
     pub system: System,
     #[serde(default = "default_storage")]
@@ -83,6 +89,26 @@ pub struct Shard {
     pub shard_replica_num: u32,
     #[serde(default = "default_max_segment_size")]
     pub max_segment_size: u32,
+    #[serde(default = "default_fsync_strategy")]
+    pub fsync_strategy: String,
+    #[serde(default = "default_fsync_interval_ms")]
+    pub fsync_interval_ms: u64,
+    #[serde(default = "default_fsync_bytes")]
+    pub fsync_bytes: u64,
+    #[serde(default = "default_compression")]
+    pub compression: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Replication {
+    #[serde(default = "default_min_insync_replica_num")]
+    pub min_insync_replica_num: u32,
+    #[serde(default = "default_replica_lag_time_max_ms")]
+    pub replica_lag_time_max_ms: u64,
+    #[serde(default = "default_replica_fetch_interval_ms")]
+    pub replica_fetch_interval_ms: u64,
+    #[serde(default = "default_replica_ack_timeout_ms")]
+    pub replica_ack_timeout_ms: u64,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -174,6 +200,16 @@ fn journal_server_toml_test() {
 
         assert_eq!(conf.system.runtime_work_threads, 100);
 
+        assert_eq!(conf.shard.fsync_strategy, "every_write".to_string());
+        assert_eq!(conf.shard.fsync_interval_ms, 1000);
+        assert_eq!(conf.shard.fsync_bytes, 1048576);
+        assert_eq!(conf.shard.compression, "none".to_string());
+
+        assert_eq!(conf.replication.min_insync_replica_num, 1);
+        assert_eq!(conf.replication.replica_lag_time_max_ms, 10000);
+        assert_eq!(conf.replication.replica_fetch_interval_ms, 100);
+        assert_eq!(conf.replication.replica_ack_timeout_ms, 30000);
+
         assert_eq!(conf.tcp_thread.accept_thread_num, 1);
         assert_eq!(conf.tcp_thread.handler_thread_num, 20);
         assert_eq!(conf.tcp_thread.response_thread_num, 2);
@@ -183,7 +219,7 @@ fn journal_server_toml_test() {
 
         assert!(!conf.prometheus.enable);
         assert_eq!(conf.prometheus.model, "pull".to_string());
-        assert_eq!(conf.prometheus.port, 9090);
+        assert_eq!(conf.prometheus.port, 9092);
         assert_eq!(conf.prometheus.interval, 10);
     }
 }
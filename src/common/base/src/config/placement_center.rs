@@ -19,7 +19,7 @@
 use toml::map::Map;
 use toml::{Table, Value};
 
-use super::common::{override_default_by_env, Log};
+use super::common::{default_prometheus, override_default_by_env, Log, Prometheus};
 use super::default_placement_center::{
     default_cluster_name, default_data_path, default_grpc_port, default_heartbeat,
     default_heartbeat_check_time_ms, default_heartbeat_timeout_ms, default_http_port,
@@ -42,6 +42,8 @@ pub struct PlacementCenterConfig {
     pub heartbeat: Heartbeat,
     #[serde(default = "default_rocksdb")]
     pub rocksdb: Rocksdb,
+    #[serde(default = "default_prometheus")]
+    pub prometheus: Prometheus,
     #[serde(default = "default_log")]
     pub log: Log,
 }
@@ -214,5 +216,8 @@ fn meta_default() {
         assert_eq!(config.rocksdb.max_open_files, Some(10000_i32));
         assert_eq!(config.heartbeat.heartbeat_timeout_ms, 5000);
         assert_eq!(config.heartbeat.heartbeat_check_time_ms, 1000);
+        assert!(!config.prometheus.enable);
+        assert_eq!(config.prometheus.model, "pull".to_string());
+        assert_eq!(config.prometheus.port, 9091);
     }
 }
@@ -21,10 +21,16 @@
 pub struct MqttAcl {
     pub resource_type: MqttAclResourceType,
     pub resource_name: String,
+    // *, a topic filter with + and # that may contain ${username}, ${clientid} and ${ip},
+    // or "eq " followed by a literal topic
     pub topic: String,
     pub ip: String,
     pub action: MqttAclAction,
     pub permission: MqttAclPermission,
+    // rules with a lower priority are checked first, rules of the same priority in the order
+    // they were added
+    #[serde(default)]
+    pub priority: u32,
 }
 
 impl MqttAcl {
@@ -41,6 +47,10 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
 pub enum MqttAclResourceType {
     ClientId,
     User,
+    // the resource name is an IP address or a CIDR
+    Ip,
+    // the rule applies to every client, the resource name is ignored
+    All,
 }
 
 impl fmt::Display for MqttAclResourceType {
@@ -51,6 +61,8 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             match self {
                 MqttAclResourceType::ClientId => "ClientId",
                 MqttAclResourceType::User => "User",
+                MqttAclResourceType::Ip => "Ip",
+                MqttAclResourceType::All => "All",
             }
         )
     }
@@ -66,8 +78,9 @@ pub enum MqttAclAction {
     Qos,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone, Default)]
 pub enum MqttAclPermission {
+    #[default]
     Allow,
     Deny,
 }
@@ -0,0 +1,185 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The compression of record batches, shared by the journal server that writes them to segment
+//! files and the journal client that reads them as they are stored.
+//!
+//! The decompressed payload of a batch holds its records one after another as
+//!
+//! ```text
+//! [offset: u64][len: u32][data: bytes]
+//! ```
+//!
+//! where `data` is the encoded `JournalRecord` and all integers are big endian.
+
+use bytes::{Buf, Bytes};
+
+use super::shard::CompressionType;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// the size of the header of a record in the payload of a batch
+pub const BATCH_RECORD_HEADER_LEN: usize = 12;
+
+/// the codec id stored with a compressed batch
+pub fn codec_id(compression: CompressionType) -> u8 {
+    match compression {
+        CompressionType::None => 0,
+        CompressionType::Lz4 => 1,
+        CompressionType::Zstd => 2,
+        CompressionType::Snappy => 3,
+    }
+}
+
+pub fn codec_from_id(id: u8) -> Option<CompressionType> {
+    match id {
+        0 => Some(CompressionType::None),
+        1 => Some(CompressionType::Lz4),
+        2 => Some(CompressionType::Zstd),
+        3 => Some(CompressionType::Snappy),
+        _ => None,
+    }
+}
+
+pub fn compress(compression: CompressionType, data: &[u8]) -> Result<Vec<u8>, String> {
+    match compression {
+        CompressionType::None => Ok(data.to_vec()),
+        CompressionType::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+        CompressionType::Zstd => zstd::encode_all(data, ZSTD_LEVEL).map_err(|e| e.to_string()),
+        CompressionType::Snappy => snap::raw::Encoder::new()
+            .compress_vec(data)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+pub fn decompress(compression: CompressionType, data: &[u8]) -> Result<Vec<u8>, String> {
+    match compression {
+        CompressionType::None => Ok(data.to_vec()),
+        CompressionType::Lz4 => {
+            lz4_flex::decompress_size_prepended(data).map_err(|e| e.to_string())
+        }
+        CompressionType::Zstd => zstd::decode_all(data).map_err(|e| e.to_string()),
+        CompressionType::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// append a record to the decompressed payload of a batch
+pub fn append_batch_record(raw: &mut Vec<u8>, offset: u64, data: &[u8]) {
+    raw.extend_from_slice(&offset.to_be_bytes());
+    raw.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    raw.extend_from_slice(data);
+}
+
+/// split the decompressed payload of a batch into its `count` records, as pairs of offset and
+/// encoded record
+pub fn split_batch_records(mut raw: Bytes, count: u32) -> Result<Vec<(u64, Bytes)>, String> {
+    let mut records = Vec::with_capacity(count.min(1024) as usize);
+    for _ in 0..count {
+        if raw.remaining() < BATCH_RECORD_HEADER_LEN {
+            return Err(format!(
+                "expected {} records, found {}",
+                count,
+                records.len()
+            ));
+        }
+        let offset = raw.get_u64();
+        let len = raw.get_u32() as usize;
+        if raw.remaining() < len {
+            return Err(format!("record at offset {} is incomplete", offset));
+        }
+        records.push((offset, raw.split_to(len)));
+    }
+
+    if raw.has_remaining() {
+        return Err(format!(
+            "{} bytes are left after {} records",
+            raw.remaining(),
+            count
+        ));
+    }
+    Ok(records)
+}
+
+/// decompress the payload of a batch stored with the codec `codec` and split it into records
+pub fn decode_batch(codec: u8, count: u32, payload: &[u8]) -> Result<Vec<(u64, Bytes)>, String> {
+    let compression = if let Some(compression) = codec_from_id(codec) {
+        compression
+    } else {
+        return Err(format!("unknown compression codec {}", codec));
+    };
+    let raw = decompress(compression, payload)?;
+    split_batch_records(Bytes::from(raw), count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        append_batch_record, codec_from_id, codec_id, compress, decode_batch, decompress,
+        split_batch_records,
+    };
+    use crate::journal::shard::CompressionType;
+
+    #[test]
+    fn compress_test() {
+        let data = "robustmq journal ".repeat(100).into_bytes();
+        for compression in [
+            CompressionType::None,
+            CompressionType::Lz4,
+            CompressionType::Zstd,
+            CompressionType::Snappy,
+        ] {
+            let compressed = compress(compression, &data).unwrap();
+            if compression != CompressionType::None {
+                assert!(compressed.len() < data.len());
+            }
+            assert_eq!(decompress(compression, &compressed).unwrap(), data);
+            assert_eq!(codec_from_id(codec_id(compression)), Some(compression));
+        }
+        assert_eq!(codec_from_id(100), None);
+    }
+
+    #[test]
+    fn decompress_invalid_data_test() {
+        // a small size prefix, so that lz4 does not allocate a huge buffer for garbage
+        let data = vec![0x08, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF];
+        for compression in [
+            CompressionType::Lz4,
+            CompressionType::Zstd,
+            CompressionType::Snappy,
+        ] {
+            assert!(decompress(compression, &data).is_err());
+        }
+    }
+
+    #[test]
+    fn decode_batch_test() {
+        let mut raw = Vec::new();
+        append_batch_record(&mut raw, 5, b"r5");
+        append_batch_record(&mut raw, 6, b"r6");
+
+        let payload = compress(CompressionType::Zstd, &raw).unwrap();
+        let records = decode_batch(codec_id(CompressionType::Zstd), 2, &payload).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0, 5);
+        assert_eq!(records[0].1.as_ref(), b"r5");
+        assert_eq!(records[1].0, 6);
+        assert_eq!(records[1].1.as_ref(), b"r6");
+
+        assert!(decode_batch(100, 2, &payload).is_err());
+        assert!(split_batch_records(raw.clone().into(), 3).is_err());
+        assert!(split_batch_records(raw.into(), 1).is_err());
+    }
+}
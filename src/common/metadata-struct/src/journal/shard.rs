@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use common_base::error::common::CommonError;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -51,4 +52,154 @@ pub enum JournalShardStatus {
 pub struct JournalShardConfig {
     pub replica_num: u32,
     pub max_segment_size: u32,
+    // falls back to the fsync strategy configured on the journal server when not set
+    #[serde(default)]
+    pub fsync_policy: Option<FsyncPolicy>,
+    // falls back to the compression configured on the journal server when not set
+    #[serde(default)]
+    pub compression: Option<CompressionType>,
+}
+
+/// When the data appended to a segment file is flushed to disk with fsync.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// fsync before every write is acknowledged
+    EveryWrite,
+    /// fsync at most every given number of milliseconds
+    IntervalMs(u64),
+    /// fsync once the given number of bytes have been written since the last fsync
+    Bytes(u64),
+    /// never fsync, leave flushing to the operating system
+    Os,
+}
+
+/// build the fsync policy from the `fsync_strategy` configuration, an empty strategy means
+/// `every_write`
+pub fn build_fsync_policy(
+    strategy: &str,
+    interval_ms: u64,
+    bytes: u64,
+) -> Result<FsyncPolicy, CommonError> {
+    match strategy {
+        "" | "every_write" => Ok(FsyncPolicy::EveryWrite),
+        "interval" => {
+            if interval_ms == 0 {
+                return Err(CommonError::InvalidParameterFormat(
+                    "fsync_interval_ms".to_string(),
+                    interval_ms.to_string(),
+                ));
+            }
+            Ok(FsyncPolicy::IntervalMs(interval_ms))
+        }
+        "bytes" => {
+            if bytes == 0 {
+                return Err(CommonError::InvalidParameterFormat(
+                    "fsync_bytes".to_string(),
+                    bytes.to_string(),
+                ));
+            }
+            Ok(FsyncPolicy::Bytes(bytes))
+        }
+        "os" => Ok(FsyncPolicy::Os),
+        _ => Err(CommonError::InvalidParameterFormat(
+            "fsync_strategy".to_string(),
+            strategy.to_string(),
+        )),
+    }
+}
+
+/// How record batches are compressed in segment files.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionType {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+    Snappy,
+}
+
+/// build the compression type from the `compression` configuration, an empty value means `none`
+pub fn build_compression_type(compression: &str) -> Result<CompressionType, CommonError> {
+    match compression {
+        "" | "none" => Ok(CompressionType::None),
+        "lz4" => Ok(CompressionType::Lz4),
+        "zstd" => Ok(CompressionType::Zstd),
+        "snappy" => Ok(CompressionType::Snappy),
+        _ => Err(CommonError::InvalidParameterFormat(
+            "compression".to_string(),
+            compression.to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_compression_type, build_fsync_policy, CompressionType, FsyncPolicy,
+        JournalShardConfig,
+    };
+
+    #[test]
+    fn build_fsync_policy_test() {
+        assert_eq!(
+            build_fsync_policy("every_write", 0, 0).unwrap(),
+            FsyncPolicy::EveryWrite
+        );
+        assert_eq!(
+            build_fsync_policy("interval", 100, 0).unwrap(),
+            FsyncPolicy::IntervalMs(100)
+        );
+        assert_eq!(
+            build_fsync_policy("bytes", 0, 4096).unwrap(),
+            FsyncPolicy::Bytes(4096)
+        );
+        assert_eq!(build_fsync_policy("os", 0, 0).unwrap(), FsyncPolicy::Os);
+        assert_eq!(
+            build_fsync_policy("", 0, 0).unwrap(),
+            FsyncPolicy::EveryWrite
+        );
+        assert!(build_fsync_policy("interval", 0, 0).is_err());
+        assert!(build_fsync_policy("bytes", 0, 0).is_err());
+        assert!(build_fsync_policy("always", 0, 0).is_err());
+    }
+
+    #[test]
+    fn shard_config_compatible_test() {
+        let config = serde_json::from_str::<JournalShardConfig>(
+            r#"{"replica_num":1,"max_segment_size":1024}"#,
+        )
+        .unwrap();
+        assert!(config.fsync_policy.is_none());
+        assert!(config.compression.is_none());
+
+        let config = JournalShardConfig {
+            replica_num: 1,
+            max_segment_size: 1024,
+            fsync_policy: Some(FsyncPolicy::Bytes(4096)),
+            compression: Some(CompressionType::Zstd),
+        };
+        let data = serde_json::to_string(&config).unwrap();
+        let config = serde_json::from_str::<JournalShardConfig>(&data).unwrap();
+        assert_eq!(config.fsync_policy, Some(FsyncPolicy::Bytes(4096)));
+        assert_eq!(config.compression, Some(CompressionType::Zstd));
+    }
+
+    #[test]
+    fn build_compression_type_test() {
+        assert_eq!(build_compression_type("").unwrap(), CompressionType::None);
+        assert_eq!(
+            build_compression_type("none").unwrap(),
+            CompressionType::None
+        );
+        assert_eq!(build_compression_type("lz4").unwrap(), CompressionType::Lz4);
+        assert_eq!(
+            build_compression_type("zstd").unwrap(),
+            CompressionType::Zstd
+        );
+        assert_eq!(
+            build_compression_type("snappy").unwrap(),
+            CompressionType::Snappy
+        );
+        assert!(build_compression_type("gzip").is_err());
+    }
 }
@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+
 use protocol::mqtt::common::QoS;
 use serde::{Deserialize, Serialize};
 
+use crate::acl::mqtt_acl::MqttAclPermission;
+
 pub const DEFAULT_DYNAMIC_CONFIG_SLOW_SUB: &str = "slow_sub";
 pub const DEFAULT_DYNAMIC_CONFIG_FLAPPING_DETECT: &str = "flapping_detect";
 pub const DEFAULT_DYNAMIC_CONFIG_PROTOCOL: &str = "protocol";
@@ -60,6 +64,9 @@ pub fn encode(&self) -> Vec<u8> {
 pub struct MqttClusterDynamicConfigSecurity {
     pub is_self_protection_status: bool,
     pub secret_free_login: bool,
+    // the permission of an action that no acl rule matches
+    #[serde(default)]
+    pub acl_no_match: MqttAclPermission,
 }
 
 impl MqttClusterDynamicConfigSecurity {
@@ -77,6 +84,21 @@ pub struct MqttClusterDynamicConfigNetwork {
     pub websockets_max_connection_num: u64,
     pub response_max_try_mut_times: u64,
     pub response_try_mut_sleep_time_ms: u64,
+    // token bucket rates, 0 means unlimited
+    #[serde(default)]
+    pub max_connection_rate: u64,
+    #[serde(default)]
+    pub max_publish_rate: u64,
+    #[serde(default)]
+    pub max_publish_bytes_rate: u64,
+    #[serde(default)]
+    pub max_subscribe_rate: u64,
+}
+
+impl MqttClusterDynamicConfigNetwork {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
 }
 
 // MQTT cluster Feature related dynamic configuration
@@ -87,6 +109,18 @@ pub struct MqttClusterDynamicConfigFeature {
     pub subscription_identifiers_available: AvailableFlag,
     pub shared_subscription_available: AvailableFlag,
     pub exclusive_subscription_available: AvailableFlag,
+    // how the messages of a shared subscription group are spread over its members
+    #[serde(default)]
+    pub shared_subscription_strategy: SharedSubscriptionStrategy,
+    // (group_name, strategy), overrides shared_subscription_strategy for a group
+    #[serde(default)]
+    pub shared_subscription_group_strategy: HashMap<String, SharedSubscriptionStrategy>,
+}
+
+impl MqttClusterDynamicConfigFeature {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
@@ -134,6 +168,23 @@ pub enum AvailableFlag {
     Enable,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Eq, Default, Clone, Copy, Debug)]
+pub enum SharedSubscriptionStrategy {
+    #[default]
+    #[serde(rename = "round_robin")]
+    RoundRobin,
+    #[serde(rename = "random")]
+    Random,
+    #[serde(rename = "hash_clientid")]
+    HashClientId,
+    #[serde(rename = "hash_topic")]
+    HashTopic,
+    #[serde(rename = "sticky")]
+    Sticky,
+    #[serde(rename = "least_inflight")]
+    LeastInflight,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::mqtt::cluster::AvailableFlag;
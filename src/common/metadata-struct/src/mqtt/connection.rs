@@ -31,6 +31,8 @@ pub struct MQTTConnection {
     pub source_ip_addr: String,
     //
     pub login_user: String,
+    // The authentication method of the MQTT 5 enhanced authentication, which a re-authentication must use again
+    pub authentication_method: Option<String>,
     // When the client does not report a heartbeat, the maximum survival time of the connection,
     pub keep_alive: u16,
     // Records the Topic alias information for the connection dimension
@@ -0,0 +1,62 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::tools::now_second;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum InflightState {
+    // the PUBLISH was sent, waiting for the PUBACK or PUBREC
+    #[default]
+    Publish,
+    // the PUBREL was sent, waiting for the PUBCOMP
+    PubRel,
+}
+
+/// a QoS1/QoS2 message pushed to a client of a persistent session that is not yet fully
+/// acknowledged, the message itself is read again from the topic at `offset`
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct MqttInflightMessage {
+    pub client_id: String,
+    pub pkid: u16,
+    pub group_id: String,
+    pub topic_id: String,
+    pub offset: u64,
+    pub state: InflightState,
+    pub create_time: u64,
+}
+
+impl MqttInflightMessage {
+    pub fn new(
+        client_id: String,
+        pkid: u16,
+        group_id: String,
+        topic_id: String,
+        offset: u64,
+    ) -> Self {
+        MqttInflightMessage {
+            client_id,
+            pkid,
+            group_id,
+            topic_id,
+            offset,
+            state: InflightState::Publish,
+            create_time: now_second(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        serde_json::to_string(&self).unwrap()
+    }
+}
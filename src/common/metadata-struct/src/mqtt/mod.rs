@@ -16,9 +16,11 @@
 pub mod bridge;
 pub mod cluster;
 pub mod connection;
+pub mod inflight;
 pub mod lastwill;
 pub mod message;
 pub mod node_extend;
+pub mod psk;
 pub mod session;
 pub mod subscribe_data;
 pub mod topic;
@@ -0,0 +1,35 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+/// a TLS-PSK identity of the tcps listener, the identity is also the username of the client
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct MqttPsk {
+    pub identity: String,
+    // the pre-shared key, hex encoded
+    pub psk: String,
+    pub create_time: u64,
+}
+
+impl MqttPsk {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
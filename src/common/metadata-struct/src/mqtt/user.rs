@@ -12,17 +12,115 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
+
+use common_base::error::common::CommonError;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
 pub struct MqttUser {
     pub username: String,
+    // the hash of the password, or the password itself when hash_algorithm is Plain
     pub password: String,
+    #[serde(default)]
+    pub salt: String,
+    // users saved before passwords were hashed are Plain
+    #[serde(default)]
+    pub hash_algorithm: PasswordHashAlgorithm,
     pub is_superuser: bool,
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub enum PasswordHashAlgorithm {
+    #[default]
+    Plain,
+    Bcrypt,
+    Pbkdf2,
+    Sha256,
+}
+
+impl fmt::Display for PasswordHashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PasswordHashAlgorithm::Plain => "plain",
+                PasswordHashAlgorithm::Bcrypt => "bcrypt",
+                PasswordHashAlgorithm::Pbkdf2 => "pbkdf2",
+                PasswordHashAlgorithm::Sha256 => "sha256",
+            }
+        )
+    }
+}
+
 impl MqttUser {
     pub fn encode(&self) -> Vec<u8> {
         serde_json::to_vec(&self).unwrap()
     }
 }
+
+/// build the hash algorithm from the `password_hash_algorithm` configuration, an empty value
+/// means `plain`
+pub fn build_password_hash_algorithm(
+    algorithm: &str,
+) -> Result<PasswordHashAlgorithm, CommonError> {
+    match algorithm {
+        "" | "plain" => Ok(PasswordHashAlgorithm::Plain),
+        "bcrypt" => Ok(PasswordHashAlgorithm::Bcrypt),
+        "pbkdf2" => Ok(PasswordHashAlgorithm::Pbkdf2),
+        "sha256" => Ok(PasswordHashAlgorithm::Sha256),
+        _ => Err(CommonError::InvalidParameterFormat(
+            "password_hash_algorithm".to_string(),
+            algorithm.to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_password_hash_algorithm, MqttUser, PasswordHashAlgorithm};
+
+    #[test]
+    fn build_password_hash_algorithm_test() {
+        assert_eq!(
+            build_password_hash_algorithm("").unwrap(),
+            PasswordHashAlgorithm::Plain
+        );
+        assert_eq!(
+            build_password_hash_algorithm("bcrypt").unwrap(),
+            PasswordHashAlgorithm::Bcrypt
+        );
+        assert_eq!(
+            build_password_hash_algorithm("pbkdf2").unwrap(),
+            PasswordHashAlgorithm::Pbkdf2
+        );
+        assert_eq!(
+            build_password_hash_algorithm("sha256").unwrap(),
+            PasswordHashAlgorithm::Sha256
+        );
+        assert!(build_password_hash_algorithm("md5").is_err());
+
+        for algorithm in [
+            PasswordHashAlgorithm::Plain,
+            PasswordHashAlgorithm::Bcrypt,
+            PasswordHashAlgorithm::Pbkdf2,
+            PasswordHashAlgorithm::Sha256,
+        ] {
+            assert_eq!(
+                build_password_hash_algorithm(&algorithm.to_string()).unwrap(),
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn decode_plaintext_user_test() {
+        // users saved before passwords were hashed
+        let data = r#"{"username":"robustmq","password":"robustmq@2024","is_superuser":true}"#;
+        let user = serde_json::from_str::<MqttUser>(data).unwrap();
+        assert_eq!(user.password, "robustmq@2024");
+        assert!(user.salt.is_empty());
+        assert_eq!(user.hash_algorithm, PasswordHashAlgorithm::Plain);
+    }
+}
@@ -14,3 +14,4 @@
 
 pub mod admin;
 pub mod inner;
+pub mod replica;
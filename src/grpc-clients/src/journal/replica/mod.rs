@@ -0,0 +1,69 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use mobc::Manager;
+use protocol::journal_server::journal_replica::journal_server_replica_service_client::JournalServerReplicaServiceClient;
+use protocol::journal_server::journal_replica::{ReplicaFetchReply, ReplicaFetchRequest};
+use tonic::transport::Channel;
+
+use crate::macros::impl_retriable_request;
+
+pub mod call;
+
+#[derive(Clone)]
+pub struct JournalReplicaServiceManager {
+    pub addr: String,
+}
+
+impl JournalReplicaServiceManager {
+    pub fn new(addr: String) -> Self {
+        Self { addr }
+    }
+}
+
+#[tonic::async_trait]
+impl Manager for JournalReplicaServiceManager {
+    type Connection = JournalServerReplicaServiceClient<Channel>;
+    type Error = CommonError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        match JournalServerReplicaServiceClient::connect(format!("http://{}", self.addr.clone()))
+            .await
+        {
+            Ok(client) => {
+                return Ok(client);
+            }
+            Err(err) => {
+                return Err(CommonError::CommonError(format!(
+                    "{},{}",
+                    err,
+                    self.addr.clone()
+                )))
+            }
+        };
+    }
+
+    async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
+        Ok(conn)
+    }
+}
+
+impl_retriable_request!(
+    ReplicaFetchRequest,
+    JournalServerReplicaServiceClient<Channel>,
+    ReplicaFetchReply,
+    journal_replica_services_client,
+    replica_fetch
+);
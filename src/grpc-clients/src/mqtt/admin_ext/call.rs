@@ -0,0 +1,125 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttCheckAclReply, MqttCheckAclRequest, MqttCleanSessionReply, MqttCleanSessionRequest,
+    MqttCreatePskReply, MqttCreatePskRequest, MqttDeletePskReply, MqttDeletePskRequest,
+    MqttDeleteRetainMessageReply, MqttDeleteRetainMessageRequest, MqttGetClientDetailReply,
+    MqttGetClientDetailRequest, MqttGetRetainMessageReply, MqttGetRetainMessageRequest,
+    MqttKickClientReply, MqttKickClientRequest, MqttListPskReply, MqttListPskRequest,
+    MqttListRetainMessageReply, MqttListRetainMessageRequest, MqttListSessionReply,
+    MqttListSessionRequest, MqttRetainMessageStatsReply, MqttRetainMessageStatsRequest,
+};
+
+use crate::pool::ClientPool;
+
+macro_rules! generate_mqtt_admin_ext_service_call {
+    ($fn_name:ident, $req_ty:ty, $rep_ty:ty, $variant:ident) => {
+        pub async fn $fn_name(
+            client_pool: &ClientPool,
+            addrs: &[impl AsRef<str>],
+            request: $req_ty,
+        ) -> Result<$rep_ty, CommonError> {
+            $crate::utils::retry_call(client_pool, addrs, request).await
+        }
+    };
+}
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_check_acl,
+    MqttCheckAclRequest,
+    MqttCheckAclReply,
+    MqttCheckAcl
+);
+
+// psk
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_list_psk,
+    MqttListPskRequest,
+    MqttListPskReply,
+    MqttListPsk
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_create_psk,
+    MqttCreatePskRequest,
+    MqttCreatePskReply,
+    MqttCreatePsk
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_delete_psk,
+    MqttDeletePskRequest,
+    MqttDeletePskReply,
+    MqttDeletePsk
+);
+
+// session
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_list_session,
+    MqttListSessionRequest,
+    MqttListSessionReply,
+    MqttListSession
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_get_client_detail,
+    MqttGetClientDetailRequest,
+    MqttGetClientDetailReply,
+    MqttGetClientDetail
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_kick_client,
+    MqttKickClientRequest,
+    MqttKickClientReply,
+    MqttKickClient
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_clean_session,
+    MqttCleanSessionRequest,
+    MqttCleanSessionReply,
+    MqttCleanSession
+);
+
+// retain
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_list_retain_message,
+    MqttListRetainMessageRequest,
+    MqttListRetainMessageReply,
+    MqttListRetainMessage
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_get_retain_message,
+    MqttGetRetainMessageRequest,
+    MqttGetRetainMessageReply,
+    MqttGetRetainMessage
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_delete_retain_message,
+    MqttDeleteRetainMessageRequest,
+    MqttDeleteRetainMessageReply,
+    MqttDeleteRetainMessage
+);
+
+generate_mqtt_admin_ext_service_call!(
+    mqtt_broker_retain_message_stats,
+    MqttRetainMessageStatsRequest,
+    MqttRetainMessageStatsReply,
+    MqttRetainMessageStats
+);
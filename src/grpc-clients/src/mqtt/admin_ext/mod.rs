@@ -0,0 +1,165 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use mobc::Manager;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::mqtt_broker_admin_ext_service_client::MqttBrokerAdminExtServiceClient;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttCheckAclReply, MqttCheckAclRequest, MqttCleanSessionReply, MqttCleanSessionRequest,
+    MqttCreatePskReply, MqttCreatePskRequest, MqttDeletePskReply, MqttDeletePskRequest,
+    MqttDeleteRetainMessageReply, MqttDeleteRetainMessageRequest, MqttGetClientDetailReply,
+    MqttGetClientDetailRequest, MqttGetRetainMessageReply, MqttGetRetainMessageRequest,
+    MqttKickClientReply, MqttKickClientRequest, MqttListPskReply, MqttListPskRequest,
+    MqttListRetainMessageReply, MqttListRetainMessageRequest, MqttListSessionReply,
+    MqttListSessionRequest, MqttRetainMessageStatsReply, MqttRetainMessageStatsRequest,
+};
+use tonic::transport::Channel;
+
+use crate::macros::impl_retriable_request;
+
+pub mod call;
+
+#[derive(Clone)]
+pub struct MqttBrokerAdminExtServiceManager {
+    pub addr: String,
+}
+
+impl MqttBrokerAdminExtServiceManager {
+    pub fn new(addr: String) -> Self {
+        Self { addr }
+    }
+}
+
+#[tonic::async_trait]
+impl Manager for MqttBrokerAdminExtServiceManager {
+    type Connection = MqttBrokerAdminExtServiceClient<Channel>;
+    type Error = CommonError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        match MqttBrokerAdminExtServiceClient::connect(format!("http://{}", self.addr.clone()))
+            .await
+        {
+            Ok(client) => {
+                return Ok(client);
+            }
+            Err(err) => {
+                return Err(CommonError::CommonError(format!(
+                    "{},{}",
+                    err,
+                    self.addr.clone()
+                )))
+            }
+        };
+    }
+
+    async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
+        Ok(conn)
+    }
+}
+
+impl_retriable_request!(
+    MqttCheckAclRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttCheckAclReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_check_acl
+);
+
+impl_retriable_request!(
+    MqttListPskRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttListPskReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_list_psk
+);
+
+impl_retriable_request!(
+    MqttCreatePskRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttCreatePskReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_create_psk
+);
+
+impl_retriable_request!(
+    MqttDeletePskRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttDeletePskReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_delete_psk
+);
+
+impl_retriable_request!(
+    MqttListSessionRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttListSessionReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_list_session
+);
+
+impl_retriable_request!(
+    MqttGetClientDetailRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttGetClientDetailReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_get_client_detail
+);
+
+impl_retriable_request!(
+    MqttKickClientRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttKickClientReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_kick_client
+);
+
+impl_retriable_request!(
+    MqttCleanSessionRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttCleanSessionReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_clean_session
+);
+
+impl_retriable_request!(
+    MqttListRetainMessageRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttListRetainMessageReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_list_retain_message
+);
+
+impl_retriable_request!(
+    MqttGetRetainMessageRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttGetRetainMessageReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_get_retain_message
+);
+
+impl_retriable_request!(
+    MqttDeleteRetainMessageRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttDeleteRetainMessageReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_delete_retain_message
+);
+
+impl_retriable_request!(
+    MqttRetainMessageStatsRequest,
+    MqttBrokerAdminExtServiceClient<Channel>,
+    MqttRetainMessageStatsReply,
+    mqtt_broker_admin_ext_services_client,
+    mqtt_retain_message_stats
+);
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 pub mod admin;
+pub mod admin_ext;
 pub mod inner;
 
 #[cfg(test)]
@@ -20,7 +20,9 @@
 
 use crate::journal::admin::JournalAdminServiceManager;
 use crate::journal::inner::JournalInnerServiceManager;
+use crate::journal::replica::JournalReplicaServiceManager;
 use crate::mqtt::admin::MqttBrokerAdminServiceManager;
+use crate::mqtt::admin_ext::MqttBrokerAdminExtServiceManager;
 use crate::mqtt::inner::MqttBrokerPlacementServiceManager;
 use crate::placement::inner::PlacementServiceManager;
 use crate::placement::journal::JournalServiceManager;
@@ -43,10 +45,12 @@ pub struct ClientPool {
     // modules: mqtt broker
     mqtt_broker_placement_service_pools: DashMap<String, Pool<MqttBrokerPlacementServiceManager>>,
     mqtt_broker_admin_service_pools: DashMap<String, Pool<MqttBrokerAdminServiceManager>>,
+    mqtt_broker_admin_ext_service_pools: DashMap<String, Pool<MqttBrokerAdminExtServiceManager>>,
 
     // modules: journal engine
     journal_admin_service_pools: DashMap<String, Pool<JournalAdminServiceManager>>,
     journal_inner_service_pools: DashMap<String, Pool<JournalInnerServiceManager>>,
+    journal_replica_service_pools: DashMap<String, Pool<JournalReplicaServiceManager>>,
 }
 
 impl ClientPool {
@@ -63,9 +67,11 @@ pub fn new(max_open_connection: u64) -> Self {
             // modules: mqtt_broker
             mqtt_broker_placement_service_pools: DashMap::with_capacity(2),
             mqtt_broker_admin_service_pools: DashMap::with_capacity(2),
+            mqtt_broker_admin_ext_service_pools: DashMap::with_capacity(2),
             // modules: journal_engine
             journal_admin_service_pools: DashMap::with_capacity(2),
             journal_inner_service_pools: DashMap::with_capacity(2),
+            journal_replica_service_pools: DashMap::with_capacity(2),
         }
     }
 
@@ -298,6 +304,38 @@ pub async fn mqtt_broker_admin_services_client(
         ))
     }
 
+    pub async fn mqtt_broker_admin_ext_services_client(
+        &self,
+        addr: &str,
+    ) -> Result<Connection<MqttBrokerAdminExtServiceManager>, CommonError> {
+        if !self.mqtt_broker_admin_ext_service_pools.contains_key(addr) {
+            let manager = MqttBrokerAdminExtServiceManager::new(addr.to_owned());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.mqtt_broker_admin_ext_service_pools
+                .insert(addr.to_owned(), pool);
+        }
+
+        if let Some(pool) = self.mqtt_broker_admin_ext_service_pools.get(addr) {
+            match pool.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(CommonError::NoAvailableGrpcConnection(
+                        "BrokerAdminExtServices".to_string(),
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+        Err(CommonError::NoAvailableGrpcConnection(
+            "BrokerAdminExtServices".to_string(),
+            "connection pool is not initialized".to_string(),
+        ))
+    }
+
     // ----------modules: journal engine -------------
     pub async fn journal_inner_services_client(
         &self,
@@ -332,6 +370,39 @@ pub async fn journal_inner_services_client(
         ))
     }
 
+    pub async fn journal_replica_services_client(
+        &self,
+        addr: &str,
+    ) -> Result<Connection<JournalReplicaServiceManager>, CommonError> {
+        if !self.journal_replica_service_pools.contains_key(addr) {
+            let manager = JournalReplicaServiceManager::new(addr.to_owned());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.journal_replica_service_pools
+                .insert(addr.to_owned(), pool);
+        }
+
+        if let Some(pool) = self.journal_replica_service_pools.get(addr) {
+            match pool.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(CommonError::NoAvailableGrpcConnection(
+                        "JournalEngine".to_string(),
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        Err(CommonError::NoAvailableGrpcConnection(
+            "JournalEngine".to_string(),
+            "connection pool is not initialized".to_string(),
+        ))
+    }
+
     pub async fn journal_admin_services_client(
         &self,
         addr: &str,
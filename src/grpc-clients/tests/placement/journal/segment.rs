@@ -55,6 +55,8 @@ async fn segment_test() {
         let config = JournalShardConfig {
             replica_num: 1,
             max_segment_size: 10 * 1024 * 1024,
+            fsync_policy: None,
+            compression: None,
         };
         //  create shard
         let request = CreateShardRequest {
@@ -71,6 +71,8 @@ async fn shard_segment_metadata_test() {
         let config = JournalShardConfig {
             replica_num: 1,
             max_segment_size: 10 * 1024 * 1024,
+            fsync_policy: None,
+            compression: None,
         };
 
         // create shard
@@ -283,6 +285,8 @@ pub async fn sealup_segment_test() {
         let config = JournalShardConfig {
             replica_num: 1,
             max_segment_size: 10 * 1024 * 1024,
+            fsync_policy: None,
+            compression: None,
         };
         // create shard
         let request = CreateShardRequest {
@@ -424,6 +428,8 @@ pub async fn delete_segment_test() {
         let config = JournalShardConfig {
             replica_num: 1,
             max_segment_size: 10 * 1024 * 1024,
+            fsync_policy: None,
+            compression: None,
         };
         // create shard
         let request = CreateShardRequest {
@@ -533,6 +539,8 @@ pub async fn delete_shard_test() {
         let config = JournalShardConfig {
             replica_num: 1,
             max_segment_size: 10 * 1024 * 1024,
+            fsync_policy: None,
+            compression: None,
         };
         // create shard
         let request = CreateShardRequest {
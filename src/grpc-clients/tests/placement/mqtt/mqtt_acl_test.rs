@@ -41,6 +41,7 @@ async fn mqtt_acl_test() {
             ip: "*".to_string(),
             action: MqttAclAction::All,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
 
         let request = CreateAclRequest {
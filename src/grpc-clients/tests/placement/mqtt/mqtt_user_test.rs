@@ -40,6 +40,7 @@ async fn mqtt_user_test() {
             username: user_name.clone(),
             password: password.clone(),
             is_superuser: false,
+            ..Default::default()
         };
 
         let request: CreateUserRequest = CreateUserRequest {
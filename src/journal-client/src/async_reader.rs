@@ -19,12 +19,16 @@
 use dashmap::DashMap;
 use log::error;
 use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::journal::compression::decode_batch;
 use metadata_struct::journal::segment::segment_name;
 use metadata_struct::journal::shard::shard_name_iden;
+use prost::Message;
 use protocol::journal_server::journal_engine::{
     FetchOffsetReqBody, FetchOffsetShard, ReadReqBody, ReadReqFilter, ReadReqMessage,
-    ReadReqOptions, ReadType,
+    ReadReqOptions, ReadRespBody, ReadType,
 };
+use protocol::journal_server::journal_engine_ext::ReadRespBodyExt;
+use protocol::journal_server::journal_record::JournalRecord;
 use tokio::select;
 use tokio::sync::broadcast::{self, Receiver, Sender};
 use tokio::time::sleep;
@@ -182,22 +186,8 @@ pub async fn async_read_data_by_offset(
         }
 
         let body = ReadReqBody { messages };
-        let result = batch_read(connection_manager, leader_id, body).await?;
-        for shard_data in result.messages {
-            for message in shard_data.messages {
-                let val = ReadMessageData {
-                    namespace: shard_data.namespace.clone(),
-                    shard_name: shard_data.shard_name.clone(),
-                    segment: shard_data.segment,
-                    offset: message.offset,
-                    key: message.key,
-                    value: message.value,
-                    tags: message.tags,
-                    timestamp: message.timestamp,
-                };
-                results.push(val);
-            }
-        }
+        let (result, ext) = batch_read(connection_manager, leader_id, body).await?;
+        results.extend(build_read_messages(result, ext)?);
     }
     Ok(results)
 }
@@ -232,22 +222,8 @@ pub async fn async_read_data_by_key(
         }
 
         let body = ReadReqBody { messages };
-        let result = batch_read(connection_manager, leader_id, body).await?;
-        for shard_data in result.messages {
-            for message in shard_data.messages {
-                let val = ReadMessageData {
-                    namespace: shard_data.namespace.clone(),
-                    shard_name: shard_data.shard_name.clone(),
-                    segment: shard_data.segment,
-                    offset: message.offset,
-                    key: message.key,
-                    value: message.value,
-                    tags: message.tags,
-                    timestamp: message.timestamp,
-                };
-                results.push(val);
-            }
-        }
+        let (result, ext) = batch_read(connection_manager, leader_id, body).await?;
+        results.extend(build_read_messages(result, ext)?);
     }
     Ok(results)
 }
@@ -282,23 +258,76 @@ pub async fn async_read_data_by_tag(
         }
 
         let body = ReadReqBody { messages };
-        let result = batch_read(connection_manager, leader_id, body).await?;
-        for shard_data in result.messages {
-            for message in shard_data.messages {
-                let val = ReadMessageData {
-                    namespace: shard_data.namespace.clone(),
-                    shard_name: shard_data.shard_name.clone(),
-                    segment: shard_data.segment,
-                    offset: message.offset,
-                    key: message.key,
-                    value: message.value,
-                    tags: message.tags,
-                    timestamp: message.timestamp,
-                };
-                results.push(val);
+        let (result, ext) = batch_read(connection_manager, leader_id, body).await?;
+        results.extend(build_read_messages(result, ext)?);
+    }
+    Ok(results)
+}
+
+/// convert a read response into messages, decompressing the batches that the journal server
+/// sent as they are stored in the segment file
+///
+/// The records of each segment are returned in the order of their offsets.
+fn build_read_messages(
+    body: ReadRespBody,
+    ext: ReadRespBodyExt,
+) -> Result<Vec<ReadMessageData>, JournalClientError> {
+    let mut results = Vec::new();
+    for shard_data in body.messages {
+        for message in shard_data.messages {
+            let val = ReadMessageData {
+                namespace: shard_data.namespace.clone(),
+                shard_name: shard_data.shard_name.clone(),
+                segment: shard_data.segment,
+                offset: message.offset,
+                key: message.key,
+                value: message.value,
+                tags: message.tags,
+                timestamp: message.timestamp,
+            };
+            results.push(val);
+        }
+    }
+
+    for batch in ext.compressed_batches {
+        let segment = segment_name(&batch.namespace, &batch.shard_name, batch.segment);
+        let codec = u8::try_from(batch.codec).unwrap_or(u8::MAX);
+        let records = decode_batch(codec, batch.record_num, &batch.payload).map_err(|e| {
+            JournalClientError::InvalidCompressedBatch(segment.clone(), batch.offset, e)
+        })?;
+
+        for (offset, data) in records {
+            let keep = if batch.offsets.is_empty() {
+                offset >= batch.min_offset
+            } else {
+                batch.offsets.contains(&offset)
+            };
+            if !keep {
+                continue;
             }
+
+            let record = JournalRecord::decode(data)?;
+            results.push(ReadMessageData {
+                namespace: batch.namespace.clone(),
+                shard_name: batch.shard_name.clone(),
+                segment: batch.segment,
+                offset,
+                key: record.key,
+                value: record.content,
+                tags: record.tags,
+                timestamp: record.create_time,
+            });
         }
     }
+
+    results.sort_by(|a, b| {
+        (&a.namespace, &a.shard_name, a.segment, a.offset).cmp(&(
+            &b.namespace,
+            &b.shard_name,
+            b.segment,
+            b.offset,
+        ))
+    });
     Ok(results)
 }
 
@@ -23,6 +23,7 @@
     CreateShardReqBody, DeleteShardReqBody, GetClusterMetadataNode, GetShardMetadataRespShard,
     ListShardReqBody,
 };
+use protocol::journal_server::journal_engine_ext::CreateShardReqBodyExt;
 use tokio::sync::broadcast::{self, Sender};
 
 use super::cache::{load_node_cache, MetadataCache};
@@ -34,6 +35,7 @@
 };
 use crate::async_writer::{AsyncWriter, SenderMessage, SenderMessageResp};
 use crate::cache::get_active_segment;
+use crate::option::JournalShardOption;
 use crate::service::{create_shard, delete_shard, list_shard};
 
 #[derive(Default, Clone)]
@@ -88,13 +90,36 @@ pub async fn create_shard(
         namespace: &str,
         shard_name: &str,
         replica_num: u32,
+    ) -> Result<(), JournalClientError> {
+        self.create_shard_with_option(
+            namespace,
+            shard_name,
+            replica_num,
+            JournalShardOption::default(),
+        )
+        .await
+    }
+
+    pub async fn create_shard_with_option(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        replica_num: u32,
+        option: JournalShardOption,
     ) -> Result<(), JournalClientError> {
         let body = CreateShardReqBody {
             namespace: namespace.to_string(),
             shard_name: shard_name.to_string(),
             replica_num,
         };
-        let _ = create_shard(&self.connection_manager, body).await?;
+        let mut ext = CreateShardReqBodyExt::default();
+        if let Some(fsync_policy) = option.fsync_policy {
+            ext.fsync_policy = serde_json::to_vec(&fsync_policy)?;
+        }
+        if let Some(compression) = option.compression {
+            ext.compression = serde_json::to_vec(&compression)?;
+        }
+        let _ = create_shard(&self.connection_manager, body, ext).await?;
         Ok(())
     }
 
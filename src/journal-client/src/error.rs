@@ -86,4 +86,10 @@ pub enum JournalClientError {
 
     #[error("{0}")]
     SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    DecodeError(#[from] prost::DecodeError),
+
+    #[error("Invalid compressed batch at offset {1} of Segment {0}, error message: {2}")]
+    InvalidCompressedBatch(String, u64, String),
 }
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use common_base::error::common::CommonError;
+use metadata_struct::journal::shard::{CompressionType, FsyncPolicy};
 
 #[derive(Default, Clone)]
 pub struct JournalClientOption {
@@ -33,6 +34,13 @@ pub fn set_addrs(&mut self, addrs: Vec<String>) {
     }
 }
 
+/// Settings of a new shard, the ones that are not set use the journal server configuration.
+#[derive(Default, Clone, Debug)]
+pub struct JournalShardOption {
+    pub fsync_policy: Option<FsyncPolicy>,
+    pub compression: Option<CompressionType>,
+}
+
 pub fn options_validator(option: &JournalClientOption) -> Result<(), CommonError> {
     if option.addrs.is_empty() {
         return Err(CommonError::ParameterCannotBeNull(
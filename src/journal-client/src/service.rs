@@ -23,6 +23,9 @@
     ListShardReqBody, ListShardRespBody, ReadReq, ReadReqBody, ReadRespBody, ReqHeader, WriteReq,
     WriteReqBody, WriteRespBody,
 };
+use protocol::journal_server::journal_engine_ext::{
+    CreateShardReqBodyExt, ReadReqBodyExt, ReadRespBodyExt,
+};
 
 use crate::connection::ConnectionManager;
 use crate::error::JournalClientError;
@@ -91,14 +94,18 @@ pub(crate) async fn get_shard_metadata(
 pub(crate) async fn create_shard(
     connection_manager: &Arc<ConnectionManager>,
     shard: CreateShardReqBody,
+    ext: CreateShardReqBodyExt,
 ) -> Result<CreateShardRespBody, JournalClientError> {
-    let req_packet = JournalEnginePacket::CreateShardReq(CreateShardReq {
-        header: Some(ReqHeader {
-            api_key: ApiKey::CreateShard.into(),
-            api_version: ApiVersion::V0.into(),
-        }),
-        body: Some(shard),
-    });
+    let req_packet = JournalEnginePacket::CreateShardReq(
+        CreateShardReq {
+            header: Some(ReqHeader {
+                api_key: ApiKey::CreateShard.into(),
+                api_version: ApiVersion::V0.into(),
+            }),
+            body: Some(shard),
+        },
+        ext,
+    );
 
     let resp_packet = connection_manager.admin_send(req_packet.clone()).await?;
 
@@ -211,27 +218,34 @@ pub(crate) async fn batch_write(
     ))
 }
 
+/// read from a journal server, compressed batches are returned as they are stored and have to
+/// be decompressed by the caller
 pub(crate) async fn batch_read(
     connection_manager: &Arc<ConnectionManager>,
     node_id: u64,
     body: ReadReqBody,
-) -> Result<ReadRespBody, JournalClientError> {
-    let req_packet = JournalEnginePacket::ReadReq(ReadReq {
-        header: Some(ReqHeader {
-            api_key: ApiKey::Read.into(),
-            api_version: ApiVersion::V0.into(),
-        }),
-        body: Some(body),
-    });
+) -> Result<(ReadRespBody, ReadRespBodyExt), JournalClientError> {
+    let req_packet = JournalEnginePacket::ReadReq(
+        ReadReq {
+            header: Some(ReqHeader {
+                api_key: ApiKey::Read.into(),
+                api_version: ApiVersion::V0.into(),
+            }),
+            body: Some(body),
+        },
+        ReadReqBodyExt {
+            compressed_batches: true,
+        },
+    );
 
     let resp_packet = connection_manager
         .read_send(node_id, req_packet.clone())
         .await?;
 
-    if let JournalEnginePacket::ReadResp(data) = resp_packet {
+    if let JournalEnginePacket::ReadResp(data, ext) = resp_packet {
         resp_header_error(&data.header, req_packet.clone())?;
         if let Some(body) = data.body {
-            return Ok(body);
+            return Ok((body, ext));
         }
         return Err(JournalClientError::ReceivedPacketNotContainBody(
             req_packet.to_string(),
@@ -32,6 +32,8 @@
 
 use super::cluster_config::JournalEngineClusterConfig;
 use crate::index::build::IndexBuildThreadData;
+use crate::isr::fetch::ReplicaFetchThreadData;
+use crate::isr::replica::SegmentReplicaState;
 use crate::segment::write::SegmentWrite;
 use crate::segment::SegmentIdentity;
 
@@ -60,6 +62,12 @@ pub struct CacheManager {
 
     // (segment_name, SegmentWrite)
     segment_writes: DashMap<String, SegmentWrite>,
+
+    // (segment_name, SegmentReplicaState)
+    segment_replica_states: DashMap<String, Arc<SegmentReplicaState>>,
+
+    // (segment_name, ReplicaFetchThreadData)
+    segment_replica_fetch_thread: DashMap<String, ReplicaFetchThreadData>,
 }
 
 impl CacheManager {
@@ -72,6 +80,8 @@ pub fn new() -> Self {
         let leader_segments = DashMap::with_capacity(8);
         let segment_index_build_thread = DashMap::with_capacity(2);
         let segment_write = DashMap::with_capacity(2);
+        let segment_replica_states = DashMap::with_capacity(2);
+        let segment_replica_fetch_thread = DashMap::with_capacity(2);
         CacheManager {
             cluster,
             node_list,
@@ -81,6 +91,8 @@ pub fn new() -> Self {
             leader_segments,
             segment_index_build_thread,
             segment_writes: segment_write,
+            segment_replica_states,
+            segment_replica_fetch_thread,
         }
     }
 
@@ -234,6 +246,10 @@ pub fn delete_segment(&self, segment: &SegmentIdentity) {
                 error!("Trying to stop the segment write thread for segment {} failed with error message:{}", segment.name(),e);
             }
         }
+
+        // delete replication state and replica fetch thread by segment
+        self.segment_replica_states.remove(&segment.name());
+        self.remove_replica_fetch_thread(segment);
     }
 
     pub fn get_segment(&self, segment: &SegmentIdentity) -> Option<JournalSegment> {
@@ -271,6 +287,17 @@ pub fn update_segment_status(&self, segment_iden: &SegmentIdentity, status: Segm
         }
     }
 
+    pub fn update_segment_isr(&self, segment_iden: &SegmentIdentity, isr: Vec<u64>) {
+        if let Some(sgement_list) = self.segments.get(&shard_name_iden(
+            &segment_iden.namespace,
+            &segment_iden.shard_name,
+        )) {
+            if let Some(mut segment) = sgement_list.get_mut(&segment_iden.segment_seq) {
+                segment.isr = isr;
+            }
+        }
+    }
+
     // Segment Meta
     pub fn set_segment_meta(&self, segment: JournalSegmentMetadata) {
         let key = shard_name_iden(&segment.namespace, &segment.shard_name);
@@ -348,6 +375,55 @@ pub fn get_segment_write_thread(&self, segment_iden: &SegmentIdentity) -> Option
         None
     }
 
+    // Segment Replica State
+    pub fn get_segment_replica_state(&self, segment: &JournalSegment) -> Arc<SegmentReplicaState> {
+        let segment_iden = SegmentIdentity::from_journal_segment(segment);
+        if let Some(state) = self.segment_replica_states.get(&segment_iden.name()) {
+            // the state is rebuilt when the leader of the segment changes
+            if state.leader() == segment.leader {
+                return state.clone();
+            }
+        }
+
+        let state = Arc::new(SegmentReplicaState::new(segment));
+        self.segment_replica_states
+            .insert(segment_iden.name(), state.clone());
+        state
+    }
+
+    // Replica Fetch Thread
+    pub fn add_replica_fetch_thread(
+        &self,
+        segment_iden: &SegmentIdentity,
+        fetch_thread_data: ReplicaFetchThreadData,
+    ) {
+        self.segment_replica_fetch_thread
+            .insert(segment_iden.name(), fetch_thread_data);
+    }
+
+    pub fn remove_replica_fetch_thread(&self, segment_iden: &SegmentIdentity) {
+        if let Some((_, data)) = self
+            .segment_replica_fetch_thread
+            .remove(&segment_iden.name())
+        {
+            // the thread may have already exited by itself
+            let _ = data.stop_send.send(true);
+        }
+    }
+
+    pub fn contain_replica_fetch_thread(&self, segment_iden: &SegmentIdentity) -> bool {
+        self.segment_replica_fetch_thread
+            .contains_key(&segment_iden.name())
+    }
+
+    pub fn stop_all_replica_fetch_thread(&self) {
+        for raw in self.segment_replica_fetch_thread.iter() {
+            if let Err(e) = raw.value().stop_send.send(true) {
+                error!("Trying to stop the replica fetch thread for segment {} failed with error message:{}", raw.key(),e);
+            }
+        }
+    }
+
     // Leader Segment
     pub fn get_leader_segment(&self) -> Vec<SegmentIdentity> {
         let mut results = Vec::new();
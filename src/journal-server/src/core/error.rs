@@ -16,6 +16,7 @@
 use std::string::FromUtf8Error;
 
 use common_base::error::common::CommonError;
+use metadata_struct::journal::shard::CompressionType;
 use thiserror::Error;
 
 use crate::segment::write::SegmentWriteData;
@@ -52,6 +53,9 @@ pub enum JournalServerError {
     #[error("{0}")]
     ParseIntError(#[from] ParseIntError),
 
+    #[error("{0}")]
+    JournalCodecError(#[from] protocol::journal_server::Error),
+
     #[error("{0} request body cannot be empty")]
     RequestBodyNotEmpty(String),
 
@@ -102,6 +106,30 @@ pub enum JournalServerError {
 
     #[error("Segment Offset is at the end and can no longer be written.")]
     SegmentOffsetAtTheEnd,
+
+    #[error("Segment {0} has {1} in-sync replicas, which is less than the required {2}")]
+    NotEnoughInSyncReplicas(String, usize, u32),
+
+    #[error("Timed out waiting for the in-sync replicas of Segment {0} to persist offset {1}")]
+    ReplicaAckTimeout(String, u64),
+
+    #[error("No address is available for node {0}")]
+    NodeNoAvailableAddr(u64),
+
+    #[error("Failed to fetch data of Segment {0} from the leader, error message: {1}")]
+    ReplicaFetchFailed(String, String),
+
+    #[error("Node {0} is not allowed to fetch Segment {1} because {2}")]
+    ReplicaFetchNotAllowed(u64, String, String),
+
+    #[error("Failed to write data to Segment {0}, error message: {1}")]
+    SegmentWriteFailed(String, String),
+
+    #[error("Record at position {1} of segment file {0} is corrupted: {2}")]
+    SegmentRecordCorrupted(String, u64, String),
+
+    #[error("Failed to compress or decompress data with {0:?}, error message: {1}")]
+    CompressionFailed(CompressionType, String),
 }
 
 pub fn get_journal_server_code(e: &JournalServerError) -> String {
@@ -119,6 +147,7 @@ pub fn get_journal_server_code(e: &JournalServerError) -> String {
         JournalServerError::ProstDecodeError(_) => "ProstDecodeError".to_string(),
         JournalServerError::SerdeJsonError(_) => "SerdeJsonError".to_string(),
         JournalServerError::ParseIntError(_) => "ParseIntError".to_string(),
+        JournalServerError::JournalCodecError(_) => "JournalCodecError".to_string(),
         JournalServerError::RequestBodyNotEmpty(_) => "RequestBodyNotEmpty".to_string(),
         JournalServerError::ShardNotExist(_) => "ShardNotExist".to_string(),
         JournalServerError::NotAvailableSegments(_) => "NotAvailableSegments".to_string(),
@@ -143,6 +172,16 @@ pub fn get_journal_server_code(e: &JournalServerError) -> String {
             "NotAvailableOffsetByTimestamp".to_string()
         }
         JournalServerError::SegmentOffsetAtTheEnd => "SegmentOffsetAtTheEnd".to_string(),
+        JournalServerError::NotEnoughInSyncReplicas(_, _, _) => {
+            "NotEnoughInSyncReplicas".to_string()
+        }
+        JournalServerError::ReplicaAckTimeout(_, _) => "ReplicaAckTimeout".to_string(),
+        JournalServerError::NodeNoAvailableAddr(_) => "NodeNoAvailableAddr".to_string(),
+        JournalServerError::ReplicaFetchFailed(_, _) => "ReplicaFetchFailed".to_string(),
+        JournalServerError::ReplicaFetchNotAllowed(_, _, _) => "ReplicaFetchNotAllowed".to_string(),
+        JournalServerError::SegmentWriteFailed(_, _) => "SegmentWriteFailed".to_string(),
+        JournalServerError::SegmentRecordCorrupted(_, _, _) => "SegmentRecordCorrupted".to_string(),
+        JournalServerError::CompressionFailed(_, _) => "CompressionFailed".to_string(),
     }
 }
 #[cfg(test)]
@@ -0,0 +1,199 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::metrics::registry::exponential_buckets;
+use prometheus_client::encoding::EncodeLabelSet;
+
+use crate::segment::SegmentIdentity;
+
+pub const SCROLL_EVENT_CREATE_NEXT_SEGMENT: &str = "create_next_segment";
+pub const SCROLL_EVENT_PRE_SEALUP: &str = "pre_sealup";
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ShardLabel {
+    namespace: String,
+    shard_name: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ScrollLabel {
+    namespace: String,
+    shard_name: String,
+    event: String,
+}
+
+impl ShardLabel {
+    fn new(namespace: &str, shard_name: &str) -> Self {
+        ShardLabel {
+            namespace: namespace.to_string(),
+            shard_name: shard_name.to_string(),
+        }
+    }
+}
+
+common_base::register_counter_metric!(
+    WRITE_RECORDS,
+    "journal_write_records",
+    "Number of records written to the segment files",
+    ShardLabel
+);
+common_base::register_counter_metric!(
+    WRITE_BYTES,
+    "journal_write_bytes",
+    "Number of bytes written to the segment files",
+    ShardLabel
+);
+common_base::register_histogram_metric!(
+    WRITE_BATCH_RECORDS,
+    "journal_write_batch_records",
+    "Number of records written to a segment file by one group commit",
+    ShardLabel,
+    exponential_buckets(1.0, 2.0, 12)
+);
+common_base::register_counter_metric!(
+    READ_RECORDS,
+    "journal_read_records",
+    "Number of records read from the segment files",
+    ShardLabel
+);
+common_base::register_counter_metric!(
+    READ_BYTES,
+    "journal_read_bytes",
+    "Number of bytes of the records read from the segment files",
+    ShardLabel
+);
+common_base::register_histogram_metric!(
+    READ_BATCH_RECORDS,
+    "journal_read_batch_records",
+    "Number of records returned by one read of a segment",
+    ShardLabel,
+    exponential_buckets(1.0, 2.0, 12)
+);
+common_base::register_counter_metric!(
+    INDEX_BUILD_RECORDS,
+    "journal_index_build_records",
+    "Number of records indexed by the index build threads",
+    ShardLabel
+);
+common_base::register_gauge_metric!(
+    INDEX_BUILD_LAG,
+    "journal_index_build_lag",
+    "Number of records written to the active segment but not indexed yet",
+    ShardLabel
+);
+common_base::register_counter_metric!(
+    SEGMENT_SCROLL_EVENTS,
+    "journal_segment_scroll_events",
+    "Number of times a segment triggered the creation of the next segment or was pre-sealed",
+    ScrollLabel
+);
+common_base::register_gauge_metric!(
+    SEGMENT_NUM,
+    "journal_segment_num",
+    "Number of segment files of the shard on this node",
+    ShardLabel
+);
+common_base::register_gauge_metric!(
+    SHARD_DISK_USAGE,
+    "journal_shard_disk_usage_bytes",
+    "Size of the segment files of the shard on this node",
+    ShardLabel
+);
+
+/// a group commit of `records` records, `bytes` bytes on disk, to the segment
+pub fn record_write_metrics(segment_iden: &SegmentIdentity, records: u64, bytes: u64) {
+    let labels = ShardLabel::new(&segment_iden.namespace, &segment_iden.shard_name);
+    common_base::counter_metric_inc_by!(WRITE_RECORDS, labels, records);
+    common_base::counter_metric_inc_by!(WRITE_BYTES, labels, bytes);
+    common_base::histogram_metric_observe!(WRITE_BATCH_RECORDS, labels, records as f64);
+}
+
+/// a read of `records` records, whose values are `bytes` bytes, from the segment
+pub fn record_read_metrics(segment_iden: &SegmentIdentity, records: u64, bytes: u64) {
+    let labels = ShardLabel::new(&segment_iden.namespace, &segment_iden.shard_name);
+    common_base::counter_metric_inc_by!(READ_RECORDS, labels, records);
+    common_base::counter_metric_inc_by!(READ_BYTES, labels, bytes);
+    common_base::histogram_metric_observe!(READ_BATCH_RECORDS, labels, records as f64);
+}
+
+/// `records` more records of the segment were indexed, `lag` records are left to index
+pub fn record_index_build_metrics(segment_iden: &SegmentIdentity, records: u64, lag: i64) {
+    let labels = ShardLabel::new(&segment_iden.namespace, &segment_iden.shard_name);
+    common_base::counter_metric_inc_by!(INDEX_BUILD_RECORDS, labels, records);
+    common_base::gauge_metric_set!(INDEX_BUILD_LAG, labels, lag);
+}
+
+pub fn record_segment_scroll_event(segment_iden: &SegmentIdentity, event: &str) {
+    let labels = ScrollLabel {
+        namespace: segment_iden.namespace.clone(),
+        shard_name: segment_iden.shard_name.clone(),
+        event: event.to_string(),
+    };
+    common_base::counter_metric_inc!(SEGMENT_SCROLL_EVENTS, labels);
+}
+
+pub fn record_shard_disk_usage(namespace: &str, shard_name: &str, segment_num: i64, bytes: i64) {
+    let labels = ShardLabel::new(namespace, shard_name);
+    common_base::gauge_metric_set!(SEGMENT_NUM, labels, segment_num);
+    common_base::gauge_metric_set!(SHARD_DISK_USAGE, labels, bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        record_index_build_metrics, record_segment_scroll_event, record_shard_disk_usage,
+        record_write_metrics, ScrollLabel, ShardLabel, INDEX_BUILD_LAG, SCROLL_EVENT_PRE_SEALUP,
+        SEGMENT_NUM, SEGMENT_SCROLL_EVENTS, SHARD_DISK_USAGE, WRITE_BYTES, WRITE_RECORDS,
+    };
+    use crate::segment::SegmentIdentity;
+
+    #[test]
+    fn record_metrics_test() {
+        let segment_iden = SegmentIdentity::new("metrics_ns", "metrics_shard", 0);
+        let labels = ShardLabel::new("metrics_ns", "metrics_shard");
+
+        record_write_metrics(&segment_iden, 10, 1024);
+        record_write_metrics(&segment_iden, 5, 512);
+        let mut records = 0;
+        common_base::counter_metric_get!(WRITE_RECORDS, labels, records);
+        assert_eq!(records, 15);
+        let mut bytes = 0;
+        common_base::counter_metric_get!(WRITE_BYTES, labels, bytes);
+        assert_eq!(bytes, 1536);
+
+        record_index_build_metrics(&segment_iden, 10, 5);
+        record_index_build_metrics(&segment_iden, 5, 0);
+        let mut lag = -1;
+        common_base::gauge_metric_get!(INDEX_BUILD_LAG, labels, lag);
+        assert_eq!(lag, 0);
+
+        record_segment_scroll_event(&segment_iden, SCROLL_EVENT_PRE_SEALUP);
+        let scroll_labels = ScrollLabel {
+            namespace: "metrics_ns".to_string(),
+            shard_name: "metrics_shard".to_string(),
+            event: SCROLL_EVENT_PRE_SEALUP.to_string(),
+        };
+        let mut events = 0;
+        common_base::counter_metric_get!(SEGMENT_SCROLL_EVENTS, scroll_labels, events);
+        assert_eq!(events, 1);
+
+        record_shard_disk_usage("metrics_ns", "metrics_shard", 2, 4096);
+        let mut segment_num = 0;
+        common_base::gauge_metric_get!(SEGMENT_NUM, labels, segment_num);
+        assert_eq!(segment_num, 2);
+        let mut usage = 0;
+        common_base::gauge_metric_get!(SHARD_DISK_USAGE, labels, usage);
+        assert_eq!(usage, 4096);
+    }
+}
@@ -20,7 +20,9 @@
 use common_base::config::journal_server::journal_server_conf;
 use grpc_clients::pool::ClientPool;
 use log::{error, info};
-use metadata_struct::journal::shard::{shard_name_iden, JournalShardConfig};
+use metadata_struct::journal::shard::{
+    shard_name_iden, CompressionType, FsyncPolicy, JournalShardConfig,
+};
 use protocol::journal_server::journal_inner::{
     DeleteShardFileRequest, GetShardDeleteStatusRequest,
 };
@@ -108,16 +110,22 @@ pub fn is_delete_by_shard(req: &GetShardDeleteStatusRequest) -> Result<bool, Jou
 /// After placement center receives the request and creates the shard, it will invoke a `update_cache` call back to the journal server. Journal server will update its cache
 ///
 /// Will wait for 3s for the cache update to take effect
+///
+/// A shard created without an fsync policy uses the one configured on the journal server.
 pub async fn create_shard_to_place(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
     namespace: &str,
     shard_name: &str,
+    fsync_policy: Option<FsyncPolicy>,
+    compression: Option<CompressionType>,
 ) -> Result<(), JournalServerError> {
     let cluster_config = cache_manager.get_cluster();
     let config = JournalShardConfig {
         replica_num: cluster_config.shard_replica_num,
         max_segment_size: cluster_config.max_segment_size,
+        fsync_policy,
+        compression,
     };
     let conf = journal_server_conf();
     let request = CreateShardRequest {
@@ -193,7 +201,15 @@ pub async fn try_auto_create_shard(
         )));
     }
 
-    create_shard_to_place(cache_manager, client_pool, namespace, shard_name).await?;
+    create_shard_to_place(
+        cache_manager,
+        client_pool,
+        namespace,
+        shard_name,
+        None,
+        None,
+    )
+    .await?;
     let mut i = 0;
     loop {
         if i >= 30 {
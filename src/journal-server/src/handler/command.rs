@@ -24,6 +24,7 @@
     GetShardMetadataResp, GetShardMetadataRespBody, JournalEngineError, ListShardResp,
     ListShardRespBody, ReadResp, ReadRespBody, RespHeader, WriteResp, WriteRespBody,
 };
+use protocol::journal_server::journal_engine_ext::ReadRespBodyExt;
 use rocksdb_engine::RocksDBEngine;
 
 use super::cluster::ClusterHandler;
@@ -99,7 +100,7 @@ pub async fn apply(
             }
 
             /* Shard Handler */
-            JournalEnginePacket::CreateShardReq(request) => {
+            JournalEnginePacket::CreateShardReq(request, ext) => {
                 info!("recv create shard request: {:?}", request);
                 let mut resp = CreateShardResp::default();
                 let mut header = RespHeader {
@@ -107,7 +108,7 @@ pub async fn apply(
                     api_version: ApiVersion::V0.into(),
                     ..Default::default()
                 };
-                match self.shard_handler.create_shard(request).await {
+                match self.shard_handler.create_shard(request, ext).await {
                     Ok(()) => {
                         resp.body = Some(CreateShardRespBody {});
                     }
@@ -216,16 +217,18 @@ pub async fn apply(
                 return Some(JournalEnginePacket::WriteResp(resp));
             }
 
-            JournalEnginePacket::ReadReq(request) => {
+            JournalEnginePacket::ReadReq(request, ext) => {
                 let mut resp = ReadResp::default();
+                let mut resp_ext = ReadRespBodyExt::default();
                 let mut header = RespHeader {
                     api_key: ApiKey::Read.into(),
                     api_version: ApiVersion::V0.into(),
                     ..Default::default()
                 };
-                match self.data_handler.read(request).await {
-                    Ok(messages) => {
+                match self.data_handler.read(request, ext).await {
+                    Ok((messages, ext)) => {
                         resp.body = Some(ReadRespBody { messages });
+                        resp_ext = ext;
                     }
                     Err(e) => {
                         header.error = Some(JournalEngineError {
@@ -236,7 +239,7 @@ pub async fn apply(
                     }
                 }
                 resp.header = Some(header);
-                return Some(JournalEnginePacket::ReadResp(resp));
+                return Some(JournalEnginePacket::ReadResp(resp, resp_ext));
             }
 
             JournalEnginePacket::FetchOffsetReq(request) => {
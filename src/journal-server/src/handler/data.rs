@@ -21,6 +21,7 @@
     FetchOffsetReq, FetchOffsetRespBody, FetchOffsetShardMeta, JournalEngineError, ReadReq,
     ReadRespSegmentMessage, WriteReq, WriteRespMessage,
 };
+use protocol::journal_server::journal_engine_ext::{ReadReqBodyExt, ReadRespBodyExt};
 use rocksdb_engine::RocksDBEngine;
 
 use crate::core::cache::CacheManager;
@@ -28,7 +29,7 @@
 use crate::core::shard::try_auto_create_shard;
 use crate::index::time::TimestampIndexManager;
 use crate::segment::manager::SegmentFileManager;
-use crate::segment::read::read_data_req;
+use crate::segment::read::read_data_req_with_ext;
 use crate::segment::write::write_data_req;
 use crate::segment::SegmentIdentity;
 
@@ -95,7 +96,8 @@ pub async fn write(
     pub async fn read(
         &self,
         request: ReadReq,
-    ) -> Result<Vec<ReadRespSegmentMessage>, JournalServerError> {
+        ext: ReadReqBodyExt,
+    ) -> Result<(Vec<ReadRespSegmentMessage>, ReadRespBodyExt), JournalServerError> {
         if request.body.is_none() {
             return Err(JournalServerError::RequestBodyNotEmpty("write".to_string()));
         }
@@ -119,14 +121,14 @@ pub async fn read(
         }
 
         let conf = journal_server_conf();
-        let results = read_data_req(
+        read_data_req_with_ext(
             &self.cache_manager,
             &self.rocksdb_engine_handler,
             &req_body,
+            &ext,
             conf.node_id,
         )
-        .await?;
-        Ok(results)
+        .await
     }
 
     pub async fn fetch_offset(
@@ -18,10 +18,12 @@
 use grpc_clients::placement::journal::call::update_segment_status;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::journal::segment::{JournalSegment, SegmentStatus};
+use metadata_struct::journal::shard::{CompressionType, FsyncPolicy};
 use protocol::journal_server::journal_engine::{
     ClientSegmentMetadata, CreateShardReq, DeleteShardReq, GetShardMetadataReq,
     GetShardMetadataRespShard, ListShardReq,
 };
+use protocol::journal_server::journal_engine_ext::CreateShardReqBodyExt;
 use protocol::placement_center::placement_center_journal::{
     CreateNextSegmentRequest, UpdateSegmentStatusRequest,
 };
@@ -45,7 +47,11 @@ pub fn new(cache_manager: Arc<CacheManager>, client_pool: Arc<ClientPool>) -> Sh
         }
     }
 
-    pub async fn create_shard(&self, request: CreateShardReq) -> Result<(), JournalServerError> {
+    pub async fn create_shard(
+        &self,
+        request: CreateShardReq,
+        ext: CreateShardReqBodyExt,
+    ) -> Result<(), JournalServerError> {
         if request.body.is_none() {
             return Err(JournalServerError::RequestBodyNotEmpty(
                 "create_shard".to_string(),
@@ -53,6 +59,17 @@ pub async fn create_shard(&self, request: CreateShardReq) -> Result<(), JournalS
         }
         let req_body = request.body.unwrap();
 
+        let fsync_policy = if ext.fsync_policy.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_slice::<FsyncPolicy>(&ext.fsync_policy)?)
+        };
+        let compression = if ext.compression.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_slice::<CompressionType>(&ext.compression)?)
+        };
+
         if self
             .cache_manager
             .get_shard(&req_body.namespace, &req_body.shard_name)
@@ -63,6 +80,8 @@ pub async fn create_shard(&self, request: CreateShardReq) -> Result<(), JournalS
                 &self.client_pool,
                 &req_body.namespace,
                 &req_body.shard_name,
+                fsync_policy,
+                compression,
             )
             .await?;
         };
@@ -33,8 +33,9 @@
 use crate::core::cache::CacheManager;
 use crate::core::consts::{BUILD_INDE_PER_RECORD_NUM, DB_COLUMN_FAMILY_INDEX};
 use crate::core::error::JournalServerError;
+use crate::core::metrics::record_index_build_metrics;
 use crate::index::IndexData;
-use crate::segment::file::{open_segment_write, ReadData};
+use crate::segment::file::{open_segment_write, ReadData, SegmentFile};
 use crate::segment::manager::SegmentFileManager;
 use crate::segment::SegmentIdentity;
 
@@ -86,6 +87,7 @@ pub async fn try_trigger_build_index(
 
     start_segment_build_index_thread(
         cache_manager.clone(),
+        segment_file_manager.clone(),
         rocksdb_engine_handler.clone(),
         segment_iden.clone(),
         segment_file_meta.start_offset as u64,
@@ -102,8 +104,10 @@ pub async fn try_trigger_build_index(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn start_segment_build_index_thread(
     cache_manager: Arc<CacheManager>,
+    segment_file_manager: Arc<SegmentFileManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     segment_iden: SegmentIdentity,
     start_offset: u64,
@@ -179,6 +183,15 @@ async fn start_segment_build_index_thread(
                                 continue;
                             }
 
+                            let end_offset = segment_file_manager
+                                .get_end_offset(&segment_iden)
+                                .unwrap_or(last_build_offset as i64);
+                            record_index_build_metrics(
+                                &segment_iden,
+                                data.len() as u64,
+                                end_offset - last_build_offset as i64,
+                            );
+
 
                         }
                         Err(e) => {
@@ -199,6 +212,55 @@ async fn start_segment_build_index_thread(
     Ok(())
 }
 
+/// drop the indexes of the segment and rebuild the offset, timestamp, key and tag indexes
+/// from the records in the segment file
+///
+/// Returns the offset of the last record indexed.
+pub async fn rebuild_segment_index(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    segment_iden: &SegmentIdentity,
+    segment_file: &SegmentFile,
+    start_offset: u64,
+) -> Result<Option<u64>, JournalServerError> {
+    delete_segment_index(rocksdb_engine_handler, segment_iden)?;
+
+    let offset_index = OffsetIndexManager::new(rocksdb_engine_handler.clone());
+    let time_index = TimestampIndexManager::new(rocksdb_engine_handler.clone());
+    let tag_index = TagIndexManager::new(rocksdb_engine_handler.clone());
+
+    let mut position = 0;
+    let mut last_build_offset = None;
+    loop {
+        // the last record of the previous batch is read again from its position and skipped
+        let next_offset = last_build_offset.map(|offset| offset + 1).unwrap_or(0);
+        let data = segment_file
+            .read_by_offset(position, next_offset, 10 * 1024 * 1024, 1000)
+            .await?;
+        if data.is_empty() {
+            break;
+        }
+
+        save_record_index(
+            &data,
+            start_offset,
+            segment_iden,
+            &offset_index,
+            &time_index,
+            &tag_index,
+        )
+        .await?;
+
+        let last = data.last().unwrap();
+        position = last.position;
+        last_build_offset = Some(last.record.offset as u64);
+    }
+
+    if let Some(offset) = last_build_offset {
+        save_last_offset_build_index(rocksdb_engine_handler, segment_iden, offset)?;
+    }
+    Ok(last_build_offset)
+}
+
 fn try_finish_segment_index_build(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     cache_manager: &Arc<CacheManager>,
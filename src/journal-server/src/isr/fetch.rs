@@ -11,3 +11,431 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::config::journal_server::journal_server_conf;
+use common_base::tools::now_mills;
+use grpc_clients::journal::replica::call::journal_replica_fetch;
+use grpc_clients::pool::ClientPool;
+use log::{debug, error, info};
+use metadata_struct::journal::segment::{JournalSegment, SegmentStatus};
+use protocol::journal_server::journal_record::JournalRecord;
+use protocol::journal_server::journal_replica::{ReplicaFetchRecord, ReplicaFetchRequest};
+use rocksdb_engine::RocksDBEngine;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use crate::core::cache::CacheManager;
+use crate::core::error::JournalServerError;
+use crate::index::build::try_trigger_build_index;
+use crate::segment::compression::get_compression;
+use crate::segment::file::{open_segment_write, SegmentFile};
+use crate::segment::fsync::{get_fsync_policy, FsyncTracker};
+use crate::segment::manager::SegmentFileManager;
+use crate::segment::SegmentIdentity;
+
+const REPLICA_FETCH_MAX_SIZE: u64 = 1024 * 1024;
+
+const REPLICA_FETCH_MAX_RECORD: u64 = 1000;
+
+#[derive(Clone)]
+pub struct ReplicaFetchThreadData {
+    pub stop_send: broadcast::Sender<bool>,
+}
+
+/// start a fetch thread for every segment the local node is a follower of
+///
+/// The segments are rescanned every second, so that segments created or reassigned later are
+/// picked up as well.
+pub async fn start_replica_fetch_manager(
+    client_pool: Arc<ClientPool>,
+    cache_manager: Arc<CacheManager>,
+    segment_file_manager: Arc<SegmentFileManager>,
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    loop {
+        let mut stop_recv = stop_send.subscribe();
+        select! {
+            val = stop_recv.recv() =>{
+                if let Ok(flag) = val {
+                    if flag {
+                        cache_manager.stop_all_replica_fetch_thread();
+                        debug!("{}","Replica fetch manager thread exited successfully");
+                        break;
+                    }
+                }
+            }
+            _ = try_start_replica_fetch_thread(
+                &client_pool,
+                &cache_manager,
+                &segment_file_manager,
+                &rocksdb_engine_handler,
+            ) => {
+
+            }
+        }
+    }
+}
+
+async fn try_start_replica_fetch_thread(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+) {
+    for shard in cache_manager.get_shards() {
+        for segment in cache_manager.get_segments_list_by_shard(&shard.namespace, &shard.shard_name)
+        {
+            let segment_iden = SegmentIdentity::from_journal_segment(&segment);
+            if cache_manager.contain_replica_fetch_thread(&segment_iden)
+                || !need_fetch(cache_manager, segment_file_manager, &segment)
+            {
+                continue;
+            }
+
+            if let Err(e) = start_segment_fetch_thread(
+                client_pool.clone(),
+                cache_manager.clone(),
+                segment_file_manager.clone(),
+                rocksdb_engine_handler.clone(),
+                segment_iden,
+            )
+            .await
+            {
+                error!(
+                    "Failed to start the replica fetch thread, error message: {}",
+                    e
+                );
+            }
+        }
+    }
+    sleep(Duration::from_secs(1)).await;
+}
+
+/// whether the local node is a follower of the segment that still has data to fetch
+fn need_fetch(
+    cache_manager: &Arc<CacheManager>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    segment: &JournalSegment,
+) -> bool {
+    let conf = journal_server_conf();
+    if segment.leader == conf.node_id || segment.get_fold(conf.node_id).is_none() {
+        return false;
+    }
+
+    let segment_iden = SegmentIdentity::from_journal_segment(segment);
+    match segment.status {
+        SegmentStatus::PreWrite | SegmentStatus::Write | SegmentStatus::PreSealUp => true,
+        // a sealed segment only needs to be fetched until the follower has caught up
+        SegmentStatus::SealUp => {
+            let local_end_offset = segment_file_manager
+                .get_end_offset(&segment_iden)
+                .unwrap_or(-1);
+            if let Some(meta) = cache_manager.get_segment_meta(&segment_iden) {
+                return local_end_offset < meta.end_offset;
+            }
+            false
+        }
+        SegmentStatus::Idle | SegmentStatus::PreDelete | SegmentStatus::Deleting => false,
+    }
+}
+
+async fn start_segment_fetch_thread(
+    client_pool: Arc<ClientPool>,
+    cache_manager: Arc<CacheManager>,
+    segment_file_manager: Arc<SegmentFileManager>,
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    segment_iden: SegmentIdentity,
+) -> Result<(), JournalServerError> {
+    let (segment_file, _) = open_segment_write(&cache_manager, &segment_iden).await?;
+    let mut fsync_tracker = FsyncTracker::new(
+        get_fsync_policy(&cache_manager, &segment_iden)?,
+        now_mills(),
+    );
+    let (stop_send, mut stop_recv) = broadcast::channel::<bool>(1);
+    cache_manager.add_replica_fetch_thread(&segment_iden, ReplicaFetchThreadData { stop_send });
+
+    tokio::spawn(async move {
+        info!(
+            "Replica fetch thread for segment {} started",
+            segment_iden.name()
+        );
+        let conf = journal_server_conf();
+        let fetch_interval = Duration::from_millis(conf.replication.replica_fetch_interval_ms);
+
+        loop {
+            let segment = match cache_manager.get_segment(&segment_iden) {
+                Some(segment) if need_fetch(&cache_manager, &segment_file_manager, &segment) => {
+                    segment
+                }
+                _ => {
+                    cache_manager.remove_replica_fetch_thread(&segment_iden);
+                    info!(
+                        "Replica fetch thread for segment {} exited, no more data needs to be fetched",
+                        segment_iden.name()
+                    );
+                    break;
+                }
+            };
+
+            select! {
+                val = stop_recv.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("Replica fetch thread for segment {} exited successfully",
+                                segment_iden.name());
+                            break;
+                        }
+                    }
+                },
+                val = fetch_from_leader(
+                    &client_pool,
+                    &cache_manager,
+                    &segment_file_manager,
+                    &rocksdb_engine_handler,
+                    &segment,
+                    &segment_file,
+                    &mut fsync_tracker,
+                ) => {
+                    match val {
+                        Ok(num) => {
+                            if num == 0 {
+                                sleep(fetch_interval).await;
+                            }
+                        }
+                        Err(e) => {
+                            error!("Failed to replicate segment {} from leader {}, error message: {}",
+                                segment_iden.name(), segment.leader, e);
+                            sleep(fetch_interval).await;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = fsync_tracker.flush(&segment_file, now_mills()).await {
+            error!(
+                "Failed to fsync segment {} when the replica fetch thread exited, error message: {}",
+                segment_iden.name(),
+                e
+            );
+        }
+    });
+    Ok(())
+}
+
+/// fetch the records after the local end offset from the leader and append them to the local
+/// segment file, returning the number of records replicated
+async fn fetch_from_leader(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    segment: &JournalSegment,
+    segment_file: &SegmentFile,
+    fsync_tracker: &mut FsyncTracker,
+) -> Result<usize, JournalServerError> {
+    let segment_iden = SegmentIdentity::from_journal_segment(segment);
+    let local_end_offset =
+        if let Some(end_offset) = segment_file_manager.get_end_offset(&segment_iden) {
+            end_offset
+        } else {
+            return Err(JournalServerError::SegmentFileMetaNotExists(
+                segment_iden.name(),
+            ));
+        };
+
+    let conf = journal_server_conf();
+    let request = build_fetch_request(
+        &conf.cluster_name,
+        &segment_iden,
+        conf.node_id,
+        (local_end_offset + 1) as u64,
+    );
+    let addr = get_node_grpc_addr(cache_manager, segment.leader)?;
+    let reply = journal_replica_fetch(client_pool, &[addr], request)
+        .await
+        .map_err(|e| JournalServerError::ReplicaFetchFailed(segment_iden.name(), e.to_string()))?;
+
+    append_replica_records(
+        cache_manager,
+        segment_file_manager,
+        rocksdb_engine_handler,
+        &segment_iden,
+        segment_file,
+        fsync_tracker,
+        local_end_offset,
+        reply.records,
+    )
+    .await
+}
+
+fn build_fetch_request(
+    cluster_name: &str,
+    segment_iden: &SegmentIdentity,
+    node_id: u64,
+    offset: u64,
+) -> ReplicaFetchRequest {
+    ReplicaFetchRequest {
+        cluster_name: cluster_name.to_string(),
+        node_id,
+        namespace: segment_iden.namespace.clone(),
+        shard_name: segment_iden.shard_name.clone(),
+        segment: segment_iden.segment_seq,
+        offset,
+        max_size: REPLICA_FETCH_MAX_SIZE,
+        max_record: REPLICA_FETCH_MAX_RECORD,
+    }
+}
+
+fn get_node_grpc_addr(
+    cache_manager: &Arc<CacheManager>,
+    node_id: u64,
+) -> Result<String, JournalServerError> {
+    for node in cache_manager.all_node() {
+        if node.node_id == node_id {
+            return Ok(node.node_inner_addr);
+        }
+    }
+    Err(JournalServerError::NodeNoAvailableAddr(node_id))
+}
+
+/// append the records fetched from the leader to the local segment file, keeping the offsets
+/// assigned by the leader
+///
+/// The records are compressed with the compression of the shard. The next fetch tells the
+/// leader that these records are persisted, so they are fsynced according to the fsync policy
+/// of the shard before returning.
+#[allow(clippy::too_many_arguments)]
+async fn append_replica_records(
+    cache_manager: &Arc<CacheManager>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    segment_iden: &SegmentIdentity,
+    segment_file: &SegmentFile,
+    fsync_tracker: &mut FsyncTracker,
+    local_end_offset: i64,
+    messages: Vec<ReplicaFetchRecord>,
+) -> Result<usize, JournalServerError> {
+    let records = build_replica_records(segment_iden, local_end_offset, messages);
+    if records.is_empty() {
+        // records written by an earlier fetch may still be waiting for an interval fsync
+        fsync_tracker.try_sync(segment_file, now_mills()).await?;
+        return Ok(0);
+    }
+
+    let compression = get_compression(cache_manager, segment_iden)?;
+    let size = segment_file.write_batch(&records, compression).await?;
+    fsync_tracker.record_write(size);
+    fsync_tracker.try_sync(segment_file, now_mills()).await?;
+
+    let first = records.first().unwrap();
+    let last = records.last().unwrap();
+    if let Some(segment_file_meta) = segment_file_manager.get_segment_file(segment_iden) {
+        if segment_file_meta.start_offset < 0 {
+            segment_file_manager.update_start_offset(segment_iden, first.offset)?;
+            segment_file_manager.update_start_timestamp(segment_iden, first.create_time)?;
+        }
+    }
+    segment_file_manager.update_end_offset(segment_iden, last.offset)?;
+    segment_file_manager.update_end_timestamp(segment_iden, last.create_time)?;
+
+    // the records are already persisted, the next fetch triggers the index build again
+    if let Err(e) = try_trigger_build_index(
+        cache_manager,
+        segment_file_manager,
+        rocksdb_engine_handler,
+        segment_iden,
+    )
+    .await
+    {
+        error!(
+            "Failed to trigger the index build of segment {}, error message: {}",
+            segment_iden.name(),
+            e
+        );
+    }
+
+    Ok(records.len())
+}
+
+/// convert the fetched records into segment records, skipping the ones that are already persisted
+/// locally
+fn build_replica_records(
+    segment_iden: &SegmentIdentity,
+    local_end_offset: i64,
+    messages: Vec<ReplicaFetchRecord>,
+) -> Vec<JournalRecord> {
+    let mut last_offset = local_end_offset;
+    let mut records = Vec::new();
+    for message in messages {
+        if message.offset as i64 <= last_offset {
+            continue;
+        }
+        last_offset = message.offset as i64;
+
+        records.push(JournalRecord {
+            content: message.value,
+            create_time: message.timestamp,
+            key: message.key,
+            namespace: segment_iden.namespace.clone(),
+            shard_name: segment_iden.shard_name.clone(),
+            segment: segment_iden.segment_seq,
+            tags: message.tags,
+            pkid: 0,
+            producer_id: "".to_string(),
+            offset: message.offset as i64,
+        });
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use protocol::journal_server::journal_replica::ReplicaFetchRecord;
+
+    use super::{build_fetch_request, build_replica_records};
+    use crate::core::test::test_build_segment;
+
+    #[test]
+    fn build_fetch_request_test() {
+        let segment_iden = test_build_segment();
+        let request = build_fetch_request("cluster-1", &segment_iden, 2, 10);
+        assert_eq!(request.cluster_name, "cluster-1");
+        assert_eq!(request.node_id, 2);
+        assert_eq!(request.offset, 10);
+        assert_eq!(request.namespace, segment_iden.namespace);
+        assert_eq!(request.shard_name, segment_iden.shard_name);
+        assert_eq!(request.segment, segment_iden.segment_seq);
+    }
+
+    #[test]
+    fn build_replica_records_test() {
+        let segment_iden = test_build_segment();
+        let messages = (3..8)
+            .map(|offset| ReplicaFetchRecord {
+                offset,
+                key: format!("key-{}", offset),
+                value: format!("data-{}", offset).into_bytes(),
+                tags: vec![format!("tag-{}", offset)],
+                timestamp: 100 + offset,
+            })
+            .collect::<Vec<_>>();
+
+        // offsets 3 and 4 have already been persisted locally
+        let records = build_replica_records(&segment_iden, 4, messages);
+        assert_eq!(records.len(), 3);
+        for (i, record) in records.iter().enumerate() {
+            let offset = i as u64 + 5;
+            assert_eq!(record.offset, offset as i64);
+            assert_eq!(record.key, format!("key-{}", offset));
+            assert_eq!(record.create_time, 100 + offset);
+            assert_eq!(record.namespace, segment_iden.namespace);
+            assert_eq!(record.segment, segment_iden.segment_seq);
+        }
+    }
+}
@@ -0,0 +1,489 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::config::journal_server::journal_server_conf;
+use common_base::tools::now_mills;
+use dashmap::DashMap;
+use log::info;
+use metadata_struct::journal::segment::JournalSegment;
+use metadata_struct::placement::node::BrokerNode;
+use protocol::journal_server::journal_engine::{
+    ReadReqBody, ReadReqFilter, ReadReqMessage, ReadReqOptions, ReadType,
+};
+use protocol::journal_server::journal_replica::{
+    ReplicaFetchRecord, ReplicaFetchReply, ReplicaFetchRequest,
+};
+use rocksdb_engine::RocksDBEngine;
+use tokio::sync::watch;
+use tokio::time::{timeout, Instant};
+
+use crate::core::cache::CacheManager;
+use crate::core::error::JournalServerError;
+use crate::segment::manager::SegmentFileManager;
+use crate::segment::read::read_data_req;
+use crate::segment::SegmentIdentity;
+
+/// the replication progress of a follower, as seen by the segment leader
+#[derive(Clone, Debug, Default)]
+pub struct ReplicaProgress {
+    pub node_id: u64,
+    // the last offset persisted by the follower, -1 means nothing has been persisted yet
+    pub end_offset: i64,
+    // the last time (in milliseconds) the follower caught up with the end offset of the leader
+    pub last_caught_up_time: u128,
+}
+
+/// the replication state of a segment, maintained by the leader of the segment
+///
+/// Followers report their progress through their fetch requests. A follower stays in the ISR
+/// as long as it has caught up with the leader within `replica_lag_time_max_ms`, and the high
+/// watermark is the smallest end offset among the members of the ISR.
+pub struct SegmentReplicaState {
+    leader: u64,
+    // (node_id, ReplicaProgress)
+    followers: DashMap<u64, ReplicaProgress>,
+    high_watermark: watch::Sender<i64>,
+}
+
+impl SegmentReplicaState {
+    pub fn new(segment: &JournalSegment) -> Self {
+        // followers start in the ISR and have `replica_lag_time_max_ms` to catch up
+        let now = now_mills();
+        let followers = DashMap::with_capacity(2);
+        for replica in segment.replicas.iter() {
+            if replica.node_id == segment.leader {
+                continue;
+            }
+            followers.insert(
+                replica.node_id,
+                ReplicaProgress {
+                    node_id: replica.node_id,
+                    end_offset: -1,
+                    last_caught_up_time: now,
+                },
+            );
+        }
+
+        let (high_watermark, _) = watch::channel(-1);
+        SegmentReplicaState {
+            leader: segment.leader,
+            followers,
+            high_watermark,
+        }
+    }
+
+    pub fn leader(&self) -> u64 {
+        self.leader
+    }
+
+    pub fn high_watermark(&self) -> i64 {
+        *self.high_watermark.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<i64> {
+        self.high_watermark.subscribe()
+    }
+
+    pub fn get_follower(&self, node_id: u64) -> Option<ReplicaProgress> {
+        if let Some(progress) = self.followers.get(&node_id) {
+            return Some(progress.clone());
+        }
+        None
+    }
+
+    /// record the end offset reported by a follower
+    pub fn update_follower(
+        &self,
+        node_id: u64,
+        end_offset: i64,
+        leader_end_offset: i64,
+        now: u128,
+    ) {
+        if let Some(mut progress) = self.followers.get_mut(&node_id) {
+            progress.end_offset = end_offset;
+            if end_offset >= leader_end_offset {
+                progress.last_caught_up_time = now;
+            }
+        }
+    }
+
+    /// the node ids of the in-sync replicas, the leader is always part of the ISR
+    pub fn isr(&self, now: u128, lag_time_max_ms: u64) -> Vec<u64> {
+        let mut results = vec![self.leader];
+        for raw in self.followers.iter() {
+            if is_in_sync(raw.value(), now, lag_time_max_ms) {
+                results.push(raw.node_id);
+            }
+        }
+        results.sort();
+        results
+    }
+
+    /// recalculate the high watermark from the ISR and return it
+    ///
+    /// The high watermark never moves backwards.
+    pub fn update_high_watermark(
+        &self,
+        leader_end_offset: i64,
+        now: u128,
+        lag_time_max_ms: u64,
+    ) -> i64 {
+        let mut high_watermark = leader_end_offset;
+        for raw in self.followers.iter() {
+            if is_in_sync(raw.value(), now, lag_time_max_ms) {
+                high_watermark = high_watermark.min(raw.end_offset);
+            }
+        }
+
+        self.high_watermark.send_if_modified(|current| {
+            if high_watermark > *current {
+                *current = high_watermark;
+                return true;
+            }
+            false
+        });
+        self.high_watermark()
+    }
+}
+
+fn is_in_sync(progress: &ReplicaProgress, now: u128, lag_time_max_ms: u64) -> bool {
+    now.saturating_sub(progress.last_caught_up_time) <= lag_time_max_ms as u128
+}
+
+/// serve the fetch request of a follower and record its progress
+///
+/// The fetch offset moves the high watermark forward, so only the followers of the segment that
+/// are registered nodes of the cluster may fetch, from the address they registered with. Sealed
+/// segments are still served until the followers have caught up.
+pub async fn replica_fetch_by_req(
+    cache_manager: &Arc<CacheManager>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    remote_ip: Option<IpAddr>,
+    req: &ReplicaFetchRequest,
+) -> Result<ReplicaFetchReply, JournalServerError> {
+    let conf = journal_server_conf();
+    let segment_iden = SegmentIdentity::new(&req.namespace, &req.shard_name, req.segment);
+    if req.cluster_name != conf.cluster_name {
+        return Err(JournalServerError::ReplicaFetchNotAllowed(
+            req.node_id,
+            segment_iden.name(),
+            format!("it belongs to cluster {}", req.cluster_name),
+        ));
+    }
+
+    let segment = if let Some(segment) = cache_manager.get_segment(&segment_iden) {
+        segment
+    } else {
+        return Err(JournalServerError::SegmentNotExist(segment_iden.name()));
+    };
+
+    if segment.leader != conf.node_id {
+        return Err(JournalServerError::NotLeader(segment_iden.name()));
+    }
+
+    let node = cache_manager
+        .all_node()
+        .into_iter()
+        .find(|node| node.node_id == req.node_id);
+    check_replica_fetch(&segment, req.node_id, node.as_ref(), remote_ip).map_err(|reason| {
+        JournalServerError::ReplicaFetchNotAllowed(req.node_id, segment_iden.name(), reason)
+    })?;
+
+    if cache_manager.get_segment_meta(&segment_iden).is_none() {
+        return Err(JournalServerError::SegmentFileMetaNotExists(
+            segment_iden.name(),
+        ));
+    }
+
+    let req_body = ReadReqBody {
+        messages: vec![ReadReqMessage {
+            namespace: req.namespace.clone(),
+            shard_name: req.shard_name.clone(),
+            segment: req.segment,
+            ready_type: ReadType::Offset.into(),
+            filter: Some(ReadReqFilter {
+                offset: req.offset,
+                ..Default::default()
+            }),
+            options: Some(ReadReqOptions {
+                max_size: req.max_size,
+                max_record: req.max_record,
+            }),
+        }],
+    };
+    let results = read_data_req(
+        cache_manager,
+        rocksdb_engine_handler,
+        &req_body,
+        conf.node_id,
+    )
+    .await?;
+
+    record_replica_fetch(
+        cache_manager,
+        segment_file_manager,
+        &segment_iden,
+        req.node_id,
+        req.offset,
+    )?;
+
+    let records = results
+        .into_iter()
+        .flat_map(|shard_message| shard_message.messages)
+        .map(|message| ReplicaFetchRecord {
+            offset: message.offset,
+            key: message.key,
+            value: message.value,
+            tags: message.tags,
+            timestamp: message.timestamp,
+        })
+        .collect();
+    Ok(ReplicaFetchReply { records })
+}
+
+/// whether `node_id` may fetch the segment as a follower, returning the reason if not
+fn check_replica_fetch(
+    segment: &JournalSegment,
+    node_id: u64,
+    node: Option<&BrokerNode>,
+    remote_ip: Option<IpAddr>,
+) -> Result<(), String> {
+    if node_id == segment.leader || !segment.replicas.iter().any(|r| r.node_id == node_id) {
+        return Err("it is not a follower of the segment".to_string());
+    }
+
+    let node = if let Some(node) = node {
+        node
+    } else {
+        return Err("it is not a node of the cluster".to_string());
+    };
+
+    if let (Some(remote_ip), Ok(node_ip)) = (remote_ip, node.node_ip.parse::<IpAddr>()) {
+        if remote_ip.to_canonical() != node_ip.to_canonical() {
+            return Err(format!(
+                "the request comes from {} instead of {}",
+                remote_ip, node_ip
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// record the progress of a follower carried by its fetch request
+///
+/// A fetch from `fetch_offset` means that the follower has persisted every record before it.
+pub fn record_replica_fetch(
+    cache_manager: &Arc<CacheManager>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    segment_iden: &SegmentIdentity,
+    node_id: u64,
+    fetch_offset: u64,
+) -> Result<(), JournalServerError> {
+    let segment = if let Some(segment) = cache_manager.get_segment(segment_iden) {
+        segment
+    } else {
+        return Err(JournalServerError::SegmentNotExist(segment_iden.name()));
+    };
+
+    let conf = journal_server_conf();
+    let lag_time_max_ms = conf.replication.replica_lag_time_max_ms;
+    let leader_end_offset = segment_file_manager
+        .get_end_offset(segment_iden)
+        .unwrap_or(-1);
+    let now = now_mills();
+
+    // a follower cannot have persisted more than the leader has written
+    let end_offset = (fetch_offset as i64 - 1).min(leader_end_offset);
+    let state = cache_manager.get_segment_replica_state(&segment);
+    state.update_follower(node_id, end_offset, leader_end_offset, now);
+    state.update_high_watermark(leader_end_offset, now, lag_time_max_ms);
+    update_isr_cache(cache_manager, &segment, state.isr(now, lag_time_max_ms));
+    Ok(())
+}
+
+/// wait until the in-sync replicas of the segment have persisted `offset`
+///
+/// Returns an error if the ISR shrinks below `min_insync_replica_num`, or if the replicas
+/// do not catch up within `replica_ack_timeout_ms`.
+pub async fn wait_replica_ack(
+    cache_manager: &Arc<CacheManager>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    segment_iden: &SegmentIdentity,
+    offset: u64,
+) -> Result<(), JournalServerError> {
+    let segment = if let Some(segment) = cache_manager.get_segment(segment_iden) {
+        segment
+    } else {
+        return Err(JournalServerError::SegmentNotExist(segment_iden.name()));
+    };
+
+    if segment.replicas.len() <= 1 {
+        return Ok(());
+    }
+
+    let conf = journal_server_conf();
+    let lag_time_max_ms = conf.replication.replica_lag_time_max_ms;
+    let min_insync_replica_num = conf
+        .replication
+        .min_insync_replica_num
+        .clamp(1, segment.replicas.len() as u32);
+    let check_interval = Duration::from_millis(conf.replication.replica_fetch_interval_ms);
+    let deadline = Instant::now() + Duration::from_millis(conf.replication.replica_ack_timeout_ms);
+
+    let state = cache_manager.get_segment_replica_state(&segment);
+    let mut high_watermark_recv = state.subscribe();
+    loop {
+        let now = now_mills();
+        let leader_end_offset = segment_file_manager
+            .get_end_offset(segment_iden)
+            .unwrap_or(-1);
+        let high_watermark = state.update_high_watermark(leader_end_offset, now, lag_time_max_ms);
+        let isr = state.isr(now, lag_time_max_ms);
+        update_isr_cache(cache_manager, &segment, isr.clone());
+
+        if isr.len() < min_insync_replica_num as usize {
+            return Err(JournalServerError::NotEnoughInSyncReplicas(
+                segment_iden.name(),
+                isr.len(),
+                min_insync_replica_num,
+            ));
+        }
+
+        if high_watermark >= offset as i64 {
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            return Err(JournalServerError::ReplicaAckTimeout(
+                segment_iden.name(),
+                offset,
+            ));
+        }
+
+        // Woken up by the high watermark moving forward, or periodically so that a follower
+        // that stopped fetching is eventually removed from the ISR.
+        let _ = timeout(check_interval, high_watermark_recv.changed()).await;
+    }
+}
+
+fn update_isr_cache(cache_manager: &Arc<CacheManager>, segment: &JournalSegment, isr: Vec<u64>) {
+    let segment_iden = SegmentIdentity::from_journal_segment(segment);
+    if let Some(current) = cache_manager.get_segment(&segment_iden) {
+        if current.isr != isr {
+            info!(
+                "The ISR of segment {} changed from {:?} to {:?}",
+                segment_iden.name(),
+                current.isr,
+                isr
+            );
+            cache_manager.update_segment_isr(&segment_iden, isr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use metadata_struct::journal::segment::{JournalSegment, Replica};
+    use metadata_struct::placement::node::BrokerNode;
+
+    use super::{check_replica_fetch, SegmentReplicaState};
+
+    fn build_segment() -> JournalSegment {
+        JournalSegment {
+            leader: 1,
+            replicas: (1..=3)
+                .map(|node_id| Replica {
+                    replica_seq: node_id,
+                    node_id,
+                    fold: "/tmp".to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn isr_test() {
+        let state = SegmentReplicaState::new(&build_segment());
+        let start = state.get_follower(2).unwrap().last_caught_up_time;
+
+        // every replica is in sync right after the segment is created
+        assert_eq!(state.isr(start, 1000), vec![1, 2, 3]);
+
+        // follower 2 catches up, follower 3 never fetches
+        state.update_follower(2, 9, 9, start + 800);
+        assert_eq!(state.isr(start + 1500, 1000), vec![1, 2]);
+
+        // lagging behind the leader does not refresh the caught up time
+        state.update_follower(2, 15, 20, start + 1900);
+        assert_eq!(state.isr(start + 1900, 1000), vec![1]);
+        assert_eq!(state.get_follower(2).unwrap().end_offset, 15);
+    }
+
+    #[test]
+    fn high_watermark_test() {
+        let state = SegmentReplicaState::new(&build_segment());
+        let start = state.get_follower(2).unwrap().last_caught_up_time;
+        assert_eq!(state.high_watermark(), -1);
+
+        // the followers have not persisted anything yet
+        assert_eq!(state.update_high_watermark(9, start, 1000), -1);
+
+        state.update_follower(2, 9, 9, start + 10);
+        state.update_follower(3, 4, 9, start + 10);
+        assert_eq!(state.update_high_watermark(9, start + 10, 1000), 4);
+
+        state.update_follower(3, 9, 9, start + 20);
+        assert_eq!(state.update_high_watermark(9, start + 20, 1000), 9);
+
+        // follower 3 falls out of the ISR and no longer holds back the high watermark
+        state.update_follower(2, 19, 19, start + 1500);
+        assert_eq!(state.update_high_watermark(19, start + 1500, 1000), 19);
+
+        // the high watermark never moves backwards
+        assert_eq!(state.update_high_watermark(5, start + 1500, 1000), 19);
+    }
+
+    #[test]
+    fn check_replica_fetch_test() {
+        let segment = build_segment();
+        let node = BrokerNode {
+            node_id: 2,
+            node_ip: "192.168.1.2".to_string(),
+            ..Default::default()
+        };
+        let node_ip = "192.168.1.2".parse::<IpAddr>().unwrap();
+
+        assert!(check_replica_fetch(&segment, 2, Some(&node), Some(node_ip)).is_ok());
+        assert!(check_replica_fetch(&segment, 2, Some(&node), None).is_ok());
+        let mapped_ip = "::ffff:192.168.1.2".parse::<IpAddr>().unwrap();
+        assert!(check_replica_fetch(&segment, 2, Some(&node), Some(mapped_ip)).is_ok());
+
+        // the leader and nodes outside of the replica set cannot fetch
+        assert!(check_replica_fetch(&segment, 1, Some(&node), Some(node_ip)).is_err());
+        assert!(check_replica_fetch(&segment, 4, Some(&node), Some(node_ip)).is_err());
+
+        // the node has to be registered and fetch from its registered address
+        assert!(check_replica_fetch(&segment, 2, None, Some(node_ip)).is_err());
+        let other_ip = "192.168.1.9".parse::<IpAddr>().unwrap();
+        assert!(check_replica_fetch(&segment, 2, Some(&node), Some(other_ip)).is_err());
+    }
+}
@@ -27,11 +27,13 @@
 use common_base::runtime::create_runtime;
 use grpc_clients::pool::ClientPool;
 use index::engine::{column_family_list, storage_data_fold};
+use isr::fetch::start_replica_fetch_manager;
 use log::{error, info};
 use rocksdb_engine::RocksDBEngine;
 use segment::manager::{
     load_local_segment_cache, metadata_and_local_segment_diff_check, SegmentFileManager,
 };
+use segment::recover::recover_local_segment_files;
 use segment::scroll::SegmentScrollManager;
 use server::connection_manager::ConnectionManager;
 use server::grpc::server::GrpcServer;
@@ -48,6 +50,8 @@
 mod segment;
 mod server;
 
+pub use segment::file::{scan_segment_file, SegmentScanError, SegmentScanResult};
+
 pub struct JournalServer {
     config: JournalServerConfig,
     stop_send: broadcast::Sender<bool>,
@@ -172,6 +176,22 @@ fn start_daemon_thread(&self) {
         self.daemon_runtime.spawn(async move {
             segment_scroll.trigger_segment_scroll().await;
         });
+
+        let client_pool = self.client_pool.clone();
+        let cache_manager = self.cache_manager.clone();
+        let segment_file_manager = self.segment_file_manager.clone();
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let stop_sx = self.stop_send.clone();
+        self.daemon_runtime.spawn(async move {
+            start_replica_fetch_manager(
+                client_pool,
+                cache_manager,
+                segment_file_manager,
+                rocksdb_engine_handler,
+                stop_sx,
+            )
+            .await
+        });
     }
 
     fn waiting_stop(&self) {
@@ -212,6 +232,16 @@ fn init_node(&self) {
                 }
             }
 
+            if let Err(e) = recover_local_segment_files(
+                &self.rocksdb_engine_handler,
+                &self.segment_file_manager,
+                &self.config.storage.data_path,
+            )
+            .await
+            {
+                panic!("{}", e);
+            }
+
             metadata_and_local_segment_diff_check();
 
             // todo
@@ -229,6 +259,7 @@ fn init_node(&self) {
 
     async fn stop_server(&self) {
         self.cache_manager.stop_all_build_index_thread();
+        self.cache_manager.stop_all_replica_fetch_thread();
 
         match unregister_journal_node(self.client_pool.clone(), self.config.clone()).await {
             Ok(()) => {}
@@ -0,0 +1,54 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::journal_server::journal_server_conf;
+use metadata_struct::journal::compression;
+use metadata_struct::journal::shard::{build_compression_type, CompressionType};
+
+use super::SegmentIdentity;
+use crate::core::cache::CacheManager;
+use crate::core::error::JournalServerError;
+
+/// get the compression of the shard the segment belongs to
+///
+/// Shards created without a compression use the `compression` of the journal server.
+pub fn get_compression(
+    cache_manager: &Arc<CacheManager>,
+    segment_iden: &SegmentIdentity,
+) -> Result<CompressionType, JournalServerError> {
+    if let Some(shard) = cache_manager.get_shard(&segment_iden.namespace, &segment_iden.shard_name)
+    {
+        if let Some(compression) = shard.config.compression {
+            return Ok(compression);
+        }
+    }
+
+    let conf = journal_server_conf();
+    Ok(build_compression_type(&conf.shard.compression)?)
+}
+
+pub fn compress(compression: CompressionType, data: &[u8]) -> Result<Vec<u8>, JournalServerError> {
+    compression::compress(compression, data)
+        .map_err(|e| JournalServerError::CompressionFailed(compression, e))
+}
+
+pub fn decompress(
+    compression: CompressionType,
+    data: &[u8],
+) -> Result<Vec<u8>, JournalServerError> {
+    compression::decompress(compression, data)
+        .map_err(|e| JournalServerError::CompressionFailed(compression, e))
+}
@@ -13,18 +13,20 @@
 // limitations under the License.
 
 use std::fs::remove_file;
-use std::io::ErrorKind;
 use std::path::Path;
 use std::sync::Arc;
 
-use bytes::BytesMut;
 use common_base::config::journal_server::journal_server_conf;
 use common_base::tools::{file_exists, try_create_fold};
-use prost::Message;
+use metadata_struct::journal::shard::CompressionType;
 use protocol::journal_server::journal_record::JournalRecord;
 use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
+use super::record::{
+    decode_record, encode_batch, encode_record, read_record_frame, FrameRecord, RecordFrame,
+    StoredBatch,
+};
 use super::SegmentIdentity;
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
@@ -36,6 +38,25 @@ pub struct ReadData {
     pub record: JournalRecord,
 }
 
+/// A compressed batch read from the segment file as it is stored
+#[derive(Debug, Clone)]
+pub struct ReadBatch {
+    pub position: u64,
+    pub batch: StoredBatch,
+    /// the offsets of the records of the batch that were asked for, filled by reads through an
+    /// index, empty if every record from the start offset of the read was asked for
+    pub offsets: Vec<u64>,
+}
+
+/// The records and compressed batches read from the segment file
+#[derive(Debug, Clone, Default)]
+pub struct ReadFrames {
+    pub records: Vec<ReadData>,
+    /// only filled if the compressed batches are kept compressed, otherwise their records are
+    /// in `records`
+    pub batches: Vec<ReadBatch>,
+}
+
 /// Given a segment identity, open a segment file for reading and writing.
 pub async fn open_segment_write(
     cache_manager: &Arc<CacheManager>,
@@ -109,19 +130,52 @@ pub async fn delete(&self) -> Result<(), JournalServerError> {
         Ok(remove_file(segment_file)?)
     }
 
-    /// append a list of records to the segment file
-    pub async fn write(&self, records: &[JournalRecord]) -> Result<(), JournalServerError> {
+    /// append a list of records to the segment file uncompressed, returning the number of bytes
+    /// written
+    ///
+    /// The data is handed over to the operating system but not fsynced, see [`sync_data`].
+    pub async fn write(&self, records: &[JournalRecord]) -> Result<u64, JournalServerError> {
+        self.write_batch(records, CompressionType::None).await
+    }
+
+    /// append a list of records to the segment file, returning the number of bytes written
+    ///
+    /// Without compression every record is written on its own, otherwise the records are
+    /// compressed together and written as a single batch, see [`super::record`].
+    pub async fn write_batch(
+        &self,
+        records: &[JournalRecord],
+        compression: CompressionType,
+    ) -> Result<u64, JournalServerError> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+
         let segment_file = data_file_segment(&self.data_fold, self.segment_no);
         let file = OpenOptions::new().append(true).open(segment_file).await?;
         let mut writer = tokio::io::BufWriter::new(file);
 
-        for record in records {
-            let data = JournalRecord::encode_to_vec(record);
-            writer.write_u64(record.offset as u64).await?;
-            writer.write_u32(data.len() as u32).await?;
+        let mut size = 0;
+        if compression == CompressionType::None {
+            for record in records {
+                let data = encode_record(record);
+                writer.write_all(data.as_ref()).await?;
+                size += data.len() as u64;
+            }
+        } else {
+            let data = encode_batch(records, compression)?;
             writer.write_all(data.as_ref()).await?;
+            size += data.len() as u64;
         }
         writer.flush().await?;
+        Ok(size)
+    }
+
+    /// fsync the data of the segment file to disk
+    pub async fn sync_data(&self) -> Result<(), JournalServerError> {
+        let segment_file = data_file_segment(&self.data_fold, self.segment_no);
+        let file = OpenOptions::new().write(true).open(segment_file).await?;
+        file.sync_data().await?;
         Ok(())
     }
 
@@ -139,11 +193,12 @@ pub async fn size(&self) -> Result<u64, JournalServerError> {
     ///     2. the total size of the records is less than or equal to `max_size`
     ///     3. the number of records is less than or equal to `max_record`
     ///
-    /// The records are stored in the segment file in the following format:
-    ///
-    ///     [offset: u64][len: u32][data: bytes]
+    /// See [`super::record`] for the format of the records in the segment file.
     ///
-    /// We only consider `data` when calculating the size of a record.
+    /// We only consider `data` when calculating the size of a record. Reading stops at a record
+    /// that is still being written, and fails at a record that does not pass the checksum.
+    /// Compressed batches are decompressed, and every record of a batch has the position of
+    /// the batch.
     ///
     /// # Return
     ///
@@ -156,6 +211,27 @@ pub async fn read_by_offset(
         max_size: u64,
         max_record: u64,
     ) -> Result<Vec<ReadData>, JournalServerError> {
+        let frames = self
+            .read_frames_by_offset(start_position, start_offset, max_size, max_record, false)
+            .await?;
+        Ok(frames.records)
+    }
+
+    /// the same as [`read_by_offset`], but compressed batches are kept as they are stored if
+    /// `keep_compressed` is set
+    ///
+    /// A compressed batch is returned as a whole, so it may hold records before `start_offset`,
+    /// which the reader has to skip. Its compressed size and all of its records count towards
+    /// the limits, except for a batch that starts before `start_offset`, so that a reader
+    /// always makes progress.
+    pub async fn read_frames_by_offset(
+        &self,
+        start_position: u64,
+        start_offset: u64,
+        max_size: u64,
+        max_record: u64,
+        keep_compressed: bool,
+    ) -> Result<ReadFrames, JournalServerError> {
         let segment_file = data_file_segment(&self.data_fold, self.segment_no);
         let file = File::open(segment_file).await?;
         let mut reader = tokio::io::BufReader::new(file);
@@ -164,44 +240,68 @@ pub async fn read_by_offset(
             .seek(std::io::SeekFrom::Current(start_position as i64))
             .await?;
 
-        let mut results = Vec::new();
+        let mut results = ReadFrames::default();
         let mut already_size = 0;
-        loop {
+        let mut record_num = 0;
+        let mut position = start_position;
+        'frame: loop {
             if already_size > max_size {
                 break;
             }
 
-            // read offset
-            let position = reader.stream_position().await?;
-
-            let record_offset = match reader.read_u64().await {
-                Ok(offset) => offset,
-                Err(e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        break;
+            let frame_position = position;
+            let records = match read_record_frame(&mut reader).await? {
+                RecordFrame::Records { records, size } => {
+                    position += size;
+                    records
+                }
+                RecordFrame::Batch { batch, size } => {
+                    position += size;
+                    if keep_compressed {
+                        let counted = batch.offset >= start_offset;
+                        if counted {
+                            already_size += batch.payload.len() as u64;
+                            record_num += batch.record_num as u64;
+                        }
+                        results.batches.push(ReadBatch {
+                            position: frame_position,
+                            batch,
+                            offsets: Vec::new(),
+                        });
+
+                        if counted && record_num >= max_record {
+                            break;
+                        }
+                        continue;
                     }
-                    return Err(e.into());
+                    self.batch_records(&batch, frame_position)?
+                }
+                RecordFrame::End | RecordFrame::Truncated => break,
+                RecordFrame::Corrupted(reason) => {
+                    return Err(JournalServerError::SegmentRecordCorrupted(
+                        self.name(),
+                        position,
+                        reason,
+                    ));
                 }
             };
 
-            // read len
-            let len = reader.read_u32().await?;
-
-            if record_offset < start_offset {
-                reader.seek(std::io::SeekFrom::Current(len as i64)).await?;
-                continue;
-            }
-
-            // read body
-            let mut buf = BytesMut::with_capacity(len as usize);
-            reader.read_buf(&mut buf).await?;
+            for FrameRecord { offset, data } in records {
+                if offset < start_offset {
+                    continue;
+                }
 
-            already_size += buf.len() as u64;
-            let record = JournalRecord::decode(buf)?;
-            results.push(ReadData { position, record });
+                already_size += data.len() as u64;
+                record_num += 1;
+                let record = decode_record(data)?;
+                results.records.push(ReadData {
+                    position: frame_position,
+                    record,
+                });
 
-            if results.len() >= max_record as usize {
-                break;
+                if record_num >= max_record || already_size > max_size {
+                    break 'frame;
+                }
             }
         }
 
@@ -210,48 +310,95 @@ pub async fn read_by_offset(
 
     /// read a list of records by their byte positions in the segment file
     ///
-    /// See [`read_by_offset`] for more details.
+    /// A position of a compressed batch returns every record of the batch. See
+    /// [`read_by_offset`] for more details.
     pub async fn read_by_positions(
         &self,
         positions: Vec<u64>,
     ) -> Result<Vec<ReadData>, JournalServerError> {
+        let frames = self.read_frames_by_positions(positions, false).await?;
+        Ok(frames.records)
+    }
+
+    /// the same as [`read_by_positions`], but compressed batches are kept as they are stored if
+    /// `keep_compressed` is set
+    pub async fn read_frames_by_positions(
+        &self,
+        positions: Vec<u64>,
+        keep_compressed: bool,
+    ) -> Result<ReadFrames, JournalServerError> {
         let segment_file = data_file_segment(&self.data_fold, self.segment_no);
         let file = File::open(segment_file).await?;
         let mut reader = tokio::io::BufReader::new(file);
 
-        let mut results = Vec::new();
+        let mut results = ReadFrames::default();
 
         for position in positions {
             reader.seek(std::io::SeekFrom::Start(position)).await?;
 
-            // read offset
-            let _ = match reader.read_u64().await {
-                Ok(offset) => offset,
-                Err(e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        break;
+            let records = match read_record_frame(&mut reader).await? {
+                RecordFrame::Records { records, .. } => records,
+                RecordFrame::Batch { batch, .. } => {
+                    if keep_compressed {
+                        results.batches.push(ReadBatch {
+                            position,
+                            batch,
+                            offsets: Vec::new(),
+                        });
+                        continue;
                     }
-                    return Err(e.into());
+                    self.batch_records(&batch, position)?
+                }
+                RecordFrame::End | RecordFrame::Truncated => break,
+                RecordFrame::Corrupted(reason) => {
+                    return Err(JournalServerError::SegmentRecordCorrupted(
+                        self.name(),
+                        position,
+                        reason,
+                    ));
                 }
             };
 
-            // read len
-            let len = reader.read_u32().await?;
+            for FrameRecord { data, .. } in records {
+                if data.is_empty() {
+                    continue;
+                }
+
+                let record = decode_record(data)?;
 
-            if len == 0 {
-                continue;
+                results.records.push(ReadData { position, record });
             }
+        }
 
-            // read body
-            let mut buf = BytesMut::with_capacity(len as usize);
-            reader.read_buf(&mut buf).await?;
+        Ok(results)
+    }
 
-            let record = JournalRecord::decode(buf)?;
+    fn batch_records(
+        &self,
+        batch: &StoredBatch,
+        position: u64,
+    ) -> Result<Vec<FrameRecord>, JournalServerError> {
+        batch.records().map_err(|reason| {
+            JournalServerError::SegmentRecordCorrupted(self.name(), position, reason)
+        })
+    }
 
-            results.push(ReadData { position, record });
-        }
+    /// scan every record of the segment file, see [`scan_segment_file`]
+    pub async fn scan(&self) -> Result<SegmentScanResult, JournalServerError> {
+        scan_segment_file(&data_file_segment(&self.data_fold, self.segment_no)).await
+    }
 
-        Ok(results)
+    /// truncate the segment file to `size` bytes and fsync it
+    pub async fn truncate(&self, size: u64) -> Result<(), JournalServerError> {
+        let segment_file = data_file_segment(&self.data_fold, self.segment_no);
+        let file = OpenOptions::new().write(true).open(segment_file).await?;
+        file.set_len(size).await?;
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    pub fn name(&self) -> String {
+        data_file_segment(&self.data_fold, self.segment_no)
     }
 
     pub fn exists(&self) -> bool {
@@ -260,6 +407,165 @@ pub fn exists(&self) -> bool {
     }
 }
 
+/// the result of scanning a segment file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentScanResult {
+    /// the size of the segment file
+    pub file_size: u64,
+    /// the size of the valid records at the head of the segment file
+    pub valid_size: u64,
+    pub record_num: u64,
+    /// the offset of the first and last valid record, -1 if there is no valid record
+    pub start_offset: i64,
+    pub end_offset: i64,
+    /// the create time of the first and last valid record, -1 if there is no valid record
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    /// the first invalid record, if any
+    pub error: Option<SegmentScanError>,
+}
+
+impl SegmentScanResult {
+    /// whether the segment file ends in the middle of a record or with invalid records
+    pub fn is_damaged(&self) -> bool {
+        self.valid_size < self.file_size
+    }
+}
+
+/// the first invalid record found while scanning a segment file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentScanError {
+    pub position: u64,
+    pub reason: String,
+    /// whether everything from `position` to the end of the file is a torn write, that is an
+    /// incomplete record or zero-filled space, rather than corrupted data in the middle of the
+    /// segment file
+    pub torn_tail: bool,
+}
+
+/// read every record of the segment file at `path`, verifying checksums and that offsets are
+/// increasing, until the end of the file or the first invalid record
+///
+/// The first invalid record is reported as a torn tail if the file ends in the middle of it or
+/// only zeros follow it, which is what a crash while appending looks like. Anything else is
+/// corruption that dropping the rest of the file would hide.
+pub async fn scan_segment_file(path: &str) -> Result<SegmentScanResult, JournalServerError> {
+    let file = File::open(path).await?;
+    let file_size = file.metadata().await?.len();
+    let mut reader = tokio::io::BufReader::new(file);
+
+    let mut result = SegmentScanResult {
+        file_size,
+        start_offset: -1,
+        end_offset: -1,
+        start_timestamp: -1,
+        end_timestamp: -1,
+        ..Default::default()
+    };
+
+    'frame: loop {
+        let position = result.valid_size;
+        let (records, size) = match read_record_frame(&mut reader).await? {
+            RecordFrame::Records { records, size } => (records, size),
+            RecordFrame::Batch { batch, size } => match batch.records() {
+                Ok(records) => (records, size),
+                Err(reason) => {
+                    result.error = Some(SegmentScanError {
+                        position,
+                        reason,
+                        torn_tail: false,
+                    });
+                    break;
+                }
+            },
+            RecordFrame::End => break,
+            RecordFrame::Truncated => {
+                result.error = Some(SegmentScanError {
+                    position,
+                    reason: "the segment file ends in the middle of a record".to_string(),
+                    torn_tail: true,
+                });
+                break;
+            }
+            RecordFrame::Corrupted(reason) => {
+                result.error = Some(SegmentScanError {
+                    position,
+                    reason,
+                    torn_tail: false,
+                });
+                break;
+            }
+        };
+
+        // a frame is either valid as a whole or, like every frame after it, lost
+        let mut frame = Vec::with_capacity(records.len());
+        let mut last_offset = result.end_offset;
+        for FrameRecord { offset, data } in records {
+            let record = match decode_record(data) {
+                Ok(record) => record,
+                Err(e) => {
+                    result.error = Some(SegmentScanError {
+                        position,
+                        reason: e.to_string(),
+                        torn_tail: false,
+                    });
+                    break 'frame;
+                }
+            };
+
+            if last_offset >= 0 && offset as i64 <= last_offset {
+                result.error = Some(SegmentScanError {
+                    position,
+                    reason: format!(
+                        "offset {} is not greater than the previous offset {}",
+                        offset, last_offset
+                    ),
+                    torn_tail: false,
+                });
+                break 'frame;
+            }
+            last_offset = offset as i64;
+            frame.push((offset, record.create_time));
+        }
+
+        for (offset, create_time) in frame {
+            if result.start_offset < 0 {
+                result.start_offset = offset as i64;
+                result.start_timestamp = create_time as i64;
+            }
+            result.end_offset = offset as i64;
+            result.end_timestamp = create_time as i64;
+            result.record_num += 1;
+        }
+        result.valid_size += size;
+    }
+
+    if let Some(error) = result.error.as_mut() {
+        if !error.torn_tail {
+            error.torn_tail = is_zero_filled(path, error.position).await?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// whether every byte of the file at `path` from `position` to the end is zero
+async fn is_zero_filled(path: &str, position: u64) -> Result<bool, JournalServerError> {
+    let mut file = File::open(path).await?;
+    file.seek(std::io::SeekFrom::Start(position)).await?;
+
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(true);
+        }
+        if buf[..n].iter().any(|b| *b != 0) {
+            return Ok(false);
+        }
+    }
+}
+
 pub fn data_fold_shard(namespace: &str, shard_name: &str, data_fold: &str) -> String {
     let file_name = format!("{}/{}", namespace, shard_name);
     format!("{}/{}", data_fold, file_name)
@@ -278,6 +584,7 @@ mod tests {
     };
     use common_base::tools::{now_second, unique_id};
     use metadata_struct::journal::segment::{JournalSegment, Replica, SegmentConfig};
+    use metadata_struct::journal::shard::CompressionType;
     use protocol::journal_server::journal_record::JournalRecord;
 
     use super::{data_file_segment, data_fold_shard, open_segment_write, SegmentFile};
@@ -435,13 +742,93 @@ async fn segment_read_position_test() {
         let res = segment.read_by_positions(vec![0]).await.unwrap();
         assert_eq!(res.len(), 1);
 
-        let res = segment.read_by_positions(vec![45]).await.unwrap();
+        let res = segment.read_by_positions(vec![51]).await.unwrap();
         assert_eq!(res.len(), 1);
 
-        let res = segment.read_by_positions(vec![0, 45, 90]).await.unwrap();
+        let res = segment.read_by_positions(vec![0, 51, 102]).await.unwrap();
         assert_eq!(res.len(), 3);
 
         let size = segment.size().await.unwrap();
         assert!(size > 0);
     }
+
+    #[tokio::test]
+    async fn segment_compressed_read_test() {
+        let data_fold = test_build_data_fold();
+        let segment_iden = test_build_segment();
+
+        let segment = SegmentFile::new(
+            segment_iden.namespace.to_string(),
+            segment_iden.shard_name.to_string(),
+            segment_iden.segment_seq,
+            data_fold.first().unwrap().to_string(),
+        );
+        segment.try_create().await.unwrap();
+
+        let records: Vec<JournalRecord> = (0..30)
+            .map(|i| JournalRecord {
+                content: format!("data1#-{}", i).as_bytes().to_vec(),
+                create_time: now_second(),
+                key: format!("k{}", i),
+                offset: 1000 + i,
+                ..Default::default()
+            })
+            .collect();
+
+        // one uncompressed write followed by two compressed batches with different codecs
+        segment.write(&records[0..10]).await.unwrap();
+        let size = segment.size().await.unwrap();
+        segment
+            .write_batch(&records[10..20], CompressionType::Lz4)
+            .await
+            .unwrap();
+        segment
+            .write_batch(&records[20..30], CompressionType::Zstd)
+            .await
+            .unwrap();
+
+        let res = segment.read_by_offset(0, 0, 20000, 1000).await.unwrap();
+        assert_eq!(res.len(), 30);
+        for (read_data, record) in res.iter().zip(records.iter()) {
+            assert_eq!(&read_data.record, record);
+        }
+
+        // every record of a batch has the position of the batch
+        let res = segment.read_by_offset(0, 1015, 20000, 3).await.unwrap();
+        let offsets: Vec<i64> = res.iter().map(|raw| raw.record.offset).collect();
+        assert_eq!(offsets, vec![1015, 1016, 1017]);
+        assert!(res.iter().all(|raw| raw.position == size));
+
+        let res = segment.read_by_positions(vec![size]).await.unwrap();
+        assert_eq!(res.len(), 10);
+        assert_eq!(res.first().unwrap().record.offset, 1010);
+
+        // batches kept compressed are returned as they are stored
+        let res = segment
+            .read_frames_by_offset(0, 1005, 20000, 12, true)
+            .await
+            .unwrap();
+        let offsets: Vec<i64> = res.records.iter().map(|raw| raw.record.offset).collect();
+        assert_eq!(offsets, (1005..1010).collect::<Vec<i64>>());
+        assert_eq!(res.batches.len(), 1);
+        assert_eq!(res.batches[0].position, size);
+        assert_eq!(res.batches[0].batch.offset, 1010);
+        assert_eq!(res.batches[0].batch.record_num, 10);
+        assert_eq!(res.batches[0].batch.records().unwrap().len(), 10);
+
+        // a batch that starts before the start offset does not count towards the limits
+        let res = segment
+            .read_frames_by_offset(size, 1015, 20000, 1, true)
+            .await
+            .unwrap();
+        assert!(res.records.is_empty());
+        assert_eq!(res.batches.len(), 2);
+        assert_eq!(res.batches[1].batch.offset, 1020);
+
+        let scan = segment.scan().await.unwrap();
+        assert!(!scan.is_damaged());
+        assert_eq!(scan.record_num, 30);
+        assert_eq!(scan.start_offset, 1000);
+        assert_eq!(scan.end_offset, 1029);
+    }
 }
@@ -0,0 +1,195 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::journal_server::journal_server_conf;
+use metadata_struct::journal::shard::{build_fsync_policy, FsyncPolicy};
+
+use super::file::SegmentFile;
+use super::SegmentIdentity;
+use crate::core::cache::CacheManager;
+use crate::core::error::JournalServerError;
+
+/// get the fsync policy of the shard the segment belongs to
+///
+/// Shards created without an fsync policy use the `fsync_strategy` of the journal server.
+pub fn get_fsync_policy(
+    cache_manager: &Arc<CacheManager>,
+    segment_iden: &SegmentIdentity,
+) -> Result<FsyncPolicy, JournalServerError> {
+    if let Some(shard) = cache_manager.get_shard(&segment_iden.namespace, &segment_iden.shard_name)
+    {
+        if let Some(policy) = shard.config.fsync_policy {
+            return Ok(policy);
+        }
+    }
+
+    let conf = journal_server_conf();
+    Ok(build_fsync_policy(
+        &conf.shard.fsync_strategy,
+        conf.shard.fsync_interval_ms,
+        conf.shard.fsync_bytes,
+    )?)
+}
+
+/// tracks the data appended to a segment file since the last fsync and decides when the next
+/// fsync is due
+///
+/// It is owned by the single thread that writes the segment file.
+pub struct FsyncTracker {
+    policy: FsyncPolicy,
+    unsynced_bytes: u64,
+    last_sync_time: u128,
+}
+
+impl FsyncTracker {
+    pub fn new(policy: FsyncPolicy, now: u128) -> Self {
+        FsyncTracker {
+            policy,
+            unsynced_bytes: 0,
+            last_sync_time: now,
+        }
+    }
+
+    pub fn policy(&self) -> FsyncPolicy {
+        self.policy
+    }
+
+    pub fn record_write(&mut self, bytes: u64) {
+        self.unsynced_bytes += bytes;
+    }
+
+    pub fn need_sync(&self, now: u128) -> bool {
+        if self.unsynced_bytes == 0 {
+            return false;
+        }
+
+        match self.policy {
+            FsyncPolicy::EveryWrite => true,
+            FsyncPolicy::IntervalMs(interval_ms) => {
+                now.saturating_sub(self.last_sync_time) >= interval_ms as u128
+            }
+            FsyncPolicy::Bytes(bytes) => self.unsynced_bytes >= bytes,
+            FsyncPolicy::Os => false,
+        }
+    }
+
+    /// fsync the segment file if it is due, returning whether it was synced
+    pub async fn try_sync(
+        &mut self,
+        segment_file: &SegmentFile,
+        now: u128,
+    ) -> Result<bool, JournalServerError> {
+        if !self.need_sync(now) {
+            return Ok(false);
+        }
+        self.sync(segment_file, now).await?;
+        Ok(true)
+    }
+
+    /// fsync the segment file if anything has been written since the last fsync, regardless of
+    /// the policy unless it is left to the operating system
+    pub async fn flush(
+        &mut self,
+        segment_file: &SegmentFile,
+        now: u128,
+    ) -> Result<(), JournalServerError> {
+        if self.unsynced_bytes == 0 || self.policy == FsyncPolicy::Os {
+            return Ok(());
+        }
+        self.sync(segment_file, now).await
+    }
+
+    async fn sync(
+        &mut self,
+        segment_file: &SegmentFile,
+        now: u128,
+    ) -> Result<(), JournalServerError> {
+        segment_file.sync_data().await?;
+        self.unsynced_bytes = 0;
+        self.last_sync_time = now;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::journal::shard::FsyncPolicy;
+
+    use super::FsyncTracker;
+
+    #[test]
+    fn every_write_test() {
+        let mut tracker = FsyncTracker::new(FsyncPolicy::EveryWrite, 0);
+        assert!(!tracker.need_sync(0));
+
+        tracker.record_write(1);
+        assert!(tracker.need_sync(0));
+    }
+
+    #[test]
+    fn interval_test() {
+        let mut tracker = FsyncTracker::new(FsyncPolicy::IntervalMs(100), 1000);
+        assert!(!tracker.need_sync(2000));
+
+        tracker.record_write(10);
+        assert!(!tracker.need_sync(1050));
+        assert!(tracker.need_sync(1100));
+    }
+
+    #[test]
+    fn bytes_test() {
+        let mut tracker = FsyncTracker::new(FsyncPolicy::Bytes(100), 0);
+        tracker.record_write(60);
+        assert!(!tracker.need_sync(0));
+
+        tracker.record_write(40);
+        assert!(tracker.need_sync(0));
+    }
+
+    #[test]
+    fn os_test() {
+        let mut tracker = FsyncTracker::new(FsyncPolicy::Os, 0);
+        tracker.record_write(1024 * 1024 * 1024);
+        assert!(!tracker.need_sync(u128::MAX));
+    }
+
+    #[tokio::test]
+    async fn sync_test() {
+        use crate::core::test::{test_build_data_fold, test_build_segment};
+        use crate::segment::file::SegmentFile;
+
+        let data_fold = test_build_data_fold();
+        let segment_iden = test_build_segment();
+        let segment_file = SegmentFile::new(
+            segment_iden.namespace.to_string(),
+            segment_iden.shard_name.to_string(),
+            segment_iden.segment_seq,
+            data_fold.first().unwrap().to_string(),
+        );
+        segment_file.try_create().await.unwrap();
+
+        let mut tracker = FsyncTracker::new(FsyncPolicy::Bytes(100), 0);
+        tracker.record_write(60);
+        assert!(!tracker.try_sync(&segment_file, 10).await.unwrap());
+
+        tracker.flush(&segment_file, 10).await.unwrap();
+        assert!(!tracker.need_sync(10));
+
+        tracker.record_write(100);
+        assert!(tracker.try_sync(&segment_file, 20).await.unwrap());
+        assert!(!tracker.need_sync(20));
+    }
+}
@@ -92,7 +92,7 @@ pub fn update_start_offset(
         if let Some(mut data) = self.segment_files.get_mut(&segment_iden.name()) {
             data.start_offset = start_offset;
             let offset_index = OffsetIndexManager::new(self.rocksdb_engine_handler.clone());
-            offset_index.save_start_offset(segment_iden, data.end_offset as u64)?;
+            offset_index.save_start_offset(segment_iden, data.start_offset as u64)?;
         }
         Ok(())
     }
@@ -14,9 +14,13 @@
 
 use metadata_struct::journal::segment::{segment_name, JournalSegment};
 
+pub mod compression;
 pub mod file;
+pub mod fsync;
 pub mod manager;
 pub mod read;
+pub mod record;
+pub mod recover;
 pub mod scroll;
 pub mod write;
 
@@ -12,19 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use protocol::journal_server::journal_engine::{
     ReadReqBody, ReadReqFilter, ReadReqOptions, ReadRespMessage, ReadRespSegmentMessage, ReadType,
 };
+use protocol::journal_server::journal_engine_ext::{
+    ReadReqBodyExt, ReadRespBodyExt, ReadRespCompressedBatch,
+};
 use rocksdb_engine::RocksDBEngine;
 
-use super::file::{ReadData, SegmentFile};
+use super::file::{ReadFrames, SegmentFile};
 use super::SegmentIdentity;
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
+use crate::core::metrics::record_read_metrics;
 use crate::index::offset::OffsetIndexManager;
 use crate::index::tag::TagIndexManager;
+use crate::index::IndexData;
 
 /// handle all read requests from Journal Client
 ///
@@ -35,7 +41,29 @@ pub async fn read_data_req(
     req_body: &ReadReqBody,
     node_id: u64,
 ) -> Result<Vec<ReadRespSegmentMessage>, JournalServerError> {
+    let (results, _) = read_data_req_with_ext(
+        cache_manager,
+        rocksdb_engine_handler,
+        req_body,
+        &ReadReqBodyExt::default(),
+        node_id,
+    )
+    .await?;
+    Ok(results)
+}
+
+/// the same as [`read_data_req`], but compressed batches are returned as they are stored in
+/// the [`ReadRespBodyExt`] if the reader asked for it, instead of being decompressed here
+pub async fn read_data_req_with_ext(
+    cache_manager: &Arc<CacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req_body: &ReadReqBody,
+    req_ext: &ReadReqBodyExt,
+    node_id: u64,
+) -> Result<(Vec<ReadRespSegmentMessage>, ReadRespBodyExt), JournalServerError> {
+    let keep_compressed = req_ext.compressed_batches;
     let mut results = Vec::new();
+    let mut resp_ext = ReadRespBodyExt::default();
     for raw in req_body.messages.iter() {
         let mut shard_message = ReadRespSegmentMessage {
             namespace: raw.namespace.to_string(),
@@ -98,6 +126,7 @@ pub async fn read_data_req(
                     &segment_iden,
                     &filter,
                     &read_options,
+                    keep_compressed,
                 )
                 .await?
             }
@@ -109,6 +138,7 @@ pub async fn read_data_req(
                     &segment_iden,
                     &filter,
                     &read_options,
+                    keep_compressed,
                 )
                 .await?
             }
@@ -120,13 +150,40 @@ pub async fn read_data_req(
                     &segment_iden,
                     &filter,
                     &read_options,
+                    keep_compressed,
                 )
                 .await?
             }
         };
 
+        let mut record_num = read_data_list.records.len() as u64;
+        let mut record_size: u64 = read_data_list
+            .records
+            .iter()
+            .map(|read_data| read_data.record.content.len() as u64)
+            .sum();
+        for read_batch in read_data_list.batches.iter() {
+            record_num += read_batch.batch.record_num as u64;
+            record_size += read_batch.batch.payload.len() as u64;
+        }
+        record_read_metrics(&segment_iden, record_num, record_size);
+
+        for read_batch in read_data_list.batches {
+            resp_ext.compressed_batches.push(ReadRespCompressedBatch {
+                namespace: raw.namespace.to_string(),
+                shard_name: raw.shard_name.to_string(),
+                segment: raw.segment,
+                offset: read_batch.batch.offset,
+                codec: read_batch.batch.codec as u32,
+                record_num: read_batch.batch.record_num,
+                payload: read_batch.batch.payload.to_vec(),
+                offsets: read_batch.offsets,
+                min_offset: filter.offset,
+            });
+        }
+
         let mut record_message = Vec::new();
-        for read_data in read_data_list {
+        for read_data in read_data_list.records {
             let record = read_data.record;
             record_message.push(ReadRespMessage {
                 offset: record.offset as u64,
@@ -140,7 +197,7 @@ pub async fn read_data_req(
 
         results.push(shard_message);
     }
-    Ok(results)
+    Ok((results, resp_ext))
 }
 
 /// handle read requests by offset
@@ -152,7 +209,8 @@ async fn read_by_offset(
     segment_iden: &SegmentIdentity,
     filter: &ReadReqFilter,
     read_options: &ReadReqOptions,
-) -> Result<Vec<ReadData>, JournalServerError> {
+    keep_compressed: bool,
+) -> Result<ReadFrames, JournalServerError> {
     let offset_index = OffsetIndexManager::new(rocksdb_engine_handler.clone());
     let start_position = if let Some(position) = offset_index
         .get_last_nearest_position_by_offset(segment_iden, filter.offset)
@@ -164,11 +222,12 @@ async fn read_by_offset(
     };
 
     let res = segment_file
-        .read_by_offset(
+        .read_frames_by_offset(
             start_position,
             filter.offset,
             read_options.max_size,
             read_options.max_record,
+            keep_compressed,
         )
         .await?;
 
@@ -184,7 +243,8 @@ async fn read_by_key(
     segment_iden: &SegmentIdentity,
     filter: &ReadReqFilter,
     read_options: &ReadReqOptions,
-) -> Result<Vec<ReadData>, JournalServerError> {
+    keep_compressed: bool,
+) -> Result<ReadFrames, JournalServerError> {
     let tag_index = TagIndexManager::new(rocksdb_engine_handler.clone());
     let index_data_list = tag_index
         .get_last_positions_by_key(
@@ -195,9 +255,7 @@ async fn read_by_key(
         )
         .await?;
 
-    let positions = index_data_list.iter().map(|raw| raw.position).collect();
-
-    segment_file.read_by_positions(positions).await
+    read_by_index(segment_file, &index_data_list, keep_compressed).await
 }
 
 /// handle read requests by tag
@@ -209,7 +267,8 @@ async fn read_by_tag(
     segment_iden: &SegmentIdentity,
     filter: &ReadReqFilter,
     read_options: &ReadReqOptions,
-) -> Result<Vec<ReadData>, JournalServerError> {
+    keep_compressed: bool,
+) -> Result<ReadFrames, JournalServerError> {
     let tag_index = TagIndexManager::new(rocksdb_engine_handler.clone());
     let index_data_list = tag_index
         .get_last_positions_by_tag(
@@ -219,8 +278,40 @@ async fn read_by_tag(
             read_options.max_record,
         )
         .await?;
-    let positions = index_data_list.iter().map(|raw| raw.position).collect();
-    segment_file.read_by_positions(positions).await
+    read_by_index(segment_file, &index_data_list, keep_compressed).await
+}
+
+/// read the records found in an index
+///
+/// The records of a compressed batch share the position of the batch, so each position is
+/// read once and only the records of the index are kept. A batch kept compressed carries the
+/// offsets of the index for the reader to filter it.
+async fn read_by_index(
+    segment_file: &SegmentFile,
+    index_data_list: &[IndexData],
+    keep_compressed: bool,
+) -> Result<ReadFrames, JournalServerError> {
+    let mut positions = Vec::new();
+    for index_data in index_data_list {
+        if !positions.contains(&index_data.position) {
+            positions.push(index_data.position);
+        }
+    }
+
+    let offsets: HashSet<u64> = index_data_list.iter().map(|raw| raw.offset).collect();
+    let mut res = segment_file
+        .read_frames_by_positions(positions, keep_compressed)
+        .await?;
+    res.records
+        .retain(|read_data| offsets.contains(&(read_data.record.offset as u64)));
+    for read_batch in res.batches.iter_mut() {
+        read_batch.offsets = index_data_list
+            .iter()
+            .filter(|index_data| index_data.position == read_batch.position)
+            .map(|index_data| index_data.offset)
+            .collect();
+    }
+    Ok(res)
 }
 
 #[cfg(test)]
@@ -264,10 +355,11 @@ async fn read_by_offset_test() {
             &segment_iden,
             &filter,
             &read_options,
+            false,
         )
         .await;
         assert!(res.is_ok());
-        let resp = res.unwrap();
+        let resp = res.unwrap().records;
         assert_eq!(resp.len(), 2);
 
         let mut i = 5;
@@ -291,10 +383,11 @@ async fn read_by_offset_test() {
             &segment_iden,
             &filter,
             &read_options,
+            false,
         )
         .await;
         assert!(res.is_ok());
-        let resp = res.unwrap();
+        let resp = res.unwrap().records;
         assert_eq!(resp.len(), 5);
 
         let mut i = 10;
@@ -344,11 +437,12 @@ async fn read_by_key_test() {
             &segment_iden,
             &filter,
             &read_options,
+            false,
         )
         .await;
         println!("{:?}", res);
         assert!(res.is_ok());
-        let resp = res.unwrap();
+        let resp = res.unwrap().records;
         assert_eq!(resp.len(), 1);
         assert_eq!(resp.first().unwrap().record.key, key);
     }
@@ -393,11 +487,12 @@ async fn read_by_tag_test() {
             &segment_iden,
             &filter,
             &read_options,
+            false,
         )
         .await;
         println!("{:?}", res);
         assert!(res.is_ok());
-        let resp = res.unwrap();
+        let resp = res.unwrap().records;
         assert_eq!(resp.len(), 1);
         assert!(resp.first().unwrap().record.tags.contains(&tag));
     }
@@ -0,0 +1,591 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The on-disk format of the records in a segment file.
+//!
+//! Records are written in the following format:
+//!
+//! ```text
+//! [magic: u8][version: u8][crc: u32][offset: u64][len: u32][data: bytes]
+//! ```
+//!
+//! where `crc` is the CRC32 of `offset`, `len` and `data`, and all integers are big endian.
+//!
+//! Shards with compression enabled write each batch of records as a single frame instead:
+//!
+//! ```text
+//! [magic: u8][version: u8][crc: u32][offset: u64][len: u32][codec: u8][count: u32][payload: bytes]
+//! ```
+//!
+//! where `offset` is the offset of the first record in the batch, `len` is the length of the
+//! payload, `codec` identifies the compression (see [`codec_id`]), and `crc` covers everything
+//! after it. The decompressed payload holds `count` records in the legacy format below. All
+//! records of a batch share the position of the batch, and since the codec is stored in every
+//! batch, a segment file may mix compressed and uncompressed records. Batches are read as they
+//! are stored, so that they can be sent to readers without decompressing them.
+//!
+//! Segment files written before the format was versioned store records as
+//!
+//! ```text
+//! [offset: u64][len: u32][data: bytes]
+//! ```
+//!
+//! Offsets are far below 2^56, so the first byte of such a record is always 0, which never
+//! matches the magic. All formats can be read, new records are always written in the latest
+//! formats.
+
+use std::io::ErrorKind;
+
+use bytes::Bytes;
+use common_base::utils::crc::calc_crc32;
+use metadata_struct::journal::compression::{
+    append_batch_record, codec_from_id, codec_id, decode_batch,
+};
+use metadata_struct::journal::shard::CompressionType;
+use prost::Message;
+use protocol::journal_server::journal_record::JournalRecord;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::compression::compress;
+use crate::core::error::JournalServerError;
+
+pub const RECORD_MAGIC: u8 = 0xA7;
+
+pub const RECORD_FORMAT_VERSION: u8 = 1;
+
+pub const BATCH_FORMAT_VERSION: u8 = 2;
+
+/// the size of the record header in the latest format
+pub const RECORD_HEADER_LEN: u64 = 18;
+
+/// the size of the header of a compressed batch
+pub const BATCH_HEADER_LEN: u64 = 23;
+
+/// the size of the record header in the legacy format
+pub const LEGACY_RECORD_HEADER_LEN: u64 = 12;
+
+/// a record with a larger length is treated as corrupted, rather than trusting a garbage length
+/// and allocating a huge buffer for it
+const MAX_RECORD_LEN: u32 = 256 * 1024 * 1024;
+
+/// a record read from a frame, `data` is the encoded [`JournalRecord`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameRecord {
+    pub offset: u64,
+    pub data: Bytes,
+}
+
+/// a compressed batch as it is stored in a segment file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredBatch {
+    /// the offset of the first record of the batch
+    pub offset: u64,
+    pub codec: u8,
+    pub record_num: u32,
+    pub payload: Bytes,
+}
+
+impl StoredBatch {
+    /// decompress the batch into its records
+    pub fn records(&self) -> Result<Vec<FrameRecord>, String> {
+        let records = decode_batch(self.codec, self.record_num, &self.payload)
+            .map_err(|e| format!("invalid batch at offset {}: {}", self.offset, e))?;
+        if records.first().map(|(offset, _)| *offset) != Some(self.offset) {
+            return Err(format!(
+                "the first record of the batch at offset {} does not match its offset",
+                self.offset
+            ));
+        }
+        Ok(records
+            .into_iter()
+            .map(|(offset, data)| FrameRecord { offset, data })
+            .collect())
+    }
+}
+
+/// the result of reading a frame from a segment file
+#[derive(Debug)]
+pub enum RecordFrame {
+    /// a complete and valid frame holding a single record, `size` is the number of bytes the
+    /// frame takes in the segment file
+    Records {
+        records: Vec<FrameRecord>,
+        size: u64,
+    },
+    /// a complete compressed batch whose checksum is valid, it is only decompressed by
+    /// [`StoredBatch::records`]
+    Batch { batch: StoredBatch, size: u64 },
+    /// the segment file ends right before the frame
+    End,
+    /// the segment file ends in the middle of the frame, usually the result of a torn write
+    Truncated,
+    /// the frame is not valid, with the reason
+    Corrupted(String),
+}
+
+/// encode a record in the latest format
+pub fn encode_record(record: &JournalRecord) -> Vec<u8> {
+    let data = JournalRecord::encode_to_vec(record);
+    let mut buf = Vec::with_capacity(RECORD_HEADER_LEN as usize + data.len());
+    buf.push(RECORD_MAGIC);
+    buf.push(RECORD_FORMAT_VERSION);
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(&(record.offset as u64).to_be_bytes());
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&data);
+
+    let crc = calc_crc32(&buf[6..]);
+    buf[2..6].copy_from_slice(&crc.to_be_bytes());
+    buf
+}
+
+/// encode a batch of records as a single frame compressed with `compression`
+pub fn encode_batch(
+    records: &[JournalRecord],
+    compression: CompressionType,
+) -> Result<Vec<u8>, JournalServerError> {
+    let first_offset = records.first().map(|record| record.offset).unwrap_or(0);
+
+    let mut raw = Vec::new();
+    for record in records {
+        let data = JournalRecord::encode_to_vec(record);
+        append_batch_record(&mut raw, record.offset as u64, &data);
+    }
+    let payload = compress(compression, &raw)?;
+
+    let mut buf = Vec::with_capacity(BATCH_HEADER_LEN as usize + payload.len());
+    buf.push(RECORD_MAGIC);
+    buf.push(BATCH_FORMAT_VERSION);
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(&(first_offset as u64).to_be_bytes());
+    buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    buf.push(codec_id(compression));
+    buf.extend_from_slice(&(records.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&payload);
+
+    let crc = calc_crc32(&buf[6..]);
+    buf[2..6].copy_from_slice(&crc.to_be_bytes());
+    Ok(buf)
+}
+
+/// decode the data of a record
+pub fn decode_record(data: Bytes) -> Result<JournalRecord, JournalServerError> {
+    Ok(JournalRecord::decode(data)?)
+}
+
+/// read the next frame from `reader`, verifying its checksum
+pub async fn read_record_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<RecordFrame, JournalServerError> {
+    let mut first = [0; 1];
+    if reader.read(&mut first).await? == 0 {
+        return Ok(RecordFrame::End);
+    }
+
+    match first[0] {
+        RECORD_MAGIC => read_frame(reader).await,
+        0 => read_legacy_frame(reader).await,
+        magic => Ok(RecordFrame::Corrupted(format!(
+            "unknown record magic {:#04x}",
+            magic
+        ))),
+    }
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<RecordFrame, JournalServerError> {
+    // [version: u8][crc: u32], the magic has already been read
+    let mut header = [0; 5];
+    if !read_exact_or_eof(reader, &mut header).await? {
+        return Ok(RecordFrame::Truncated);
+    }
+
+    let version = header[0];
+    let crc = u32::from_be_bytes(header[1..5].try_into().unwrap());
+    match version {
+        RECORD_FORMAT_VERSION => read_record_body(reader, crc).await,
+        BATCH_FORMAT_VERSION => read_batch_body(reader, crc).await,
+        _ => Ok(RecordFrame::Corrupted(format!(
+            "unsupported record format version {}",
+            version
+        ))),
+    }
+}
+
+async fn read_record_body<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    crc: u32,
+) -> Result<RecordFrame, JournalServerError> {
+    // [offset: u64][len: u32][data: bytes], the part covered by the checksum
+    let mut body = vec![0; 12];
+    if !read_exact_or_eof(reader, &mut body).await? {
+        return Ok(RecordFrame::Truncated);
+    }
+    let offset = u64::from_be_bytes(body[0..8].try_into().unwrap());
+    let len = u32::from_be_bytes(body[8..12].try_into().unwrap());
+    if len > MAX_RECORD_LEN {
+        return Ok(RecordFrame::Corrupted(format!(
+            "record length {} exceeds the limit {}",
+            len, MAX_RECORD_LEN
+        )));
+    }
+
+    body.resize(12 + len as usize, 0);
+    if !read_exact_or_eof(reader, &mut body[12..]).await? {
+        return Ok(RecordFrame::Truncated);
+    }
+
+    let actual_crc = calc_crc32(&body);
+    if actual_crc != crc {
+        return Ok(RecordFrame::Corrupted(format!(
+            "checksum mismatch for offset {}, expected {:#010x}, actual {:#010x}",
+            offset, crc, actual_crc
+        )));
+    }
+
+    Ok(RecordFrame::Records {
+        records: vec![FrameRecord {
+            offset,
+            data: Bytes::from(body).slice(12..),
+        }],
+        size: RECORD_HEADER_LEN + len as u64,
+    })
+}
+
+async fn read_batch_body<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    crc: u32,
+) -> Result<RecordFrame, JournalServerError> {
+    // [offset: u64][len: u32][codec: u8][count: u32][payload: bytes], the part covered by the
+    // checksum
+    let mut body = vec![0; 17];
+    if !read_exact_or_eof(reader, &mut body).await? {
+        return Ok(RecordFrame::Truncated);
+    }
+    let offset = u64::from_be_bytes(body[0..8].try_into().unwrap());
+    let len = u32::from_be_bytes(body[8..12].try_into().unwrap());
+    let codec = body[12];
+    let count = u32::from_be_bytes(body[13..17].try_into().unwrap());
+    if len > MAX_RECORD_LEN {
+        return Ok(RecordFrame::Corrupted(format!(
+            "batch length {} exceeds the limit {}",
+            len, MAX_RECORD_LEN
+        )));
+    }
+
+    body.resize(17 + len as usize, 0);
+    if !read_exact_or_eof(reader, &mut body[17..]).await? {
+        return Ok(RecordFrame::Truncated);
+    }
+
+    let actual_crc = calc_crc32(&body);
+    if actual_crc != crc {
+        return Ok(RecordFrame::Corrupted(format!(
+            "checksum mismatch for the batch at offset {}, expected {:#010x}, actual {:#010x}",
+            offset, crc, actual_crc
+        )));
+    }
+
+    if codec_from_id(codec).is_none() {
+        return Ok(RecordFrame::Corrupted(format!(
+            "unknown compression codec {} for the batch at offset {}",
+            codec, offset
+        )));
+    }
+
+    Ok(RecordFrame::Batch {
+        batch: StoredBatch {
+            offset,
+            codec,
+            record_num: count,
+            payload: Bytes::from(body).slice(17..),
+        },
+        size: BATCH_HEADER_LEN + len as u64,
+    })
+}
+
+async fn read_legacy_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<RecordFrame, JournalServerError> {
+    // the rest of [offset: u64][len: u32], the first byte of the offset has already been read
+    let mut header = [0; 12];
+    if !read_exact_or_eof(reader, &mut header[1..]).await? {
+        return Ok(RecordFrame::Truncated);
+    }
+    let offset = u64::from_be_bytes(header[0..8].try_into().unwrap());
+    let len = u32::from_be_bytes(header[8..12].try_into().unwrap());
+    // every record written in the legacy format has data, an empty one is what zero-filled
+    // space at the tail of a segment file looks like
+    if len == 0 {
+        return Ok(RecordFrame::Corrupted(format!(
+            "legacy record at offset {} has no data",
+            offset
+        )));
+    }
+    if len > MAX_RECORD_LEN {
+        return Ok(RecordFrame::Corrupted(format!(
+            "record length {} exceeds the limit {}",
+            len, MAX_RECORD_LEN
+        )));
+    }
+
+    let mut data = vec![0; len as usize];
+    if !read_exact_or_eof(reader, &mut data).await? {
+        return Ok(RecordFrame::Truncated);
+    }
+
+    Ok(RecordFrame::Records {
+        records: vec![FrameRecord {
+            offset,
+            data: Bytes::from(data),
+        }],
+        size: LEGACY_RECORD_HEADER_LEN + len as u64,
+    })
+}
+
+/// fill `buf` from `reader`, returning false if the reader ends first
+async fn read_exact_or_eof<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &mut [u8],
+) -> Result<bool, JournalServerError> {
+    match reader.read_exact(buf).await {
+        Ok(_) => Ok(true),
+        Err(e) => {
+            if e.kind() == ErrorKind::UnexpectedEof {
+                return Ok(false);
+            }
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use common_base::utils::crc::calc_crc32;
+    use metadata_struct::journal::shard::CompressionType;
+    use prost::Message;
+    use protocol::journal_server::journal_record::JournalRecord;
+
+    use super::{
+        decode_record, encode_batch, encode_record, read_record_frame, RecordFrame,
+        BATCH_HEADER_LEN, RECORD_HEADER_LEN,
+    };
+
+    fn build_record(offset: i64) -> JournalRecord {
+        JournalRecord {
+            content: format!("data-{}", offset).as_bytes().to_vec(),
+            key: format!("k{}", offset),
+            offset,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn read_record_frame_test() {
+        let mut buf = encode_record(&build_record(10));
+        buf.extend(encode_record(&build_record(11)));
+        let mut reader = Cursor::new(buf);
+
+        for offset in 10..12 {
+            match read_record_frame(&mut reader).await.unwrap() {
+                RecordFrame::Records { mut records, size } => {
+                    assert_eq!(records.len(), 1);
+                    let frame_record = records.remove(0);
+                    assert_eq!(frame_record.offset, offset);
+                    let record = decode_record(frame_record.data).unwrap();
+                    assert_eq!(record, build_record(offset as i64));
+                    assert_eq!(size, RECORD_HEADER_LEN + record.encoded_len() as u64);
+                }
+                frame => panic!("unexpected frame {:?}", frame),
+            }
+        }
+
+        assert!(matches!(
+            read_record_frame(&mut reader).await.unwrap(),
+            RecordFrame::End
+        ));
+    }
+
+    #[tokio::test]
+    async fn legacy_record_frame_test() {
+        let record = build_record(3);
+        let data = record.encode_to_vec();
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&3u64.to_be_bytes());
+        buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&data);
+
+        match read_record_frame(&mut Cursor::new(buf)).await.unwrap() {
+            RecordFrame::Records { records, size } => {
+                assert_eq!(records.len(), 1);
+                assert_eq!(records[0].offset, 3);
+                assert_eq!(decode_record(records[0].data.clone()).unwrap(), record);
+                assert_eq!(size, 12 + record.encoded_len() as u64);
+            }
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+    }
+
+    #[tokio::test]
+    async fn zero_filled_frame_test() {
+        let mut reader = Cursor::new(vec![0; 64]);
+        assert!(matches!(
+            read_record_frame(&mut reader).await.unwrap(),
+            RecordFrame::Corrupted(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn truncated_record_frame_test() {
+        let buf = encode_record(&build_record(1));
+        for len in [1, 5, 17, buf.len() - 1] {
+            let mut reader = Cursor::new(buf[..len].to_vec());
+            assert!(matches!(
+                read_record_frame(&mut reader).await.unwrap(),
+                RecordFrame::Truncated
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn corrupted_record_frame_test() {
+        let buf = encode_record(&build_record(1));
+
+        // a flipped bit in the data
+        let mut corrupted = buf.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0x01;
+        assert!(matches!(
+            read_record_frame(&mut Cursor::new(corrupted))
+                .await
+                .unwrap(),
+            RecordFrame::Corrupted(_)
+        ));
+
+        // a flipped bit in the offset
+        let mut corrupted = buf.clone();
+        corrupted[13] ^= 0x01;
+        assert!(matches!(
+            read_record_frame(&mut Cursor::new(corrupted))
+                .await
+                .unwrap(),
+            RecordFrame::Corrupted(_)
+        ));
+
+        // an unknown magic
+        let mut corrupted = buf.clone();
+        corrupted[0] = 0xFF;
+        assert!(matches!(
+            read_record_frame(&mut Cursor::new(corrupted))
+                .await
+                .unwrap(),
+            RecordFrame::Corrupted(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn batch_frame_test() {
+        let records: Vec<JournalRecord> = (20..30).map(build_record).collect();
+        for compression in [
+            CompressionType::None,
+            CompressionType::Lz4,
+            CompressionType::Zstd,
+            CompressionType::Snappy,
+        ] {
+            let buf = encode_batch(&records, compression).unwrap();
+            let len = buf.len() as u64;
+            let mut reader = Cursor::new(buf);
+
+            match read_record_frame(&mut reader).await.unwrap() {
+                RecordFrame::Batch { batch, size } => {
+                    assert_eq!(size, len);
+                    assert_eq!(batch.offset, 20);
+                    assert_eq!(batch.record_num, records.len() as u32);
+                    let frame_records = batch.records().unwrap();
+                    assert_eq!(frame_records.len(), records.len());
+                    for (frame_record, record) in frame_records.into_iter().zip(records.iter()) {
+                        assert_eq!(frame_record.offset, record.offset as u64);
+                        assert_eq!(&decode_record(frame_record.data).unwrap(), record);
+                    }
+                }
+                frame => panic!("unexpected frame {:?}", frame),
+            }
+
+            assert!(matches!(
+                read_record_frame(&mut reader).await.unwrap(),
+                RecordFrame::End
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn mixed_frame_test() {
+        // a segment file written before and after compression was enabled for the shard
+        let mut buf = encode_record(&build_record(0));
+        buf.extend(
+            encode_batch(&[build_record(1), build_record(2)], CompressionType::Lz4).unwrap(),
+        );
+        buf.extend(encode_record(&build_record(3)));
+        let mut reader = Cursor::new(buf);
+
+        let mut offsets = Vec::new();
+        loop {
+            let records = match read_record_frame(&mut reader).await.unwrap() {
+                RecordFrame::Records { records, .. } => records,
+                RecordFrame::Batch { batch, .. } => batch.records().unwrap(),
+                _ => break,
+            };
+            offsets.extend(records.iter().map(|record| record.offset));
+        }
+        assert_eq!(offsets, vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn corrupted_batch_frame_test() {
+        let buf = encode_batch(&[build_record(1), build_record(2)], CompressionType::Zstd).unwrap();
+
+        // torn in the middle of the payload
+        let mut reader = Cursor::new(buf[..buf.len() - 1].to_vec());
+        assert!(matches!(
+            read_record_frame(&mut reader).await.unwrap(),
+            RecordFrame::Truncated
+        ));
+
+        // a flipped bit in the payload
+        let mut corrupted = buf.clone();
+        corrupted[BATCH_HEADER_LEN as usize] ^= 0x01;
+        assert!(matches!(
+            read_record_frame(&mut Cursor::new(corrupted))
+                .await
+                .unwrap(),
+            RecordFrame::Corrupted(_)
+        ));
+
+        // an unknown codec with a valid checksum
+        let mut corrupted = buf.clone();
+        corrupted[18] = 0xFF;
+        let crc = calc_crc32(&corrupted[6..]);
+        corrupted[2..6].copy_from_slice(&crc.to_be_bytes());
+        match read_record_frame(&mut Cursor::new(corrupted))
+            .await
+            .unwrap()
+        {
+            RecordFrame::Corrupted(reason) => assert!(reason.contains("codec")),
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use log::{info, warn};
+use rocksdb_engine::RocksDBEngine;
+
+use super::file::{SegmentFile, SegmentScanResult};
+use super::manager::{SegmentFileManager, SegmentFileMetadata};
+use super::SegmentIdentity;
+use crate::core::error::JournalServerError;
+use crate::index::build::rebuild_segment_index;
+use crate::index::offset::OffsetIndexManager;
+use crate::index::time::TimestampIndexManager;
+
+/// Check every local segment file loaded by `load_local_segment_cache` when the node starts.
+///
+/// A segment file that ends with a torn write is truncated to its last valid record, and the
+/// indexes of a segment that no longer match its segment file are rebuilt from the file. A
+/// segment file corrupted anywhere else fails the recovery, so that its records are not dropped
+/// without an operator looking at it.
+pub async fn recover_local_segment_files(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    segment_file_manager: &Arc<SegmentFileManager>,
+    local_data_folds: &[String],
+) -> Result<(), JournalServerError> {
+    let segment_files: Vec<SegmentFileMetadata> = segment_file_manager
+        .segment_files
+        .iter()
+        .map(|raw| raw.value().clone())
+        .collect();
+
+    for metadata in segment_files {
+        let segment_iden = SegmentIdentity::new(
+            &metadata.namespace,
+            &metadata.shard_name,
+            metadata.segment_no,
+        );
+
+        let segment_file = local_data_folds
+            .iter()
+            .map(|fold| {
+                SegmentFile::new(
+                    metadata.namespace.clone(),
+                    metadata.shard_name.clone(),
+                    metadata.segment_no,
+                    fold.clone(),
+                )
+            })
+            .find(|segment_file| segment_file.exists());
+
+        if let Some(segment_file) = segment_file {
+            let metadata =
+                recover_segment_file(rocksdb_engine_handler, &segment_iden, &segment_file).await?;
+            segment_file_manager.add_segment_file(metadata);
+        }
+    }
+    Ok(())
+}
+
+/// Recover a single segment file, returning its metadata as found in the segment file.
+pub async fn recover_segment_file(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    segment_iden: &SegmentIdentity,
+    segment_file: &SegmentFile,
+) -> Result<SegmentFileMetadata, JournalServerError> {
+    let scan = segment_file.scan().await?;
+    let mut rebuild_index = false;
+
+    if scan.is_damaged() {
+        let (reason, torn_tail) = scan
+            .error
+            .as_ref()
+            .map(|e| (e.reason.clone(), e.torn_tail))
+            .unwrap_or_default();
+
+        // only a torn write at the tail may be dropped, valid records may follow a corrupted
+        // record in the middle of the segment file
+        if !torn_tail {
+            return Err(JournalServerError::SegmentRecordCorrupted(
+                segment_file.name(),
+                scan.valid_size,
+                reason,
+            ));
+        }
+
+        warn!(
+            "Segment file {} is damaged at position {}: {}, truncating {} bytes, the last valid offset is {}",
+            segment_file.name(),
+            scan.valid_size,
+            reason,
+            scan.file_size - scan.valid_size,
+            scan.end_offset
+        );
+        segment_file.truncate(scan.valid_size).await?;
+        rebuild_index = true;
+    }
+
+    let offset_index = OffsetIndexManager::new(rocksdb_engine_handler.clone());
+    if offset_index.get_start_offset(segment_iden)? != scan.start_offset
+        || offset_index.get_end_offset(segment_iden)? != scan.end_offset
+    {
+        rebuild_index = true;
+    }
+
+    if rebuild_index {
+        rebuild_segment_index(
+            rocksdb_engine_handler,
+            segment_iden,
+            segment_file,
+            scan.start_offset.max(0) as u64,
+        )
+        .await?;
+        save_segment_range(rocksdb_engine_handler, segment_iden, &scan)?;
+        info!(
+            "The index of segment {} was rebuilt, {} records from offset {} to {}",
+            segment_iden.name(),
+            scan.record_num,
+            scan.start_offset,
+            scan.end_offset
+        );
+    }
+
+    Ok(SegmentFileMetadata {
+        namespace: segment_iden.namespace.clone(),
+        shard_name: segment_iden.shard_name.clone(),
+        segment_no: segment_iden.segment_seq,
+        start_offset: scan.start_offset,
+        end_offset: scan.end_offset,
+        start_timestamp: scan.start_timestamp,
+        end_timestamp: scan.end_timestamp,
+    })
+}
+
+fn save_segment_range(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    segment_iden: &SegmentIdentity,
+    scan: &SegmentScanResult,
+) -> Result<(), JournalServerError> {
+    // an empty segment file has no range
+    if scan.record_num == 0 {
+        return Ok(());
+    }
+
+    let offset_index = OffsetIndexManager::new(rocksdb_engine_handler.clone());
+    offset_index.save_start_offset(segment_iden, scan.start_offset as u64)?;
+    offset_index.save_end_offset(segment_iden, scan.end_offset as u64)?;
+
+    let timestamp_index = TimestampIndexManager::new(rocksdb_engine_handler.clone());
+    timestamp_index.save_start_timestamp(segment_iden, scan.start_timestamp as u64)?;
+    timestamp_index.save_end_timestamp(segment_iden, scan.end_timestamp as u64)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::now_second;
+    use protocol::journal_server::journal_record::JournalRecord;
+    use rocksdb_engine::RocksDBEngine;
+    use tokio::fs::OpenOptions;
+    use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+    use super::recover_segment_file;
+    use crate::core::error::JournalServerError;
+    use crate::core::test::{test_build_data_fold, test_build_rocksdb_sgement};
+    use crate::index::offset::OffsetIndexManager;
+    use crate::index::tag::TagIndexManager;
+    use crate::segment::file::{data_file_segment, SegmentFile};
+    use crate::segment::SegmentIdentity;
+
+    async fn build_test_segment_file() -> (
+        Arc<RocksDBEngine>,
+        SegmentIdentity,
+        SegmentFile,
+        Vec<JournalRecord>,
+        u64,
+    ) {
+        let (rocksdb_engine_handler, segment_iden) = test_build_rocksdb_sgement();
+        let data_fold = test_build_data_fold();
+        let segment_file = SegmentFile::new(
+            segment_iden.namespace.clone(),
+            segment_iden.shard_name.clone(),
+            segment_iden.segment_seq,
+            data_fold.first().unwrap().to_string(),
+        );
+        segment_file.try_create().await.unwrap();
+
+        let records: Vec<JournalRecord> = (0..10)
+            .map(|i| JournalRecord {
+                content: format!("data-{}", i).as_bytes().to_vec(),
+                create_time: now_second() + i,
+                key: format!("k{}", i),
+                tags: vec!["t1".to_string()],
+                offset: i as i64,
+                ..Default::default()
+            })
+            .collect();
+        let size = segment_file.write(&records).await.unwrap();
+        (
+            rocksdb_engine_handler,
+            segment_iden,
+            segment_file,
+            records,
+            size,
+        )
+    }
+
+    #[tokio::test]
+    async fn recover_torn_segment_file_test() {
+        let (rocksdb_engine_handler, segment_iden, segment_file, records, size) =
+            build_test_segment_file().await;
+
+        // a crash in the middle of appending a record
+        let path = data_file_segment(&segment_file.data_fold, segment_file.segment_no);
+        let mut file = OpenOptions::new().append(true).open(&path).await.unwrap();
+        file.write_all(&[0xA7, 0x01, 0x00]).await.unwrap();
+        file.flush().await.unwrap();
+
+        let metadata = recover_segment_file(&rocksdb_engine_handler, &segment_iden, &segment_file)
+            .await
+            .unwrap();
+        assert_eq!(metadata.start_offset, 0);
+        assert_eq!(metadata.end_offset, 9);
+        assert_eq!(
+            metadata.end_timestamp,
+            records.last().unwrap().create_time as i64
+        );
+        assert_eq!(segment_file.size().await.unwrap(), size);
+
+        // the indexes are rebuilt from the segment file
+        let offset_index = OffsetIndexManager::new(rocksdb_engine_handler.clone());
+        assert_eq!(offset_index.get_start_offset(&segment_iden).unwrap(), 0);
+        assert_eq!(offset_index.get_end_offset(&segment_iden).unwrap(), 9);
+        let tag_index = TagIndexManager::new(rocksdb_engine_handler.clone());
+        let positions = tag_index
+            .get_last_positions_by_tag(&segment_iden, 0, "t1".to_string(), 100)
+            .await
+            .unwrap();
+        assert_eq!(positions.len(), 10);
+
+        // the segment file is readable and writable again
+        let res = segment_file
+            .read_by_offset(0, 0, 1024 * 1024, 1000)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 10);
+
+        let scan = segment_file.scan().await.unwrap();
+        assert!(!scan.is_damaged());
+        assert_eq!(scan.record_num, 10);
+    }
+
+    #[tokio::test]
+    async fn recover_zero_filled_segment_file_test() {
+        let (rocksdb_engine_handler, segment_iden, segment_file, _, size) =
+            build_test_segment_file().await;
+
+        // space allocated by the file system but never written before a crash
+        let path = data_file_segment(&segment_file.data_fold, segment_file.segment_no);
+        let mut file = OpenOptions::new().append(true).open(&path).await.unwrap();
+        file.write_all(&[0; 4096]).await.unwrap();
+        file.flush().await.unwrap();
+
+        let metadata = recover_segment_file(&rocksdb_engine_handler, &segment_iden, &segment_file)
+            .await
+            .unwrap();
+        assert_eq!(metadata.end_offset, 9);
+        assert_eq!(segment_file.size().await.unwrap(), size);
+    }
+
+    #[tokio::test]
+    async fn recover_corrupted_segment_file_test() {
+        let (rocksdb_engine_handler, segment_iden, segment_file, _, size) =
+            build_test_segment_file().await;
+
+        // a flipped bit in the first record, with valid records after it
+        let path = data_file_segment(&segment_file.data_fold, segment_file.segment_no);
+        let mut file = OpenOptions::new().write(true).open(&path).await.unwrap();
+        file.seek(std::io::SeekFrom::Start(20)).await.unwrap();
+        file.write_all(&[0xFF]).await.unwrap();
+        file.flush().await.unwrap();
+
+        let scan = segment_file.scan().await.unwrap();
+        let error = scan.error.unwrap();
+        assert_eq!(error.position, 0);
+        assert!(!error.torn_tail);
+
+        let res = recover_segment_file(&rocksdb_engine_handler, &segment_iden, &segment_file).await;
+        assert!(matches!(
+            res,
+            Err(JournalServerError::SegmentRecordCorrupted(_, 0, _))
+        ));
+        // nothing is dropped
+        assert_eq!(segment_file.size().await.unwrap(), size);
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -25,8 +26,13 @@
 use tokio::time::sleep;
 
 use super::file::open_segment_write;
-use super::manager::SegmentFileManager;
+use super::manager::{SegmentFileManager, SegmentFileMetadata};
+use super::SegmentIdentity;
 use crate::core::cache::CacheManager;
+use crate::core::metrics::{
+    record_segment_scroll_event, record_shard_disk_usage, SCROLL_EVENT_CREATE_NEXT_SEGMENT,
+    SCROLL_EVENT_PRE_SEALUP,
+};
 use crate::core::segment_meta::update_end_and_start_offset;
 use crate::core::segment_status::pre_sealup_segment;
 
@@ -59,6 +65,7 @@ pub async fn trigger_segment_scroll(&self) {
         let conf = journal_server_conf();
         info!("Segment scroll thread started successfully");
         loop {
+            self.report_shard_disk_usage().await;
             for segment_iden in self.cache_manager.get_leader_segment() {
                 let (segment_write, max_size) =
                     match open_segment_write(&self.cache_manager, &segment_iden).await {
@@ -105,6 +112,10 @@ pub async fn trigger_segment_scroll(&self) {
                         .await
                     {
                         Ok(_) => {
+                            record_segment_scroll_event(
+                                &segment_iden,
+                                SCROLL_EVENT_CREATE_NEXT_SEGMENT,
+                            );
                             self.percentage50_cache.insert(key.clone(), now_second());
                         }
                         Err(e) => {
@@ -146,6 +157,7 @@ pub async fn trigger_segment_scroll(&self) {
                             continue;
                         }
 
+                        record_segment_scroll_event(&segment_iden, SCROLL_EVENT_PRE_SEALUP);
                         self.percentage90_cache.insert(key.clone(), now_second());
                     } else {
                         error!("When the file size is 90%, try adjusting the segment state. The segment file metadata does not exist, maybe a file is missing.")
@@ -156,6 +168,37 @@ pub async fn trigger_segment_scroll(&self) {
         }
     }
 
+    // the number and the size of the segment files of each shard on this node
+    async fn report_shard_disk_usage(&self) {
+        let mut usage: HashMap<(String, String), (i64, i64)> = HashMap::new();
+        let segment_files: Vec<SegmentFileMetadata> = self
+            .segment_file_manager
+            .segment_files
+            .iter()
+            .map(|segment_file| segment_file.value().clone())
+            .collect();
+        for segment_file in segment_files {
+            let segment_iden = SegmentIdentity::new(
+                &segment_file.namespace,
+                &segment_file.shard_name,
+                segment_file.segment_no,
+            );
+            let size = match open_segment_write(&self.cache_manager, &segment_iden).await {
+                Ok((segment_write, _)) => segment_write.size().await.unwrap_or(0),
+                Err(_) => 0,
+            };
+            let shard_usage = usage
+                .entry((segment_iden.namespace, segment_iden.shard_name))
+                .or_default();
+            shard_usage.0 += 1;
+            shard_usage.1 += size as i64;
+        }
+
+        for ((namespace, shard_name), (segment_num, bytes)) in usage {
+            record_shard_disk_usage(&namespace, &shard_name, segment_num, bytes);
+        }
+    }
+
     async fn calc_end_offset(&self) -> u64 {
         // todo
         10000
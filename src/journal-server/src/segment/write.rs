@@ -14,16 +14,21 @@
 
 use crate::core::cache::CacheManager;
 use crate::core::error::{get_journal_server_code, JournalServerError};
+use crate::core::metrics::record_write_metrics;
 use crate::core::segment_meta::{update_meta_end_timestamp, update_meta_start_timestamp};
 use crate::core::segment_status::sealup_segment;
 use crate::index::build::try_trigger_build_index;
+use crate::isr::replica::wait_replica_ack;
+use crate::segment::compression::get_compression;
 use crate::segment::file::{open_segment_write, SegmentFile};
+use crate::segment::fsync::{get_fsync_policy, FsyncTracker};
 use crate::segment::manager::SegmentFileManager;
 use crate::segment::SegmentIdentity;
-use common_base::tools::now_second;
+use common_base::tools::{now_mills, now_second};
 use grpc_clients::pool::ClientPool;
 use log::{error, warn};
 use metadata_struct::journal::segment::SegmentStatus;
+use metadata_struct::journal::shard::FsyncPolicy;
 use protocol::journal_server::journal_engine::{
     WriteReqBody, WriteRespMessage, WriteRespMessageStatus,
 };
@@ -35,7 +40,10 @@
 use tokio::select;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::{broadcast, oneshot};
-use tokio::time::{sleep, timeout};
+use tokio::time::{interval, sleep, timeout};
+
+/// the maximum number of write requests committed together by the write thread of a segment
+const MAX_GROUP_COMMIT_PACKETS: usize = 100;
 
 /// the write handle for a segment
 #[derive(Clone)]
@@ -141,6 +149,15 @@ pub async fn write_data_req(
             return Err(e);
         }
 
+        // only acknowledge the write once the in-sync replicas have persisted it
+        wait_replica_ack(
+            cache_manager,
+            segment_file_manager,
+            &segment_iden,
+            resp.last_offset,
+        )
+        .await?;
+
         let mut resp_message_status = Vec::new();
         for (pkid, offset) in resp.offsets {
             let status = WriteRespMessageStatus {
@@ -257,6 +274,8 @@ pub(crate) async fn create_write_thread(
         };
 
     let (segment_write, _) = open_segment_write(cache_manager, segment_iden).await?;
+    let fsync_tracker =
+        FsyncTracker::new(get_fsync_policy(cache_manager, segment_iden)?, now_mills());
 
     create_write_thread0(
         rocksdb_engine_handler.clone(),
@@ -267,6 +286,7 @@ pub(crate) async fn create_write_thread(
         segment_write,
         data_recv,
         stop_recv,
+        fsync_tracker,
     )
     .await;
 
@@ -279,6 +299,9 @@ pub(crate) async fn create_write_thread(
 }
 
 /// spawn the write thread for a segment
+///
+/// The write requests queued up while the previous group was being written are committed
+/// together: their records are appended with a single write and covered by a single fsync.
 #[allow(clippy::too_many_arguments)]
 async fn create_write_thread0(
     rocksdb_engine_handler: Arc<RocksDBEngine>,
@@ -289,18 +312,29 @@ async fn create_write_thread0(
     segment_write: SegmentFile,
     mut data_recv: Receiver<SegmentWriteData>,
     mut stop_recv: broadcast::Receiver<bool>,
+    mut fsync_tracker: FsyncTracker,
 ) {
     tokio::spawn(async move {
+        let mut fsync_check = interval(fsync_check_interval(fsync_tracker.policy()));
         loop {
             select! {
                 val = stop_recv.recv() =>{
                     if let Ok(flag) = val {
                         if flag {
+                            if let Err(e) = fsync_tracker.flush(&segment_write, now_mills()).await {
+                                error!("Failed to fsync segment {} when the write thread exited, error message: {}",
+                                    segment_iden.name(), e);
+                            }
                             cache_manager.remove_segment_write_thread(&segment_iden);
                             break;
                         }
                     }
                 },
+                _ = fsync_check.tick() => {
+                    if let Err(e) = fsync_tracker.try_sync(&segment_write, now_mills()).await {
+                        error!("Failed to fsync segment {}, error message: {}", segment_iden.name(), e);
+                    }
+                },
                 val = data_recv.recv()=>{
 
                     if val.is_none(){
@@ -308,130 +342,235 @@ async fn create_write_thread0(
                         continue;
                     }
 
-                    let packet = val.unwrap();
+                    let mut packets = vec![val.unwrap()];
+                    while packets.len() < MAX_GROUP_COMMIT_PACKETS {
+                        match data_recv.try_recv() {
+                            Ok(packet) => packets.push(packet),
+                            Err(_) => break,
+                        }
+                    }
 
-                    let resp = match batch_write(
+                    local_segment_end_offset = group_write(
                         &rocksdb_engine_handler,
                         &segment_iden,
                         &segment_file_manager,
                         &cache_manager,
+                        &mut fsync_tracker,
                         local_segment_end_offset,
                         &segment_write,
-                        packet.data
-                    ).await{
-                        Ok(Some(resp)) => {
-                            local_segment_end_offset = resp.last_offset as i64;
-                            resp
-                        },
-                        Ok(None) =>{
-                            sleep(Duration::from_millis(100)).await;
-                            continue;
-                        },
-                        Err(e) => {
-                            SegmentWriteResp {
-                                error: Some(e),
-                                ..Default::default()
-                            }
-                        }
-                    };
-
-
-                    if packet.resp_sx.send(resp).is_err(){
-                        error!("Write data to the Segment file, write success, call the oneshot channel to return the write information failed. Failure message");
-                    }
+                        packets
+                    ).await;
                 }
             }
         }
     });
 }
 
-/// validate whether the data can be written to the segment, write the data to the segment file and update the index
+/// how often the write thread checks whether an fsync is due while no data is written
+fn fsync_check_interval(policy: FsyncPolicy) -> Duration {
+    match policy {
+        FsyncPolicy::IntervalMs(interval_ms) => Duration::from_millis(interval_ms.max(1)),
+        _ => Duration::from_millis(1000),
+    }
+}
+
+/// validate each write request, write the accepted records to the segment file, fsync them
+/// according to the fsync policy, update the index and respond to every request
+///
+/// Returns the local end offset of the segment after the write.
 ///
 /// Note that this function will be executed serially by the write thread of the segment
-async fn batch_write(
+#[allow(clippy::too_many_arguments)]
+async fn group_write(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     segment_iden: &SegmentIdentity,
     segment_file_manager: &Arc<SegmentFileManager>,
     cache_manager: &Arc<CacheManager>,
+    fsync_tracker: &mut FsyncTracker,
     local_segment_end_offset: i64,
     segment_write: &SegmentFile,
-    data: Vec<JournalRecord>,
-) -> Result<Option<SegmentWriteResp>, JournalServerError> {
-    if data.is_empty() {
-        return Ok(None);
+    packets: Vec<SegmentWriteData>,
+) -> i64 {
+    let mut end_offset = local_segment_end_offset;
+    let mut records = Vec::new();
+    let mut accepted = Vec::new();
+    for packet in packets {
+        // the sender of an empty request gets no response
+        if packet.data.is_empty() {
+            continue;
+        }
+
+        if let Err(e) = write_validator(
+            cache_manager,
+            segment_write,
+            end_offset as u64,
+            packet.data.len() as u64,
+        )
+        .await
+        {
+            send_write_resp(
+                packet.resp_sx,
+                SegmentWriteResp {
+                    error: Some(e),
+                    ..Default::default()
+                },
+            );
+            continue;
+        }
+
+        let resp = build_write_records(packet.data, &mut end_offset, &mut records);
+        accepted.push((packet.resp_sx, resp));
+    }
+
+    if records.is_empty() {
+        return local_segment_end_offset;
     }
 
-    write_validator(
+    let (end_offset, result) = batch_write(
+        rocksdb_engine_handler,
+        segment_iden,
+        segment_file_manager,
         cache_manager,
+        fsync_tracker,
+        local_segment_end_offset,
         segment_write,
-        local_segment_end_offset as u64,
-        data.len() as u64,
+        &records,
     )
-    .await?;
+    .await;
 
-    let resp = batch_write0(
-        data,
-        segment_write,
-        segment_file_manager,
-        segment_iden,
-        local_segment_end_offset as u64,
-    )
-    .await?;
+    match result {
+        Ok(()) => {
+            for (resp_sx, resp) in accepted {
+                send_write_resp(resp_sx, resp);
+            }
+        }
+        Err(e) => {
+            error!(
+                "Failed to write data to segment {}, error message: {}",
+                segment_iden.name(),
+                e
+            );
+            for (resp_sx, _) in accepted {
+                send_write_resp(
+                    resp_sx,
+                    SegmentWriteResp {
+                        error: Some(JournalServerError::SegmentWriteFailed(
+                            segment_iden.name(),
+                            e.to_string(),
+                        )),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
 
-    try_trigger_build_index(
-        cache_manager,
-        segment_file_manager,
-        rocksdb_engine_handler,
-        segment_iden,
-    )
-    .await?;
+    end_offset
+}
 
-    Ok(resp)
+/// assign offsets to the records of a write request, appending them to `records`
+fn build_write_records(
+    data: Vec<JournalRecord>,
+    local_segment_end_offset: &mut i64,
+    records: &mut Vec<JournalRecord>,
+) -> SegmentWriteResp {
+    let mut offsets = HashMap::new();
+    for mut record in data {
+        let offset = *local_segment_end_offset + 1;
+        record.offset = offset;
+        offsets.insert(record.pkid, offset as u64);
+        records.push(record);
+        *local_segment_end_offset = offset;
+    }
+
+    SegmentWriteResp {
+        offsets,
+        last_offset: *local_segment_end_offset as u64,
+        ..Default::default()
+    }
 }
 
-/// write a batch of data to the segment file
+/// write a batch of records to the segment file, compressed with the compression of the shard,
+/// fsync them if it is due and update the index
+///
+/// Returns the local end offset of the segment, which only moves forward once the records are
+/// written to the segment file, and the result of the write.
 ///
 /// Note that this function will be executed serially by the write thread of the segment
-async fn batch_write0(
-    data: Vec<JournalRecord>,
-    segment_write: &SegmentFile,
+#[allow(clippy::too_many_arguments)]
+async fn batch_write(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    segment_iden: &SegmentIdentity,
     segment_file_manager: &Arc<SegmentFileManager>,
+    cache_manager: &Arc<CacheManager>,
+    fsync_tracker: &mut FsyncTracker,
+    local_segment_end_offset: i64,
+    segment_write: &SegmentFile,
+    records: &[JournalRecord],
+) -> (i64, Result<(), JournalServerError>) {
+    let compression = match get_compression(cache_manager, segment_iden) {
+        Ok(compression) => compression,
+        Err(e) => return (local_segment_end_offset, Err(e)),
+    };
+    let size = match segment_write.write_batch(records, compression).await {
+        Ok(size) => size,
+        Err(e) => return (local_segment_end_offset, Err(e)),
+    };
+    fsync_tracker.record_write(size);
+    record_write_metrics(segment_iden, records.len() as u64, size);
+
+    let record = records.last().unwrap();
+    let result = batch_write0(
+        rocksdb_engine_handler,
+        segment_iden,
+        segment_file_manager,
+        cache_manager,
+        fsync_tracker,
+        segment_write,
+        record,
+    )
+    .await;
+    (record.offset, result)
+}
+
+/// update the end offset of the segment after a batch of records was written, then fsync and
+/// update the index
+async fn batch_write0(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
     segment_iden: &SegmentIdentity,
-    mut local_segment_end_offset: u64,
-) -> Result<Option<SegmentWriteResp>, JournalServerError> {
-    if data.is_empty() {
-        return Ok(None);
-    }
+    segment_file_manager: &Arc<SegmentFileManager>,
+    cache_manager: &Arc<CacheManager>,
+    fsync_tracker: &mut FsyncTracker,
+    segment_write: &SegmentFile,
+    last_record: &JournalRecord,
+) -> Result<(), JournalServerError> {
+    segment_file_manager.update_end_offset(segment_iden, last_record.offset)?;
+    segment_file_manager.update_end_timestamp(segment_iden, last_record.create_time)?;
 
-    // build write data
-    let mut offsets = HashMap::new();
-    let mut records = Vec::new();
-    for mut record in data.clone() {
-        let offset = local_segment_end_offset + 1;
-        record.offset = offset as i64;
-        records.push(record.clone());
+    fsync_tracker.try_sync(segment_write, now_mills()).await?;
 
-        offsets.insert(record.pkid, offset);
-        local_segment_end_offset = offset;
+    // the records are durable at this point, the index build is triggered again by the next
+    // batch, so failing to trigger it must not fail the write
+    if let Err(e) = try_trigger_build_index(
+        cache_manager,
+        segment_file_manager,
+        rocksdb_engine_handler,
+        segment_iden,
+    )
+    .await
+    {
+        error!(
+            "Failed to trigger the index build of segment {}, error message: {}",
+            segment_iden.name(),
+            e
+        );
     }
+    Ok(())
+}
 
-    // batch write data
-    match segment_write.write(&records).await {
-        Ok(_) => {
-            let record = records.last().unwrap();
-            segment_file_manager.update_end_offset(segment_iden, record.offset)?;
-            segment_file_manager.update_end_timestamp(segment_iden, record.create_time)?;
-
-            Ok(Some(SegmentWriteResp {
-                offsets: offsets.clone(),
-                last_offset: record.offset as u64,
-                ..Default::default()
-            }))
-        }
-        Err(e) => Ok(Some(SegmentWriteResp {
-            error: Some(e),
-            ..Default::default()
-        })),
+fn send_write_resp(resp_sx: oneshot::Sender<SegmentWriteResp>, resp: SegmentWriteResp) {
+    if resp_sx.send(resp).is_err() {
+        error!("Write data to the Segment file, write success, call the oneshot channel to return the write information failed. Failure message");
     }
 }
 
@@ -491,7 +630,7 @@ mod tests {
     use prost::Message;
     use protocol::journal_server::journal_record::JournalRecord;
 
-    use super::{create_write_thread, is_end_offset, write_data};
+    use super::{build_write_records, create_write_thread, is_end_offset, write_data};
     use crate::core::test::test_init_segment;
     use crate::segment::file::open_segment_write;
 
@@ -597,4 +736,103 @@ async fn write_test() {
             assert_eq!(i, row.record.offset as usize);
         }
     }
+
+    #[tokio::test]
+    async fn build_write_records_test() {
+        let mut end_offset = -1;
+        let mut records = Vec::new();
+
+        let data = (0..3)
+            .map(|i| JournalRecord {
+                pkid: i,
+                ..Default::default()
+            })
+            .collect();
+        let resp = build_write_records(data, &mut end_offset, &mut records);
+        assert_eq!(resp.last_offset, 2);
+        assert_eq!(resp.offsets.get(&1), Some(&1));
+
+        let data = (0..2)
+            .map(|i| JournalRecord {
+                pkid: i,
+                ..Default::default()
+            })
+            .collect();
+        let resp = build_write_records(data, &mut end_offset, &mut records);
+        assert_eq!(resp.last_offset, 4);
+        assert_eq!(resp.offsets.get(&0), Some(&3));
+
+        assert_eq!(end_offset, 4);
+        assert_eq!(
+            records.iter().map(|r| r.offset).collect::<Vec<i64>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+
+    #[tokio::test]
+    async fn group_write_test() {
+        let (segment_iden, cache_manager, segment_file_manager, _, rocksdb_engine_handler) =
+            test_init_segment().await;
+
+        create_write_thread(
+            &cache_manager,
+            &rocksdb_engine_handler,
+            &segment_file_manager,
+            &segment_iden,
+        )
+        .await
+        .unwrap();
+
+        // concurrent write requests may be committed together, each of them still gets a
+        // contiguous range of offsets
+        let mut handles = Vec::new();
+        for i in 0..5 {
+            let cache_manager = cache_manager.clone();
+            let rocksdb_engine_handler = rocksdb_engine_handler.clone();
+            let segment_file_manager = segment_file_manager.clone();
+            let segment_iden = segment_iden.clone();
+            handles.push(tokio::spawn(async move {
+                let data_list = (0..10)
+                    .map(|j| JournalRecord {
+                        namespace: segment_iden.namespace.clone(),
+                        shard_name: segment_iden.shard_name.clone(),
+                        segment: segment_iden.segment_seq,
+                        content: format!("data-{}-{}", i, j).encode_to_vec(),
+                        pkid: j,
+                        ..Default::default()
+                    })
+                    .collect();
+                write_data(
+                    &cache_manager,
+                    &rocksdb_engine_handler,
+                    &segment_file_manager,
+                    &segment_iden,
+                    data_list,
+                )
+                .await
+                .unwrap()
+            }));
+        }
+
+        let mut last_offsets = Vec::new();
+        for handle in handles {
+            let resp = handle.await.unwrap();
+            assert!(resp.error.is_none());
+            assert_eq!(resp.offsets.len(), 10);
+            assert_eq!(resp.offsets.get(&0), Some(&(resp.last_offset - 9)));
+            last_offsets.push(resp.last_offset);
+        }
+        last_offsets.sort();
+        assert_eq!(last_offsets, vec![9, 19, 29, 39, 49]);
+
+        let write = open_segment_write(&cache_manager, &segment_iden)
+            .await
+            .unwrap();
+        let res = write
+            .0
+            .read_by_offset(0, 0, 1024 * 1024 * 1024, 1000)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 50);
+    }
 }
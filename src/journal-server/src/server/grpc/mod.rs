@@ -14,4 +14,5 @@
 
 pub mod admin;
 pub mod inner;
+pub mod replica;
 pub mod server;
@@ -0,0 +1,67 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use protocol::journal_server::journal_replica::journal_server_replica_service_server::JournalServerReplicaService;
+use protocol::journal_server::journal_replica::{ReplicaFetchReply, ReplicaFetchRequest};
+use rocksdb_engine::RocksDBEngine;
+use tonic::{Request, Response, Status};
+
+use crate::core::cache::CacheManager;
+use crate::isr::replica::replica_fetch_by_req;
+use crate::segment::manager::SegmentFileManager;
+
+pub struct GrpcJournalServerReplicaService {
+    cache_manager: Arc<CacheManager>,
+    segment_file_manager: Arc<SegmentFileManager>,
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl GrpcJournalServerReplicaService {
+    pub fn new(
+        cache_manager: Arc<CacheManager>,
+        segment_file_manager: Arc<SegmentFileManager>,
+        rocksdb_engine_handler: Arc<RocksDBEngine>,
+    ) -> Self {
+        GrpcJournalServerReplicaService {
+            cache_manager,
+            segment_file_manager,
+            rocksdb_engine_handler,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl JournalServerReplicaService for GrpcJournalServerReplicaService {
+    async fn replica_fetch(
+        &self,
+        request: Request<ReplicaFetchRequest>,
+    ) -> Result<Response<ReplicaFetchReply>, Status> {
+        let remote_ip = request.remote_addr().map(|addr| addr.ip());
+        let req = request.into_inner();
+        match replica_fetch_by_req(
+            &self.cache_manager,
+            &self.segment_file_manager,
+            &self.rocksdb_engine_handler,
+            remote_ip,
+            &req,
+        )
+        .await
+        {
+            Ok(reply) => Ok(Response::new(reply)),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+}
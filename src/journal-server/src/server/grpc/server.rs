@@ -19,6 +19,7 @@
 use log::info;
 use protocol::journal_server::journal_admin::journal_server_admin_service_server::JournalServerAdminServiceServer;
 use protocol::journal_server::journal_inner::journal_server_inner_service_server::JournalServerInnerServiceServer;
+use protocol::journal_server::journal_replica::journal_server_replica_service_server::JournalServerReplicaServiceServer;
 use rocksdb_engine::RocksDBEngine;
 use tonic::transport::Server;
 
@@ -26,6 +27,7 @@
 use crate::segment::manager::SegmentFileManager;
 use crate::server::grpc::admin::GrpcJournalServerAdminService;
 use crate::server::grpc::inner::GrpcJournalServerInnerService;
+use crate::server::grpc::replica::GrpcJournalServerReplicaService;
 
 pub struct GrpcServer {
     cache_manager: Arc<CacheManager>,
@@ -55,6 +57,11 @@ pub async fn start(&self) -> Result<(), CommonError> {
             self.segment_file_manager.clone(),
             self.rocksdb_engine_handler.clone(),
         );
+        let replica_handler = GrpcJournalServerReplicaService::new(
+            self.cache_manager.clone(),
+            self.segment_file_manager.clone(),
+            self.rocksdb_engine_handler.clone(),
+        );
 
         Server::builder()
             .accept_http1(true)
@@ -62,6 +69,7 @@ pub async fn start(&self) -> Result<(), CommonError> {
             .layer(tonic_web::GrpcWebLayer::new())
             .add_service(JournalServerAdminServiceServer::new(admin_handler))
             .add_service(JournalServerInnerServiceServer::new(inner_handler))
+            .add_service(JournalServerReplicaServiceServer::new(replica_handler))
             .serve(addr)
             .await?;
         Ok(())
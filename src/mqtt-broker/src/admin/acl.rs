@@ -13,18 +13,34 @@
 // limitations under the License.
 
 use crate::handler::cache::CacheManager;
+use crate::security::acl::auth::{check_acl, AclCheckResult};
 use crate::security::AuthDriver;
 use grpc_clients::pool::ClientPool;
-use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction};
 use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
+use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::broker_mqtt::broker_mqtt_admin::{
     CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest, DeleteAclReply,
     DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest, ListAclReply,
     ListBlacklistReply,
 };
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{MqttCheckAclReply, MqttCheckAclRequest};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
+/// whether a client would be allowed to perform an action on a topic
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckAclRequest {
+    pub client_id: String,
+    // the user and the ip address of the live connection of the client when empty
+    pub username: String,
+    pub source_ip_addr: String,
+    pub topic: String,
+    pub action: MqttAclAction,
+    pub retain: bool,
+}
+
 pub async fn list_acl_by_req(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
@@ -153,3 +169,76 @@ pub async fn create_blacklist_by_req(
         Err(e) => Err(Status::cancelled(e.to_string())),
     }
 }
+
+/// evaluate the acl of the broker for a client, without publishing or subscribing
+///
+/// When the client is connected, the acl carried by the login token of its connection apply too.
+pub fn check_acl_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: CheckAclRequest,
+) -> AclCheckResult {
+    let mut connection = match cache_manager
+        .get_connect_id(&request.client_id)
+        .and_then(|connect_id| cache_manager.get_connection(connect_id))
+    {
+        Some(connection) => connection,
+        None => MQTTConnection {
+            client_id: request.client_id.clone(),
+            ..Default::default()
+        },
+    };
+    if !request.username.is_empty() {
+        connection.login_user = request.username;
+    }
+    if !request.source_ip_addr.is_empty() {
+        connection.source_ip_addr = request.source_ip_addr;
+    }
+
+    check_acl(
+        cache_manager,
+        &connection,
+        &request.topic,
+        request.action,
+        request.retain,
+    )
+}
+
+pub fn mqtt_check_acl_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<MqttCheckAclRequest>,
+) -> Result<Response<MqttCheckAclReply>, Status> {
+    let req = request.into_inner();
+    let action = match req.action.as_str() {
+        "All" => MqttAclAction::All,
+        "Subscribe" => MqttAclAction::Subscribe,
+        "Publish" => MqttAclAction::Publish,
+        "PubSub" => MqttAclAction::PubSub,
+        "Retain" => MqttAclAction::Retain,
+        "Qos" => MqttAclAction::Qos,
+        _ => return Err(Status::cancelled("invalid acl action".to_string())),
+    };
+
+    let result = check_acl_by_req(
+        cache_manager,
+        CheckAclRequest {
+            client_id: req.client_id,
+            username: req.username,
+            source_ip_addr: req.source_ip_addr,
+            topic: req.topic,
+            action,
+            retain: req.retain,
+        },
+    );
+    let rule = match result.rule {
+        Some(rule) => match rule.encode() {
+            Ok(data) => data,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        },
+        None => Vec::new(),
+    };
+    Ok(Response::new(MqttCheckAclReply {
+        allow: result.allow,
+        source: format!("{:?}", result.source),
+        rule,
+    }))
+}
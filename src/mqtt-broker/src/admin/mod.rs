@@ -14,6 +14,9 @@
 
 pub mod acl;
 pub mod connector;
+pub mod psk;
+pub mod retain;
+pub mod session;
 pub mod subscribe;
 pub mod topic;
 pub mod user;
@@ -0,0 +1,130 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::security::AuthDriver;
+use common_base::tools::now_second;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::psk::MqttPsk;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttCreatePskReply, MqttCreatePskRequest, MqttDeletePskReply, MqttDeletePskRequest,
+    MqttListPskReply,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatePskRequest {
+    pub identity: String,
+    // hex encoded
+    pub psk: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeletePskRequest {
+    pub identity: String,
+}
+
+pub async fn create_psk_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: CreatePskRequest,
+) -> Result<(), MqttBrokerError> {
+    let psk = MqttPsk {
+        identity: request.identity,
+        psk: request.psk,
+        create_time: now_second(),
+    };
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    auth_driver.save_psk(psk).await
+}
+
+pub async fn delete_psk_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: DeletePskRequest,
+) -> Result<(), MqttBrokerError> {
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    auth_driver.delete_psk(request.identity).await
+}
+
+/// the PSK identities of the cluster, without their keys
+pub async fn list_psk_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<Vec<MqttPsk>, MqttBrokerError> {
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    let list = auth_driver
+        .read_all_psk()
+        .await?
+        .into_iter()
+        .map(|psk| MqttPsk {
+            psk: "".to_string(),
+            ..psk
+        })
+        .collect();
+    Ok(list)
+}
+
+pub async fn mqtt_create_psk_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttCreatePskRequest>,
+) -> Result<Response<MqttCreatePskReply>, Status> {
+    let req = request.into_inner();
+    let request = CreatePskRequest {
+        identity: req.identity,
+        psk: req.psk,
+    };
+    match create_psk_by_req(cache_manager, client_pool, request).await {
+        Ok(_) => Ok(Response::new(MqttCreatePskReply::default())),
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+pub async fn mqtt_delete_psk_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttDeletePskRequest>,
+) -> Result<Response<MqttDeletePskReply>, Status> {
+    let req = request.into_inner();
+    let request = DeletePskRequest {
+        identity: req.identity,
+    };
+    match delete_psk_by_req(cache_manager, client_pool, request).await {
+        Ok(_) => Ok(Response::new(MqttDeletePskReply::default())),
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+pub async fn mqtt_list_psk_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<Response<MqttListPskReply>, Status> {
+    match list_psk_by_req(cache_manager, client_pool).await {
+        Ok(list) => {
+            let mut psks = Vec::new();
+            for psk in list {
+                match psk.encode() {
+                    Ok(data) => psks.push(data),
+                    Err(e) => return Err(Status::cancelled(e.to_string())),
+                }
+            }
+            Ok(Response::new(MqttListPskReply { psks }))
+        }
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
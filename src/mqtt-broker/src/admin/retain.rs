@@ -0,0 +1,401 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common_base::tools::now_second;
+use grpc_clients::pool::ClientPool;
+use log::warn;
+use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::topic::MqttTopic;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttDeleteRetainMessageReply, MqttDeleteRetainMessageRequest, MqttDeleteRetainMessageResult,
+    MqttGetRetainMessageReply, MqttGetRetainMessageRequest, MqttListRetainMessageReply,
+    MqttListRetainMessageRequest, MqttRetainMessageStatsReply, MqttRetainMessageStatsRequest,
+};
+use protocol::mqtt::common::QoS;
+use serde::{Deserialize, Serialize};
+use tonic::{Request, Response, Status};
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::server::http::response::{paginate, PageQuery, PageReply};
+use crate::storage::topic::TopicStorage;
+use crate::subscribe::topic_trie::topic_match_filter;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteRetainMessageRequest {
+    // a topic name or a filter with + and #
+    pub topic_filter: String,
+}
+
+/// a retained message without its payload
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RetainMessageRaw {
+    pub topic_name: String,
+    pub client_id: String,
+    pub qos: QoS,
+    pub payload_size: usize,
+    pub expired_at: Option<u64>,
+    pub create_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetainMessageDetail {
+    pub topic_name: String,
+    pub client_id: String,
+    pub qos: QoS,
+    // the payload as text when it is valid UTF-8, base64 encoded otherwise
+    pub payload: String,
+    pub payload_encoding: String,
+    pub format_indicator: Option<u8>,
+    pub content_type: Option<String>,
+    pub response_topic: Option<String>,
+    pub correlation_data: Option<String>,
+    pub user_properties: Vec<(String, String)>,
+    pub expiry_interval: u64,
+    pub expired_at: Option<u64>,
+    pub create_time: u64,
+}
+
+/// the outcome of deleting the retained message of one topic
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeleteRetainMessageResult {
+    pub topic_name: String,
+    // empty when the retained message was deleted
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RetainMessageStats {
+    pub count: usize,
+    // the sum of the payload sizes, in bytes
+    pub total_size: usize,
+}
+
+/// A page of the retained messages of the cluster whose topic matches the filter, all of them
+/// when the filter is empty. Only the messages of the page are decoded, a message that cannot be
+/// decoded is logged and left out of the page but still counts in the total.
+pub async fn list_retain_message_by_req(
+    client_pool: &Arc<ClientPool>,
+    topic_filter: &str,
+    page: &PageQuery,
+) -> Result<PageReply<RetainMessageRaw>, MqttBrokerError> {
+    let topics = read_retain_topics(client_pool, topic_filter).await?;
+    let topics = paginate(topics, page);
+    let data = topics
+        .data
+        .into_iter()
+        .filter_map(|topic| {
+            let message = decode_or_log(&topic)?;
+            Some(RetainMessageRaw {
+                payload_size: message.payload.len(),
+                client_id: message.client_id,
+                qos: message.qos,
+                expired_at: topic.retain_message_expired_at,
+                create_time: message.create_time,
+                topic_name: topic.topic_name,
+            })
+        })
+        .collect();
+    Ok(PageReply {
+        data,
+        total: topics.total,
+        page: topics.page,
+        page_size: topics.page_size,
+    })
+}
+
+pub async fn get_retain_message_by_req(
+    client_pool: &Arc<ClientPool>,
+    topic_name: &str,
+) -> Result<RetainMessageDetail, MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let Some(topic) = topic_storage.get_topic(topic_name).await? else {
+        return Err(MqttBrokerError::TopicDoesNotExist(topic_name.to_owned()));
+    };
+    let Some(message) = decode_retain_message(&topic)? else {
+        return Err(MqttBrokerError::RetainMessageDoesNotExist(
+            topic_name.to_owned(),
+        ));
+    };
+
+    let (payload, payload_encoding) = match String::from_utf8(message.payload.to_vec()) {
+        Ok(payload) => (payload, "utf8".to_string()),
+        Err(_) => (STANDARD.encode(&message.payload), "base64".to_string()),
+    };
+    Ok(RetainMessageDetail {
+        topic_name: topic.topic_name,
+        client_id: message.client_id,
+        qos: message.qos,
+        payload,
+        payload_encoding,
+        format_indicator: message.format_indicator,
+        content_type: message.content_type,
+        response_topic: message.response_topic,
+        correlation_data: message.correlation_data.map(|data| STANDARD.encode(data)),
+        user_properties: message.user_properties,
+        expiry_interval: message.expiry_interval,
+        expired_at: topic.retain_message_expired_at,
+        create_time: message.create_time,
+    })
+}
+
+/// Delete the retained messages whose topic matches the filter, one result per topic. A failed
+/// topic does not stop the others, and a message that cannot be decoded is deleted as well.
+pub async fn delete_retain_message_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: DeleteRetainMessageRequest,
+) -> Result<Vec<DeleteRetainMessageResult>, MqttBrokerError> {
+    if request.topic_filter.is_empty() {
+        return Err(MqttBrokerError::TopicNameIsEmpty);
+    }
+
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let mut results = Vec::new();
+    for topic in read_retain_topics(client_pool, &request.topic_filter).await? {
+        let error = match topic_storage
+            .delete_retain_message(topic.topic_name.clone())
+            .await
+        {
+            Ok(_) => {
+                cache_manager.update_topic_retain_message(&topic.topic_name, Some(Vec::new()));
+                "".to_string()
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to delete the retained message of topic {}, error message: {}",
+                    topic.topic_name, e
+                );
+                e.to_string()
+            }
+        };
+        results.push(DeleteRetainMessageResult {
+            topic_name: topic.topic_name,
+            error,
+        });
+    }
+    Ok(results)
+}
+
+/// the number and size of the retained messages whose topic matches the filter, messages that
+/// cannot be decoded are logged and left out
+pub async fn retain_message_stats_by_req(
+    client_pool: &Arc<ClientPool>,
+    topic_filter: &str,
+) -> Result<RetainMessageStats, MqttBrokerError> {
+    let sizes: Vec<usize> = read_retain_topics(client_pool, topic_filter)
+        .await?
+        .iter()
+        .filter_map(|topic| decode_or_log(topic).map(|message| message.payload.len()))
+        .collect();
+    Ok(build_stats(&sizes))
+}
+
+fn build_stats(sizes: &[usize]) -> RetainMessageStats {
+    RetainMessageStats {
+        count: sizes.len(),
+        total_size: sizes.iter().sum(),
+    }
+}
+
+pub async fn mqtt_list_retain_message_by_req(
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttListRetainMessageRequest>,
+) -> Result<Response<MqttListRetainMessageReply>, Status> {
+    let req = request.into_inner();
+    let page = PageQuery {
+        page: req.page as usize,
+        page_size: req.page_size as usize,
+    };
+    let reply = list_retain_message_by_req(client_pool, &req.topic_filter, &page)
+        .await
+        .map_err(|e| Status::cancelled(e.to_string()))?;
+    let mut retain_messages = Vec::new();
+    for raw in reply.data {
+        match serde_json::to_vec(&raw) {
+            Ok(data) => retain_messages.push(data),
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        }
+    }
+    Ok(Response::new(MqttListRetainMessageReply {
+        retain_messages,
+        total: reply.total as u64,
+    }))
+}
+
+pub async fn mqtt_get_retain_message_by_req(
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttGetRetainMessageRequest>,
+) -> Result<Response<MqttGetRetainMessageReply>, Status> {
+    let req = request.into_inner();
+    let detail = get_retain_message_by_req(client_pool, &req.topic_name)
+        .await
+        .map_err(|e| Status::cancelled(e.to_string()))?;
+    match serde_json::to_vec(&detail) {
+        Ok(detail) => Ok(Response::new(MqttGetRetainMessageReply { detail })),
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+pub async fn mqtt_delete_retain_message_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttDeleteRetainMessageRequest>,
+) -> Result<Response<MqttDeleteRetainMessageReply>, Status> {
+    let req = request.into_inner();
+    let request = DeleteRetainMessageRequest {
+        topic_filter: req.topic_filter,
+    };
+    match delete_retain_message_by_req(cache_manager, client_pool, request).await {
+        Ok(results) => {
+            let results = results
+                .into_iter()
+                .map(|result| MqttDeleteRetainMessageResult {
+                    topic_name: result.topic_name,
+                    error: result.error,
+                })
+                .collect();
+            Ok(Response::new(MqttDeleteRetainMessageReply { results }))
+        }
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+pub async fn mqtt_retain_message_stats_by_req(
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttRetainMessageStatsRequest>,
+) -> Result<Response<MqttRetainMessageStatsReply>, Status> {
+    let req = request.into_inner();
+    match retain_message_stats_by_req(client_pool, &req.topic_filter).await {
+        Ok(stats) => Ok(Response::new(MqttRetainMessageStatsReply {
+            count: stats.count as u64,
+            total_size: stats.total_size as u64,
+        })),
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+// the topics with a live retained message whose name matches the filter, sorted by name so that
+// pages are stable. The messages themselves are not decoded here.
+async fn read_retain_topics(
+    client_pool: &Arc<ClientPool>,
+    topic_filter: &str,
+) -> Result<Vec<MqttTopic>, MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let mut topics: Vec<MqttTopic> = topic_storage
+        .all()
+        .await?
+        .into_iter()
+        .filter(|(topic_name, topic)| {
+            (topic_filter.is_empty() || topic_match_filter(topic_name, topic_filter))
+                && has_retain_message(topic)
+        })
+        .map(|(_, topic)| topic)
+        .collect();
+    topics.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+    Ok(topics)
+}
+
+// whether the topic has a retained message that is neither empty nor expired
+fn has_retain_message(topic: &MqttTopic) -> bool {
+    let Some(retain_message) = &topic.retain_message else {
+        return false;
+    };
+    if retain_message.is_empty() {
+        return false;
+    }
+    topic
+        .retain_message_expired_at
+        .map_or(true, |expired_at| expired_at >= now_second())
+}
+
+// the retained message of the topic, unless it is empty or has expired
+fn decode_retain_message(topic: &MqttTopic) -> Result<Option<MqttMessage>, MqttBrokerError> {
+    if !has_retain_message(topic) {
+        return Ok(None);
+    }
+    let Some(retain_message) = &topic.retain_message else {
+        return Ok(None);
+    };
+    Ok(Some(serde_json::from_slice::<MqttMessage>(retain_message)?))
+}
+
+fn decode_or_log(topic: &MqttTopic) -> Option<MqttMessage> {
+    match decode_retain_message(topic) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!(
+                "Failed to decode the retained message of topic {}, error message: {}",
+                topic.topic_name, e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use common_base::tools::now_second;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use protocol::mqtt::common::QoS;
+
+    use super::{build_stats, decode_or_log, decode_retain_message, has_retain_message};
+
+    #[test]
+    fn decode_retain_message_test() {
+        let mut topic = MqttTopic::new("t1".to_string(), "c1".to_string(), "/a/b".to_string());
+        assert!(decode_retain_message(&topic).unwrap().is_none());
+
+        topic.retain_message = Some(Vec::new());
+        assert!(decode_retain_message(&topic).unwrap().is_none());
+
+        let message = MqttMessage {
+            client_id: "client-1".to_string(),
+            qos: QoS::AtLeastOnce,
+            retain: true,
+            topic: Bytes::from("/a/b"),
+            payload: Bytes::from("hello"),
+            ..Default::default()
+        };
+        topic.retain_message = Some(serde_json::to_vec(&message).unwrap());
+        topic.retain_message_expired_at = Some(now_second() + 60);
+        let decoded = decode_retain_message(&topic).unwrap().unwrap();
+        assert_eq!(decoded.client_id, "client-1");
+        assert_eq!(decoded.payload, Bytes::from("hello"));
+
+        topic.retain_message_expired_at = Some(now_second() - 60);
+        assert!(decode_retain_message(&topic).unwrap().is_none());
+
+        topic.retain_message = Some(b"not a message".to_vec());
+        topic.retain_message_expired_at = None;
+        assert!(decode_retain_message(&topic).is_err());
+        // a message that cannot be decoded is still listed for deletion but skipped otherwise
+        assert!(has_retain_message(&topic));
+        assert!(decode_or_log(&topic).is_none());
+    }
+
+    #[test]
+    fn build_stats_test() {
+        let stats = build_stats(&[5, 10]);
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.total_size, 15);
+        assert_eq!(build_stats(&[]).count, 0);
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::extract::ws::Message;
+use bytes::BytesMut;
+use grpc_clients::pool::ClientPool;
+use log::{info, warn};
+use metadata_struct::mqtt::connection::MQTTConnection;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttCleanSessionReply, MqttCleanSessionRequest, MqttGetClientDetailReply,
+    MqttGetClientDetailRequest, MqttKickClientReply, MqttKickClientRequest, MqttListSessionReply,
+};
+use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+use protocol::mqtt::common::DisconnectReasonCode;
+use serde::{Deserialize, Serialize};
+use tonic::{Request, Response, Status};
+
+use crate::handler::cache::CacheManager;
+use crate::handler::connection::disconnect_connection;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::inflight::inflight_clear;
+use crate::handler::response::response_packet_mqtt_distinct_by_reason;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::inflight::InflightStorage;
+use crate::storage::session::SessionStorage;
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KickClientRequest {
+    pub client_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CleanSessionRequest {
+    pub client_id: String,
+}
+
+/// what the broker knows about a client, the connection is only set on the broker the client
+/// is connected to
+#[derive(Serialize, Deserialize)]
+pub struct ClientDetail {
+    pub client_id: String,
+    pub session: Option<MqttSession>,
+    pub connection: Option<MQTTConnection>,
+    pub network: Option<NetworkConnection>,
+    pub subscriptions: Vec<MqttSubscribe>,
+    // messages pushed to the client that wait for an ack
+    pub inflight_num: usize,
+    // messages of the persisted inflight window, redelivered when the session is resumed
+    pub queue_depth: usize,
+}
+
+/// the sessions of the cluster, from the placement center
+pub async fn list_session_by_req(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Vec<MqttSession>, MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    let mut list: Vec<MqttSession> = session_storage
+        .list_session()
+        .await?
+        .into_iter()
+        .map(|(_, session)| session)
+        .collect();
+    list.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+    Ok(list)
+}
+
+pub async fn get_session_by_req(
+    client_pool: &Arc<ClientPool>,
+    client_id: &str,
+) -> Result<MqttSession, MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    match session_storage.get_session(client_id.to_owned()).await? {
+        Some(session) => Ok(session),
+        None => Err(MqttBrokerError::SessionDoesNotExist),
+    }
+}
+
+pub async fn get_client_detail_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+) -> Result<ClientDetail, MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    let session = session_storage.get_session(client_id.to_owned()).await?;
+
+    let connect_id = cache_manager.get_connect_id(client_id);
+    let connection = connect_id.and_then(|id| cache_manager.get_connection(id));
+    let network = connect_id.and_then(|id| connection_manager.get_connect(id));
+    if session.is_none() && connection.is_none() {
+        return Err(MqttBrokerError::SessionDoesNotExist);
+    }
+
+    let mut subscriptions: Vec<MqttSubscribe> = subscribe_manager
+        .subscribe_list
+        .iter()
+        .filter(|entry| entry.value().client_id == client_id)
+        .map(|entry| entry.value().clone())
+        .collect();
+    subscriptions.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let inflight_storage = InflightStorage::new(client_pool.clone());
+    let queue_depth = inflight_storage.list_inflight(client_id).await?.len();
+
+    Ok(ClientDetail {
+        client_id: client_id.to_owned(),
+        session,
+        connection,
+        network,
+        subscriptions,
+        inflight_num: cache_manager.get_inflight_num(client_id),
+        queue_depth,
+    })
+}
+
+/// Disconnect a client connected to this broker. MQTT 5 clients are sent a DISCONNECT with
+/// reason code 0x98 (administrative action) first, older clients are only closed. The session
+/// is kept.
+pub async fn kick_client_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: KickClientRequest,
+) -> Result<(), MqttBrokerError> {
+    let Some(connect_id) = cache_manager.get_connect_id(&request.client_id) else {
+        return Err(MqttBrokerError::ClientNoAvailableCOnnection(
+            request.client_id,
+        ));
+    };
+
+    if let Some(network) = connection_manager.get_connect(connect_id) {
+        send_disconnect(
+            connection_manager,
+            &network,
+            DisconnectReasonCode::AdministrativeAction,
+        )
+        .await;
+    }
+
+    disconnect_connection(
+        &request.client_id,
+        connect_id,
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+        false,
+    )
+    .await?;
+    info!(
+        "Client {} was kicked by the admin, connection id {}",
+        request.client_id, connect_id
+    );
+    Ok(())
+}
+
+/// Remove the session of a client with its subscriptions and inflight window, the client is
+/// kicked first when it is connected to this broker.
+pub async fn clean_session_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: CleanSessionRequest,
+) -> Result<(), MqttBrokerError> {
+    if let Some(connect_id) = cache_manager.get_connect_id(&request.client_id) {
+        if let Some(network) = connection_manager.get_connect(connect_id) {
+            send_disconnect(
+                connection_manager,
+                &network,
+                DisconnectReasonCode::AdministrativeAction,
+            )
+            .await;
+        }
+        return disconnect_connection(
+            &request.client_id,
+            connect_id,
+            cache_manager,
+            client_pool,
+            connection_manager,
+            subscribe_manager,
+            true,
+        )
+        .await;
+    }
+
+    let session_storage = SessionStorage::new(client_pool.clone());
+    if session_storage
+        .get_session(request.client_id.clone())
+        .await?
+        .is_none()
+    {
+        return Err(MqttBrokerError::SessionDoesNotExist);
+    }
+    session_storage
+        .delete_session(request.client_id.clone())
+        .await?;
+    inflight_clear(cache_manager, client_pool, &request.client_id).await?;
+    cache_manager.remove_session(&request.client_id);
+    subscribe_manager.remove_client_id(&request.client_id);
+    Ok(())
+}
+
+pub async fn mqtt_list_session_by_req(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Response<MqttListSessionReply>, Status> {
+    match list_session_by_req(client_pool).await {
+        Ok(list) => {
+            let sessions = list
+                .into_iter()
+                .map(|session| session.encode().into_bytes())
+                .collect();
+            Ok(Response::new(MqttListSessionReply { sessions }))
+        }
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+pub async fn mqtt_get_client_detail_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<MqttGetClientDetailRequest>,
+) -> Result<Response<MqttGetClientDetailReply>, Status> {
+    let req = request.into_inner();
+    let detail = get_client_detail_by_req(
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+        &req.client_id,
+    )
+    .await
+    .map_err(|e| Status::cancelled(e.to_string()))?;
+    match serde_json::to_vec(&detail) {
+        Ok(detail) => Ok(Response::new(MqttGetClientDetailReply { detail })),
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+pub async fn mqtt_kick_client_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<MqttKickClientRequest>,
+) -> Result<Response<MqttKickClientReply>, Status> {
+    let req = request.into_inner();
+    let request = KickClientRequest {
+        client_id: req.client_id,
+    };
+    match kick_client_by_req(
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+        request,
+    )
+    .await
+    {
+        Ok(_) => Ok(Response::new(MqttKickClientReply::default())),
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+pub async fn mqtt_clean_session_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<MqttCleanSessionRequest>,
+) -> Result<Response<MqttCleanSessionReply>, Status> {
+    let req = request.into_inner();
+    let request = CleanSessionRequest {
+        client_id: req.client_id,
+    };
+    match clean_session_by_req(
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+        request,
+    )
+    .await
+    {
+        Ok(_) => Ok(Response::new(MqttCleanSessionReply::default())),
+        Err(e) => Err(Status::cancelled(e.to_string())),
+    }
+}
+
+// Only MQTT 5 defines a DISCONNECT sent by the server. The connection is closed by the caller
+// either way, so a failed write is only logged.
+async fn send_disconnect(
+    connection_manager: &Arc<ConnectionManager>,
+    network: &NetworkConnection,
+    code: DisconnectReasonCode,
+) {
+    if !network.is_mqtt5() {
+        return;
+    }
+    if let Err(e) = write_disconnect(connection_manager, network, code).await {
+        warn!(
+            "Failed to send DISCONNECT to connection {}, error message: {}",
+            network.connection_id, e
+        );
+    }
+}
+
+async fn write_disconnect(
+    connection_manager: &Arc<ConnectionManager>,
+    network: &NetworkConnection,
+    code: DisconnectReasonCode,
+) -> Result<(), MqttBrokerError> {
+    let Some(protocol) = network.protocol.clone() else {
+        return Ok(());
+    };
+    let wrap = MqttPacketWrapper {
+        protocol_version: protocol.clone().into(),
+        packet: response_packet_mqtt_distinct_by_reason(&protocol, Some(code)),
+    };
+
+    match network.connection_type {
+        NetworkConnectionType::Tcp | NetworkConnectionType::Tls => {
+            connection_manager
+                .write_tcp_frame(network.connection_id, wrap)
+                .await
+        }
+        NetworkConnectionType::WebSocket | NetworkConnectionType::WebSockets => {
+            let mut codec = MqttCodec::new(Some(protocol.into()));
+            let mut buff = BytesMut::new();
+            codec
+                .encode_data(wrap.clone(), &mut buff)
+                .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+            connection_manager
+                .write_websocket_frame(network.connection_id, wrap, Message::Binary(buff.to_vec()))
+                .await
+        }
+        // QUIC connections are closed without a DISCONNECT
+        NetworkConnectionType::Quic => Ok(()),
+    }
+}
@@ -32,6 +32,7 @@ pub async fn create_user_by_req(
         username: req.username,
         password: req.password,
         is_superuser: req.is_superuser,
+        ..Default::default()
     };
 
     let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::handler::error::MqttBrokerError;
+use crate::subscribe::subscribe_manager::SubscribeManager;
 use axum::async_trait;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use log::{error, info};
@@ -45,6 +46,7 @@ pub trait BridgePlugin {
 pub async fn start_connector_thread<S>(
     message_storage: Arc<S>,
     connector_manager: Arc<ConnectorManager>,
+    subscribe_manager: Arc<SubscribeManager>,
     stop_send: broadcast::Sender<bool>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
@@ -63,6 +65,7 @@ pub async fn start_connector_thread<S>(
             _ = check_connector(
                 &message_storage,
                 &connector_manager,
+                &subscribe_manager,
             ) => {
                 sleep(Duration::from_secs(1)).await;
             }
@@ -70,8 +73,11 @@ pub async fn start_connector_thread<S>(
     }
 }
 
-async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<ConnectorManager>)
-where
+async fn check_connector<S>(
+    message_storage: &Arc<S>,
+    connector_manager: &Arc<ConnectorManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     let config = broker_mqtt_conf();
@@ -104,6 +110,7 @@ async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<Co
         start_thread(
             connector_manager.clone(),
             message_storage.clone(),
+            subscribe_manager.clone(),
             raw.clone(),
             thread,
         );
@@ -139,6 +146,7 @@ async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<Co
 fn start_thread<S>(
     connector_manager: Arc<ConnectorManager>,
     message_storage: Arc<S>,
+    subscribe_manager: Arc<SubscribeManager>,
     connector: MQTTConnector,
     thread: BridgePluginThread,
 ) where
@@ -160,6 +168,7 @@ fn start_thread<S>(
                 let bridge = FileBridgePlugin::new(
                     connector_manager.clone(),
                     message_storage.clone(),
+                    subscribe_manager.clone(),
                     connector.connector_name.clone(),
                     local_file_config,
                     thread.stop_send.clone(),
@@ -16,6 +16,8 @@
 
 use super::core::{BridgePlugin, BridgePluginReadConfig};
 use super::manager::ConnectorManager;
+use crate::subscribe::message_reader::TopicMessageReader;
+use crate::subscribe::subscribe_manager::SubscribeManager;
 use crate::{handler::error::MqttBrokerError, storage::message::MessageStorage};
 use axum::async_trait;
 use log::error;
@@ -30,6 +32,7 @@
 pub struct FileBridgePlugin<S> {
     connector_manager: Arc<ConnectorManager>,
     message_storage: Arc<S>,
+    subscribe_manager: Arc<SubscribeManager>,
     connector_name: String,
     config: LocalFileConnectorConfig,
     stop_send: broadcast::Sender<bool>,
@@ -42,6 +45,7 @@ impl<S> FileBridgePlugin<S>
     pub fn new(
         connector_manager: Arc<ConnectorManager>,
         message_storage: Arc<S>,
+        subscribe_manager: Arc<SubscribeManager>,
         connector_name: String,
         config: LocalFileConnectorConfig,
         stop_send: broadcast::Sender<bool>,
@@ -49,6 +53,7 @@ pub fn new(
         FileBridgePlugin {
             connector_manager,
             message_storage,
+            subscribe_manager,
             connector_name,
             config,
             stop_send,
@@ -77,7 +82,9 @@ impl<S> BridgePlugin for FileBridgePlugin<S>
     async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
         let message_storage = MessageStorage::new(self.message_storage.clone());
         let group_name = self.connector_name.clone();
-        let offset = message_storage.get_group_offset(&group_name).await?;
+        let mut offset = message_storage.get_group_offset(&group_name).await?;
+        let mut message_reader =
+            TopicMessageReader::new(&self.subscribe_manager, message_storage, &config.topic_id);
         let mut recv = self.stop_send.subscribe();
         let file = OpenOptions::new()
             .append(true)
@@ -95,18 +102,24 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
                     }
                 },
 
-                val = message_storage.read_topic_message(&config.topic_id, offset, config.record_num) => {
+                val = message_reader.read(offset, config.record_num) => {
                     match val {
                         Ok(data) => {
                             self.connector_manager.report_heartbeat(&self.connector_name);
                             if data.is_empty() {
-                                sleep(Duration::from_millis(100)).await;
                                 continue;
                             }
 
-                            if let Err(e) = self.append(&data,&mut writer).await{
-                                error!("Connector {} failed to write data to {}, error message :{}", self.connector_name,self.config.local_file_path, e);
-                                sleep(Duration::from_millis(100)).await;
+                            match self.append(&data,&mut writer).await {
+                                Ok(_) => {
+                                    if let Some(last_offset) = data.last().and_then(|record| record.offset) {
+                                        offset = last_offset + 1;
+                                    }
+                                }
+                                Err(e) => {
+                                    error!("Connector {} failed to write data to {}, error message :{}", self.connector_name,self.config.local_file_path, e);
+                                    sleep(Duration::from_millis(100)).await;
+                                }
                             }
                         },
                         Err(e) => {
@@ -21,6 +21,8 @@
 use storage_adapter::storage::StorageAdapter;
 use tokio::{select, sync::broadcast, time::sleep};
 
+use crate::subscribe::message_reader::TopicMessageReader;
+use crate::subscribe::subscribe_manager::SubscribeManager;
 use crate::{handler::error::MqttBrokerError, storage::message::MessageStorage};
 
 use super::{
@@ -31,6 +33,7 @@
 pub struct KafkaBridgePlugin<S> {
     connector_manager: Arc<ConnectorManager>,
     message_storage: Arc<S>,
+    subscribe_manager: Arc<SubscribeManager>,
     connector_name: String,
     config: KafkaConnectorConfig,
     stop_send: broadcast::Sender<bool>,
@@ -43,6 +46,7 @@ impl<S> KafkaBridgePlugin<S>
     pub fn new(
         connector_manager: Arc<ConnectorManager>,
         message_storage: Arc<S>,
+        subscribe_manager: Arc<SubscribeManager>,
         connector_name: String,
         config: KafkaConnectorConfig,
         stop_send: broadcast::Sender<bool>,
@@ -50,6 +54,7 @@ pub fn new(
         KafkaBridgePlugin {
             connector_manager,
             message_storage,
+            subscribe_manager,
             connector_name,
             config,
             stop_send,
@@ -87,7 +92,9 @@ impl<S> BridgePlugin for KafkaBridgePlugin<S>
     async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
         let message_storage = MessageStorage::new(self.message_storage.clone());
         let group_name = self.connector_name.clone();
-        let offset = message_storage.get_group_offset(&group_name).await?;
+        let mut offset = message_storage.get_group_offset(&group_name).await?;
+        let mut message_reader =
+            TopicMessageReader::new(&self.subscribe_manager, message_storage, &config.topic_id);
         let mut recv = self.stop_send.subscribe();
         let producer: FutureProducer = rdkafka::ClientConfig::new()
             .set("bootstrap.servers", self.config.bootstrap_servers.as_str())
@@ -105,18 +112,24 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
                     }
                 }
 
-                val = message_storage.read_topic_message(&config.topic_id, offset, config.record_num) => {
+                val = message_reader.read(offset, config.record_num) => {
                     match val {
                         Ok(data) => {
                             self.connector_manager.report_heartbeat(&self.connector_name);
                             if data.is_empty() {
-                                tokio::time::sleep(Duration::from_millis(100)).await;
                                 continue;
                             }
 
-                            if let Err(e) = self.append(&data, producer.clone()).await{
-                                error!("Connector {} failed to write data to kafka topic {}, error message: {}", self.connector_name, self.config.topic, e);
-                                sleep(Duration::from_millis(100)).await;
+                            match self.append(&data, producer.clone()).await {
+                                Ok(_) => {
+                                    if let Some(last_offset) = data.last().and_then(|record| record.offset) {
+                                        offset = last_offset + 1;
+                                    }
+                                }
+                                Err(e) => {
+                                    error!("Connector {} failed to write data to kafka topic {}, error message: {}", self.connector_name, self.config.topic, e);
+                                    sleep(Duration::from_millis(100)).await;
+                                }
                             }
                         },
                         Err(e) => {
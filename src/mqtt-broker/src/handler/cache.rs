@@ -21,6 +21,7 @@
 use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
 use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
 use metadata_struct::mqtt::connection::MQTTConnection;
+use metadata_struct::mqtt::psk::MqttPsk;
 use metadata_struct::mqtt::session::MqttSession;
 use metadata_struct::mqtt::topic::MqttTopic;
 use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
@@ -33,7 +34,9 @@
 use tokio::sync::broadcast::Sender;
 use tokio::time::sleep;
 
+use super::inflight::InflightPersistQueue;
 use crate::security::acl::metadata::AclMetadata;
+use crate::subscribe::topic_trie::TopicTrie;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub enum MetadataCacheAction {
@@ -101,6 +104,9 @@ pub struct CacheManager {
     // (username, User)
     pub user_info: DashMap<String, MqttUser>,
 
+    // (identity, Psk)
+    pub psk_info: DashMap<String, MqttPsk>,
+
     // (client_id, Session)
     pub session_info: DashMap<String, MqttSession>,
 
@@ -116,6 +122,9 @@ pub struct CacheManager {
     // (topic_id, topic_name)
     pub topic_id_name: DashMap<String, String>,
 
+    // topic levels of all topics, valued by topic_id
+    pub topic_trie: TopicTrie,
+
     // (client_id, HeartbeatShard)
     pub heartbeat_data: DashMap<String, ConnectionLiveTime>,
 
@@ -133,6 +142,9 @@ pub struct CacheManager {
 
     // All auto subscribe rule
     pub auto_subscribe_rule: DashMap<String, MqttAutoSubscribeRule>,
+
+    // inflight changes waiting to be written to the placement center
+    pub inflight_persist_queue: Arc<InflightPersistQueue>,
 }
 
 impl CacheManager {
@@ -142,9 +154,11 @@ pub fn new(client_pool: Arc<ClientPool>, cluster_name: String) -> Self {
             cluster_name,
             cluster_info: DashMap::with_capacity(1),
             user_info: DashMap::with_capacity(8),
+            psk_info: DashMap::with_capacity(8),
             session_info: DashMap::with_capacity(8),
             topic_info: DashMap::with_capacity(8),
             topic_id_name: DashMap::with_capacity(8),
+            topic_trie: TopicTrie::new(),
             connection_info: DashMap::with_capacity(8),
             publish_pkid_info: DashMap::with_capacity(8),
             heartbeat_data: DashMap::with_capacity(8),
@@ -153,6 +167,7 @@ pub fn new(client_pool: Arc<ClientPool>, cluster_name: String) -> Self {
             acl_metadata: AclMetadata::new(),
             topic_rewrite_rule: DashMap::with_capacity(8),
             auto_subscribe_rule: DashMap::with_capacity(8),
+            inflight_persist_queue: Arc::new(InflightPersistQueue::new()),
         }
     }
 
@@ -209,6 +224,24 @@ pub fn retain_users(&self, usernames: HashSet<String>) {
             .retain(|username, _| usernames.contains(username));
     }
 
+    // psk
+    pub fn add_psk(&self, psk: MqttPsk) {
+        self.psk_info.insert(psk.identity.clone(), psk);
+    }
+
+    pub fn del_psk(&self, identity: &str) {
+        self.psk_info.remove(identity);
+    }
+
+    pub fn get_psk(&self, identity: &str) -> Option<MqttPsk> {
+        self.psk_info.get(identity).map(|psk| psk.clone())
+    }
+
+    pub fn retain_psks(&self, identities: HashSet<String>) {
+        self.psk_info
+            .retain(|identity, _| identities.contains(identity));
+    }
+
     // connection
     pub fn add_connection(&self, connect_id: u64, conn: MQTTConnection) {
         if let Some(mut session) = self.session_info.get_mut(&conn.client_id) {
@@ -219,6 +252,7 @@ pub fn add_connection(&self, connect_id: u64, conn: MQTTConnection) {
 
     pub fn remove_connection(&self, connect_id: u64) {
         self.connection_info.remove(&connect_id);
+        self.acl_metadata.remove_connection_acl(connect_id);
     }
 
     pub fn get_connect_id(&self, client_id: &str) -> Option<u64> {
@@ -240,13 +274,19 @@ pub fn get_connection(&self, connect_id: u64) -> Option<MQTTConnection> {
     // topic
     pub fn add_topic(&self, topic_name: &str, topic: &MqttTopic) {
         self.topic_info.insert(topic_name.to_owned(), topic.clone());
-        self.topic_id_name
-            .insert(topic.topic_id.clone(), topic_name.to_owned());
+        if let Some(old_name) = self
+            .topic_id_name
+            .insert(topic.topic_id.clone(), topic_name.to_owned())
+        {
+            self.topic_trie.remove(&old_name, &topic.topic_id);
+        }
+        self.topic_trie.insert(topic_name, &topic.topic_id);
     }
 
     pub fn delete_topic(&self, topic_name: &String, topic: &MqttTopic) {
         self.topic_info.remove(topic_name);
         self.topic_id_name.remove(&topic.topic_id);
+        self.topic_trie.remove(topic_name, &topic.topic_id);
     }
 
     pub fn topic_exists(&self, topic: &str) -> bool {
@@ -338,13 +378,18 @@ pub fn add_topic_alias(
     // pkid
     pub async fn get_pkid(&self, client_id: &str) -> u16 {
         let pkid = self.get_available_pkid(client_id).await;
+        self.add_pkid_info(client_id, pkid);
+        pkid
+    }
+
+    /// mark a pkid of the client as in use, e.g. for a message resumed from the inflight window
+    pub fn add_pkid_info(&self, client_id: &str, pkid: u16) {
         if let Some(mut pkid_list) = self.publish_pkid_info.get_mut(client_id) {
             pkid_list.push(pkid);
         } else {
             self.publish_pkid_info
                 .insert(client_id.to_owned(), vec![pkid]);
         }
-        pkid
     }
 
     async fn get_available_pkid(&self, client_id: &str) -> u16 {
@@ -367,10 +412,18 @@ async fn get_available_pkid(&self, client_id: &str) -> u16 {
 
     pub fn remove_pkid_info(&self, client_id: &str, pkid: u16) {
         if let Some(mut pkid_list) = self.publish_pkid_info.get_mut(client_id) {
-            pkid_list.retain(|x| *x == pkid);
+            pkid_list.retain(|x| *x != pkid);
         }
     }
 
+    /// the number of messages pushed to the client that wait for an ack
+    pub fn get_inflight_num(&self, client_id: &str) -> usize {
+        if let Some(pkid_list) = self.publish_pkid_info.get(client_id) {
+            return pkid_list.len();
+        }
+        0
+    }
+
     // client pkid
     pub fn add_client_pkid(&self, client_id: &str, pkid: u16) {
         let key = self.key(client_id, pkid);
@@ -414,13 +467,8 @@ pub fn remove_acl(&self, acl: MqttAcl) {
         self.acl_metadata.remove_mqtt_acl(acl);
     }
 
-    pub fn retain_acls(&self, user_acl: HashSet<String>, client_acl: HashSet<String>) {
-        self.acl_metadata
-            .acl_user
-            .retain(|username, _| user_acl.contains(username));
-        self.acl_metadata
-            .acl_client_id
-            .retain(|client_id, _| client_acl.contains(client_id));
+    pub fn reload_acls(&self, acl_list: Vec<MqttAcl>) {
+        self.acl_metadata.reload_mqtt_acl(acl_list);
     }
 
     // blacklist
@@ -480,3 +528,35 @@ pub fn delete_auto_subscribe_rule(&self, cluster: &str, topic: &str) {
         self.auto_subscribe_rule.remove(&key);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+
+    use super::CacheManager;
+
+    #[tokio::test]
+    async fn remove_pkid_info_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = CacheManager::new(client_pool, "test".to_string());
+        let client_id = "client-1";
+        for _ in 0..3 {
+            cache_manager.get_pkid(client_id).await;
+        }
+
+        cache_manager.remove_pkid_info(client_id, 2);
+        let pkid_list = cache_manager
+            .publish_pkid_info
+            .get(client_id)
+            .unwrap()
+            .clone();
+        assert!(pkid_list.contains(&1));
+        assert!(!pkid_list.contains(&2));
+        assert!(pkid_list.contains(&3));
+
+        // the released pkid is handed out again
+        assert_eq!(cache_manager.get_pkid(client_id).await, 2);
+    }
+}
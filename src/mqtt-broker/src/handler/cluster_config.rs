@@ -12,20 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
 use crate::storage::cluster::ClusterStorage;
-use common_base::config::broker_mqtt::{broker_mqtt_conf, ConfigAvailableFlag};
+use common_base::config::broker_mqtt::{
+    broker_mqtt_conf, ConfigAclPermission, ConfigAvailableFlag, ConfigSharedSubscriptionStrategy,
+};
 use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAclPermission;
 use metadata_struct::mqtt::cluster::{
     AvailableFlag, MqttClusterDynamicConfig, MqttClusterDynamicConfigFeature,
     MqttClusterDynamicConfigNetwork, MqttClusterDynamicConfigProtocol,
     MqttClusterDynamicConfigSecurity, MqttClusterDynamicFlappingDetect,
-    MqttClusterDynamicOfflineMessage, MqttClusterDynamicSlowSub, DEFAULT_DYNAMIC_CONFIG_FEATURE,
-    DEFAULT_DYNAMIC_CONFIG_FLAPPING_DETECT, DEFAULT_DYNAMIC_CONFIG_NETWORK,
-    DEFAULT_DYNAMIC_CONFIG_OFFLINE_MESSAGE, DEFAULT_DYNAMIC_CONFIG_PROTOCOL,
+    MqttClusterDynamicOfflineMessage, MqttClusterDynamicSlowSub, SharedSubscriptionStrategy,
+    DEFAULT_DYNAMIC_CONFIG_FEATURE, DEFAULT_DYNAMIC_CONFIG_FLAPPING_DETECT,
+    DEFAULT_DYNAMIC_CONFIG_NETWORK, DEFAULT_DYNAMIC_CONFIG_OFFLINE_MESSAGE,
+    DEFAULT_DYNAMIC_CONFIG_PROTOCOL, DEFAULT_DYNAMIC_CONFIG_SECURITY,
     DEFAULT_DYNAMIC_CONFIG_SLOW_SUB,
 };
 use protocol::mqtt::common::{qos, QoS};
@@ -73,6 +78,70 @@ pub fn get_slow_sub_config(&self) -> MqttClusterDynamicSlowSub {
         self.get_cluster_info().slow
     }
 
+    pub async fn set_security_config(
+        &self,
+        security: MqttClusterDynamicConfigSecurity,
+    ) -> Result<(), MqttBrokerError> {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.security = security.clone();
+        }
+
+        self.save_dynamic_config(DEFAULT_DYNAMIC_CONFIG_SECURITY, security.encode())
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn get_security_config(&self) -> MqttClusterDynamicConfigSecurity {
+        self.get_cluster_info().security
+    }
+
+    pub async fn set_feature_config(
+        &self,
+        feature: MqttClusterDynamicConfigFeature,
+    ) -> Result<(), MqttBrokerError> {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.feature = feature.clone();
+        }
+
+        self.save_dynamic_config(DEFAULT_DYNAMIC_CONFIG_FEATURE, feature.encode())
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn get_feature_config(&self) -> MqttClusterDynamicConfigFeature {
+        self.get_cluster_info().feature
+    }
+
+    /// the strategy of a shared subscription group, which falls back to the cluster strategy
+    pub fn get_shared_subscription_strategy(&self, group_name: &str) -> SharedSubscriptionStrategy {
+        let feature = self.get_feature_config();
+        feature
+            .shared_subscription_group_strategy
+            .get(group_name)
+            .copied()
+            .unwrap_or(feature.shared_subscription_strategy)
+    }
+
+    pub async fn set_network_config(
+        &self,
+        network: MqttClusterDynamicConfigNetwork,
+    ) -> Result<(), MqttBrokerError> {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.network = network.clone();
+        }
+
+        self.save_dynamic_config(DEFAULT_DYNAMIC_CONFIG_NETWORK, network.encode())
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn get_network_config(&self) -> MqttClusterDynamicConfigNetwork {
+        self.get_cluster_info().network
+    }
+
     pub fn set_cluster_info(&self, cluster: MqttClusterDynamicConfig) {
         self.cluster_info.insert(self.cluster_name.clone(), cluster);
     }
@@ -114,10 +183,13 @@ pub fn build_default_cluster_config() -> MqttClusterDynamicConfig {
             subscription_identifiers_available: AvailableFlag::Enable,
             shared_subscription_available: AvailableFlag::Enable,
             exclusive_subscription_available: AvailableFlag::Enable,
+            shared_subscription_strategy: SharedSubscriptionStrategy::RoundRobin,
+            shared_subscription_group_strategy: HashMap::new(),
         },
         security: MqttClusterDynamicConfigSecurity {
             secret_free_login: false,
             is_self_protection_status: false,
+            acl_no_match: MqttAclPermission::Allow,
         },
         network: MqttClusterDynamicConfigNetwork {
             tcp_max_connection_num: 1000,
@@ -126,6 +198,10 @@ pub fn build_default_cluster_config() -> MqttClusterDynamicConfig {
             websockets_max_connection_num: 1000,
             response_max_try_mut_times: 128,
             response_try_mut_sleep_time_ms: 100,
+            max_connection_rate: 0,
+            max_publish_rate: 0,
+            max_publish_bytes_rate: 0,
+            max_subscribe_rate: 0,
         },
         slow: MqttClusterDynamicSlowSub {
             enable: false,
@@ -224,6 +300,22 @@ async fn build_feature(
                 .exclusive_subscription_available
                 .clone(),
         ),
+        shared_subscription_strategy: to_shared_subscription_strategy(
+            conf.cluster_dynamic_config_feature
+                .shared_subscription_strategy
+                .clone(),
+        ),
+        shared_subscription_group_strategy: conf
+            .cluster_dynamic_config_feature
+            .shared_subscription_group_strategy
+            .iter()
+            .map(|(group_name, strategy)| {
+                (
+                    group_name.clone(),
+                    to_shared_subscription_strategy(strategy.clone()),
+                )
+            })
+            .collect(),
     })
 }
 
@@ -233,13 +325,28 @@ fn to_auvailable_flag(flag: ConfigAvailableFlag) -> AvailableFlag {
         ConfigAvailableFlag::Disable => AvailableFlag::Disable,
     }
 }
+
+fn to_shared_subscription_strategy(
+    strategy: ConfigSharedSubscriptionStrategy,
+) -> SharedSubscriptionStrategy {
+    match strategy {
+        ConfigSharedSubscriptionStrategy::RoundRobin => SharedSubscriptionStrategy::RoundRobin,
+        ConfigSharedSubscriptionStrategy::Random => SharedSubscriptionStrategy::Random,
+        ConfigSharedSubscriptionStrategy::HashClientId => SharedSubscriptionStrategy::HashClientId,
+        ConfigSharedSubscriptionStrategy::HashTopic => SharedSubscriptionStrategy::HashTopic,
+        ConfigSharedSubscriptionStrategy::Sticky => SharedSubscriptionStrategy::Sticky,
+        ConfigSharedSubscriptionStrategy::LeastInflight => {
+            SharedSubscriptionStrategy::LeastInflight
+        }
+    }
+}
 async fn build_security(
     client_pool: &Arc<ClientPool>,
 ) -> Result<MqttClusterDynamicConfigSecurity, MqttBrokerError> {
     let conf = broker_mqtt_conf();
     let cluster_storage = ClusterStorage::new(client_pool.clone());
     let data = cluster_storage
-        .get_dynamic_config(&conf.cluster_name, DEFAULT_DYNAMIC_CONFIG_FEATURE)
+        .get_dynamic_config(&conf.cluster_name, DEFAULT_DYNAMIC_CONFIG_SECURITY)
         .await?;
     if !data.is_empty() {
         let cluster = serde_json::from_slice::<MqttClusterDynamicConfigSecurity>(&data)?;
@@ -250,9 +357,17 @@ async fn build_security(
         is_self_protection_status: conf
             .cluster_dynamic_config_security
             .is_self_protection_status,
+        acl_no_match: to_acl_permission(conf.cluster_dynamic_config_security.acl_no_match.clone()),
     })
 }
 
+fn to_acl_permission(permission: ConfigAclPermission) -> MqttAclPermission {
+    match permission {
+        ConfigAclPermission::Allow => MqttAclPermission::Allow,
+        ConfigAclPermission::Deny => MqttAclPermission::Deny,
+    }
+}
+
 async fn build_network(
     client_pool: &Arc<ClientPool>,
 ) -> Result<MqttClusterDynamicConfigNetwork, MqttBrokerError> {
@@ -280,6 +395,10 @@ async fn build_network(
         response_try_mut_sleep_time_ms: conf
             .cluster_dynamic_config_network
             .response_try_mut_sleep_time_ms,
+        max_connection_rate: conf.cluster_dynamic_config_network.max_connection_rate,
+        max_publish_rate: conf.cluster_dynamic_config_network.max_publish_rate,
+        max_publish_bytes_rate: conf.cluster_dynamic_config_network.max_publish_bytes_rate,
+        max_subscribe_rate: conf.cluster_dynamic_config_network.max_subscribe_rate,
     })
 }
 
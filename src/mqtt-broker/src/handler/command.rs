@@ -21,10 +21,13 @@
 use crate::handler::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
 };
+use crate::security::login::psk::apply_psk_identity;
+use crate::security::login::x509::apply_peer_cert_identity;
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::subscribe_manager::SubscribeManager;
+use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::telemetry::trace::CustomContext;
 use delay_message::DelayMessageManager;
 use grpc_clients::pool::ClientPool;
@@ -114,6 +117,11 @@ pub async fn apply(
             is_connect_pkg = true;
         }
 
+        // the enhanced authentication of a CONNECT packet continues before the login succeeds
+        if let MqttPacket::Auth(_, _) = packet {
+            is_connect_pkg = true;
+        }
+
         if !is_connect_pkg && !self.check_login_status(tcp_connection.connection_id).await {
             return Some(response_packet_mqtt_distinct_by_reason(
                 &MqttProtocol::Mqtt5,
@@ -124,15 +132,28 @@ pub async fn apply(
         match packet {
             MqttPacket::Connect(
                 protocol_version,
-                connect,
+                mut connect,
                 properties,
                 last_will,
                 last_will_properties,
-                login,
+                mut login,
             ) => {
                 connect_manager
                     .set_connect_protocol(tcp_connection.connection_id, protocol_version);
 
+                if let Some(peer_cert) = &tcp_connection.peer_cert {
+                    apply_peer_cert_identity(
+                        &broker_mqtt_conf().network,
+                        peer_cert,
+                        &mut connect,
+                        &mut login,
+                    );
+                }
+
+                if let Some(identity) = &tcp_connection.psk_identity {
+                    apply_psk_identity(identity, &mut login);
+                }
+
                 let resp_pkg = if is_mqtt3(protocol_version) {
                     Some(
                         self.mqtt3_service
@@ -144,6 +165,8 @@ pub async fn apply(
                                 last_will_properties,
                                 &login,
                                 addr,
+                                &tcp_connection.peer_cert,
+                                &tcp_connection.psk_identity,
                             )
                             .await,
                     )
@@ -158,6 +181,8 @@ pub async fn apply(
                                 last_will_properties,
                                 &login,
                                 addr,
+                                &tcp_connection.peer_cert,
+                                &tcp_connection.psk_identity,
                             )
                             .await,
                     )
@@ -172,6 +197,8 @@ pub async fn apply(
                                 last_will_properties,
                                 &login,
                                 addr,
+                                &tcp_connection.peer_cert,
+                                &tcp_connection.psk_identity,
                             )
                             .await,
                     )
@@ -185,21 +212,26 @@ pub async fn apply(
                 };
 
                 let ack_pkg = resp_pkg.unwrap();
-                if let MqttPacket::ConnAck(conn_ack, _) = ack_pkg.clone() {
-                    if conn_ack.code == ConnectReturnCode::Success {
-                        let username = if let Some(user) = login {
-                            user.username
-                        } else {
-                            "".to_string()
-                        };
-                        self.metadata_cache
-                            .login_success(tcp_connection.connection_id, username);
-                        info!("connect [{}] login success", tcp_connection.connection_id);
-                    }
-                }
+                log_login_success(tcp_connection.connection_id, &ack_pkg);
                 return Some(ack_pkg);
             }
 
+            MqttPacket::Auth(auth, auth_properties) => {
+                if !tcp_connection.is_mqtt5() {
+                    return Some(response_packet_mqtt_distinct_by_reason(
+                        &tcp_connection.get_protocol(),
+                        Some(DisconnectReasonCode::ProtocolError),
+                    ));
+                }
+
+                let resp_pkg = self
+                    .mqtt5_service
+                    .auth(tcp_connection.connection_id, auth, auth_properties)
+                    .await;
+                log_login_success(tcp_connection.connection_id, &resp_pkg);
+                return Some(resp_pkg);
+            }
+
             MqttPacket::Publish(publish, publish_properties) => {
                 let mut context = CustomContext::default();
                 if let Some(ref p) = publish_properties {
@@ -523,3 +555,11 @@ pub async fn check_login_status(&self, connection_id: u64) -> bool {
         self.metadata_cache.is_login(connection_id)
     }
 }
+
+fn log_login_success(connection_id: u64, packet: &MqttPacket) {
+    if let MqttPacket::ConnAck(conn_ack, _) = packet {
+        if conn_ack.code == ConnectReturnCode::Success {
+            info!("connect [{}] login success", connection_id);
+        }
+    }
+}
@@ -22,7 +22,9 @@
 use protocol::mqtt::common::{Connect, ConnectProperties};
 
 use super::cache::CacheManager;
+use super::enhanced_auth::authentication_method;
 use super::error::MqttBrokerError;
+use super::inflight::inflight_clear;
 use super::keep_alive::client_keep_live_time;
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::session::SessionStorage;
@@ -88,7 +90,9 @@ pub fn build_connection(
         keep_alive,
         source_ip_addr: addr.to_string(),
     };
-    MQTTConnection::new(config)
+    let mut connection = MQTTConnection::new(config);
+    connection.authentication_method = authentication_method(connect_properties);
+    connection
 }
 
 pub fn get_client_id(client_id: &str) -> (String, bool) {
@@ -131,6 +135,7 @@ pub async fn disconnect_connection(
     let session_storage = SessionStorage::new(client_pool.clone());
     if delete_session {
         session_storage.delete_session(client_id.to_owned()).await?;
+        inflight_clear(cache_manager, client_pool, client_id).await?;
         cache_manager.remove_session(client_id);
         subscribe_manager.remove_client_id(client_id);
     } else {
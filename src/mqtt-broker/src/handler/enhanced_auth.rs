@@ -0,0 +1,51 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+use metadata_struct::mqtt::connection::MQTTConnection;
+use protocol::mqtt::common::{Connect, ConnectProperties, LastWill, LastWillProperties, Login};
+
+use crate::security::login::EnhancedAuthentication;
+
+/// the CONNECT packet of a connection, held until its enhanced authentication has finished
+pub struct PendingConnect {
+    pub client_id: String,
+    pub new_client_id: bool,
+    pub connection: MQTTConnection,
+    pub connect: Connect,
+    pub connect_properties: Option<ConnectProperties>,
+    pub last_will: Option<LastWill>,
+    pub last_will_properties: Option<LastWillProperties>,
+    pub login: Option<Login>,
+}
+
+/// an enhanced authentication exchange waiting for the next AUTH packet of the client
+pub struct EnhancedAuthExchange {
+    pub mechanism: Box<dyn EnhancedAuthentication + Send + Sync>,
+    // None when an established connection re-authenticates
+    pub connect: Option<PendingConnect>,
+}
+
+pub fn authentication_method(connect_properties: &Option<ConnectProperties>) -> Option<String> {
+    connect_properties
+        .as_ref()
+        .and_then(|properties| properties.authentication_method.clone())
+}
+
+pub fn authentication_data(connect_properties: &Option<ConnectProperties>) -> Bytes {
+    connect_properties
+        .as_ref()
+        .and_then(|properties| properties.authentication_data.clone())
+        .unwrap_or_default()
+}
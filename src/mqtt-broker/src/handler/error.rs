@@ -45,6 +45,9 @@ pub enum MqttBrokerError {
     #[error("{0}")]
     FromMysqlError(#[from] mysql::Error),
 
+    #[error("{0}")]
+    RedisError(#[from] redis::RedisError),
+
     #[error("Topic alias is too long. alias is {0}")]
     TopicAliasTooLong(u16),
 
@@ -112,6 +115,57 @@ pub enum MqttBrokerError {
 
     #[error("kafka error: {0}")]
     KafkaError(#[from] KafkaError),
+
+    #[error("{0}")]
+    JwtError(#[from] jsonwebtoken::errors::Error),
+
+    #[error("Unavailable authentication type {0}")]
+    UnavailableAuthnType(String),
+
+    #[error("Invalid JWT configuration: {0}")]
+    InvalidJwtConfig(String),
+
+    #[error("JWT claim {0} does not match the connection")]
+    JwtClaimMismatch(String),
+
+    #[error("{0}")]
+    ReqwestError(#[from] reqwest::Error),
+
+    #[error("Invalid certificate: {0}")]
+    InvalidCertificate(String),
+
+    #[error("{0}")]
+    BcryptError(#[from] bcrypt::BcryptError),
+
+    #[error("{0}")]
+    JoinError(#[from] tokio::task::JoinError),
+
+    #[error("Invalid password hash of user {0}")]
+    InvalidPasswordHash(String),
+
+    #[error("{0}")]
+    OpensslError(#[from] openssl::error::ErrorStack),
+
+    #[error("{0}")]
+    SslError(#[from] openssl::ssl::Error),
+
+    #[error("PSK identity does not exist")]
+    PskIdentityDoesNotExist,
+
+    #[error("PSK identity has been existed")]
+    PskIdentityAlreadyExist,
+
+    #[error("Invalid pre-shared key: {0}")]
+    InvalidPsk(String),
+
+    #[error("Enhanced authentication failed: {0}")]
+    EnhancedAuthFailed(String),
+
+    #[error("Invalid authentication data: {0}")]
+    InvalidAuthData(String),
+
+    #[error("Topic [{0}] has no retained message")]
+    RetainMessageDoesNotExist(String),
 }
 
 impl From<MqttBrokerError> for Status {
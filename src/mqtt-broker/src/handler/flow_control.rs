@@ -12,16 +12,316 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use protocol::mqtt::common::QoS;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfigNetwork;
+use protocol::mqtt::common::{MqttPacket, QoS};
+use tokio::select;
+use tokio::time::{sleep, sleep_until};
+
+use super::cache::CacheManager;
+use crate::observability::metrics::flow_control::record_throttled_event;
+use crate::server::connection::NetworkConnectionType;
+
+pub const LIMIT_CONNECTION: &str = "connection";
+pub const LIMIT_PUBLISH: &str = "publish";
+pub const LIMIT_PUBLISH_BYTES: &str = "publish_bytes";
+pub const LIMIT_SUBSCRIBE: &str = "subscribe";
+
+// the longest debt of a bucket, a larger request only waits this long
+const MAX_DEBT_SECS: f64 = 10.0;
 
 pub fn is_qos_message(qos: QoS) -> bool {
     qos == QoS::AtLeastOnce || qos == QoS::ExactlyOnce
 }
 
-pub fn is_connection_rate_exceeded() -> bool {
-    false
+/// A token bucket that holds at most one second of tokens. Tokens are allowed to go negative,
+/// so a request larger than the bucket is let through once the debt it leaves has been waited out.
+/// The debt is capped at `MAX_DEBT_SECS` of tokens, so that one huge request can not hold a
+/// connection for hours.
+struct TokenBucket {
+    rate: u64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new() -> Self {
+        TokenBucket {
+            rate: 0,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// take `count` tokens and return how long the caller has to wait before going on
+    fn acquire(&mut self, count: u64, rate: u64, now: Instant) -> Duration {
+        if rate == 0 {
+            return Duration::ZERO;
+        }
+
+        // the rate is a dynamic config, start with a full bucket whenever it changes
+        if rate != self.rate {
+            self.rate = rate;
+            self.tokens = rate as f64;
+            self.last_refill = now;
+        }
+
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
+        self.last_refill = now;
+
+        self.tokens = (self.tokens - count as f64).max(-(rate as f64) * MAX_DEBT_SECS);
+        if self.tokens >= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(-self.tokens / rate as f64)
+    }
+}
+
+pub struct RateLimiter {
+    bucket: Mutex<TokenBucket>,
 }
 
-pub fn is_subscribe_rate_exceeded() -> bool {
-    false
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new()
+    }
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        RateLimiter {
+            bucket: Mutex::new(TokenBucket::new()),
+        }
+    }
+
+    /// take `count` tokens at `rate` tokens per second, 0 meaning unlimited, and return how long
+    /// the caller has to wait before going on
+    pub fn acquire(&self, count: u64, rate: u64) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.acquire(count, rate, Instant::now())
+    }
+}
+
+/// The publish and subscribe rate limits of one client, owned by the read loop of its connection.
+#[derive(Default)]
+pub struct ClientRateLimiter {
+    publish: RateLimiter,
+    publish_bytes: RateLimiter,
+    subscribe: RateLimiter,
+}
+
+impl ClientRateLimiter {
+    /// the longest wait the packet has to take, and the limit that caused it
+    pub fn acquire(
+        &self,
+        packet: &MqttPacket,
+        network: &MqttClusterDynamicConfigNetwork,
+    ) -> Option<(&'static str, Duration)> {
+        let waits = match packet {
+            MqttPacket::Publish(publish, _) => vec![
+                (
+                    LIMIT_PUBLISH,
+                    self.publish.acquire(1, network.max_publish_rate),
+                ),
+                (
+                    LIMIT_PUBLISH_BYTES,
+                    self.publish_bytes
+                        .acquire(publish.payload.len() as u64, network.max_publish_bytes_rate),
+                ),
+            ],
+            MqttPacket::Subscribe(_, _) => vec![(
+                LIMIT_SUBSCRIBE,
+                self.subscribe.acquire(1, network.max_subscribe_rate),
+            )],
+            _ => return None,
+        };
+
+        waits
+            .into_iter()
+            .filter(|(_, wait)| !wait.is_zero())
+            .max_by_key(|(_, wait)| *wait)
+    }
+}
+
+/// Hold the read loop of a client that exceeds its publish or subscribe rate. The client is not
+/// disconnected, the broker just stops reading its connection until the bucket allows it again.
+///
+/// `stop` resolves to true when the connection is closed, the wait is then cut short and false is
+/// returned, so that the read loop can exit instead of handling the packet.
+pub async fn throttle_client_packet<F>(
+    limiter: &ClientRateLimiter,
+    packet: &MqttPacket,
+    cache_manager: &Arc<CacheManager>,
+    network_type: &NetworkConnectionType,
+    stop: F,
+) -> bool
+where
+    F: Future<Output = bool>,
+{
+    if !matches!(
+        packet,
+        MqttPacket::Publish(_, _) | MqttPacket::Subscribe(_, _)
+    ) {
+        return true;
+    }
+
+    let network = cache_manager.get_network_config();
+    let Some((limit, wait)) = limiter.acquire(packet, &network) else {
+        return true;
+    };
+    record_throttled_event(network_type, limit);
+    let deadline = tokio::time::Instant::now() + wait;
+    select! {
+        stopped = stop => {
+            if stopped {
+                return false;
+            }
+            sleep_until(deadline).await;
+        }
+        _ = sleep_until(deadline) => {}
+    }
+    true
+}
+
+/// Hold the acceptor of a listener that accepts new connections faster than `max_connection_rate`.
+pub async fn throttle_new_connection(
+    limiter: &RateLimiter,
+    cache_manager: &Arc<CacheManager>,
+    network_type: &NetworkConnectionType,
+) {
+    let rate = cache_manager.get_network_config().max_connection_rate;
+    let wait = limiter.acquire(1, rate);
+    if !wait.is_zero() {
+        record_throttled_event(network_type, LIMIT_CONNECTION);
+        sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use bytes::Bytes;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::cluster::{
+        MqttClusterDynamicConfig, MqttClusterDynamicConfigNetwork,
+    };
+    use protocol::mqtt::common::{MqttPacket, PingReq, Publish, Subscribe};
+
+    use super::{
+        throttle_client_packet, ClientRateLimiter, TokenBucket, LIMIT_PUBLISH_BYTES,
+        LIMIT_SUBSCRIBE,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::server::connection::NetworkConnectionType;
+
+    #[test]
+    fn token_bucket_test() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new();
+
+        // unlimited
+        assert_eq!(bucket.acquire(1000, 0, now), Duration::ZERO);
+
+        // a full bucket lets one second of requests through
+        for _ in 0..10 {
+            assert_eq!(bucket.acquire(1, 10, now), Duration::ZERO);
+        }
+        assert_eq!(bucket.acquire(1, 10, now), Duration::from_millis(100));
+        assert_eq!(bucket.acquire(1, 10, now), Duration::from_millis(200));
+
+        // the debt is paid back over time
+        let later = now + Duration::from_millis(300);
+        assert_eq!(bucket.acquire(1, 10, later), Duration::ZERO);
+
+        // the bucket never holds more than one second of tokens
+        let later = later + Duration::from_secs(60);
+        assert_eq!(bucket.acquire(10, 10, later), Duration::ZERO);
+        assert_eq!(bucket.acquire(1, 10, later), Duration::from_millis(100));
+
+        // a request larger than the bucket is let through after its debt is waited out
+        let mut bucket = TokenBucket::new();
+        assert_eq!(bucket.acquire(30, 10, now), Duration::from_secs(2));
+
+        // the debt is capped
+        assert_eq!(bucket.acquire(1000, 10, now), Duration::from_secs(10));
+
+        // a new rate starts with a full bucket
+        assert_eq!(bucket.acquire(20, 20, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn client_rate_limiter_test() {
+        let limiter = ClientRateLimiter::default();
+        let mut network = MqttClusterDynamicConfigNetwork::default();
+        let publish = MqttPacket::Publish(
+            Publish {
+                payload: Bytes::from(vec![0u8; 100]),
+                ..Default::default()
+            },
+            None,
+        );
+        let subscribe = MqttPacket::Subscribe(
+            Subscribe {
+                packet_identifier: 1,
+                filters: Vec::new(),
+            },
+            None,
+        );
+
+        assert!(limiter.acquire(&publish, &network).is_none());
+        assert!(limiter.acquire(&subscribe, &network).is_none());
+
+        network.max_publish_rate = 100;
+        network.max_publish_bytes_rate = 150;
+        network.max_subscribe_rate = 1;
+        assert!(limiter.acquire(&publish, &network).is_none());
+        let (limit, wait) = limiter.acquire(&publish, &network).unwrap();
+        assert_eq!(limit, LIMIT_PUBLISH_BYTES);
+        assert!(wait > Duration::ZERO);
+
+        assert!(limiter.acquire(&subscribe, &network).is_none());
+        let (limit, _) = limiter.acquire(&subscribe, &network).unwrap();
+        assert_eq!(limit, LIMIT_SUBSCRIBE);
+
+        assert!(limiter
+            .acquire(&MqttPacket::PingReq(PingReq), &network)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn throttle_client_packet_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let mut cluster = MqttClusterDynamicConfig::default();
+        cluster.network.max_publish_rate = 1;
+        cache_manager.set_cluster_info(cluster);
+
+        let limiter = ClientRateLimiter::default();
+        let publish = MqttPacket::Publish(Publish::default(), None);
+        let network_type = NetworkConnectionType::Tcp;
+        assert!(
+            throttle_client_packet(&limiter, &publish, &cache_manager, &network_type, async {
+                false
+            })
+            .await
+        );
+
+        // the second publish waits a second, unless the connection is stopped
+        let start = Instant::now();
+        assert!(
+            !throttle_client_packet(&limiter, &publish, &cache_manager, &network_type, async {
+                true
+            })
+            .await
+        );
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
 }
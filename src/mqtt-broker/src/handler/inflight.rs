@@ -0,0 +1,258 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use grpc_clients::pool::ClientPool;
+use log::{error, info};
+use metadata_struct::mqtt::inflight::MqttInflightMessage;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use super::cache::CacheManager;
+use super::error::MqttBrokerError;
+use crate::storage::inflight::InflightStorage;
+
+// How often the inflight changes queued by the push threads are written to the placement center
+const INFLIGHT_PERSIST_INTERVAL: Duration = Duration::from_millis(100);
+
+type InflightKey = (String, u16);
+
+#[derive(Default)]
+struct InflightChanges {
+    // ((client_id, pkid), the message to save, or None to delete it)
+    pending: HashMap<InflightKey, Option<MqttInflightMessage>>,
+    // (client_id, pkid) of the messages saved in the placement center
+    persisted: HashSet<InflightKey>,
+}
+
+/// The inflight changes waiting to be written to the placement center.
+///
+/// A push thread only queues a change, so a QoS1/QoS2 publish never waits for a Raft write.
+/// Changes to the same message are merged until they are written, a message that is fully
+/// acknowledged within one `INFLIGHT_PERSIST_INTERVAL` is not written at all.
+#[derive(Default)]
+pub struct InflightPersistQueue {
+    changes: Mutex<InflightChanges>,
+}
+
+impl InflightPersistQueue {
+    pub fn new() -> Self {
+        InflightPersistQueue::default()
+    }
+
+    fn save(&self, inflight: &MqttInflightMessage) {
+        let mut changes = self.changes.lock().unwrap();
+        changes.pending.insert(
+            (inflight.client_id.clone(), inflight.pkid),
+            Some(inflight.clone()),
+        );
+    }
+
+    fn delete(&self, client_id: &str, pkid: u16) {
+        let key = (client_id.to_owned(), pkid);
+        let mut changes = self.changes.lock().unwrap();
+        if changes.persisted.contains(&key) {
+            changes.pending.insert(key, None);
+        } else {
+            changes.pending.remove(&key);
+        }
+    }
+
+    // messages read back from the placement center, saved by this or another broker before
+    fn loaded(&self, list: &[MqttInflightMessage]) {
+        let mut changes = self.changes.lock().unwrap();
+        for inflight in list.iter() {
+            changes
+                .persisted
+                .insert((inflight.client_id.clone(), inflight.pkid));
+        }
+    }
+
+    fn clear(&self, client_id: &str) {
+        let mut changes = self.changes.lock().unwrap();
+        changes.pending.retain(|(id, _), _| id != client_id);
+        changes.persisted.retain(|(id, _)| id != client_id);
+    }
+
+    // Take the queued changes. A message counts as persisted from here on, so that a delete
+    // queued while it is being written is not dropped.
+    fn take(&self) -> Vec<(InflightKey, Option<MqttInflightMessage>)> {
+        let mut changes = self.changes.lock().unwrap();
+        let batch: Vec<_> = changes.pending.drain().collect();
+        for (key, inflight) in batch.iter() {
+            if inflight.is_some() {
+                changes.persisted.insert(key.clone());
+            } else {
+                changes.persisted.remove(key);
+            }
+        }
+        batch
+    }
+
+    // Queue a change again after its write failed, unless a newer change replaced it.
+    fn retry(&self, key: InflightKey, inflight: Option<MqttInflightMessage>) {
+        let mut changes = self.changes.lock().unwrap();
+        changes.persisted.insert(key.clone());
+        changes.pending.entry(key).or_insert(inflight);
+    }
+}
+
+// Only the sessions that outlive their connection need their inflight window to survive a
+// broker restart or a session takeover, a clean session starts over anyway.
+fn is_persistent_session(cache_manager: &Arc<CacheManager>, client_id: &str) -> bool {
+    cache_manager
+        .get_session_info(client_id)
+        .is_some_and(|session| session.session_expiry > 0)
+}
+
+pub fn inflight_save(cache_manager: &Arc<CacheManager>, inflight: &MqttInflightMessage) {
+    if !is_persistent_session(cache_manager, &inflight.client_id) {
+        return;
+    }
+    cache_manager.inflight_persist_queue.save(inflight);
+}
+
+pub fn inflight_delete(cache_manager: &Arc<CacheManager>, client_id: &str, pkid: u16) {
+    cache_manager.inflight_persist_queue.delete(client_id, pkid);
+}
+
+/// the inflight message of the push group `group_id` of the client, to resume after a broker
+/// restart or a session takeover
+pub async fn inflight_resume(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    client_id: &str,
+    group_id: &str,
+) -> Result<Option<MqttInflightMessage>, MqttBrokerError> {
+    let inflight_storage = InflightStorage::new(client_pool.clone());
+    let list = inflight_storage.list_inflight(client_id).await?;
+    cache_manager.inflight_persist_queue.loaded(&list);
+    Ok(list
+        .into_iter()
+        .filter(|inflight| inflight.group_id == group_id)
+        .min_by_key(|inflight| inflight.offset))
+}
+
+/// drop the inflight window of a session that is removed or started over
+pub async fn inflight_clear(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    client_id: &str,
+) -> Result<(), MqttBrokerError> {
+    cache_manager.inflight_persist_queue.clear(client_id);
+    let inflight_storage = InflightStorage::new(client_pool.clone());
+    inflight_storage.clear_inflight(client_id).await
+}
+
+pub async fn start_inflight_persist_thread(
+    cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let mut stop_rx = stop_send.subscribe();
+    loop {
+        select! {
+            val = stop_rx.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        break;
+                    }
+                }
+            }
+            _ = sleep(INFLIGHT_PERSIST_INTERVAL) => {
+                persist_inflight_changes(&cache_manager, &client_pool).await;
+            }
+        }
+    }
+
+    persist_inflight_changes(&cache_manager, &client_pool).await;
+    info!("Inflight persist thread stopped successfully.");
+}
+
+async fn persist_inflight_changes(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) {
+    let queue = &cache_manager.inflight_persist_queue;
+    let inflight_storage = InflightStorage::new(client_pool.clone());
+    for ((client_id, pkid), inflight) in queue.take() {
+        let result = match &inflight {
+            Some(inflight) => inflight_storage.save_inflight(inflight).await,
+            None => inflight_storage.delete_inflight(&client_id, pkid).await,
+        };
+        if let Err(e) = result {
+            error!(
+                "Failed to persist the inflight message of client [{}], pkid: {}, error message: {}",
+                client_id, pkid, e
+            );
+            queue.retry((client_id, pkid), inflight);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::mqtt::inflight::MqttInflightMessage;
+
+    use super::InflightPersistQueue;
+
+    fn build_inflight(pkid: u16) -> MqttInflightMessage {
+        MqttInflightMessage::new(
+            "c1".to_string(),
+            pkid,
+            "g1".to_string(),
+            "t1".to_string(),
+            pkid as u64,
+        )
+    }
+
+    #[test]
+    fn inflight_persist_queue_test() {
+        let queue = InflightPersistQueue::new();
+
+        // a message acknowledged before it is written is never written
+        queue.save(&build_inflight(1));
+        queue.delete("c1", 1);
+        assert!(queue.take().is_empty());
+
+        // a written message is deleted again
+        queue.save(&build_inflight(2));
+        let batch = queue.take();
+        assert_eq!(batch.len(), 1);
+        assert!(batch[0].1.is_some());
+        queue.delete("c1", 2);
+        let batch = queue.take();
+        assert_eq!(batch.len(), 1);
+        assert!(batch[0].1.is_none());
+
+        // a failed delete is kept until it succeeds
+        queue.retry(("c1".to_string(), 2), None);
+        queue.delete("c1", 2);
+        assert_eq!(queue.take().len(), 1);
+
+        // a message resumed from the placement center is deleted there
+        queue.loaded(&[build_inflight(4)]);
+        queue.delete("c1", 4);
+        assert_eq!(queue.take().len(), 1);
+
+        // a cleared session drops its queued changes
+        queue.save(&build_inflight(3));
+        queue.clear("c1");
+        assert!(queue.take().is_empty());
+    }
+}
@@ -20,10 +20,12 @@
 pub mod connection;
 pub mod constant;
 pub mod delay_message;
+pub mod enhanced_auth;
 pub mod error;
 pub mod flapping_detect;
 pub mod flow_control;
 pub mod heartbreat;
+pub mod inflight;
 pub mod keep_alive;
 pub mod lastwill;
 pub mod message;
@@ -15,17 +15,18 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use common_base::tools::now_second;
 use delay_message::DelayMessageManager;
 use grpc_clients::pool::ClientPool;
 use log::{error, warn};
 use protocol::mqtt::common::{
-    Connect, ConnectProperties, ConnectReturnCode, Disconnect, DisconnectProperties,
-    DisconnectReasonCode, LastWill, LastWillProperties, Login, MqttPacket, MqttProtocol, PingReq,
-    PubAck, PubAckProperties, PubAckReason, PubComp, PubCompProperties, PubCompReason, PubRec,
-    PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason, Publish,
-    PublishProperties, QoS, Subscribe, SubscribeProperties, SubscribeReasonCode, UnsubAckReason,
-    Unsubscribe, UnsubscribeProperties,
+    Auth, AuthProperties, AuthReason, Connect, ConnectProperties, ConnectReturnCode, Disconnect,
+    DisconnectProperties, DisconnectReasonCode, LastWill, LastWillProperties, Login, MqttPacket,
+    MqttProtocol, PingReq, PubAck, PubAckProperties, PubAckReason, PubComp, PubCompProperties,
+    PubCompReason, PubRec, PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason,
+    Publish, PublishProperties, QoS, Subscribe, SubscribeProperties, SubscribeReasonCode,
+    UnsubAckReason, Unsubscribe, UnsubscribeProperties,
 };
 use schema_register::schema::SchemaRegisterManager;
 use storage_adapter::storage::StorageAdapter;
@@ -40,17 +41,21 @@
     CacheManager, ConnectionLiveTime, QosAckPackageData, QosAckPackageType,
 };
 use crate::handler::connection::{build_connection, get_client_id};
+use crate::handler::enhanced_auth::{
+    authentication_data, authentication_method, EnhancedAuthExchange, PendingConnect,
+};
 use crate::handler::flapping_detect::check_flapping_detect;
+use crate::handler::inflight::inflight_clear;
 use crate::handler::lastwill::save_last_will_message;
 use crate::handler::pkid::{pkid_delete, pkid_exists, pkid_save};
 use crate::handler::response::{
-    response_packet_mqtt_connect_fail, response_packet_mqtt_connect_success,
-    response_packet_mqtt_distinct_by_reason, response_packet_mqtt_ping_resp,
-    response_packet_mqtt_puback_fail, response_packet_mqtt_puback_success,
-    response_packet_mqtt_pubcomp_fail, response_packet_mqtt_pubcomp_success,
-    response_packet_mqtt_pubrec_fail, response_packet_mqtt_pubrec_success,
-    response_packet_mqtt_pubrel_success, response_packet_mqtt_suback,
-    response_packet_mqtt_unsuback,
+    response_packet_mqtt_auth, response_packet_mqtt_connect_fail,
+    response_packet_mqtt_connect_success, response_packet_mqtt_distinct_by_reason,
+    response_packet_mqtt_ping_resp, response_packet_mqtt_puback_fail,
+    response_packet_mqtt_puback_success, response_packet_mqtt_pubcomp_fail,
+    response_packet_mqtt_pubcomp_success, response_packet_mqtt_pubrec_fail,
+    response_packet_mqtt_pubrec_success, response_packet_mqtt_pubrel_success,
+    response_packet_mqtt_suback, response_packet_mqtt_unsuback,
 };
 use crate::handler::retain::save_retain_message;
 use crate::handler::session::{build_session, save_session};
@@ -63,6 +68,8 @@
     st_report_connected_event, st_report_disconnected_event, st_report_subscribed_event,
     st_report_unsubscribed_event,
 };
+use crate::security::login::x509::X509Identity;
+use crate::security::login::{build_enhanced_auth, EnhancedAuthStep};
 use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::sub_common::{min_qos, path_contain_sub};
@@ -120,6 +127,8 @@ pub async fn connect(
         last_will_properties: Option<LastWillProperties>,
         login: &Option<Login>,
         addr: SocketAddr,
+        peer_cert: &Option<X509Identity>,
+        psk_identity: &Option<String>,
     ) -> MqttPacket {
         let cluster = self.cache_manager.get_cluster_info();
 
@@ -156,10 +165,46 @@ pub async fn connect(
             );
         }
 
+        // enhanced authentication, continued by the AUTH packets of the client
+        if let Some(method) = authentication_method(&connect_properties) {
+            let Some(mechanism) = build_enhanced_auth(&method, self.cache_manager.clone()) else {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::BadAuthenticationMethod,
+                    &connect_properties,
+                    None,
+                );
+            };
+            let data = authentication_data(&connect_properties);
+            let exchange = EnhancedAuthExchange {
+                mechanism,
+                connect: Some(PendingConnect {
+                    client_id,
+                    new_client_id,
+                    connection,
+                    connect,
+                    connect_properties,
+                    last_will,
+                    last_will_properties,
+                    login: login.clone(),
+                }),
+            };
+            return self.enhanced_auth_step(connect_id, exchange, data).await;
+        }
+
         // login check
+        let mut login = login.clone();
         match self
             .auth_driver
-            .check_login_auth(login, &connect_properties, &addr)
+            .check_login_auth(
+                &connection,
+                &mut login,
+                &connect_properties,
+                &addr,
+                &self.protocol,
+                peer_cert,
+                psk_identity,
+            )
             .await
         {
             Ok(flag) => {
@@ -182,6 +227,43 @@ pub async fn connect(
             }
         }
 
+        self.accept_connect(
+            connect_id,
+            PendingConnect {
+                client_id,
+                new_client_id,
+                connection,
+                connect,
+                connect_properties,
+                last_will,
+                last_will_properties,
+                login,
+            },
+            None,
+        )
+        .await
+    }
+
+    // set up the session of a connection that has been authenticated
+    async fn accept_connect(
+        &self,
+        connect_id: u64,
+        pending: PendingConnect,
+        authentication_data: Option<Bytes>,
+    ) -> MqttPacket {
+        let cluster = self.cache_manager.get_cluster_info();
+        let PendingConnect {
+            client_id,
+            new_client_id,
+            connection,
+            connect,
+            connect_properties,
+            last_will,
+            last_will_properties,
+            login,
+        } = pending;
+        let login = &login;
+
         // flapping detect check
         if cluster.flapping_detect.enable {
             check_flapping_detect(connect.client_id.clone(), &self.cache_manager);
@@ -227,6 +309,19 @@ pub async fn connect(
             );
         }
 
+        // a new session does not resume the inflight messages of the previous one
+        if new_session {
+            if let Err(e) = inflight_clear(&self.cache_manager, &self.client_pool, &client_id).await
+            {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::UnspecifiedError,
+                    &connect_properties,
+                    Some(e.to_string()),
+                );
+            }
+        }
+
         if let Err(e) = save_last_will_message(
             client_id.clone(),
             &last_will,
@@ -273,6 +368,11 @@ pub async fn connect(
             .add_session(client_id.clone(), session.clone());
         self.cache_manager
             .add_connection(connect_id, connection.clone());
+        let username = login
+            .as_ref()
+            .map(|login| login.username.clone())
+            .unwrap_or_default();
+        self.cache_manager.login_success(connect_id, username);
 
         st_report_connected_event(
             &self.message_storage_adapter,
@@ -294,9 +394,150 @@ pub async fn connect(
             new_session,
             connection.keep_alive,
             &connect_properties,
+            authentication_data,
         )
     }
 
+    /// the AUTH packet of a client, which continues the enhanced authentication of its CONNECT
+    /// packet or re-authenticates an established connection
+    pub async fn auth(
+        &self,
+        connect_id: u64,
+        auth: Auth,
+        auth_properties: Option<AuthProperties>,
+    ) -> MqttPacket {
+        let (method, data) = if let Some(properties) = auth_properties {
+            (
+                properties.authentication_method,
+                properties.authentication_data.unwrap_or_default(),
+            )
+        } else {
+            (None, Bytes::new())
+        };
+
+        match auth.reason.unwrap_or(AuthReason::Success) {
+            AuthReason::ContinueAuthentication => {
+                let Some(exchange) = self.connection_manager.take_enhanced_auth(connect_id) else {
+                    return response_packet_mqtt_distinct_by_reason(
+                        &self.protocol,
+                        Some(DisconnectReasonCode::ProtocolError),
+                    );
+                };
+                if method.as_deref() != Some(exchange.mechanism.method()) {
+                    return self.enhanced_auth_fail(
+                        exchange,
+                        ConnectReturnCode::ProtocolError,
+                        DisconnectReasonCode::ProtocolError,
+                        None,
+                    );
+                }
+                self.enhanced_auth_step(connect_id, exchange, data).await
+            }
+            AuthReason::ReAuthenticate => {
+                let connection_method = self
+                    .cache_manager
+                    .get_connection(connect_id)
+                    .and_then(|connection| connection.authentication_method);
+                // only a connection authenticated by the same method can re-authenticate
+                let mechanism = match (connection_method, method) {
+                    (Some(connection_method), Some(method)) if connection_method == method => {
+                        build_enhanced_auth(&method, self.cache_manager.clone())
+                    }
+                    _ => None,
+                };
+                let Some(mechanism) = mechanism else {
+                    return response_packet_mqtt_distinct_by_reason(
+                        &self.protocol,
+                        Some(DisconnectReasonCode::ProtocolError),
+                    );
+                };
+
+                // a new re-authentication replaces the unfinished one
+                self.connection_manager.take_enhanced_auth(connect_id);
+                let exchange = EnhancedAuthExchange {
+                    mechanism,
+                    connect: None,
+                };
+                self.enhanced_auth_step(connect_id, exchange, data).await
+            }
+            AuthReason::Success => response_packet_mqtt_distinct_by_reason(
+                &self.protocol,
+                Some(DisconnectReasonCode::ProtocolError),
+            ),
+        }
+    }
+
+    async fn enhanced_auth_step(
+        &self,
+        connect_id: u64,
+        mut exchange: EnhancedAuthExchange,
+        data: Bytes,
+    ) -> MqttPacket {
+        let method = exchange.mechanism.method().to_string();
+        let step = exchange.mechanism.step(&data).await;
+        match step {
+            Ok(EnhancedAuthStep::Continue(data)) => {
+                self.connection_manager
+                    .add_enhanced_auth(connect_id, exchange);
+                response_packet_mqtt_auth(AuthReason::ContinueAuthentication, method, Some(data))
+            }
+            Ok(EnhancedAuthStep::Success { username, data }) => {
+                if let Some(mut pending) = exchange.connect.take() {
+                    pending.login = Some(Login {
+                        username,
+                        password: "".to_string(),
+                    });
+                    return self.accept_connect(connect_id, pending, data).await;
+                }
+
+                // a re-authentication may not switch the connection to another user
+                let login_user = self
+                    .cache_manager
+                    .get_connection(connect_id)
+                    .map(|connection| connection.login_user);
+                if login_user.as_deref() != Some(username.as_str()) {
+                    return self.enhanced_auth_fail(
+                        exchange,
+                        ConnectReturnCode::NotAuthorized,
+                        DisconnectReasonCode::NotAuthorized,
+                        Some(format!(
+                            "re-authentication as {} on a connection of {:?}",
+                            username, login_user
+                        )),
+                    );
+                }
+                self.cache_manager.login_success(connect_id, username);
+                response_packet_mqtt_auth(AuthReason::Success, method, data)
+            }
+            Err(e) => self.enhanced_auth_fail(
+                exchange,
+                ConnectReturnCode::NotAuthorized,
+                DisconnectReasonCode::NotAuthorized,
+                Some(e.to_string()),
+            ),
+        }
+    }
+
+    // a failed CONNECT is answered by a CONNACK, a failed re-authentication by a DISCONNECT
+    fn enhanced_auth_fail(
+        &self,
+        exchange: EnhancedAuthExchange,
+        connect_code: ConnectReturnCode,
+        disconnect_code: DisconnectReasonCode,
+        reason: Option<String>,
+    ) -> MqttPacket {
+        if let Some(pending) = exchange.connect {
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                connect_code,
+                &pending.connect_properties,
+                reason,
+            );
+        }
+        warn!("re-authentication failed, {:?}", reason);
+        response_packet_mqtt_distinct_by_reason(&self.protocol, Some(disconnect_code))
+    }
+
     pub async fn publish(
         &self,
         connect_id: u64,
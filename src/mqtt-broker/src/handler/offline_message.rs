@@ -71,8 +71,15 @@ pub async fn save_message<S>(
             return Ok(None);
         } else {
             let offsets = message_storage
-                .append_topic_message(&topic.topic_id, vec![record])
+                .append_topic_message(&topic.topic_id, vec![record.clone()])
                 .await?;
+
+            // hand the message to the local push threads without a storage read
+            if let Some(offset) = offsets.first() {
+                let mut record = record;
+                record.offset = Some(*offset);
+                subscribe_manager.notify_topic_message(&topic.topic_id, record);
+            }
             Some(format!("{:?}", offsets))
         }
     } else {
@@ -12,18 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use bytes::Bytes;
 use log::{error, warn};
 use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::mqtt::common::{
-    ConnAck, ConnAckProperties, ConnectProperties, ConnectReturnCode, Disconnect,
-    DisconnectProperties, DisconnectReasonCode, MqttPacket, MqttProtocol, PingResp, PubAck,
-    PubAckProperties, PubAckReason, PubComp, PubCompProperties, PubCompReason, PubRec,
-    PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason, SubAck,
-    SubAckProperties, SubscribeReasonCode, UnsubAck, UnsubAckProperties, UnsubAckReason,
+    Auth, AuthProperties, AuthReason, ConnAck, ConnAckProperties, ConnectProperties,
+    ConnectReturnCode, Disconnect, DisconnectProperties, DisconnectReasonCode, MqttPacket,
+    MqttProtocol, PingResp, PubAck, PubAckProperties, PubAckReason, PubComp, PubCompProperties,
+    PubCompReason, PubRec, PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason,
+    SubAck, SubAckProperties, SubscribeReasonCode, UnsubAck, UnsubAckProperties, UnsubAckReason,
 };
 
 use super::connection::response_information;
+use super::enhanced_auth::authentication_method;
 use super::keep_alive::keep_live_time;
 use super::validator::is_request_problem_info;
 
@@ -37,6 +39,7 @@ pub fn response_packet_mqtt_connect_success(
     session_present: bool,
     keep_alive: u16,
     connect_properties: &Option<ConnectProperties>,
+    authentication_data: Option<Bytes>,
 ) -> MqttPacket {
     if !protocol.is_mqtt5() {
         return MqttPacket::ConnAck(
@@ -76,8 +79,8 @@ pub fn response_packet_mqtt_connect_success(
         server_keep_alive: Some(keep_live_time(keep_alive)),
         response_information: response_information(connect_properties),
         server_reference: None,
-        authentication_method: None,
-        authentication_data: None,
+        authentication_method: authentication_method(connect_properties),
+        authentication_data,
     };
     MqttPacket::ConnAck(
         ConnAck {
@@ -88,6 +91,25 @@ pub fn response_packet_mqtt_connect_success(
     )
 }
 
+pub fn response_packet_mqtt_auth(
+    reason: AuthReason,
+    authentication_method: String,
+    authentication_data: Option<Bytes>,
+) -> MqttPacket {
+    let properties = AuthProperties {
+        authentication_method: Some(authentication_method),
+        authentication_data,
+        reason_string: None,
+        user_properties: Vec::new(),
+    };
+    MqttPacket::Auth(
+        Auth {
+            reason: Some(reason),
+        },
+        Some(properties),
+    )
+}
+
 pub fn response_packet_mqtt_connect_fail(
     protocol: &MqttProtocol,
     code: ConnectReturnCode,
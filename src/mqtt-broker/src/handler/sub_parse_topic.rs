@@ -63,7 +63,18 @@ async fn parse_subscribe_by_new_topic(
             continue;
         }
 
-        for (_, subscribe) in subscribe_manager.subscribe_list.clone() {
+        for key in subscribe_manager
+            .subscribe_trie
+            .match_topic(&topic.topic_name)
+        {
+            let Some(subscribe) = subscribe_manager
+                .subscribe_list
+                .get(&key)
+                .map(|data| data.clone())
+            else {
+                continue;
+            };
+
             if subscribe.broker_id != conf.broker_id {
                 continue;
             }
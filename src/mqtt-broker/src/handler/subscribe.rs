@@ -28,8 +28,8 @@
 
 use crate::subscribe::{
     sub_common::{
-        decode_queue_info, decode_share_info, get_share_sub_leader, is_queue_sub, is_share_sub,
-        path_regex_match,
+        decode_queue_info, decode_share_info, get_share_sub_leader, get_sub_topic_id_list,
+        is_queue_sub, is_share_sub, path_regex_match,
     },
     subscribe_manager::{ShareSubShareSub, SubscribeManager},
     subscriber::Subscriber,
@@ -87,8 +87,15 @@ pub async fn save_subscribe(
     }
 
     // parse subscribe
-    for (_, topic) in cache_manager.topic_info.clone() {
-        for filter in subscribe.filters.clone() {
+    for filter in subscribe.filters.clone() {
+        for topic_id in get_sub_topic_id_list(cache_manager, &filter.path).await {
+            let Some(topic) = cache_manager
+                .topic_name_by_id(&topic_id)
+                .and_then(|topic_name| cache_manager.get_topic_by_name(&topic_name))
+            else {
+                continue;
+            };
+
             parse_subscribe(
                 client_pool,
                 cache_manager,
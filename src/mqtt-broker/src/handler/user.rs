@@ -18,11 +18,12 @@
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
 use log::{error, info};
-use metadata_struct::mqtt::user::MqttUser;
+use metadata_struct::mqtt::user::{build_password_hash_algorithm, MqttUser};
 use tokio::select;
 use tokio::sync::broadcast;
 use tokio::time::sleep;
 
+use crate::security::password::encrypt_user_password;
 use crate::security::AuthDriver;
 use crate::storage::user::UserStorage;
 
@@ -67,6 +68,11 @@ async fn update_user_cache(&self) {
                 error!("{}", e);
             }
         };
+        if broker_mqtt_conf().network.tls_psk_enable {
+            if let Err(e) = self.auth_driver.update_psk_cache().await {
+                error!("Updating PSK identities normal exception, {}", e);
+            }
+        }
         sleep(Duration::from_secs(5)).await;
     }
 }
@@ -77,6 +83,19 @@ pub async fn init_system_user(cache_manager: &Arc<CacheManager>, client_pool: &A
         username: conf.system.default_user.clone(),
         password: conf.system.default_password.clone(),
         is_superuser: true,
+        ..Default::default()
+    };
+    let algorithm = match build_password_hash_algorithm(&conf.auth.password_hash_algorithm) {
+        Ok(algorithm) => algorithm,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
+    let system_user_info = match encrypt_user_password(system_user_info, algorithm).await {
+        Ok(user) => user,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
     };
     let user_storage = UserStorage::new(client_pool.clone());
     match user_storage.save_user(system_user_info.clone()).await {
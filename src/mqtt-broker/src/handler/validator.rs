@@ -32,9 +32,7 @@
 
 use super::cache::CacheManager;
 use super::error::MqttBrokerError;
-use super::flow_control::{
-    is_connection_rate_exceeded, is_qos_message, is_subscribe_rate_exceeded,
-};
+use super::flow_control::is_qos_message;
 use super::pkid::pkid_exists;
 use super::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
@@ -45,6 +43,7 @@
 use super::topic::topic_name_validator;
 use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
+use crate::server::tcp::tls_server::TlsServerStream;
 use crate::subscribe::sub_common::sub_path_validator;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
@@ -58,31 +57,19 @@ pub async fn tcp_establish_connection_check(
     {
         return value;
     }
-
-    if let Some(value) = handle_connection_rate_exceeded(addr, write_frame_stream).await {
-        return value;
-    }
     true
 }
 
 pub async fn tcp_tls_establish_connection_check(
     addr: &SocketAddr,
     connection_manager: &Arc<ConnectionManager>,
-    write_frame_stream: &mut FramedWrite<
-        WriteHalf<tokio_rustls::server::TlsStream<TcpStream>>,
-        MqttCodec,
-    >,
+    write_frame_stream: &mut FramedWrite<WriteHalf<TlsServerStream>, MqttCodec>,
 ) -> bool {
     if let Some(value) =
         handle_tpc_connection_overflow(addr, connection_manager, write_frame_stream).await
     {
         return value;
     }
-
-    if let Some(value) = handle_connection_rate_exceeded(addr, write_frame_stream).await {
-        return value;
-    }
-
     true
 }
 
@@ -121,40 +108,6 @@ async fn handle_tpc_connection_overflow<T>(
     None
 }
 
-async fn handle_connection_rate_exceeded<T>(
-    addr: &SocketAddr,
-    write_frame_stream: &mut FramedWrite<WriteHalf<T>, MqttCodec>,
-) -> Option<bool>
-where
-    T: AsyncWriteExt + AsyncWrite,
-{
-    if is_connection_rate_exceeded() {
-        let packet_wrapper = MqttPacketWrapper {
-            protocol_version: MqttProtocol::Mqtt5.into(),
-            packet: response_packet_mqtt_distinct_by_reason(
-                &MqttProtocol::Mqtt5,
-                Some(DisconnectReasonCode::ConnectionRateExceeded),
-            ),
-        };
-        match write_frame_stream.send(packet_wrapper).await {
-            Ok(_) => {}
-            Err(e) => error!("{}", e),
-        }
-
-        match write_frame_stream.close().await {
-            Ok(_) => {
-                error!(
-                    "tcp connection failed to establish from IP: {}",
-                    addr.to_string()
-                );
-            }
-            Err(e) => error!("{}", e),
-        }
-        return Some(false);
-    }
-    None
-}
-
 pub fn connect_validator(
     protocol: &MqttProtocol,
     cluster: &MqttClusterDynamicConfig,
@@ -430,16 +383,6 @@ pub async fn subscribe_validator(
         ));
     }
 
-    if is_subscribe_rate_exceeded() {
-        return Some(response_packet_mqtt_suback(
-            protocol,
-            connection,
-            subscribe.packet_identifier,
-            vec![SubscribeReasonCode::QuotaExceeded],
-            None,
-        ));
-    }
-
     if !check_exclusive_subscribe(metadata_cache, subscribe_manager, subscribe) {
         return Some(response_packet_mqtt_suback(
             protocol,
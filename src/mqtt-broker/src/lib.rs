@@ -12,7 +12,6 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -27,7 +26,9 @@
 use handler::acl::UpdateAclCache;
 use handler::cache::CacheManager;
 use handler::cache_update::load_metadata_cache;
+use handler::error::MqttBrokerError;
 use handler::heartbreat::{register_node, report_heartbeat};
+use handler::inflight::start_inflight_persist_thread;
 use handler::keep_alive::ClientKeepAlive;
 use handler::sub_parse_topic::start_parse_subscribe_by_new_topic_thread;
 use handler::user::{init_system_user, UpdateUserCache};
@@ -38,25 +39,32 @@
 use security::AuthDriver;
 use server::connection_manager::ConnectionManager;
 use server::grpc::server::GrpcServer;
+use server::http::server::{http_admin_server, HttpServerState};
 use server::tcp::server::start_tcp_server;
 use server::websocket::server::{websocket_server, websockets_server, WebSocketServerState};
 use storage::cluster::ClusterStorage;
+use storage_adapter::journal::JournalStorageAdapter;
 use storage_adapter::memory::MemoryStorageAdapter;
-// use storage_adapter::mysql::MySQLStorageAdapter;
-// use storage_adapter::rocksdb::RocksDBStorageAdapter;
-use crate::handler::flapping_detect::UpdateFlappingDetectCache;
-use crate::server::quic::server::start_quic_server;
+use storage_adapter::minio::MinIoStorageAdapter;
+use storage_adapter::mysql::MySQLStorageAdapter;
+use storage_adapter::placement::PlacementStorageAdapter;
+use storage_adapter::rocksdb::RocksDBStorageAdapter;
+use storage_adapter::s3::{S3Config, S3StorageAdapter};
 use storage_adapter::storage::StorageAdapter;
-use storage_adapter::StorageType;
+use storage_adapter::{check_storage_config, StorageType};
 use subscribe::exclusive_push::ExclusivePush;
 use subscribe::share_follower_resub::ShareFollowerResub;
 use subscribe::share_leader_push::ShareLeaderPush;
 use subscribe::subscribe_manager::SubscribeManager;
+use third_driver::mysql::build_mysql_conn_pool;
 use tokio::runtime::Runtime;
 use tokio::signal;
 use tokio::sync::broadcast::{self};
 use tokio::time::sleep;
 
+use crate::handler::flapping_detect::UpdateFlappingDetectCache;
+use crate::server::quic::server::start_quic_server;
+
 lazy_static! {
     pub static ref BROKER_START_TIME: u64 = now_second();
 }
@@ -70,47 +78,93 @@
 pub mod storage;
 mod subscribe;
 
-pub fn start_mqtt_broker_server(stop_send: broadcast::Sender<bool>) {
+pub fn start_mqtt_broker_server(stop_send: broadcast::Sender<bool>) -> Result<(), MqttBrokerError> {
     let conf = broker_mqtt_conf();
+    let storage_type = check_storage_config(&conf.storage)?;
+
+    let runtime = create_runtime(
+        "storage-engine-server-runtime",
+        conf.system.runtime_worker_threads,
+    );
     let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(5));
     let metadata_cache = Arc::new(CacheManager::new(
         client_pool.clone(),
         conf.cluster_name.clone(),
     ));
-    // let storage_type = conf.storage.storage_type.clone();
-    let storage_type = StorageType::from_str(conf.storage.storage_type.as_str())
-        .expect("Storage type not supported");
+
     match storage_type {
         StorageType::Memory => {
             let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
-            let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
+            let server = MqttBroker::new(
+                runtime,
+                client_pool,
+                message_storage_adapter,
+                metadata_cache,
+            );
             server.start(stop_send);
         }
-        // StorageType::Mysql => {
-        //     if conf.storage.mysql_addr.is_empty() {
-        //         panic!("storaget type is [mysql],[storage.mysql_addr] cannot be empty");
-        //     }
-        //     let pool = build_mysql_conn_pool(&conf.storage.mysql_addr).unwrap();
-        //     let message_storage_adapter = Arc::new(MySQLStorageAdapter::new(pool.clone()));
-        //     let server: MqttBroker<MySQLStorageAdapter> =
-        //         MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
-        //     server.start(stop_send);
-        // }
-        // StorageType::RocksDB => {
-        //     if conf.storage.rocksdb_data_path.is_empty() {
-        //         panic!("storaget type is [rocksdb],[storage.rocksdb_path] cannot be empty");
-        //     }
-        //     let message_storage_adapter = Arc::new(RocksDBStorageAdapter::new(
-        //         conf.storage.rocksdb_data_path.as_str(),
-        //         conf.storage.rocksdb_max_open_files.unwrap_or(10000),
-        //     ));
-        //     let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
-        //     server.start(stop_send);
-        // }
-        _ => {
-            panic!("Message data storage type configuration error, optional :mysql, memory");
+        StorageType::Journal => {
+            let journal_addrs = conf
+                .storage
+                .journal_addr
+                .split(',')
+                .map(|addr| addr.trim().to_string())
+                .filter(|addr| !addr.is_empty())
+                .collect();
+            let adapter = runtime.block_on(JournalStorageAdapter::new(
+                client_pool.clone(),
+                conf.cluster_name.clone(),
+                journal_addrs,
+                conf.placement_center.clone(),
+            ))?;
+            let server = MqttBroker::new(runtime, client_pool, Arc::new(adapter), metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::Mysql => {
+            let pool = build_mysql_conn_pool(&conf.storage.mysql_addr)?;
+            let adapter = runtime.block_on(MySQLStorageAdapter::new(pool))?;
+            let server = MqttBroker::new(runtime, client_pool, Arc::new(adapter), metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::RocksDB => {
+            let adapter = RocksDBStorageAdapter::new(
+                conf.storage.rocksdb_data_path.as_str(),
+                conf.storage.rocksdb_max_open_files.unwrap_or(10000),
+            );
+            let server = MqttBroker::new(runtime, client_pool, Arc::new(adapter), metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::Placement => {
+            let adapter =
+                PlacementStorageAdapter::new(client_pool.clone(), conf.placement_center.clone());
+            let server = MqttBroker::new(runtime, client_pool, Arc::new(adapter), metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::MinIO => {
+            let adapter = MinIoStorageAdapter::new_minio(
+                conf.storage.minio_endpoint.as_str(),
+                conf.storage.minio_access_key_id.as_str(),
+                conf.storage.minio_secret_access_key.as_str(),
+                conf.storage.minio_data_dir.as_str(),
+                conf.storage.minio_bucket.as_str(),
+            )?;
+            let server = MqttBroker::new(runtime, client_pool, Arc::new(adapter), metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::S3 => {
+            let adapter = S3StorageAdapter::new(S3Config {
+                endpoint: conf.storage.s3_endpoint.clone(),
+                region: conf.storage.s3_region.clone(),
+                bucket: conf.storage.s3_bucket.clone(),
+                root: conf.storage.s3_root.clone(),
+                access_key_id: conf.storage.s3_access_key_id.clone(),
+                secret_access_key: conf.storage.s3_secret_access_key.clone(),
+            })?;
+            let server = MqttBroker::new(runtime, client_pool, Arc::new(adapter), metadata_cache);
+            server.start(stop_send);
         }
     }
+    Ok(())
 }
 
 pub struct MqttBroker<S> {
@@ -131,16 +185,12 @@ impl<S> MqttBroker<S>
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     pub fn new(
+        runtime: Runtime,
         client_pool: Arc<ClientPool>,
         message_storage_adapter: Arc<S>,
         cache_manager: Arc<CacheManager>,
     ) -> Self {
         let conf = broker_mqtt_conf();
-        let runtime = create_runtime(
-            "storage-engine-server-runtime",
-            conf.system.runtime_worker_threads,
-        );
-
         let subscribe_manager = Arc::new(SubscribeManager::new());
         let connector_manager = Arc::new(ConnectorManager::new());
         let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
@@ -172,6 +222,7 @@ pub fn start(&self, stop_send: broadcast::Sender<bool>) {
         self.start_cluster_heartbeat_report(stop_send.clone());
 
         self.start_push_server(stop_send.clone());
+        self.start_inflight_persist_thread(stop_send.clone());
 
         self.start_grpc_server();
 
@@ -183,6 +234,7 @@ pub fn start(&self, stop_send: broadcast::Sender<bool>) {
         self.start_update_cache_thread(stop_send.clone());
         self.start_system_topic_thread(stop_send.clone());
         self.start_prometheus();
+        self.start_http_admin_server();
         self.start_connector_thread(stop_send.clone());
         self.awaiting_stop(stop_send);
     }
@@ -227,6 +279,23 @@ fn start_prometheus(&self) {
         }
     }
 
+    fn start_http_admin_server(&self) {
+        let conf = broker_mqtt_conf();
+        if conf.http_admin.enable {
+            let state = HttpServerState::new(
+                self.client_pool.clone(),
+                self.cache_manager.clone(),
+                self.connection_manager.clone(),
+                self.subscribe_manager.clone(),
+            );
+            self.runtime.spawn(async move {
+                if let Err(e) = http_admin_server(state).await {
+                    error!("HTTP admin server failed to start, error message: {}", e);
+                }
+            });
+        }
+    }
+
     fn start_quic_server(&self, stop_send: broadcast::Sender<bool>) {
         let cache = self.cache_manager.clone();
         let message_storage_adapter = self.message_storage_adapter.clone();
@@ -312,11 +381,26 @@ fn start_cluster_heartbeat_report(&self, stop_send: broadcast::Sender<bool>) {
         });
     }
 
+    fn start_inflight_persist_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let cache_manager = self.cache_manager.clone();
+        let client_pool = self.client_pool.clone();
+        self.runtime.spawn(async move {
+            start_inflight_persist_thread(cache_manager, client_pool, stop_send).await;
+        });
+    }
+
     fn start_connector_thread(&self, stop_send: broadcast::Sender<bool>) {
         let message_storage = self.message_storage_adapter.clone();
         let connector_manager = self.connector_manager.clone();
+        let subscribe_manager = self.subscribe_manager.clone();
         self.runtime.spawn(async move {
-            start_connector_thread(message_storage, connector_manager, stop_send).await;
+            start_connector_thread(
+                message_storage,
+                connector_manager,
+                subscribe_manager,
+                stop_send,
+            )
+            .await;
         });
     }
 
@@ -338,6 +422,7 @@ fn start_push_server(&self, stop_send: broadcast::Sender<bool>) {
         let exclusive_sub = ExclusivePush::new(
             self.message_storage_adapter.clone(),
             self.cache_manager.clone(),
+            self.client_pool.clone(),
             self.subscribe_manager.clone(),
             self.connection_manager.clone(),
         );
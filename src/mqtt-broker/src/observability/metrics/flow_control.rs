@@ -0,0 +1,74 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+use crate::server::connection::NetworkConnectionType;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ThrottledLabel {
+    network: String,
+    limit: String,
+}
+
+common_base::register_counter_metric!(
+    THROTTLED_EVENTS,
+    "throttled_events",
+    "Number of times a connection or a client was throttled by a rate limit",
+    ThrottledLabel
+);
+
+pub fn record_throttled_event(network_type: &NetworkConnectionType, limit: &str) {
+    let labels = ThrottledLabel {
+        network: network_type.to_string(),
+        limit: limit.to_string(),
+    };
+    common_base::counter_metric_inc!(THROTTLED_EVENTS, labels)
+}
+
+pub fn get_throttled_event_counter(network_type: &NetworkConnectionType, limit: &str) -> u64 {
+    let labels = ThrottledLabel {
+        network: network_type.to_string(),
+        limit: limit.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(THROTTLED_EVENTS, labels, res);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_throttled_event_counter, record_throttled_event};
+    use crate::server::connection::NetworkConnectionType;
+
+    #[test]
+    fn record_throttled_event_test() {
+        record_throttled_event(&NetworkConnectionType::Tcp, "publish");
+        record_throttled_event(&NetworkConnectionType::Tcp, "publish");
+        record_throttled_event(&NetworkConnectionType::Tls, "publish");
+
+        assert_eq!(
+            get_throttled_event_counter(&NetworkConnectionType::Tcp, "publish"),
+            2
+        );
+        assert_eq!(
+            get_throttled_event_counter(&NetworkConnectionType::Tls, "publish"),
+            1
+        );
+        assert_eq!(
+            get_throttled_event_counter(&NetworkConnectionType::Tcp, "subscribe"),
+            0
+        );
+    }
+}
@@ -14,6 +14,7 @@
 
 pub mod auth;
 pub mod event_metrics;
+pub mod flow_control;
 pub mod packets;
 pub mod publish;
 pub mod server;
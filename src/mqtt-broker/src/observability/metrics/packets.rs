@@ -483,6 +483,8 @@ fn calc_mqtt_packet_test() {
             connection_stop_sx: None,
             connection_id: 100,
             protocol: Some(MqttProtocol::Mqtt3),
+            peer_cert: None,
+            psk_identity: None,
         };
         let ty = NetworkConnectionType::Tcp;
         record_received_metrics(&nc, &mp, &ty);
@@ -12,20 +12,59 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
 
 use common_base::tools::now_second;
 use ipnet::IpNet;
 use log::info;
-use metadata_struct::acl::mqtt_acl::{MqttAclAction, MqttAclPermission};
+use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction, MqttAclPermission};
 use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::mqtt::common::QoS;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::handler::cache::CacheManager;
 use crate::handler::constant::WILDCARD_RESOURCE;
+use crate::subscribe::topic_trie::topic_match_filter;
+
+const ACL_TOPIC_EQ_PREFIX: &str = "eq ";
+
+/// the result of evaluating the acl for an action of a connection on a topic
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AclCheckResult {
+    pub allow: bool,
+    pub source: AclCheckSource,
+    // the rule that decided, if any
+    pub rule: Option<MqttAcl>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AclCheckSource {
+    SuperUser,
+    Blacklist,
+    // the acl carried by the login token of the connection
+    Connection,
+    Rule,
+    // no rule matches, the acl_no_match policy of the cluster decides
+    NoMatch,
+}
+
+impl AclCheckResult {
+    fn new(allow: bool, source: AclCheckSource, rule: Option<MqttAcl>) -> Self {
+        AclCheckResult {
+            allow,
+            source,
+            rule,
+        }
+    }
+
+    fn from_rule(source: AclCheckSource, rule: MqttAcl) -> Self {
+        let allow = rule.permission == MqttAclPermission::Allow;
+        AclCheckResult::new(allow, source, Some(rule))
+    }
+}
 
 pub fn is_allow_acl(
     cache_manager: &Arc<CacheManager>,
@@ -35,27 +74,68 @@ pub fn is_allow_acl(
     retain: bool,
     _: QoS,
 ) -> bool {
+    check_acl(cache_manager, connection, topic_name, action, retain).allow
+}
+
+/// evaluate the acl for an action of a connection on a topic
+///
+/// The rules of the user, the client id, the IP/CIDR and the global rules are checked in that
+/// order and the first matching rule decides. When no rule matches, the `acl_no_match` policy of
+/// the cluster applies.
+pub fn check_acl(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    action: MqttAclAction,
+    retain: bool,
+) -> AclCheckResult {
     // check super user
     if is_super_user(cache_manager, &connection.login_user) {
-        return true;
+        return AclCheckResult::new(true, AclCheckSource::SuperUser, None);
     }
 
     // check blacklist
     if is_blacklist(cache_manager, connection) {
-        return false;
+        return AclCheckResult::new(false, AclCheckSource::Blacklist, None);
     }
 
-    // check acl
-    if is_acl_deny(cache_manager, connection, topic_name, action) {
-        return false;
+    // check the acl carried by the login token of the connection
+    if let Some(rule) = match_connection_acl(cache_manager, connection, topic_name, &action) {
+        return AclCheckResult::from_rule(AclCheckSource::Connection, rule);
     }
 
-    // check retain acl
-    if retain && is_acl_deny(cache_manager, connection, topic_name, MqttAclAction::Retain) {
-        return false;
+    // check acl
+    let result = match match_acl(cache_manager, connection, topic_name, &action) {
+        Some(rule) => AclCheckResult::from_rule(AclCheckSource::Rule, rule),
+        None => AclCheckResult::new(
+            acl_no_match(cache_manager) == MqttAclPermission::Allow,
+            AclCheckSource::NoMatch,
+            None,
+        ),
+    };
+
+    // check retain acl, only an explicit rule denies retaining
+    if result.allow && retain {
+        if let Some(rule) = match_acl(
+            cache_manager,
+            connection,
+            topic_name,
+            &MqttAclAction::Retain,
+        ) {
+            if rule.permission == MqttAclPermission::Deny {
+                return AclCheckResult::from_rule(AclCheckSource::Rule, rule);
+            }
+        }
     }
 
-    true
+    result
+}
+
+fn acl_no_match(cache_manager: &Arc<CacheManager>) -> MqttAclPermission {
+    if let Some(cluster) = cache_manager.cluster_info.get(&cache_manager.cluster_name) {
+        return cluster.security.acl_no_match.clone();
+    }
+    MqttAclPermission::default()
 }
 
 fn is_super_user(cache_manager: &Arc<CacheManager>, username: &str) -> bool {
@@ -129,7 +209,7 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
         .blacklist_ip
         .get(&connection.source_ip_addr)
     {
-        if data.end_time < now_second() {
+        if data.end_time > now_second() {
             info!(
                 "ip blacklist banned,source_ip_addr:{}",
                 &connection.source_ip_addr
@@ -141,7 +221,7 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
     if let Some(data) = cache_manager.acl_metadata.get_blacklist_ip_match() {
         for raw in data {
             if ip_match(&connection.source_ip_addr, &raw.resource_name)
-                && raw.end_time < now_second()
+                && raw.end_time > now_second()
             {
                 info!(
                     "ip blacklist banned by match,source_ip_addr:{}",
@@ -155,52 +235,179 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
     false
 }
 
-fn is_acl_deny(
-    cache_mamanger: &Arc<CacheManager>,
+/// whether the user, client id, IP/CIDR and global acl deny the action, without the super user,
+/// the blacklist and the acl of the connection
+pub fn is_acl_deny(
+    cache_manager: &Arc<CacheManager>,
     connection: &MQTTConnection,
     topic_name: &str,
     action: MqttAclAction,
 ) -> bool {
+    match match_acl(cache_manager, connection, topic_name, &action) {
+        Some(rule) => rule.permission == MqttAclPermission::Deny,
+        None => acl_no_match(cache_manager) == MqttAclPermission::Deny,
+    }
+}
+
+/// the matching rule of the user, the client id, the IP/CIDR and the global acl with the lowest
+/// priority
+///
+/// Rules of the same priority are checked in the order user, client id, the most specific
+/// address or network, and global acl.
+fn match_acl(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    action: &MqttAclAction,
+) -> Option<MqttAcl> {
+    let acl_metadata = &cache_manager.acl_metadata;
+    let mut candidates = Vec::new();
+
     // check user acl
-    if let Some(acl_list) = cache_mamanger
-        .acl_metadata
-        .acl_user
-        .get(&connection.login_user)
-    {
-        for raw in acl_list.clone() {
-            if topic_match(topic_name, &raw.topic)
-                && ip_match(&connection.source_ip_addr, &raw.ip)
-                && (raw.action == action || raw.action == MqttAclAction::All)
-                && raw.permission == MqttAclPermission::Deny
-            {
-                return true;
-            }
-        }
+    if let Some(acl_list) = acl_metadata.acl_user.get(&connection.login_user) {
+        candidates.extend(match_acl_rule(connection, topic_name, action, &acl_list));
     }
+
     // check client id acl
-    if let Some(client_id_list) = cache_mamanger
+    if let Some(acl_list) = acl_metadata.acl_client_id.get(&connection.client_id) {
+        candidates.extend(match_acl_rule(connection, topic_name, action, &acl_list));
+    }
+
+    // check ip acl, the most specific address or network first
+    let source_ip = connection_ip(connection);
+    let mut ip_acl: Vec<(u8, String, Vec<MqttAcl>)> = acl_metadata
+        .acl_ip
+        .iter()
+        .filter(|raw| ip_match(&source_ip, raw.key()))
+        .map(|raw| {
+            (
+                ip_prefix_len(raw.key()),
+                raw.key().clone(),
+                raw.value().clone(),
+            )
+        })
+        .collect();
+    ip_acl.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    for (_, _, acl_list) in ip_acl {
+        candidates.extend(match_acl_rule(connection, topic_name, action, &acl_list));
+    }
+
+    // check global acl
+    if let Some(acl_list) = acl_metadata.get_acl_all() {
+        candidates.extend(match_acl_rule(connection, topic_name, action, &acl_list));
+    }
+
+    // min_by_key keeps the first of the rules with the lowest priority
+    candidates.into_iter().min_by_key(|rule| rule.priority)
+}
+
+/// the acl carried by the login token of the connection, such as the acl claim of a JWT
+///
+/// The first matching rule decides, `None` means that no rule matches and the other acl apply.
+fn match_connection_acl(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    action: &MqttAclAction,
+) -> Option<MqttAcl> {
+    let acl_list = cache_manager
         .acl_metadata
-        .acl_client_id
-        .get(&connection.client_id)
-    {
-        for raw in client_id_list.clone() {
-            if topic_match(topic_name, &raw.topic)
-                && ip_match(&connection.source_ip_addr, &raw.ip)
-                && (raw.action == action || raw.action == MqttAclAction::All)
-                && raw.permission == MqttAclPermission::Deny
-            {
-                return true;
-            }
+        .get_connection_acl(connection.connect_id)?;
+    match_acl_rule(connection, topic_name, action, &acl_list)
+}
+
+fn match_acl_rule(
+    connection: &MQTTConnection,
+    topic_name: &str,
+    action: &MqttAclAction,
+    acl_list: &[MqttAcl],
+) -> Option<MqttAcl> {
+    let source_ip = connection_ip(connection);
+    acl_list
+        .iter()
+        .find(|raw| {
+            acl_topic_match(connection, topic_name, &raw.topic)
+                && ip_match(&source_ip, &raw.ip)
+                && action_match(&raw.action, action)
+        })
+        .cloned()
+}
+
+fn action_match(rule_action: &MqttAclAction, action: &MqttAclAction) -> bool {
+    match rule_action {
+        MqttAclAction::All => true,
+        MqttAclAction::PubSub => {
+            *action == MqttAclAction::Publish || *action == MqttAclAction::Subscribe
         }
+        _ => rule_action == action,
     }
-    false
 }
 
+/// match the topic of an ACL rule after replacing the `${username}`, `${clientid}` and `${ip}`
+/// placeholders with the values of the connection
+///
+/// A rule whose placeholder has an empty value, or a value with wildcards or levels, never
+/// matches, so that a client cannot widen the rule with its own identity.
+fn acl_topic_match(connection: &MQTTConnection, topic_name: &str, match_topic_name: &str) -> bool {
+    if !match_topic_name.contains("${") || match_topic_name.starts_with(ACL_TOPIC_EQ_PREFIX) {
+        return topic_match(topic_name, match_topic_name);
+    }
+
+    let ip = connection_ip(connection);
+    let mut rule_topic = match_topic_name.to_string();
+    for (placeholder, value) in [
+        ("${username}", connection.login_user.as_str()),
+        ("${clientid}", connection.client_id.as_str()),
+        ("${ip}", ip.as_str()),
+    ] {
+        if !rule_topic.contains(placeholder) {
+            continue;
+        }
+        if value.is_empty() || value.contains(['+', '#', '/']) {
+            return false;
+        }
+        rule_topic = rule_topic.replace(placeholder, value);
+    }
+    topic_match(topic_name, &rule_topic)
+}
+
+/// whether the topic of an ACL rule matches the topic name of a PUBLISH, or covers the whole topic
+/// filter of a SUBSCRIBE
+///
+/// The topic of a rule is `*`, a topic filter with the `+` and `#` wildcards, or a literal topic
+/// prefixed by `eq `, which matches only the same topic name or filter.
 fn topic_match(topic_name: &str, match_topic_name: &str) -> bool {
     if match_topic_name == WILDCARD_RESOURCE {
         return true;
     }
-    topic_name == match_topic_name
+    if let Some(literal) = match_topic_name.strip_prefix(ACL_TOPIC_EQ_PREFIX) {
+        return topic_name == literal;
+    }
+
+    // a single level wildcard does not cover the multi level wildcard of a SUBSCRIBE filter
+    let plus_against_hash = topic_name
+        .split('/')
+        .zip(match_topic_name.split('/'))
+        .any(|(level, filter_level)| level == "#" && filter_level == "+");
+    !plus_against_hash && topic_match_filter(topic_name, match_topic_name)
+}
+
+/// the ip address of the connection, without the port
+fn connection_ip(connection: &MQTTConnection) -> String {
+    match SocketAddr::from_str(&connection.source_ip_addr) {
+        Ok(addr) => addr.ip().to_string(),
+        Err(_) => connection.source_ip_addr.clone(),
+    }
+}
+
+fn ip_prefix_len(ip_role: &str) -> u8 {
+    if ip_role == WILDCARD_RESOURCE {
+        return 0;
+    }
+    match IpNet::from_str(ip_role) {
+        Ok(ip_cidr) => ip_cidr.prefix_len(),
+        Err(_) => u8::MAX,
+    }
 }
 
 fn ip_match(source_ip_addr: &str, ip_role: &str) -> bool {
@@ -231,8 +438,14 @@ mod test {
     use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
     use metadata_struct::mqtt::user::MqttUser;
 
-    use super::{ip_match, is_acl_deny, is_blacklist, is_super_user, topic_match};
+    use protocol::mqtt::common::QoS;
+
+    use super::{
+        acl_topic_match, check_acl, ip_match, is_acl_deny, is_allow_acl, is_blacklist,
+        is_super_user, topic_match, AclCheckSource,
+    };
     use crate::handler::cache::CacheManager;
+    use crate::handler::cluster_config::build_default_cluster_config;
     use crate::handler::constant::WILDCARD_RESOURCE;
 
     #[tokio::test]
@@ -245,6 +458,7 @@ pub async fn check_super_user_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            ..Default::default()
         };
         cache_manager.add_user(user.clone());
 
@@ -260,6 +474,7 @@ pub async fn check_super_user_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: false,
+            ..Default::default()
         };
         cache_manager.add_user(user.clone());
         assert!(!is_super_user(&cache_manager, &user.username));
@@ -274,6 +489,7 @@ pub async fn check_black_list_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            ..Default::default()
         };
 
         cache_manager.add_user(user.clone());
@@ -351,6 +567,60 @@ pub async fn check_black_list_test() {
         assert!(is_blacklist(&cache_manager, &connection));
     }
 
+    #[tokio::test]
+    pub async fn check_ip_black_list_test() {
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: "127.0.0.1".to_string(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("loboxu".to_string());
+
+        let cases = [
+            (
+                MqttAclBlackListType::Ip,
+                "127.0.0.1",
+                now_second() + 100,
+                true,
+            ),
+            (
+                MqttAclBlackListType::Ip,
+                "127.0.0.1",
+                now_second() - 100,
+                false,
+            ),
+            (
+                MqttAclBlackListType::IPCIDR,
+                "127.0.0.0/24",
+                now_second() + 100,
+                true,
+            ),
+            (
+                MqttAclBlackListType::IPCIDR,
+                "127.0.0.0/24",
+                now_second() - 100,
+                false,
+            ),
+        ];
+        for (blacklist_type, resource_name, end_time, banned) in cases {
+            let client_pool = Arc::new(ClientPool::new(1));
+            let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+            cache_manager.add_blacklist(MqttAclBlackList {
+                blacklist_type,
+                resource_name: resource_name.to_string(),
+                end_time,
+                desc: "".to_string(),
+            });
+            assert_eq!(is_blacklist(&cache_manager, &connection), banned);
+        }
+    }
+
     #[tokio::test]
     pub async fn check_empty_acl_test() {
         let client_pool = Arc::new(ClientPool::new(1));
@@ -361,6 +631,7 @@ pub async fn check_empty_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            ..Default::default()
         };
 
         cache_manager.add_user(user.clone());
@@ -402,6 +673,7 @@ pub async fn check_user_wildcard_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            ..Default::default()
         };
 
         cache_manager.add_user(user.clone());
@@ -425,6 +697,7 @@ pub async fn check_user_wildcard_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Publish,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -448,6 +721,7 @@ pub async fn check_user_wildcard_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Subscribe,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -468,6 +742,7 @@ pub async fn check_user_match_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            ..Default::default()
         };
 
         cache_manager.add_user(user.clone());
@@ -491,6 +766,7 @@ pub async fn check_user_match_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Publish,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -514,6 +790,7 @@ pub async fn check_user_match_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Subscribe,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -534,6 +811,7 @@ pub async fn check_client_id_wildcard_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            ..Default::default()
         };
 
         cache_manager.add_user(user.clone());
@@ -557,6 +835,7 @@ pub async fn check_client_id_wildcard_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Publish,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -580,6 +859,7 @@ pub async fn check_client_id_wildcard_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Subscribe,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -600,6 +880,7 @@ pub async fn check_client_id_match_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            ..Default::default()
         };
 
         cache_manager.add_user(user.clone());
@@ -623,6 +904,7 @@ pub async fn check_client_id_match_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Publish,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -646,6 +928,7 @@ pub async fn check_client_id_match_acl_test() {
             ip: WILDCARD_RESOURCE.to_string(),
             action: MqttAclAction::Subscribe,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
         cache_manager.add_acl(acl);
         assert!(is_acl_deny(
@@ -663,6 +946,84 @@ pub async fn topic_match_test() {
         assert!(topic_match(topic_name, &match_topic_name));
         assert!(topic_match(topic_name, topic_name));
         assert!(!topic_match(topic_name, "v1"));
+
+        // the topic name of a PUBLISH
+        assert!(topic_match("devices/d1/up", "devices/+/up"));
+        assert!(topic_match("devices/d1/up", "devices/#"));
+        assert!(topic_match("devices", "devices/#"));
+        assert!(topic_match("devices/d1/up", "#"));
+        assert!(!topic_match("devices/d1/down", "devices/+/up"));
+        assert!(!topic_match("devices/d1/up/raw", "devices/+/up"));
+        assert!(!topic_match("devices/d1", "devices/+/up"));
+        assert!(!topic_match("$SYS/brokers", "#"));
+        assert!(!topic_match("$SYS/brokers", "+/brokers"));
+        assert!(topic_match("$SYS/brokers", "$SYS/#"));
+
+        // the topic filter of a SUBSCRIBE is covered by the rule
+        assert!(topic_match("devices/+/up", "devices/+/up"));
+        assert!(topic_match("devices/+/up", "devices/#"));
+        assert!(topic_match("devices/#", "devices/#"));
+        assert!(!topic_match("devices/#", "devices/+"));
+        assert!(!topic_match("devices/+/up", "devices/d1/up"));
+        assert!(!topic_match("#", "devices/#"));
+
+        // eq matches the literal topic
+        assert!(topic_match("devices/#", "eq devices/#"));
+        assert!(!topic_match("devices/d1", "eq devices/#"));
+    }
+
+    #[tokio::test]
+    pub async fn acl_topic_match_test() {
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: "127.0.0.1:50000".to_string(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("lobo".to_string());
+
+        assert!(acl_topic_match(
+            &connection,
+            "devices/client-1/up",
+            "devices/${clientid}/#"
+        ));
+        assert!(!acl_topic_match(
+            &connection,
+            "devices/client-2/up",
+            "devices/${clientid}/#"
+        ));
+        assert!(acl_topic_match(
+            &connection,
+            "users/lobo/127.0.0.1",
+            "users/${username}/${ip}"
+        ));
+        assert!(acl_topic_match(
+            &connection,
+            "devices/${clientid}",
+            "eq devices/${clientid}"
+        ));
+        assert!(!acl_topic_match(
+            &connection,
+            "devices/client-1",
+            "eq devices/${clientid}"
+        ));
+
+        // a placeholder with wildcards never matches
+        connection.login_success("#".to_string());
+        assert!(!acl_topic_match(
+            &connection,
+            "users/#",
+            "users/${username}"
+        ));
+
+        // a placeholder without a value never matches
+        connection.login_success("".to_string());
+        assert!(!acl_topic_match(&connection, "users/", "users/${username}"));
     }
 
     #[tokio::test]
@@ -675,4 +1036,307 @@ pub async fn ip_match_test() {
         assert!(!ip_match(source_ip, "192.1.1.1"));
         assert!(ip_match(source_ip, "127.0.0.1/24"));
     }
+
+    #[tokio::test]
+    pub async fn check_connection_acl_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cluster_name = "test".to_string();
+        let cache_manager = Arc::new(CacheManager::new(client_pool, cluster_name));
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: "127.0.0.1".to_string(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("loboxu".to_string());
+
+        let build_acl = |topic: &str, action, permission| MqttAcl {
+            resource_type: MqttAclResourceType::ClientId,
+            resource_name: connection.client_id.clone(),
+            topic: topic.to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action,
+            permission,
+            priority: 0,
+        };
+        cache_manager.acl_metadata.add_connection_acl(
+            connection.connect_id,
+            vec![
+                build_acl("tp-1", MqttAclAction::PubSub, MqttAclPermission::Allow),
+                build_acl(
+                    WILDCARD_RESOURCE,
+                    MqttAclAction::All,
+                    MqttAclPermission::Deny,
+                ),
+            ],
+        );
+
+        let allow = |topic: &str, action| {
+            is_allow_acl(
+                &cache_manager,
+                &connection,
+                topic,
+                action,
+                false,
+                QoS::AtLeastOnce,
+            )
+        };
+        assert!(allow("tp-1", MqttAclAction::Publish));
+        assert!(allow("tp-1", MqttAclAction::Subscribe));
+        assert!(!allow("tp-2", MqttAclAction::Publish));
+
+        // the rules of the token take precedence over the stored acl
+        cache_manager.add_acl(build_acl(
+            "tp-1",
+            MqttAclAction::Publish,
+            MqttAclPermission::Deny,
+        ));
+        assert!(allow("tp-1", MqttAclAction::Publish));
+
+        // the rules are dropped with the connection
+        cache_manager.remove_connection(connection.connect_id);
+        assert!(!allow("tp-1", MqttAclAction::Publish));
+        assert!(allow("tp-2", MqttAclAction::Publish));
+    }
+
+    #[tokio::test]
+    pub async fn check_acl_order_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cluster_name = "test".to_string();
+        let cache_manager = Arc::new(CacheManager::new(client_pool, cluster_name));
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: "10.0.1.5:50000".to_string(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("loboxu".to_string());
+
+        let build_acl = |resource_type, resource_name: &str, topic: &str, permission| MqttAcl {
+            resource_type,
+            resource_name: resource_name.to_string(),
+            topic: topic.to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: MqttAclAction::All,
+            permission,
+            priority: 0,
+        };
+        let check = |topic: &str| {
+            check_acl(
+                &cache_manager,
+                &connection,
+                topic,
+                MqttAclAction::Publish,
+                false,
+            )
+        };
+
+        // a global rule denies everything
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::All,
+            "",
+            WILDCARD_RESOURCE,
+            MqttAclPermission::Deny,
+        ));
+        assert!(!check("devices/d1").allow);
+        assert_eq!(check("devices/d1").source, AclCheckSource::Rule);
+
+        // the rules of the network come before the global rules, the smallest network first
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::Ip,
+            "10.0.0.0/8",
+            "devices/#",
+            MqttAclPermission::Allow,
+        ));
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::Ip,
+            "10.0.1.0/24",
+            "devices/d2",
+            MqttAclPermission::Deny,
+        ));
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::Ip,
+            "192.168.0.0/16",
+            "devices/d3",
+            MqttAclPermission::Deny,
+        ));
+        assert!(check("devices/d1").allow);
+        assert!(!check("devices/d2").allow);
+        assert!(check("devices/d3").allow);
+        assert!(!check("alarms/a1").allow);
+
+        // the rules of the client id come before the rules of the network
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::ClientId,
+            &connection.client_id,
+            "devices/d2",
+            MqttAclPermission::Allow,
+        ));
+        assert!(check("devices/d2").allow);
+
+        // the rules of the user come first, in the order they were added
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::User,
+            &connection.login_user,
+            "devices/d2",
+            MqttAclPermission::Deny,
+        ));
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::User,
+            &connection.login_user,
+            "devices/+",
+            MqttAclPermission::Allow,
+        ));
+        let result = check("devices/d2");
+        assert!(!result.allow);
+        assert_eq!(
+            result.rule.unwrap().resource_type,
+            MqttAclResourceType::User
+        );
+        assert!(check("devices/d4").allow);
+
+        // an allow rule is not overridden by a later deny rule
+        cache_manager.add_acl(build_acl(
+            MqttAclResourceType::User,
+            &connection.login_user,
+            "devices/d4",
+            MqttAclPermission::Deny,
+        ));
+        assert!(check("devices/d4").allow);
+
+        // removing a rule keeps the other rules of the resource
+        cache_manager.remove_acl(build_acl(
+            MqttAclResourceType::User,
+            &connection.login_user,
+            "devices/+",
+            MqttAclPermission::Allow,
+        ));
+        assert!(!check("devices/d4").allow);
+        assert!(!check("devices/d2").allow);
+
+        // the priority of the rules comes before the order of the resources
+        let mut rule = build_acl(
+            MqttAclResourceType::User,
+            &connection.login_user,
+            "devices/d1",
+            MqttAclPermission::Deny,
+        );
+        rule.priority = 1;
+        cache_manager.add_acl(rule);
+        let result = check("devices/d1");
+        assert!(result.allow);
+        assert_eq!(result.rule.unwrap().resource_type, MqttAclResourceType::Ip);
+    }
+
+    #[tokio::test]
+    pub async fn check_acl_no_match_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cluster_name = "test".to_string();
+        let cache_manager = Arc::new(CacheManager::new(client_pool, cluster_name));
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: "127.0.0.1".to_string(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("loboxu".to_string());
+
+        let mut cluster = build_default_cluster_config();
+        cluster.security.acl_no_match = MqttAclPermission::Deny;
+        cache_manager.set_cluster_info(cluster);
+
+        let result = check_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            false,
+        );
+        assert!(!result.allow);
+        assert_eq!(result.source, AclCheckSource::NoMatch);
+        assert!(is_acl_deny(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Subscribe
+        ));
+
+        cache_manager.add_acl(MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: connection.login_user.clone(),
+            topic: "tp-1".to_string(),
+            ip: "127.0.0.0/24".to_string(),
+            action: MqttAclAction::PubSub,
+            permission: MqttAclPermission::Allow,
+            priority: 0,
+        });
+        assert!(is_allow_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            false,
+            QoS::AtLeastOnce
+        ));
+
+        // retaining is only denied by an explicit rule
+        assert!(is_allow_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            true,
+            QoS::AtLeastOnce
+        ));
+        cache_manager.add_acl(MqttAcl {
+            resource_type: MqttAclResourceType::All,
+            resource_name: "".to_string(),
+            topic: WILDCARD_RESOURCE.to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: MqttAclAction::Retain,
+            permission: MqttAclPermission::Deny,
+            priority: 0,
+        });
+        assert!(!is_allow_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            true,
+            QoS::AtLeastOnce
+        ));
+
+        // the super user is not restricted by the policy
+        cache_manager.add_user(MqttUser {
+            username: "admin".to_string(),
+            password: "pwd123".to_string(),
+            is_superuser: true,
+            ..Default::default()
+        });
+        connection.login_success("admin".to_string());
+        let result = check_acl(
+            &cache_manager,
+            &connection,
+            "tp-2",
+            MqttAclAction::Publish,
+            false,
+        );
+        assert!(result.allow);
+        assert_eq!(result.source, AclCheckSource::SuperUser);
+    }
 }
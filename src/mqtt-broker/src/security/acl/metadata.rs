@@ -34,6 +34,13 @@ pub struct AclMetadata {
     // acl
     pub acl_user: DashMap<String, Vec<MqttAcl>>,
     pub acl_client_id: DashMap<String, Vec<MqttAcl>>,
+    // (ip or cidr, acl)
+    pub acl_ip: DashMap<String, Vec<MqttAcl>>,
+    // the global acl, kept in the order they were added
+    pub acl_all: DashMap<String, Vec<MqttAcl>>,
+
+    // (connect_id, acl carried by the login token of the connection)
+    pub acl_connection: DashMap<u64, Vec<MqttAcl>>,
 
     // connection jitter (client_id, FlappingDetectCondition)
     pub flapping_detect_map: DashMap<String, FlappingDetectCondition>,
@@ -57,6 +64,9 @@ pub fn new() -> Self {
 
             acl_user: DashMap::with_capacity(2),
             acl_client_id: DashMap::with_capacity(2),
+            acl_ip: DashMap::with_capacity(2),
+            acl_all: DashMap::with_capacity(2),
+            acl_connection: DashMap::with_capacity(2),
             flapping_detect_map: DashMap::new(),
         }
     }
@@ -102,38 +112,82 @@ pub async fn remove_flapping_detect_conditions(
     }
 
     // ACL
+    /// add a rule, keeping the rules of its resource ordered by priority
     pub fn parse_mqtt_acl(&self, acl: MqttAcl) {
-        match acl.resource_type {
-            MqttAclResourceType::ClientId => {
-                if let Some(mut raw) = self.acl_client_id.get_mut(&acl.resource_name) {
-                    raw.push(acl);
-                } else {
-                    self.acl_client_id
-                        .insert(acl.resource_name.clone(), vec![acl]);
-                }
-            }
-            MqttAclResourceType::User => {
-                if let Some(mut raw) = self.acl_user.get_mut(&acl.resource_name) {
-                    raw.push(acl);
-                } else {
-                    self.acl_user.insert(acl.resource_name.clone(), vec![acl]);
-                }
-            }
+        let (acl_map, key) = self.acl_map(&acl);
+        if let Some(mut raw) = acl_map.get_mut(&key) {
+            let index = raw.partition_point(|rule| rule.priority <= acl.priority);
+            raw.insert(index, acl);
+        } else {
+            acl_map.insert(key, vec![acl]);
         }
     }
 
     pub fn remove_mqtt_acl(&self, acl: MqttAcl) {
-        let resource_name = acl.resource_name.clone();
-        match acl.resource_type {
-            MqttAclResourceType::ClientId => {
-                self.acl_client_id.remove(&resource_name);
-            }
-            MqttAclResourceType::User => {
-                self.acl_user.remove(&resource_name);
+        let (acl_map, key) = self.acl_map(&acl);
+        if let Some(mut raw) = acl_map.get_mut(&key) {
+            raw.retain(|rule| {
+                !(rule.permission == acl.permission
+                    && rule.action == acl.action
+                    && rule.topic == acl.topic
+                    && rule.ip == acl.ip)
+            });
+        }
+        acl_map.remove_if(&key, |_, raw| raw.is_empty());
+    }
+
+    /// replace the cached acl with the rules read from the storage, keeping their order
+    pub fn reload_mqtt_acl(&self, acl_list: Vec<MqttAcl>) {
+        let latest = AclMetadata::new();
+        for acl in acl_list {
+            latest.parse_mqtt_acl(acl);
+        }
+
+        for (acl_map, latest_map) in [
+            (&self.acl_user, latest.acl_user),
+            (&self.acl_client_id, latest.acl_client_id),
+            (&self.acl_ip, latest.acl_ip),
+            (&self.acl_all, latest.acl_all),
+        ] {
+            acl_map.retain(|key, _| latest_map.contains_key(key));
+            for (key, raw) in latest_map {
+                acl_map.insert(key, raw);
             }
         }
     }
 
+    pub fn get_acl_all(&self) -> Option<Vec<MqttAcl>> {
+        let key = self.get_acl_all_key();
+        if let Some(data) = self.acl_all.get(&key) {
+            return Some(data.clone());
+        }
+        None
+    }
+
+    fn acl_map(&self, acl: &MqttAcl) -> (&DashMap<String, Vec<MqttAcl>>, String) {
+        match acl.resource_type {
+            MqttAclResourceType::ClientId => (&self.acl_client_id, acl.resource_name.clone()),
+            MqttAclResourceType::User => (&self.acl_user, acl.resource_name.clone()),
+            MqttAclResourceType::Ip => (&self.acl_ip, acl.resource_name.clone()),
+            MqttAclResourceType::All => (&self.acl_all, self.get_acl_all_key()),
+        }
+    }
+
+    pub fn add_connection_acl(&self, connect_id: u64, acl_list: Vec<MqttAcl>) {
+        self.acl_connection.insert(connect_id, acl_list);
+    }
+
+    pub fn get_connection_acl(&self, connect_id: u64) -> Option<Vec<MqttAcl>> {
+        if let Some(acl_list) = self.acl_connection.get(&connect_id) {
+            return Some(acl_list.clone());
+        }
+        None
+    }
+
+    pub fn remove_connection_acl(&self, connect_id: u64) {
+        self.acl_connection.remove(&connect_id);
+    }
+
     // Blacklist
     pub fn parse_mqtt_blacklist(&self, blacklist: MqttAclBlackList) {
         match blacklist.blacklist_type {
@@ -237,6 +291,10 @@ fn get_user_match_key(&self) -> String {
     fn get_ip_cidr_key(&self) -> String {
         "IPCIDR".to_string()
     }
+
+    fn get_acl_all_key(&self) -> String {
+        "All".to_string()
+    }
 }
 
 #[cfg(test)]
@@ -298,6 +356,7 @@ pub async fn parse_mqtt_acl_test() {
             ip: "".to_string(),
             action: MqttAclAction::All,
             permission: MqttAclPermission::Allow,
+            priority: 0,
         };
         acl_metadata.parse_mqtt_acl(client_id_acl.clone());
 
@@ -319,6 +378,7 @@ pub async fn parse_mqtt_acl_test() {
             ip: "".to_string(),
             action: MqttAclAction::All,
             permission: MqttAclPermission::Allow,
+            priority: 0,
         };
         acl_metadata.parse_mqtt_acl(user_acl.clone());
 
@@ -340,6 +400,81 @@ pub async fn parse_mqtt_acl_test() {
         acl_metadata.parse_mqtt_acl(user_acl);
         assert_eq!(acl_metadata.acl_user.get("test_user").unwrap().len(), 2);
     }
+
+    #[tokio::test]
+    pub async fn reload_mqtt_acl_test() {
+        let acl_metadata = AclMetadata::new();
+        let build_acl = |resource_type, resource_name: &str, topic: &str| MqttAcl {
+            resource_type,
+            resource_name: resource_name.to_string(),
+            topic: topic.to_string(),
+            ip: "*".to_string(),
+            action: MqttAclAction::All,
+            permission: MqttAclPermission::Deny,
+            priority: 0,
+        };
+        acl_metadata.parse_mqtt_acl(build_acl(MqttAclResourceType::User, "u1", "t1"));
+        acl_metadata.parse_mqtt_acl(build_acl(MqttAclResourceType::User, "u2", "t1"));
+
+        let acl_list = vec![
+            build_acl(MqttAclResourceType::User, "u1", "t1"),
+            build_acl(MqttAclResourceType::User, "u1", "t2"),
+            build_acl(MqttAclResourceType::Ip, "10.0.0.0/8", "t1"),
+            build_acl(MqttAclResourceType::All, "", "t1"),
+            build_acl(MqttAclResourceType::All, "", "t2"),
+        ];
+        acl_metadata.reload_mqtt_acl(acl_list.clone());
+        // reloading the same rules does not duplicate them
+        acl_metadata.reload_mqtt_acl(acl_list);
+
+        let user_acl = acl_metadata.acl_user.get("u1").unwrap().clone();
+        assert_eq!(user_acl.len(), 2);
+        assert_eq!(user_acl[0].topic, "t1");
+        assert_eq!(user_acl[1].topic, "t2");
+        assert!(!acl_metadata.acl_user.contains_key("u2"));
+        assert!(acl_metadata.acl_ip.contains_key("10.0.0.0/8"));
+        assert_eq!(acl_metadata.get_acl_all().unwrap().len(), 2);
+
+        acl_metadata.remove_mqtt_acl(build_acl(MqttAclResourceType::All, "", "t1"));
+        let all_acl = acl_metadata.get_acl_all().unwrap();
+        assert_eq!(all_acl.len(), 1);
+        assert_eq!(all_acl[0].topic, "t2");
+
+        acl_metadata.remove_mqtt_acl(build_acl(MqttAclResourceType::All, "", "t2"));
+        assert!(acl_metadata.get_acl_all().is_none());
+    }
+
+    #[tokio::test]
+    pub async fn acl_priority_test() {
+        let acl_metadata = AclMetadata::new();
+        let build_acl = |topic: &str, priority| MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: "u1".to_string(),
+            topic: topic.to_string(),
+            ip: "*".to_string(),
+            action: MqttAclAction::All,
+            permission: MqttAclPermission::Deny,
+            priority,
+        };
+        acl_metadata.reload_mqtt_acl(vec![
+            build_acl("t1", 5),
+            build_acl("t2", 1),
+            build_acl("t3", 5),
+        ]);
+        acl_metadata.parse_mqtt_acl(build_acl("t4", 0));
+        acl_metadata.parse_mqtt_acl(build_acl("t5", 1));
+
+        // ordered by priority, then in the order the rules were added
+        let topics: Vec<String> = acl_metadata
+            .acl_user
+            .get("u1")
+            .unwrap()
+            .iter()
+            .map(|rule| rule.topic.clone())
+            .collect();
+        assert_eq!(topics, vec!["t4", "t2", "t5", "t1", "t3"]);
+    }
+
     #[tokio::test]
     pub async fn parse_mqtt_blacklist_test() {
         let acl_metadata = AclMetadata::new();
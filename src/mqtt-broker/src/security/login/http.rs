@@ -11,3 +11,402 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::async_trait;
+use common_base::config::common::Auth;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use log::warn;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::Authentication;
+use crate::handler::error::MqttBrokerError;
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+// expired results are dropped once the cache grows beyond this size
+const MAX_CACHE_SIZE: usize = 10000;
+
+// everything but the unreserved characters of RFC 3986 is escaped in the url placeholders
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// the request posted to the HTTP auth service
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpAuthRequest {
+    pub clientid: String,
+    pub username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+    pub peerhost: String,
+    pub proto_ver: u8,
+    // publish or subscribe, only for ACL requests
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub action: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub topic: String,
+}
+
+/// the response of the HTTP auth service
+///
+/// A 2xx response without a body allows the request, `ignore` leaves an ACL decision to the
+/// ACL of the broker.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpAuthResponse {
+    pub result: String,
+}
+
+/// Calls an HTTP service to authenticate connections and to authorize publishes and
+/// subscriptions.
+///
+/// The request is posted as JSON to `http_auth_url` or `http_acl_url`. The URL may contain the
+/// placeholders `${clientid}`, `${username}`, `${peerhost}`, `${proto_ver}`, `${action}` and
+/// `${topic}`. Results are cached for `http_cache_ttl_sec` seconds. When the service fails or
+/// does not respond within `http_timeout_ms`, requests are allowed if `http_fail_open` is set
+/// and denied otherwise.
+pub struct HttpAuthClient {
+    client: reqwest::Client,
+    auth_url: String,
+    acl_url: String,
+    cache_ttl_sec: u64,
+    fail_open: bool,
+    // (SHA-256 of the request, (result, expire time)), the request carries the password
+    cache: DashMap<[u8; 32], (Option<bool>, u64)>,
+}
+
+impl HttpAuthClient {
+    pub fn new(auth: &Auth) -> Result<Self, MqttBrokerError> {
+        let timeout_ms = if auth.http_timeout_ms == 0 {
+            DEFAULT_TIMEOUT_MS
+        } else {
+            auth.http_timeout_ms
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(timeout_ms))
+            .build()?;
+
+        Ok(HttpAuthClient {
+            client,
+            auth_url: auth.http_auth_url.clone(),
+            acl_url: auth.http_acl_url.clone(),
+            cache_ttl_sec: auth.http_cache_ttl_sec,
+            fail_open: auth.http_fail_open,
+            cache: DashMap::with_capacity(8),
+        })
+    }
+
+    pub fn is_acl_enable(&self) -> bool {
+        !self.acl_url.is_empty()
+    }
+
+    /// whether the service accepts the login of a connection
+    pub async fn authenticate(&self, request: &HttpAuthRequest) -> bool {
+        if self.auth_url.is_empty() {
+            warn!("HTTP authentication is enabled, but http_auth_url is not configured");
+            return self.fail_open;
+        }
+        self.call(&self.auth_url, request).await.unwrap_or(false)
+    }
+
+    /// whether the service allows the publish or subscription, `None` means that the service
+    /// leaves the decision to the ACL of the broker
+    pub async fn authorize(&self, request: &HttpAuthRequest) -> Option<bool> {
+        if self.acl_url.is_empty() {
+            return None;
+        }
+        self.call(&self.acl_url, request).await
+    }
+
+    async fn call(&self, url_template: &str, request: &HttpAuthRequest) -> Option<bool> {
+        let key = cache_key(url_template, request)?;
+        if let Some(result) = self.get_cache(&key) {
+            return result;
+        }
+
+        let url = render_url(url_template, request);
+        let result = match self.client.post(&url).json(request).send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                let body = resp.bytes().await.unwrap_or_default();
+                match parse_response(status, &body) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        warn!("HTTP auth service {} failed, error message: {}", url, e);
+                        return Some(self.fail_open);
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("HTTP auth service {} failed, error message: {}", url, e);
+                return Some(self.fail_open);
+            }
+        };
+
+        self.set_cache(key, result);
+        result
+    }
+
+    fn get_cache(&self, key: &[u8; 32]) -> Option<Option<bool>> {
+        if let Some(raw) = self.cache.get(key) {
+            let (result, expire_time) = *raw;
+            if expire_time > now_second() {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    fn set_cache(&self, key: [u8; 32], result: Option<bool>) {
+        if self.cache_ttl_sec == 0 {
+            return;
+        }
+        let now = now_second();
+        if self.cache.len() >= MAX_CACHE_SIZE {
+            self.cache.retain(|_, (_, expire_time)| *expire_time > now);
+        }
+        self.cache.insert(key, (result, now + self.cache_ttl_sec));
+    }
+}
+
+fn cache_key(url_template: &str, request: &HttpAuthRequest) -> Option<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    hasher.update(url_template.as_bytes());
+    hasher.update([0]);
+    hasher.update(serde_json::to_vec(request).ok()?);
+    Some(hasher.finalize().into())
+}
+
+/// interpret the response of the service, an error means that the service failed
+fn parse_response(status: StatusCode, body: &[u8]) -> Result<Option<bool>, String> {
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        return Ok(Some(false));
+    }
+    if !status.is_success() {
+        return Err(format!("unexpected status {}", status));
+    }
+    if body.is_empty() {
+        return Ok(Some(true));
+    }
+
+    let resp: HttpAuthResponse = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+    match resp.result.as_str() {
+        "allow" => Ok(Some(true)),
+        "deny" => Ok(Some(false)),
+        "ignore" => Ok(None),
+        result => Err(format!("unknown result {}", result)),
+    }
+}
+
+fn render_url(url_template: &str, request: &HttpAuthRequest) -> String {
+    url_template
+        .replace("${clientid}", &url_encode(&request.clientid))
+        .replace("${username}", &url_encode(&request.username))
+        .replace("${peerhost}", &url_encode(&request.peerhost))
+        .replace("${proto_ver}", &request.proto_ver.to_string())
+        .replace("${action}", &url_encode(&request.action))
+        .replace("${topic}", &url_encode(&request.topic))
+}
+
+fn url_encode(value: &str) -> String {
+    utf8_percent_encode(value, URL_ENCODE_SET).to_string()
+}
+
+pub struct Http {
+    request: HttpAuthRequest,
+    client: Arc<HttpAuthClient>,
+}
+
+impl Http {
+    pub fn new(request: HttpAuthRequest, client: Arc<HttpAuthClient>) -> Self {
+        Http { request, client }
+    }
+}
+
+#[async_trait]
+impl Authentication for Http {
+    async fn apply(&self) -> Result<bool, MqttBrokerError> {
+        Ok(self.client.authenticate(&self.request).await)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use common_base::config::common::Auth;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use super::{cache_key, render_url, Http, HttpAuthClient, HttpAuthRequest};
+    use crate::security::login::Authentication;
+
+    async fn auth_handler(
+        State(counter): State<Arc<AtomicUsize>>,
+        Json(body): Json<Value>,
+    ) -> (StatusCode, Json<Value>) {
+        counter.fetch_add(1, Ordering::SeqCst);
+        if body["password"] == "pwd123" {
+            return (StatusCode::OK, Json(json!({"result": "allow"})));
+        }
+        (StatusCode::OK, Json(json!({"result": "deny"})))
+    }
+
+    async fn acl_handler(Json(body): Json<Value>) -> (StatusCode, Json<Value>) {
+        let result = match body["topic"].as_str().unwrap_or_default() {
+            "t/allow" => "allow",
+            "t/deny" => "deny",
+            "t/slow" => {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                "allow"
+            }
+            "t/error" => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({}))),
+            _ => "ignore",
+        };
+        (StatusCode::OK, Json(json!({ "result": result })))
+    }
+
+    async fn start_server() -> (SocketAddr, Arc<AtomicUsize>) {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route("/auth", post(auth_handler))
+            .route("/acl", post(acl_handler))
+            .with_state(counter.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (addr, counter)
+    }
+
+    fn build_auth(addr: &SocketAddr) -> Auth {
+        Auth {
+            authn_type: "http".to_string(),
+            http_auth_url: format!("http://{}/auth?clientid=${{clientid}}", addr),
+            http_acl_url: format!("http://{}/acl", addr),
+            http_timeout_ms: 200,
+            http_cache_ttl_sec: 60,
+            ..Default::default()
+        }
+    }
+
+    fn build_request(password: &str, action: &str, topic: &str) -> HttpAuthRequest {
+        HttpAuthRequest {
+            clientid: "c1".to_string(),
+            username: "lobo".to_string(),
+            password: password.to_string(),
+            peerhost: "127.0.0.1".to_string(),
+            proto_ver: 5,
+            action: action.to_string(),
+            topic: topic.to_string(),
+        }
+    }
+
+    async fn authorize_publish(client: &HttpAuthClient, topic: &str) -> Option<bool> {
+        client.authorize(&build_request("", "publish", topic)).await
+    }
+
+    #[tokio::test]
+    async fn http_authenticate_test() {
+        let (addr, counter) = start_server().await;
+        let client = Arc::new(HttpAuthClient::new(&build_auth(&addr)).unwrap());
+
+        let http = Http::new(build_request("pwd123", "", ""), client.clone());
+        assert!(http.apply().await.unwrap());
+        let http = Http::new(build_request("pwd456", "", ""), client.clone());
+        assert!(!http.apply().await.unwrap());
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        // the results are cached
+        assert!(client.authenticate(&build_request("pwd123", "", "")).await);
+        assert!(!client.authenticate(&build_request("pwd456", "", "")).await);
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn http_authorize_test() {
+        let (addr, _) = start_server().await;
+        let client = HttpAuthClient::new(&build_auth(&addr)).unwrap();
+        assert!(client.is_acl_enable());
+
+        assert_eq!(authorize_publish(&client, "t/allow").await, Some(true));
+        assert_eq!(authorize_publish(&client, "t/deny").await, Some(false));
+        assert_eq!(authorize_publish(&client, "t/other").await, None);
+
+        // failures and timeouts are denied when failing closed
+        assert_eq!(authorize_publish(&client, "t/error").await, Some(false));
+        assert_eq!(authorize_publish(&client, "t/slow").await, Some(false));
+    }
+
+    #[tokio::test]
+    async fn http_fail_open_test() {
+        let (addr, _) = start_server().await;
+        let auth = Auth {
+            http_fail_open: true,
+            ..build_auth(&addr)
+        };
+        let client = HttpAuthClient::new(&auth).unwrap();
+        let request = build_request("", "subscribe", "t/slow");
+        assert_eq!(client.authorize(&request).await, Some(true));
+
+        // the service is unreachable
+        let auth = Auth {
+            http_auth_url: "http://127.0.0.1:1/auth".to_string(),
+            ..Default::default()
+        };
+        let client = HttpAuthClient::new(&auth).unwrap();
+        assert!(!client.authenticate(&build_request("pwd123", "", "")).await);
+        assert!(!client.is_acl_enable());
+    }
+
+    #[test]
+    fn render_url_test() {
+        let request = HttpAuthRequest {
+            clientid: "c 1".to_string(),
+            username: "lobo".to_string(),
+            proto_ver: 4,
+            topic: "a/+/#".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_url(
+                "http://host/auth?c=${clientid}&u=${username}&v=${proto_ver}&t=${topic}",
+                &request
+            ),
+            "http://host/auth?c=c%201&u=lobo&v=4&t=a%2F%2B%2F%23"
+        );
+    }
+    #[test]
+    fn cache_key_test() {
+        let url = "http://host/auth";
+        let key = cache_key(url, &build_request("pwd123", "", "")).unwrap();
+        assert_eq!(
+            key,
+            cache_key(url, &build_request("pwd123", "", "")).unwrap()
+        );
+        assert_ne!(
+            key,
+            cache_key(url, &build_request("pwd456", "", "")).unwrap()
+        );
+        assert_ne!(
+            key,
+            cache_key("http://host/acl", &build_request("pwd123", "", "")).unwrap()
+        );
+    }
+}
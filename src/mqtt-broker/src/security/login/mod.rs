@@ -12,13 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
+use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
 use axum::async_trait;
+use bytes::Bytes;
+use scram::{Scram, ScramAlgorithm};
 
 pub mod http;
 pub mod jwt;
 pub mod plaintext;
 pub mod psk;
+pub mod scram;
 pub mod x509;
 
 #[async_trait]
@@ -26,5 +32,37 @@ pub trait Authentication {
     async fn apply(&self) -> Result<bool, MqttBrokerError>;
 }
 
+/// the outcome of one step of an MQTT 5 enhanced authentication exchange
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnhancedAuthStep {
+    // the authentication data sent back to the client in an AUTH packet with the reason code
+    // Continue Authentication
+    Continue(Bytes),
+    // the client is authenticated, the data is sent in the CONNACK or the final AUTH packet
+    Success {
+        username: String,
+        data: Option<Bytes>,
+    },
+}
+
+/// A mechanism of the MQTT 5 enhanced authentication, chosen by the Authentication Method of the
+/// CONNECT packet. An instance keeps the state of one exchange and is fed the Authentication Data
+/// of the CONNECT packet and of every AUTH packet the client sends after it.
+#[async_trait]
+pub trait EnhancedAuthentication {
+    fn method(&self) -> &str;
+
+    async fn step(&mut self, data: &[u8]) -> Result<EnhancedAuthStep, MqttBrokerError>;
+}
+
+/// the mechanism of an Authentication Method, or None when the method is not supported
+pub fn build_enhanced_auth(
+    method: &str,
+    cache_manager: Arc<CacheManager>,
+) -> Option<Box<dyn EnhancedAuthentication + Send + Sync>> {
+    let algorithm = ScramAlgorithm::from_method(method)?;
+    Some(Box::new(Scram::new(algorithm, cache_manager)))
+}
+
 #[cfg(test)]
 mod test {}
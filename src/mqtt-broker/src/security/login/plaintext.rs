@@ -19,6 +19,7 @@
 use super::Authentication;
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::security::password::verify_user_password;
 
 pub struct Plaintext {
     username: String,
@@ -39,8 +40,13 @@ pub fn new(username: String, password: String, cache_manager: Arc<CacheManager>)
 #[async_trait]
 impl Authentication for Plaintext {
     async fn apply(&self) -> Result<bool, MqttBrokerError> {
-        if let Some(user) = self.cache_manager.user_info.get(&self.username) {
-            return Ok(user.password == self.password);
+        let user = self
+            .cache_manager
+            .user_info
+            .get(&self.username)
+            .map(|user| user.clone());
+        if let Some(user) = user {
+            return verify_user_password(&user, &self.password).await;
         }
         return Err(MqttBrokerError::UserDoesNotExist);
     }
@@ -52,12 +58,13 @@ mod test {
 
     use common_base::config::broker_mqtt::BrokerMqttConfig;
     use grpc_clients::pool::ClientPool;
-    use metadata_struct::mqtt::user::MqttUser;
+    use metadata_struct::mqtt::user::{MqttUser, PasswordHashAlgorithm};
     use protocol::mqtt::common::Login;
 
     use super::Plaintext;
     use crate::handler::cache::CacheManager;
     use crate::security::login::Authentication;
+    use crate::security::password::encrypt_user_password;
 
     #[tokio::test]
     pub async fn plaintext_test() {
@@ -76,6 +83,7 @@ pub async fn plaintext_test() {
             username: username.clone(),
             password: password.clone(),
             is_superuser: true,
+            ..Default::default()
         };
         cache_manager.add_user(user);
 
@@ -88,11 +96,29 @@ pub async fn plaintext_test() {
         assert!(res);
 
         let login = Login {
-            username,
+            username: username.clone(),
             password: "pwd1111".to_string(),
         };
         let pt = Plaintext::new(login.username, login.password, cache_manager.clone());
         let res = pt.apply().await.unwrap();
         assert!(!res);
+
+        // the password is hashed
+        let user = MqttUser {
+            username: username.clone(),
+            password: password.clone(),
+            is_superuser: true,
+            ..Default::default()
+        };
+        let user = encrypt_user_password(user, PasswordHashAlgorithm::Pbkdf2)
+            .await
+            .unwrap();
+        cache_manager.add_user(user);
+
+        let pt = Plaintext::new(username.clone(), password, cache_manager.clone());
+        assert!(pt.apply().await.unwrap());
+
+        let pt = Plaintext::new(username, "pwd1111".to_string(), cache_manager.clone());
+        assert!(!pt.apply().await.unwrap());
     }
 }
@@ -11,3 +11,302 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::config::broker_mqtt::Network;
+use log::warn;
+use openssl::ssl::{Ssl, SslAcceptor, SslFiletype, SslMethod, SslRef, SslVerifyMode};
+use protocol::mqtt::common::Login;
+use tokio::net::TcpStream;
+use tokio_openssl::SslStream;
+
+use super::Authentication;
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+
+const DEFAULT_PSK_CIPHERS: &str =
+    "PSK-AES128-GCM-SHA256:PSK-AES256-GCM-SHA384:PSK-AES128-CBC-SHA256:PSK-AES256-CBC-SHA384";
+// the cipher suites of the clients with a certificate when tls_cert is configured
+const CERT_CIPHERS: &str = "ECDHE+AESGCM:ECDHE+CHACHA20:DHE+AESGCM";
+// PSK_MAX_PSK_LEN of OpenSSL 1.1
+const MAX_PSK_LEN: usize = 256;
+
+/// decode a hex encoded pre-shared key
+pub fn decode_psk(psk: &str) -> Result<Vec<u8>, MqttBrokerError> {
+    let key = hex::decode(psk).map_err(|e| MqttBrokerError::InvalidPsk(e.to_string()))?;
+    if key.is_empty() || key.len() > MAX_PSK_LEN {
+        return Err(MqttBrokerError::InvalidPsk(format!(
+            "the key must be 1 to {} bytes",
+            MAX_PSK_LEN
+        )));
+    }
+    Ok(key)
+}
+
+/// the acceptor of the tcps listener when `tls_psk_enable` is set
+///
+/// rustls has no PSK cipher suites, so the listener is served by OpenSSL instead. Clients with a
+/// certificate are still accepted when `tls_cert` is configured.
+pub fn build_psk_acceptor(
+    network: &Network,
+    cache_manager: Arc<CacheManager>,
+) -> Result<SslAcceptor, MqttBrokerError> {
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server())?;
+    let psk_ciphers = if network.tls_psk_ciphers.is_empty() {
+        DEFAULT_PSK_CIPHERS
+    } else {
+        network.tls_psk_ciphers.as_str()
+    };
+
+    if network.tls_cert.is_empty() {
+        builder.set_cipher_list(psk_ciphers)?;
+    } else {
+        builder.set_cipher_list(&format!("{}:{}", psk_ciphers, CERT_CIPHERS))?;
+        builder.set_certificate_chain_file(&network.tls_cert)?;
+        builder.set_private_key_file(&network.tls_key, SslFiletype::PEM)?;
+        builder.check_private_key()?;
+    }
+
+    if !network.tls_ca.is_empty() {
+        builder.set_ca_file(&network.tls_ca)?;
+        let mut mode = SslVerifyMode::PEER;
+        if network.tls_fail_if_no_peer_cert {
+            mode |= SslVerifyMode::FAIL_IF_NO_PEER_CERT;
+        }
+        builder.set_verify(mode);
+    }
+
+    builder.set_psk_server_callback(move |_, identity, psk| {
+        Ok(lookup_psk(&cache_manager, identity, psk))
+    });
+    Ok(builder.build())
+}
+
+/// complete the TLS handshake of a client of the tcps listener
+pub async fn accept_psk_stream(
+    acceptor: &SslAcceptor,
+    stream: TcpStream,
+) -> Result<SslStream<TcpStream>, MqttBrokerError> {
+    let ssl = Ssl::new(acceptor.context())?;
+    let mut stream = SslStream::new(ssl, stream)?;
+    Pin::new(&mut stream).accept().await?;
+    Ok(stream)
+}
+
+/// the identity of a client that completed a TLS-PSK handshake
+pub fn psk_identity(ssl: &SslRef) -> Option<String> {
+    let identity = ssl.psk_identity()?;
+    String::from_utf8(identity.to_vec()).ok()
+}
+
+/// replace the username of a CONNECT with the PSK identity of the connection
+pub fn apply_psk_identity(identity: &str, login: &mut Option<Login>) {
+    match login {
+        Some(login) => login.username = identity.to_string(),
+        None => {
+            *login = Some(Login {
+                username: identity.to_string(),
+                password: "".to_string(),
+            })
+        }
+    }
+}
+
+// write the key of the identity and return its length, 0 fails the handshake
+fn lookup_psk(cache_manager: &CacheManager, identity: Option<&[u8]>, out: &mut [u8]) -> usize {
+    let Some(identity) = identity.and_then(|identity| std::str::from_utf8(identity).ok()) else {
+        return 0;
+    };
+    let Some(psk) = cache_manager.get_psk(identity) else {
+        warn!("Unknown PSK identity {}", identity);
+        return 0;
+    };
+    match decode_psk(&psk.psk) {
+        Ok(key) if key.len() <= out.len() => {
+            out[..key.len()].copy_from_slice(&key);
+            key.len()
+        }
+        Ok(_) => {
+            warn!("The pre-shared key of identity {} is too long", identity);
+            0
+        }
+        Err(e) => {
+            warn!("Invalid pre-shared key of identity {}: {}", identity, e);
+            0
+        }
+    }
+}
+
+/// A client is authenticated by the TLS-PSK handshake, which only completes with the key of its
+/// identity. The identity must still exist when the client connects.
+pub struct Psk {
+    identity: String,
+    cache_manager: Arc<CacheManager>,
+}
+
+impl Psk {
+    pub fn new(identity: String, cache_manager: Arc<CacheManager>) -> Self {
+        Psk {
+            identity,
+            cache_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Authentication for Psk {
+    async fn apply(&self) -> Result<bool, MqttBrokerError> {
+        Ok(self.cache_manager.psk_info.contains_key(&self.identity))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::pin::Pin;
+    use std::sync::Arc;
+
+    use common_base::config::broker_mqtt::Network;
+    use common_base::tools::now_second;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::psk::MqttPsk;
+    use openssl::ssl::{Ssl, SslConnector, SslMethod, SslVerifyMode, SslVersion};
+    use protocol::mqtt::common::Login;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_openssl::SslStream;
+
+    use super::{
+        accept_psk_stream, apply_psk_identity, build_psk_acceptor, decode_psk, lookup_psk,
+        psk_identity, Psk,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::security::login::Authentication;
+
+    fn build_cache_manager() -> Arc<CacheManager> {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.add_psk(MqttPsk {
+            identity: "sensor-1".to_string(),
+            psk: "0123456789abcdef".to_string(),
+            create_time: now_second(),
+        });
+        cache_manager
+    }
+
+    async fn connect_psk(
+        addr: String,
+        identity: &'static str,
+        key: Vec<u8>,
+    ) -> Result<SslStream<TcpStream>, openssl::ssl::Error> {
+        let mut builder = SslConnector::builder(SslMethod::tls_client()).unwrap();
+        builder.set_cipher_list("PSK-AES128-GCM-SHA256").unwrap();
+        builder
+            .set_max_proto_version(Some(SslVersion::TLS1_2))
+            .unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        builder.set_psk_client_callback(move |_, _, identity_out, psk_out| {
+            identity_out[..identity.len()].copy_from_slice(identity.as_bytes());
+            identity_out[identity.len()] = 0;
+            psk_out[..key.len()].copy_from_slice(&key);
+            Ok(key.len())
+        });
+        let connector = builder.build();
+        let ssl: Ssl = connector
+            .configure()
+            .unwrap()
+            .into_ssl("localhost")
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = SslStream::new(ssl, stream).unwrap();
+        Pin::new(&mut stream).connect().await?;
+        Ok(stream)
+    }
+
+    #[test]
+    fn decode_psk_test() {
+        assert_eq!(decode_psk("0a0b").unwrap(), vec![10, 11]);
+        assert!(decode_psk("").is_err());
+        assert!(decode_psk("xyz").is_err());
+        assert!(decode_psk(&"00".repeat(257)).is_err());
+    }
+
+    #[test]
+    fn lookup_psk_test() {
+        let cache_manager = build_cache_manager();
+        let mut out = [0u8; 64];
+        let len = lookup_psk(&cache_manager, Some(b"sensor-1"), &mut out);
+        assert_eq!(
+            &out[..len],
+            decode_psk("0123456789abcdef").unwrap().as_slice()
+        );
+        assert_eq!(lookup_psk(&cache_manager, Some(b"sensor-2"), &mut out), 0);
+        assert_eq!(lookup_psk(&cache_manager, None, &mut out), 0);
+        assert_eq!(
+            lookup_psk(&cache_manager, Some(b"sensor-1"), &mut [0u8; 4]),
+            0
+        );
+    }
+
+    #[test]
+    fn apply_psk_identity_test() {
+        let mut login = None;
+        apply_psk_identity("sensor-1", &mut login);
+        assert_eq!(login.unwrap().username, "sensor-1");
+
+        let mut login = Some(Login {
+            username: "admin".to_string(),
+            password: "pwd123".to_string(),
+        });
+        apply_psk_identity("sensor-1", &mut login);
+        let login = login.unwrap();
+        assert_eq!(login.username, "sensor-1");
+        assert_eq!(login.password, "pwd123");
+    }
+
+    #[tokio::test]
+    async fn psk_authenticate_test() {
+        let cache_manager = build_cache_manager();
+        let psk = Psk::new("sensor-1".to_string(), cache_manager.clone());
+        assert!(psk.apply().await.unwrap());
+
+        cache_manager.del_psk("sensor-1");
+        assert!(!psk.apply().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn psk_handshake_test() {
+        let network = Network {
+            tls_psk_enable: true,
+            ..Default::default()
+        };
+        let acceptor = build_psk_acceptor(&network, build_cache_manager()).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                if let Ok(mut stream) = accept_psk_stream(&acceptor, stream).await {
+                    let identity = psk_identity(stream.ssl()).unwrap_or_default();
+                    stream.write_all(identity.as_bytes()).await.unwrap();
+                    stream.shutdown().await.unwrap();
+                }
+            }
+        });
+
+        let key = decode_psk("0123456789abcdef").unwrap();
+        let mut stream = connect_psk(addr.clone(), "sensor-1", key.clone())
+            .await
+            .unwrap();
+        let mut identity = String::new();
+        stream.read_to_string(&mut identity).await.unwrap();
+        assert_eq!(identity, "sensor-1");
+
+        // a wrong key or an unknown identity fails the handshake
+        assert!(connect_psk(addr.clone(), "sensor-1", vec![1, 2, 3])
+            .await
+            .is_err());
+        assert!(connect_psk(addr, "sensor-2", key).await.is_err());
+    }
+}
@@ -0,0 +1,610 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Arc, OnceLock};
+
+use axum::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use log::warn;
+use metadata_struct::mqtt::user::{MqttUser, PasswordHashAlgorithm};
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use super::{EnhancedAuthStep, EnhancedAuthentication};
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::security::password::parse_pbkdf2_hash;
+
+const SCRAM_ITERATIONS: u32 = 4096;
+const NONCE_LEN: usize = 18;
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScramAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl ScramAlgorithm {
+    pub fn from_method(method: &str) -> Option<Self> {
+        match method {
+            "SCRAM-SHA-256" => Some(ScramAlgorithm::Sha256),
+            "SCRAM-SHA-512" => Some(ScramAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn method(&self) -> &'static str {
+        match self {
+            ScramAlgorithm::Sha256 => "SCRAM-SHA-256",
+            ScramAlgorithm::Sha512 => "SCRAM-SHA-512",
+        }
+    }
+
+    fn hash(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            ScramAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            ScramAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn hmac(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match self {
+            ScramAlgorithm::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+            ScramAlgorithm::Sha512 => {
+                let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("any key length");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+        }
+    }
+
+    fn salted_password(&self, password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+        match self {
+            ScramAlgorithm::Sha256 => {
+                let mut hash = [0u8; 32];
+                pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut hash);
+                hash.to_vec()
+            }
+            ScramAlgorithm::Sha512 => {
+                let mut hash = [0u8; 64];
+                pbkdf2::pbkdf2_hmac::<Sha512>(password, salt, iterations, &mut hash);
+                hash.to_vec()
+            }
+        }
+    }
+}
+
+enum ScramState {
+    // waiting for the client-first-message
+    Initial,
+    // the server-first-message has been sent, waiting for the client-final-message
+    ServerFirst {
+        username: String,
+        gs2_header: String,
+        client_first_bare: String,
+        server_first: String,
+        nonce: String,
+        // None when the user does not exist or its password can not be used, the exchange is
+        // then carried on as for a known user and fails on the client-final-message
+        salted_password: Option<Vec<u8>>,
+    },
+    Done,
+}
+
+/// SCRAM-SHA-256 and SCRAM-SHA-512 (RFC 5802, RFC 7677) against the users of the broker.
+///
+/// The salted password is derived from the password of users stored in clear text. Users hashed
+/// with `pbkdf2`, the default `password_hash_algorithm`, can log in with SCRAM-SHA-256, as their
+/// hash already is the salted password. Channel binding is not supported.
+///
+/// Unknown users get a server-first-message like a user stored in clear text, so that a client can
+/// not tell whether a username exists before the exchange fails.
+pub struct Scram {
+    algorithm: ScramAlgorithm,
+    cache_manager: Arc<CacheManager>,
+    state: ScramState,
+}
+
+impl Scram {
+    pub fn new(algorithm: ScramAlgorithm, cache_manager: Arc<CacheManager>) -> Self {
+        Scram {
+            algorithm,
+            cache_manager,
+            state: ScramState::Initial,
+        }
+    }
+
+    fn client_first(&mut self, message: &str) -> Result<EnhancedAuthStep, MqttBrokerError> {
+        let mut parts = message.splitn(3, ',');
+        let cbind_flag = parts.next().unwrap_or_default();
+        let authzid = parts.next();
+        let client_first_bare = parts
+            .next()
+            .ok_or_else(|| invalid_data("client-first-message"))?;
+        if cbind_flag.starts_with('p') {
+            return Err(MqttBrokerError::EnhancedAuthFailed(
+                "channel binding is not supported".to_string(),
+            ));
+        }
+        if cbind_flag != "n" && cbind_flag != "y" {
+            return Err(invalid_data("gs2-cbind-flag"));
+        }
+        let gs2_header = format!("{},{},", cbind_flag, authzid.unwrap_or_default());
+
+        let attributes = parse_attributes(client_first_bare)?;
+        let username =
+            decode_username(attribute(&attributes, 'n').ok_or_else(|| invalid_data("username"))?)?;
+        let client_nonce = attribute(&attributes, 'r').ok_or_else(|| invalid_data("nonce"))?;
+
+        let user = self
+            .cache_manager
+            .user_info
+            .get(&username)
+            .map(|user| user.clone());
+        let credentials = match user {
+            Some(user) => match self.user_credentials(&user) {
+                Ok(credentials) => Some(credentials),
+                Err(e) => {
+                    warn!("{}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let (salt, iterations, salted_password) = match credentials {
+            Some((salt, iterations, salted_password)) => (salt, iterations, Some(salted_password)),
+            None => (self.user_salt(&username), SCRAM_ITERATIONS, None),
+        };
+
+        let nonce = format!(
+            "{}{}",
+            client_nonce,
+            STANDARD.encode(random_bytes(NONCE_LEN))
+        );
+        let server_first = format!("r={},s={},i={}", nonce, STANDARD.encode(salt), iterations);
+        self.state = ScramState::ServerFirst {
+            username,
+            gs2_header,
+            client_first_bare: client_first_bare.to_string(),
+            server_first: server_first.clone(),
+            nonce,
+            salted_password,
+        };
+        Ok(EnhancedAuthStep::Continue(Bytes::from(server_first)))
+    }
+
+    // the salt, iteration count and salted password of the user
+    fn user_credentials(
+        &self,
+        user: &MqttUser,
+    ) -> Result<(Vec<u8>, u32, Vec<u8>), MqttBrokerError> {
+        match (user.hash_algorithm, self.algorithm) {
+            (PasswordHashAlgorithm::Plain, _) => {
+                let salt = self.user_salt(&user.username);
+                let salted_password = self.algorithm.salted_password(
+                    user.password.as_bytes(),
+                    &salt,
+                    SCRAM_ITERATIONS,
+                );
+                Ok((salt, SCRAM_ITERATIONS, salted_password))
+            }
+            (PasswordHashAlgorithm::Pbkdf2, ScramAlgorithm::Sha256) => {
+                let (rounds, salted_password) = parse_pbkdf2_hash(user)?;
+                Ok((user.salt.as_bytes().to_vec(), rounds, salted_password))
+            }
+            _ => Err(MqttBrokerError::EnhancedAuthFailed(format!(
+                "the password of user {} can not be used with {}",
+                user.username,
+                self.algorithm.method()
+            ))),
+        }
+    }
+
+    // the salt of users stored in clear text and of unknown users, the same for every exchange of
+    // a username
+    fn user_salt(&self, username: &str) -> Vec<u8> {
+        static SALT_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+        let key = SALT_KEY.get_or_init(|| random_bytes(32));
+        let mut salt = self.algorithm.hmac(key, username.as_bytes());
+        salt.truncate(SALT_LEN);
+        salt
+    }
+
+    fn client_final(&mut self, message: &str) -> Result<EnhancedAuthStep, MqttBrokerError> {
+        let ScramState::ServerFirst {
+            username,
+            gs2_header,
+            client_first_bare,
+            server_first,
+            nonce,
+            salted_password,
+        } = std::mem::replace(&mut self.state, ScramState::Done)
+        else {
+            return Err(invalid_data("unexpected client-final-message"));
+        };
+
+        let (without_proof, proof) = message
+            .rsplit_once(",p=")
+            .ok_or_else(|| invalid_data("client proof"))?;
+        let attributes = parse_attributes(without_proof)?;
+        if attribute(&attributes, 'c') != Some(STANDARD.encode(&gs2_header).as_str()) {
+            return Err(invalid_data("channel binding"));
+        }
+        if attribute(&attributes, 'r') != Some(nonce.as_str()) {
+            return Err(invalid_data("nonce"));
+        }
+        let proof = STANDARD
+            .decode(proof)
+            .map_err(|_| invalid_data("client proof"))?;
+
+        let auth_message = format!("{},{},{}", client_first_bare, server_first, without_proof);
+        let Some(salted_password) = salted_password else {
+            return Err(auth_failed());
+        };
+        let server_signature =
+            verify_client_proof(self.algorithm, &salted_password, &auth_message, &proof)?;
+        Ok(EnhancedAuthStep::Success {
+            username,
+            data: Some(Bytes::from(format!(
+                "v={}",
+                STANDARD.encode(server_signature)
+            ))),
+        })
+    }
+}
+
+#[async_trait]
+impl EnhancedAuthentication for Scram {
+    fn method(&self) -> &str {
+        self.algorithm.method()
+    }
+
+    async fn step(&mut self, data: &[u8]) -> Result<EnhancedAuthStep, MqttBrokerError> {
+        let message = std::str::from_utf8(data).map_err(|_| invalid_data("not utf-8"))?;
+        let result = match self.state {
+            ScramState::Initial => self.client_first(message),
+            ScramState::ServerFirst { .. } => self.client_final(message),
+            ScramState::Done => Err(invalid_data("the exchange has finished")),
+        };
+        // a failed exchange can not be continued
+        if result.is_err() {
+            self.state = ScramState::Done;
+        }
+        result
+    }
+}
+
+/// check the ClientProof of the client-final-message and return the ServerSignature
+fn verify_client_proof(
+    algorithm: ScramAlgorithm,
+    salted_password: &[u8],
+    auth_message: &str,
+    proof: &[u8],
+) -> Result<Vec<u8>, MqttBrokerError> {
+    let client_key = algorithm.hmac(salted_password, b"Client Key");
+    let stored_key = algorithm.hash(&client_key);
+    let client_signature = algorithm.hmac(&stored_key, auth_message.as_bytes());
+    if proof.len() != client_signature.len() {
+        return Err(invalid_data("client proof"));
+    }
+
+    let proof_key: Vec<u8> = proof
+        .iter()
+        .zip(client_signature.iter())
+        .map(|(p, s)| p ^ s)
+        .collect();
+    if !bool::from(algorithm.hash(&proof_key).ct_eq(&stored_key)) {
+        return Err(auth_failed());
+    }
+
+    let server_key = algorithm.hmac(salted_password, b"Server Key");
+    Ok(algorithm.hmac(&server_key, auth_message.as_bytes()))
+}
+
+fn parse_attributes(message: &str) -> Result<Vec<(char, &str)>, MqttBrokerError> {
+    let mut attributes = Vec::new();
+    for part in message.split(',') {
+        let Some((name, value)) = part.split_once('=') else {
+            return Err(invalid_data(part));
+        };
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(name), None) if name.is_ascii_alphabetic() => attributes.push((name, value)),
+            _ => return Err(invalid_data(part)),
+        }
+    }
+    Ok(attributes)
+}
+
+fn attribute<'a>(attributes: &[(char, &'a str)], name: char) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, value)| *value)
+}
+
+// "," and "=" are escaped as "=2C" and "=3D" in the username
+fn decode_username(value: &str) -> Result<String, MqttBrokerError> {
+    let mut username = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('=') {
+        username.push_str(&rest[..index]);
+        match rest.get(index..index + 3) {
+            Some("=2C") => username.push(','),
+            Some("=3D") => username.push('='),
+            _ => return Err(invalid_data("username")),
+        }
+        rest = &rest[index + 3..];
+    }
+    username.push_str(rest);
+    Ok(username)
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+// the same error for an unknown user and a wrong password
+fn auth_failed() -> MqttBrokerError {
+    MqttBrokerError::EnhancedAuthFailed("authentication failed".to_string())
+}
+
+fn invalid_data(reason: &str) -> MqttBrokerError {
+    MqttBrokerError::InvalidAuthData(reason.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::user::{MqttUser, PasswordHashAlgorithm};
+
+    use super::{
+        decode_username, parse_attributes, verify_client_proof, Scram, ScramAlgorithm,
+        SCRAM_ITERATIONS,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::security::login::{EnhancedAuthStep, EnhancedAuthentication};
+    use crate::security::password::encrypt_user_password;
+
+    fn build_cache_manager() -> Arc<CacheManager> {
+        let client_pool = Arc::new(ClientPool::new(100));
+        Arc::new(CacheManager::new(client_pool, "test".to_string()))
+    }
+
+    fn build_user(password: &str) -> MqttUser {
+        MqttUser {
+            username: "lobo".to_string(),
+            password: password.to_string(),
+            is_superuser: false,
+            ..Default::default()
+        }
+    }
+
+    async fn login(
+        algorithm: ScramAlgorithm,
+        cache_manager: Arc<CacheManager>,
+        password: &str,
+    ) -> Result<EnhancedAuthStep, String> {
+        login_as(algorithm, cache_manager, "lobo", password).await
+    }
+
+    // the client side of the exchange, returns the server data of the last step
+    async fn login_as(
+        algorithm: ScramAlgorithm,
+        cache_manager: Arc<CacheManager>,
+        username: &str,
+        password: &str,
+    ) -> Result<EnhancedAuthStep, String> {
+        let mut scram = Scram::new(algorithm, cache_manager);
+        let client_first_bare = format!("n={},r=fyko+d2lbbFgONRv9qkxdawL", username);
+        let server_first = match scram
+            .step(format!("n,,{}", client_first_bare).as_bytes())
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            EnhancedAuthStep::Continue(data) => String::from_utf8(data.to_vec()).unwrap(),
+            step => panic!("unexpected step {:?}", step),
+        };
+
+        let mut nonce = "";
+        let mut salt = Vec::new();
+        let mut iterations = 0;
+        for part in server_first.split(',') {
+            match &part[..2] {
+                "r=" => nonce = &part[2..],
+                "s=" => salt = STANDARD.decode(&part[2..]).unwrap(),
+                "i=" => iterations = part[2..].parse().unwrap(),
+                _ => {}
+            }
+        }
+        assert!(nonce.starts_with("fyko+d2lbbFgONRv9qkxdawL"));
+
+        let salted_password = algorithm.salted_password(password.as_bytes(), &salt, iterations);
+        let client_key = algorithm.hmac(&salted_password, b"Client Key");
+        let stored_key = algorithm.hash(&client_key);
+        let without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!("{},{},{}", client_first_bare, server_first, without_proof);
+        let client_signature = algorithm.hmac(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key
+            .iter()
+            .zip(client_signature.iter())
+            .map(|(k, s)| k ^ s)
+            .collect();
+        let client_final = format!("{},p={}", without_proof, STANDARD.encode(proof));
+        let step = scram
+            .step(client_final.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let server_key = algorithm.hmac(&salted_password, b"Server Key");
+        let server_signature = algorithm.hmac(&server_key, auth_message.as_bytes());
+        if let EnhancedAuthStep::Success { data, .. } = &step {
+            assert_eq!(
+                data.clone().unwrap(),
+                format!("v={}", STANDARD.encode(server_signature))
+            );
+        }
+        Ok(step)
+    }
+
+    #[test]
+    fn rfc7677_test_vector() {
+        let algorithm = ScramAlgorithm::Sha256;
+        let salt = STANDARD.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap();
+        let salted_password = algorithm.salted_password(b"pencil", &salt, SCRAM_ITERATIONS);
+        let auth_message = "n=user,r=rOprNGfwEbeRWgbNEkqO,\
+            r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096,\
+            c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0";
+        let proof = STANDARD
+            .decode("dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=")
+            .unwrap();
+
+        let server_signature =
+            verify_client_proof(algorithm, &salted_password, auth_message, &proof).unwrap();
+        assert_eq!(
+            STANDARD.encode(server_signature),
+            "6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4="
+        );
+
+        let mut wrong_proof = proof.clone();
+        wrong_proof[0] ^= 1;
+        assert!(
+            verify_client_proof(algorithm, &salted_password, auth_message, &wrong_proof).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn scram_plain_user_test() {
+        let cache_manager = build_cache_manager();
+        cache_manager.add_user(build_user("pwd123"));
+
+        for algorithm in [ScramAlgorithm::Sha256, ScramAlgorithm::Sha512] {
+            let step = login(algorithm, cache_manager.clone(), "pwd123")
+                .await
+                .unwrap();
+            assert!(
+                matches!(step, EnhancedAuthStep::Success { username, .. } if username == "lobo")
+            );
+
+            assert!(login(algorithm, cache_manager.clone(), "pwd1234")
+                .await
+                .is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn scram_hashed_user_test() {
+        let cache_manager = build_cache_manager();
+        let user = encrypt_user_password(build_user("pwd123"), PasswordHashAlgorithm::Pbkdf2)
+            .await
+            .unwrap();
+        cache_manager.add_user(user);
+
+        let step = login(ScramAlgorithm::Sha256, cache_manager.clone(), "pwd123")
+            .await
+            .unwrap();
+        assert!(matches!(step, EnhancedAuthStep::Success { .. }));
+
+        // a pbkdf2 hash only holds the salted password of SHA-256
+        assert!(
+            login(ScramAlgorithm::Sha512, cache_manager.clone(), "pwd123")
+                .await
+                .is_err()
+        );
+
+        let user = encrypt_user_password(build_user("pwd123"), PasswordHashAlgorithm::Bcrypt)
+            .await
+            .unwrap();
+        cache_manager.add_user(user);
+        assert!(login(ScramAlgorithm::Sha256, cache_manager, "pwd123")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn scram_invalid_message_test() {
+        let cache_manager = build_cache_manager();
+        cache_manager.add_user(build_user("pwd123"));
+
+        // an unknown user fails like a wrong password, after a server-first-message
+        let unknown = login_as(
+            ScramAlgorithm::Sha256,
+            cache_manager.clone(),
+            "admin",
+            "pwd123",
+        )
+        .await
+        .unwrap_err();
+        let wrong_password = login(ScramAlgorithm::Sha256, cache_manager.clone(), "pwd")
+            .await
+            .unwrap_err();
+        assert_eq!(unknown, wrong_password);
+
+        // channel binding
+        let mut scram = Scram::new(ScramAlgorithm::Sha256, cache_manager.clone());
+        assert!(scram.step(b"p=tls-unique,,n=lobo,r=abc").await.is_err());
+
+        // malformed client-first-message
+        let mut scram = Scram::new(ScramAlgorithm::Sha256, cache_manager.clone());
+        assert!(scram.step(b"n=lobo,r=abc").await.is_err());
+
+        // the nonce of the client-final-message does not match
+        let mut scram = Scram::new(ScramAlgorithm::Sha256, cache_manager.clone());
+        assert!(matches!(
+            scram.step(b"n,,n=lobo,r=abc").await.unwrap(),
+            EnhancedAuthStep::Continue(_)
+        ));
+        assert!(scram.step(b"c=biws,r=abc,p=AAAA").await.is_err());
+        // the exchange can not be continued after a failure
+        assert!(scram.step(b"c=biws,r=abc,p=AAAA").await.is_err());
+
+        assert_eq!(scram.method(), "SCRAM-SHA-256");
+        assert_eq!(ScramAlgorithm::from_method("SCRAM-SHA-1"), None);
+    }
+
+    #[test]
+    fn parse_attributes_test() {
+        assert_eq!(
+            parse_attributes("n=lobo,r=a=b").unwrap(),
+            vec![('n', "lobo"), ('r', "a=b")]
+        );
+        assert!(parse_attributes("é=lobo").is_err());
+        assert!(parse_attributes("nn=lobo").is_err());
+        assert!(parse_attributes("1=lobo").is_err());
+        assert!(parse_attributes("n").is_err());
+    }
+
+    #[test]
+    fn decode_username_test() {
+        assert_eq!(decode_username("lobo").unwrap(), "lobo");
+        assert_eq!(decode_username("a=2Cb=3Dc").unwrap(), "a,b=c");
+        assert!(decode_username("a=2").is_err());
+        assert!(decode_username("a=41").is_err());
+    }
+}
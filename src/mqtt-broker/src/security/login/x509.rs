@@ -11,3 +11,383 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::config::broker_mqtt::Network;
+use log::warn;
+use protocol::mqtt::common::{Connect, Login};
+use rustls::server::danger::ClientCertVerifier;
+use rustls::server::WebPkiClientVerifier;
+use rustls::RootCertStore;
+use rustls_pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::FromDer;
+
+use super::Authentication;
+use crate::handler::error::MqttBrokerError;
+use crate::server::tcp::tls_server::load_certs;
+
+/// the identity of the certificate a client presented during the TLS handshake
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct X509Identity {
+    pub common_name: String,
+    pub distinguished_name: String,
+    // DNS names, emails and URIs of the subject alternative name
+    pub subject_alt_names: Vec<String>,
+}
+
+impl X509Identity {
+    pub fn from_der(der: &[u8]) -> Result<Self, MqttBrokerError> {
+        let (_, cert) = X509Certificate::from_der(der)
+            .map_err(|e| MqttBrokerError::InvalidCertificate(e.to_string()))?;
+
+        let common_name = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        let mut subject_alt_names = Vec::new();
+        if let Some(san) = cert
+            .subject_alternative_name()
+            .map_err(|e| MqttBrokerError::InvalidCertificate(e.to_string()))?
+        {
+            for name in san.value.general_names.iter() {
+                match name {
+                    GeneralName::DNSName(value)
+                    | GeneralName::RFC822Name(value)
+                    | GeneralName::URI(value) => subject_alt_names.push(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(X509Identity {
+            common_name,
+            distinguished_name: cert.subject().to_string(),
+            subject_alt_names,
+        })
+    }
+
+    /// the identity of the end-entity certificate, if the client presented one
+    pub fn from_peer_certs(peer_certs: Option<&[CertificateDer<'_>]>) -> Option<Self> {
+        let cert = peer_certs?.first()?;
+        match X509Identity::from_der(cert.as_ref()) {
+            Ok(identity) => Some(identity),
+            Err(e) => {
+                warn!(
+                    "Failed to parse the client certificate, error message: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// the values of `cn`, `dn` or `san`, as configured by `peer_cert_as_username` and
+    /// `peer_cert_as_clientid`, `san` has a value for every subject alternative name
+    pub fn field_values(&self, name: &str) -> Vec<&str> {
+        let values: Vec<&str> = match name {
+            "cn" => vec![self.common_name.as_str()],
+            "dn" => vec![self.distinguished_name.as_str()],
+            "san" => self.subject_alt_names.iter().map(|n| n.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        values.into_iter().filter(|v| !v.is_empty()).collect()
+    }
+
+    /// the value of a field the connection is identified by: the value the client requested in
+    /// its CONNECT when the certificate carries it, the first value of the field otherwise
+    pub fn select_field(&self, name: &str, requested: &str) -> Option<String> {
+        let values = self.field_values(name);
+        if values.contains(&requested) {
+            return Some(requested.to_string());
+        }
+        values.first().map(|v| v.to_string())
+    }
+}
+
+/// the verifier of client certificates, built from the CA bundle in `tls_ca`
+pub fn build_client_verifier(
+    network: &Network,
+) -> Result<Arc<dyn ClientCertVerifier>, MqttBrokerError> {
+    let mut roots = RootCertStore::empty();
+    let certs = load_certs(Path::new(&network.tls_ca))
+        .map_err(|e| MqttBrokerError::InvalidCertificate(format!("{}: {}", network.tls_ca, e)))?;
+    let (added, _) = roots.add_parsable_certificates(certs);
+    if added == 0 {
+        return Err(MqttBrokerError::InvalidCertificate(format!(
+            "no CA certificate found in {}",
+            network.tls_ca
+        )));
+    }
+
+    let mut builder = WebPkiClientVerifier::builder(Arc::new(roots));
+    if !network.tls_fail_if_no_peer_cert {
+        builder = builder.allow_unauthenticated();
+    }
+    builder
+        .build()
+        .map_err(|e| MqttBrokerError::InvalidCertificate(e.to_string()))
+}
+
+/// replace the client id and username of a CONNECT with the identity of the client certificate
+pub fn apply_peer_cert_identity(
+    network: &Network,
+    identity: &X509Identity,
+    connect: &mut Connect,
+    login: &mut Option<Login>,
+) {
+    if let Some(client_id) =
+        identity.select_field(&network.peer_cert_as_clientid, &connect.client_id)
+    {
+        connect.client_id = client_id;
+    }
+
+    let requested = login
+        .as_ref()
+        .map(|l| l.username.as_str())
+        .unwrap_or_default();
+    if let Some(username) = identity.select_field(&network.peer_cert_as_username, requested) {
+        match login {
+            Some(login) => login.username = username,
+            None => {
+                *login = Some(Login {
+                    username,
+                    password: "".to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// A client is authenticated by its certificate, which has been verified against `tls_ca` in the
+/// TLS handshake, when the username is one of the values of the certificate field that provides
+/// usernames.
+pub struct X509 {
+    identity: X509Identity,
+    username_field: String,
+    username: String,
+}
+
+impl X509 {
+    pub fn new(identity: X509Identity, username_field: String, username: String) -> Self {
+        X509 {
+            identity,
+            username_field,
+            username,
+        }
+    }
+}
+
+#[async_trait]
+impl Authentication for X509 {
+    async fn apply(&self) -> Result<bool, MqttBrokerError> {
+        Ok(self
+            .identity
+            .field_values(&self.username_field)
+            .contains(&self.username.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use common_base::config::broker_mqtt::Network;
+    use common_base::tools::unique_id;
+    use protocol::mqtt::common::{Connect, Login};
+    use rcgen::{
+        BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+        KeyPair, SanType,
+    };
+    use rustls_pki_types::UnixTime;
+
+    use super::{apply_peer_cert_identity, build_client_verifier, X509Identity, X509};
+    use crate::security::login::Authentication;
+
+    fn build_ca() -> (Certificate, KeyPair) {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "robustmq-ca");
+        (params.self_signed(&key).unwrap(), key)
+    }
+
+    fn build_client_cert(ca: &Certificate, ca_key: &KeyPair) -> Certificate {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::new()).unwrap();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "device-1");
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "robustmq");
+        params.subject_alt_names = vec![
+            SanType::DnsName("device-1.robustmq.com".try_into().unwrap()),
+            SanType::Rfc822Name("device-1@robustmq.com".try_into().unwrap()),
+        ];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        params.signed_by(&key, ca, ca_key).unwrap()
+    }
+
+    fn build_network(tls_ca: String) -> Network {
+        Network {
+            tls_ca,
+            tls_fail_if_no_peer_cert: true,
+            peer_cert_as_username: "cn".to_string(),
+            peer_cert_as_clientid: "san".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identity_from_der_test() {
+        let (ca, ca_key) = build_ca();
+        let cert = build_client_cert(&ca, &ca_key);
+
+        let identity = X509Identity::from_der(cert.der().as_ref()).unwrap();
+        assert_eq!(identity.common_name, "device-1");
+        assert!(identity.distinguished_name.contains("CN=device-1"));
+        assert_eq!(
+            identity.subject_alt_names,
+            vec![
+                "device-1.robustmq.com".to_string(),
+                "device-1@robustmq.com".to_string()
+            ]
+        );
+        assert_eq!(identity.field_values("cn"), vec!["device-1"]);
+        assert_eq!(
+            identity.field_values("san"),
+            vec!["device-1.robustmq.com", "device-1@robustmq.com"]
+        );
+        assert!(identity.field_values("").is_empty());
+
+        // any SAN entry can be requested, the first one is used otherwise
+        assert_eq!(
+            identity.select_field("san", "device-1@robustmq.com"),
+            Some("device-1@robustmq.com".to_string())
+        );
+        assert_eq!(
+            identity.select_field("san", "other"),
+            Some("device-1.robustmq.com".to_string())
+        );
+        assert_eq!(identity.select_field("", "other"), None);
+
+        assert!(X509Identity::from_der(b"not a certificate").is_err());
+        assert_eq!(X509Identity::from_peer_certs(None), None);
+    }
+
+    #[test]
+    fn apply_peer_cert_identity_test() {
+        let identity = X509Identity {
+            common_name: "device-1".to_string(),
+            distinguished_name: "CN=device-1".to_string(),
+            subject_alt_names: vec![
+                "device-1.robustmq.com".to_string(),
+                "device-1.robustmq.io".to_string(),
+            ],
+        };
+        let network = build_network("".to_string());
+
+        let mut connect = Connect {
+            keep_alive: 10,
+            client_id: "c1".to_string(),
+            clean_session: true,
+        };
+        let mut login = None;
+        apply_peer_cert_identity(&network, &identity, &mut connect, &mut login);
+        assert_eq!(connect.client_id, "device-1.robustmq.com");
+        assert_eq!(login.unwrap().username, "device-1");
+
+        // the client id may be any SAN entry of the certificate
+        let mut connect = Connect {
+            keep_alive: 10,
+            client_id: "device-1.robustmq.io".to_string(),
+            clean_session: true,
+        };
+        let mut login = None;
+        apply_peer_cert_identity(&network, &identity, &mut connect, &mut login);
+        assert_eq!(connect.client_id, "device-1.robustmq.io");
+
+        let mut login = Some(Login {
+            username: "admin".to_string(),
+            password: "pwd123".to_string(),
+        });
+        let network = Network::default();
+        let mut connect = Connect {
+            keep_alive: 10,
+            client_id: "c1".to_string(),
+            clean_session: true,
+        };
+        apply_peer_cert_identity(&network, &identity, &mut connect, &mut login);
+        assert_eq!(connect.client_id, "c1");
+        assert_eq!(login.unwrap().username, "admin");
+    }
+
+    #[test]
+    fn client_verifier_test() {
+        let (ca, ca_key) = build_ca();
+        let cert = build_client_cert(&ca, &ca_key);
+
+        let path = format!("/tmp/robustmq-ca-{}.pem", unique_id());
+        fs::write(&path, ca.pem()).unwrap();
+        let verifier = build_client_verifier(&build_network(path.clone())).unwrap();
+        assert!(verifier.client_auth_mandatory());
+        assert!(verifier
+            .verify_client_cert(cert.der(), &[], UnixTime::now())
+            .is_ok());
+
+        // a certificate signed by another CA is rejected
+        let (other_ca, other_ca_key) = build_ca();
+        let other_cert = build_client_cert(&other_ca, &other_ca_key);
+        assert!(verifier
+            .verify_client_cert(other_cert.der(), &[], UnixTime::now())
+            .is_err());
+
+        let mut network = build_network(path.clone());
+        network.tls_fail_if_no_peer_cert = false;
+        let verifier = build_client_verifier(&network).unwrap();
+        assert!(!verifier.client_auth_mandatory());
+
+        fs::remove_file(&path).unwrap();
+        assert!(build_client_verifier(&build_network(path)).is_err());
+    }
+
+    #[tokio::test]
+    async fn x509_authenticate_test() {
+        let identity = X509Identity {
+            common_name: "device-1".to_string(),
+            subject_alt_names: vec!["a.robustmq.com".to_string(), "b.robustmq.com".to_string()],
+            ..Default::default()
+        };
+        let x509 = X509::new(identity.clone(), "cn".to_string(), "device-1".to_string());
+        assert!(x509.apply().await.unwrap());
+
+        let x509 = X509::new(identity.clone(), "cn".to_string(), "admin".to_string());
+        assert!(!x509.apply().await.unwrap());
+
+        // every SAN entry is matched
+        let x509 = X509::new(
+            identity.clone(),
+            "san".to_string(),
+            "b.robustmq.com".to_string(),
+        );
+        assert!(x509.apply().await.unwrap());
+
+        let x509 = X509::new(identity.clone(), "dn".to_string(), "device-1".to_string());
+        assert!(!x509.apply().await.unwrap());
+
+        let x509 = X509::new(identity, "".to_string(), "device-1".to_string());
+        assert!(!x509.apply().await.unwrap());
+    }
+}
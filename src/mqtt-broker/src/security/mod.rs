@@ -23,15 +23,23 @@
 use common_base::config::common::Auth;
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
+use log::{info, warn};
+use login::http::{Http, HttpAuthClient, HttpAuthRequest};
+use login::jwt::{Jwt, JwtVerifier};
 use login::plaintext::Plaintext;
+use login::psk::{decode_psk, Psk};
+use login::x509::{X509Identity, X509};
 use login::Authentication;
-use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction, MqttAclResourceType};
+use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction};
 use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
 use metadata_struct::mqtt::connection::MQTTConnection;
-use metadata_struct::mqtt::user::MqttUser;
-use protocol::mqtt::common::{ConnectProperties, Login, QoS, Subscribe};
+use metadata_struct::mqtt::psk::MqttPsk;
+use metadata_struct::mqtt::user::{build_password_hash_algorithm, MqttUser, PasswordHashAlgorithm};
+use password::encrypt_user_password;
+use protocol::mqtt::common::{ConnectProperties, Login, MqttProtocol, QoS, Subscribe};
 use storage::mysql::MySQLAuthStorageAdapter;
 use storage::placement::PlacementAuthStorageAdapter;
+use storage::redis::RedisAuthStorageAdapter;
 use storage_adapter::StorageType;
 
 use crate::handler::cache::CacheManager;
@@ -41,6 +49,7 @@
 
 pub mod acl;
 pub mod login;
+pub mod password;
 pub mod storage;
 
 #[async_trait]
@@ -64,12 +73,30 @@ pub trait AuthStorageAdapter {
     async fn save_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError>;
 
     async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError>;
+
+    async fn read_all_psk(&self) -> Result<Vec<MqttPsk>, MqttBrokerError>;
+
+    async fn save_psk(&self, psk: MqttPsk) -> Result<(), MqttBrokerError>;
+
+    async fn delete_psk(&self, identity: String) -> Result<(), MqttBrokerError>;
 }
 
 pub struct AuthDriver {
     cache_manager: Arc<CacheManager>,
     client_pool: Arc<ClientPool>,
     driver: Arc<dyn AuthStorageAdapter + Send + 'static + Sync>,
+    authn: Authn,
+    // consulted before the ACL of the broker when `http_acl_url` is configured
+    http_acl: Option<Arc<HttpAuthClient>>,
+    password_hash_algorithm: PasswordHashAlgorithm,
+}
+
+/// how the login of a connection is checked, selected by `authn_type` in the `[auth]` config
+#[derive(Clone)]
+pub enum Authn {
+    Plaintext,
+    Jwt(Arc<JwtVerifier>),
+    Http(Arc<HttpAuthClient>),
 }
 
 impl AuthDriver {
@@ -81,16 +108,44 @@ pub fn new(cache_manager: Arc<CacheManager>, client_pool: Arc<ClientPool>) -> Au
                 panic!("{}", e.to_string());
             }
         };
+        let authn = match build_authn(&conf.auth) {
+            Ok(authn) => authn,
+            Err(e) => {
+                panic!("{}", e.to_string());
+            }
+        };
+        let http_acl = match build_http_acl(&conf.auth) {
+            Ok(http_acl) => http_acl,
+            Err(e) => {
+                panic!("{}", e.to_string());
+            }
+        };
+        let password_hash_algorithm =
+            match build_password_hash_algorithm(&conf.auth.password_hash_algorithm) {
+                Ok(algorithm) => algorithm,
+                Err(e) => {
+                    panic!("{}", e.to_string());
+                }
+            };
         AuthDriver {
             cache_manager,
             driver,
             client_pool,
+            authn,
+            http_acl,
+            password_hash_algorithm,
         }
     }
 
     pub fn update_driver(&mut self, auth: Auth) -> Result<(), MqttBrokerError> {
+        let authn = build_authn(&auth)?;
+        let http_acl = build_http_acl(&auth)?;
+        let password_hash_algorithm = build_password_hash_algorithm(&auth.password_hash_algorithm)?;
         let driver = build_driver(self.client_pool.clone(), auth)?;
         self.driver = driver;
+        self.authn = authn;
+        self.http_acl = http_acl;
+        self.password_hash_algorithm = password_hash_algorithm;
         Ok(())
     }
 
@@ -111,6 +166,7 @@ pub async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError
         if let Some(_user) = self.cache_manager.user_info.get(&username) {
             return Err(MqttBrokerError::UserAlreadyExist);
         }
+        let user_info = encrypt_user_password(user_info, self.password_hash_algorithm).await?;
         self.cache_manager.add_user(user_info.clone());
         self.driver.save_user(user_info).await
     }
@@ -124,6 +180,39 @@ pub async fn delete_user(&self, username: String) -> Result<(), MqttBrokerError>
         Ok(())
     }
 
+    pub async fn read_all_psk(&self) -> Result<Vec<MqttPsk>, MqttBrokerError> {
+        self.driver.read_all_psk().await
+    }
+
+    pub async fn save_psk(&self, psk: MqttPsk) -> Result<(), MqttBrokerError> {
+        if self.cache_manager.psk_info.contains_key(&psk.identity) {
+            return Err(MqttBrokerError::PskIdentityAlreadyExist);
+        }
+        decode_psk(&psk.psk)?;
+        self.driver.save_psk(psk.clone()).await?;
+        self.cache_manager.add_psk(psk);
+        Ok(())
+    }
+
+    pub async fn delete_psk(&self, identity: String) -> Result<(), MqttBrokerError> {
+        if !self.cache_manager.psk_info.contains_key(&identity) {
+            return Err(MqttBrokerError::PskIdentityDoesNotExist);
+        }
+        self.driver.delete_psk(identity.clone()).await?;
+        self.cache_manager.del_psk(&identity);
+        Ok(())
+    }
+
+    pub async fn update_psk_cache(&self) -> Result<(), MqttBrokerError> {
+        let all_psks = self.driver.read_all_psk().await?;
+        let identities: HashSet<String> = all_psks.iter().map(|psk| psk.identity.clone()).collect();
+        for psk in all_psks {
+            self.cache_manager.add_psk(psk);
+        }
+        self.cache_manager.retain_psks(identities);
+        Ok(())
+    }
+
     pub async fn update_user_cache(&self) -> Result<(), MqttBrokerError> {
         let all_users: DashMap<String, MqttUser> = self.driver.read_all_user().await?;
 
@@ -139,11 +228,20 @@ pub async fn update_user_cache(&self) -> Result<(), MqttBrokerError> {
         Ok(())
     }
 
+    /// check the credentials of a CONNECT packet
+    ///
+    /// When a JWT from the username is accepted, the username of `login` is replaced by the
+    /// username claim of the token.
+    #[allow(clippy::too_many_arguments)]
     pub async fn check_login_auth(
         &self,
-        login: &Option<Login>,
+        connection: &MQTTConnection,
+        login: &mut Option<Login>,
         _: &Option<ConnectProperties>,
-        _: &SocketAddr,
+        addr: &SocketAddr,
+        protocol: &MqttProtocol,
+        peer_cert: &Option<X509Identity>,
+        psk_identity: &Option<String>,
     ) -> Result<bool, MqttBrokerError> {
         let cluster = self.cache_manager.get_cluster_info();
 
@@ -151,10 +249,64 @@ pub async fn check_login_auth(
             return Ok(true);
         }
 
+        if let Some(identity) = peer_cert {
+            let username = login
+                .as_ref()
+                .map(|info| info.username.clone())
+                .unwrap_or_default();
+            let x509 = X509::new(
+                identity.clone(),
+                broker_mqtt_conf().network.peer_cert_as_username.clone(),
+                username,
+            );
+            if x509.apply().await? {
+                return Ok(true);
+            }
+        }
+
+        if let Some(identity) = psk_identity {
+            let psk = Psk::new(identity.clone(), self.cache_manager.clone());
+            if psk.apply().await? {
+                return Ok(true);
+            }
+        }
+
         if let Some(info) = login {
-            return self
-                .plaintext_check_login(&info.username, &info.password)
-                .await;
+            return match &self.authn {
+                Authn::Plaintext => {
+                    self.plaintext_check_login(&info.username, &info.password)
+                        .await
+                }
+                Authn::Jwt(verifier) => {
+                    let jwt = Jwt::new(
+                        connection.connect_id,
+                        connection.client_id.clone(),
+                        info.username.clone(),
+                        info.password.clone(),
+                        verifier.clone(),
+                        self.cache_manager.clone(),
+                    );
+                    Ok(match jwt.login() {
+                        Some(username) => {
+                            info.username = username;
+                            true
+                        }
+                        None => false,
+                    })
+                }
+                Authn::Http(client) => {
+                    let request = HttpAuthRequest {
+                        clientid: connection.client_id.clone(),
+                        username: info.username.clone(),
+                        password: info.password.clone(),
+                        peerhost: addr.ip().to_string(),
+                        proto_ver: u8::from(protocol.clone()),
+                        ..Default::default()
+                    };
+                    let http = Http::new(request, client.clone());
+                    http.apply().await
+                }
+            };
         }
 
         Ok(false)
@@ -173,22 +325,7 @@ pub async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
 
     pub async fn update_acl_cache(&self) -> Result<(), MqttBrokerError> {
         let all_acls: Vec<MqttAcl> = self.driver.read_all_acl().await?;
-
-        for acl in all_acls.iter() {
-            self.cache_manager.add_acl(acl.to_owned());
-        }
-
-        let mut user_acl = HashSet::new();
-        let mut client_acl = HashSet::new();
-
-        for acl in all_acls.clone() {
-            match acl.resource_type {
-                MqttAclResourceType::User => user_acl.insert(acl.resource_name.clone()),
-                MqttAclResourceType::ClientId => client_acl.insert(acl.resource_name.clone()),
-            };
-        }
-        self.cache_manager.retain_acls(user_acl, client_acl);
-
+        self.cache_manager.reload_acls(all_acls);
         Ok(())
     }
 
@@ -218,6 +355,10 @@ pub async fn allow_publish(
         retain: bool,
         qos: QoS,
     ) -> bool {
+        if let Some(allow) = self.http_authorize(connection, "publish", topic_name).await {
+            return allow;
+        }
+
         is_allow_acl(
             &self.cache_manager,
             connection,
@@ -234,6 +375,28 @@ pub async fn allow_subscribe(
         subscribe: &Subscribe,
     ) -> bool {
         for filter in subscribe.filters.clone() {
+            if let Some(allow) = self
+                .http_authorize(connection, "subscribe", &filter.path)
+                .await
+            {
+                if !allow {
+                    return false;
+                }
+                continue;
+            }
+
+            // the filter itself, and the existing topics it subscribes to
+            if !is_allow_acl(
+                &self.cache_manager,
+                connection,
+                &filter.path,
+                MqttAclAction::Subscribe,
+                false,
+                filter.qos,
+            ) {
+                return false;
+            }
+
             let topic_list = get_sub_topic_id_list(&self.cache_manager, &filter.path).await;
             for topic in topic_list {
                 if !is_allow_acl(
@@ -251,6 +414,35 @@ pub async fn allow_subscribe(
         true
     }
 
+    async fn http_authorize(
+        &self,
+        connection: &MQTTConnection,
+        action: &str,
+        topic: &str,
+    ) -> Option<bool> {
+        let client = self.http_acl.as_ref()?;
+        let peerhost = match SocketAddr::from_str(&connection.source_ip_addr) {
+            Ok(addr) => addr.ip().to_string(),
+            Err(_) => connection.source_ip_addr.clone(),
+        };
+        let proto_ver = self
+            .cache_manager
+            .heartbeat_data
+            .get(&connection.client_id)
+            .map(|live_time| u8::from(live_time.protocol.clone()))
+            .unwrap_or_default();
+        let request = HttpAuthRequest {
+            clientid: connection.client_id.clone(),
+            username: connection.login_user.clone(),
+            peerhost,
+            proto_ver,
+            action: action.to_string(),
+            topic: topic.to_string(),
+            ..Default::default()
+        };
+        client.authorize(&request).await
+    }
+
     async fn plaintext_check_login(
         &self,
         username: &str,
@@ -264,13 +456,14 @@ async fn plaintext_check_login(
         match plaintext.apply().await {
             Ok(flag) => {
                 if flag {
+                    self.try_migrate_user_password(username).await;
                     return Ok(true);
                 }
             }
             Err(e) => {
                 // If the user does not exist, try to get the user information from the storage layer
                 if e.to_string() == MqttBrokerError::UserDoesNotExist.to_string() {
-                    return self.try_get_check_user_by_driver(username).await;
+                    return self.try_get_check_user_by_driver(username, password).await;
                 }
                 return Err(e);
             }
@@ -278,17 +471,53 @@ async fn plaintext_check_login(
         Ok(false)
     }
 
-    async fn try_get_check_user_by_driver(&self, username: &str) -> Result<bool, MqttBrokerError> {
+    /// hash the password of a user saved in clear text, once the user has logged in
+    async fn try_migrate_user_password(&self, username: &str) {
+        let user = if let Some(user) = self.cache_manager.user_info.get(username) {
+            user.clone()
+        } else {
+            return;
+        };
+        if user.hash_algorithm != PasswordHashAlgorithm::Plain
+            || self.password_hash_algorithm == PasswordHashAlgorithm::Plain
+        {
+            return;
+        }
+
+        let result = match encrypt_user_password(user, self.password_hash_algorithm).await {
+            Ok(user) => self.driver.save_user(user.clone()).await.map(|_| user),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(user) => {
+                self.cache_manager.add_user(user);
+                info!("The password of user {} is hashed", username);
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to hash the password of user {}, error message: {}",
+                    username, e
+                );
+            }
+        }
+    }
+
+    async fn try_get_check_user_by_driver(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<bool, MqttBrokerError> {
         if let Some(user) = self.driver.get_user(username.to_owned()).await? {
             self.cache_manager.add_user(user.clone());
 
             let plaintext = Plaintext::new(
                 user.username.clone(),
-                user.password.clone(),
+                password.to_owned(),
                 self.cache_manager.clone(),
             );
 
             if plaintext.apply().await? {
+                self.try_migrate_user_password(username).await;
                 return Ok(true);
             }
         }
@@ -297,10 +526,34 @@ async fn try_get_check_user_by_driver(&self, username: &str) -> Result<bool, Mqt
     }
 }
 
+pub fn build_authn(auth: &Auth) -> Result<Authn, MqttBrokerError> {
+    match auth.authn_type.as_str() {
+        "" | "plaintext" => Ok(Authn::Plaintext),
+        "jwt" => Ok(Authn::Jwt(Arc::new(JwtVerifier::new(auth)?))),
+        "http" => Ok(Authn::Http(Arc::new(HttpAuthClient::new(auth)?))),
+        authn_type => Err(MqttBrokerError::UnavailableAuthnType(
+            authn_type.to_string(),
+        )),
+    }
+}
+
+pub fn build_http_acl(auth: &Auth) -> Result<Option<Arc<HttpAuthClient>>, MqttBrokerError> {
+    if auth.http_acl_url.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(HttpAuthClient::new(auth)?)))
+}
+
 pub fn build_driver(
     client_pool: Arc<ClientPool>,
     auth: Auth,
 ) -> Result<Arc<dyn AuthStorageAdapter + Send + 'static + Sync>, MqttBrokerError> {
+    // redis only stores the auth data, so it is not one of the message storage types
+    if auth.storage_type == "redis" {
+        let driver = RedisAuthStorageAdapter::new(&auth)?;
+        return Ok(Arc::new(driver));
+    }
+
     let storage_type = StorageType::from_str(&auth.storage_type)
         .map_err(|_| MqttBrokerError::UnavailableStorageType)?;
     if matches!(storage_type, StorageType::Placement) {
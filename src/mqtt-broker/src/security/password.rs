@@ -0,0 +1,224 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use metadata_struct::mqtt::user::{MqttUser, PasswordHashAlgorithm};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::handler::error::MqttBrokerError;
+
+const BCRYPT_COST: u32 = 10;
+pub(crate) const PBKDF2_ROUNDS: u32 = 600_000;
+const PBKDF2_HASH_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+/// hash the password of a user that still holds its password in clear text
+///
+/// Users whose password is already hashed are returned unchanged. bcrypt and PBKDF2 are slow by
+/// design, so the hash is computed on the blocking thread pool.
+pub async fn encrypt_user_password(
+    user: MqttUser,
+    algorithm: PasswordHashAlgorithm,
+) -> Result<MqttUser, MqttBrokerError> {
+    if user.hash_algorithm != PasswordHashAlgorithm::Plain
+        || algorithm == PasswordHashAlgorithm::Plain
+    {
+        return Ok(user);
+    }
+    tokio::task::spawn_blocking(move || hash_user_password(user, algorithm)).await?
+}
+
+/// whether the password matches the password of the user, compared in constant time
+pub async fn verify_user_password(
+    user: &MqttUser,
+    password: &str,
+) -> Result<bool, MqttBrokerError> {
+    if user.hash_algorithm == PasswordHashAlgorithm::Plain {
+        return check_user_password(user, password);
+    }
+    let user = user.clone();
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || check_user_password(&user, &password)).await?
+}
+
+/// the iteration count and the salted password of a PBKDF2 hash, stored as `{rounds}${hex}`
+pub(crate) fn parse_pbkdf2_hash(user: &MqttUser) -> Result<(u32, Vec<u8>), MqttBrokerError> {
+    let invalid = || MqttBrokerError::InvalidPasswordHash(user.username.clone());
+    let (rounds, hash) = user.password.split_once('$').ok_or_else(invalid)?;
+    let rounds = rounds.parse().map_err(|_| invalid())?;
+    let hash = hex::decode(hash).map_err(|_| invalid())?;
+    Ok((rounds, hash))
+}
+
+fn hash_user_password(
+    mut user: MqttUser,
+    algorithm: PasswordHashAlgorithm,
+) -> Result<MqttUser, MqttBrokerError> {
+    let salt = match algorithm {
+        // bcrypt keeps the salt in the hash
+        PasswordHashAlgorithm::Bcrypt => "".to_string(),
+        _ => build_salt(),
+    };
+    user.password = hash_password(algorithm, &user.password, &salt)?;
+    user.salt = salt;
+    user.hash_algorithm = algorithm;
+    Ok(user)
+}
+
+fn check_user_password(user: &MqttUser, password: &str) -> Result<bool, MqttBrokerError> {
+    match user.hash_algorithm {
+        PasswordHashAlgorithm::Bcrypt => Ok(bcrypt::verify(password, &user.password)?),
+        PasswordHashAlgorithm::Pbkdf2 => {
+            let (rounds, expected) = parse_pbkdf2_hash(user)?;
+            let hash = pbkdf2_hash(password, &user.salt, rounds);
+            Ok(hash.ct_eq(&expected).into())
+        }
+        _ => {
+            let hash = hash_password(user.hash_algorithm, password, &user.salt)?;
+            Ok(hash.as_bytes().ct_eq(user.password.as_bytes()).into())
+        }
+    }
+}
+
+fn hash_password(
+    algorithm: PasswordHashAlgorithm,
+    password: &str,
+    salt: &str,
+) -> Result<String, MqttBrokerError> {
+    match algorithm {
+        PasswordHashAlgorithm::Plain => Ok(password.to_string()),
+        PasswordHashAlgorithm::Bcrypt => Ok(bcrypt::hash(password, BCRYPT_COST)?),
+        PasswordHashAlgorithm::Pbkdf2 => Ok(format!(
+            "{}${}",
+            PBKDF2_ROUNDS,
+            hex::encode(pbkdf2_hash(password, salt, PBKDF2_ROUNDS))
+        )),
+        PasswordHashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            hasher.update(password.as_bytes());
+            hasher.update(salt.as_bytes());
+            Ok(hex::encode(hasher.finalize()))
+        }
+    }
+}
+
+fn pbkdf2_hash(password: &str, salt: &str, rounds: u32) -> [u8; PBKDF2_HASH_LEN] {
+    let mut hash = [0u8; PBKDF2_HASH_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), rounds, &mut hash);
+    hash
+}
+
+fn build_salt() -> String {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    hex::encode(salt)
+}
+
+#[cfg(test)]
+mod test {
+    use metadata_struct::mqtt::user::{MqttUser, PasswordHashAlgorithm};
+
+    use super::{encrypt_user_password, parse_pbkdf2_hash, pbkdf2_hash, verify_user_password};
+
+    fn build_user(password: &str) -> MqttUser {
+        MqttUser {
+            username: "lobo".to_string(),
+            password: password.to_string(),
+            is_superuser: false,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn encrypt_user_password_test() {
+        for algorithm in [
+            PasswordHashAlgorithm::Bcrypt,
+            PasswordHashAlgorithm::Pbkdf2,
+            PasswordHashAlgorithm::Sha256,
+        ] {
+            let user = encrypt_user_password(build_user("pwd123"), algorithm)
+                .await
+                .unwrap();
+            assert_eq!(user.hash_algorithm, algorithm);
+            assert_ne!(user.password, "pwd123");
+            assert!(verify_user_password(&user, "pwd123").await.unwrap());
+            assert!(!verify_user_password(&user, "pwd1234").await.unwrap());
+
+            // a user that is already hashed is not hashed again
+            let again = encrypt_user_password(user.clone(), algorithm)
+                .await
+                .unwrap();
+            assert_eq!(again, user);
+        }
+    }
+
+    #[tokio::test]
+    async fn salted_password_test() {
+        let user1 = encrypt_user_password(build_user("pwd123"), PasswordHashAlgorithm::Sha256)
+            .await
+            .unwrap();
+        let user2 = encrypt_user_password(build_user("pwd123"), PasswordHashAlgorithm::Sha256)
+            .await
+            .unwrap();
+        assert_ne!(user1.salt, user2.salt);
+        assert_ne!(user1.password, user2.password);
+    }
+
+    #[tokio::test]
+    async fn plain_password_test() {
+        let user = encrypt_user_password(build_user("pwd123"), PasswordHashAlgorithm::Plain)
+            .await
+            .unwrap();
+        assert_eq!(user.password, "pwd123");
+        assert_eq!(user.hash_algorithm, PasswordHashAlgorithm::Plain);
+        assert!(verify_user_password(&user, "pwd123").await.unwrap());
+        assert!(!verify_user_password(&user, "pwd12").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn pbkdf2_rounds_test() {
+        let user = encrypt_user_password(build_user("pwd123"), PasswordHashAlgorithm::Pbkdf2)
+            .await
+            .unwrap();
+        let (rounds, _) = parse_pbkdf2_hash(&user).unwrap();
+        assert_eq!(rounds, 600_000);
+
+        // the stored iteration count is used to verify the password
+        let user = MqttUser {
+            password: format!(
+                "10000${}",
+                hex::encode(pbkdf2_hash("pwd123", "salt", 10_000))
+            ),
+            salt: "salt".to_string(),
+            hash_algorithm: PasswordHashAlgorithm::Pbkdf2,
+            ..build_user("")
+        };
+        assert!(verify_user_password(&user, "pwd123").await.unwrap());
+        assert!(!verify_user_password(&user, "pwd1234").await.unwrap());
+
+        // a hash without an iteration count is invalid
+        let invalid = MqttUser {
+            password: hex::encode(pbkdf2_hash("pwd123", "salt", 10_000)),
+            ..user.clone()
+        };
+        assert!(verify_user_password(&invalid, "pwd123").await.is_err());
+
+        let invalid = MqttUser {
+            password: "rounds$00".to_string(),
+            ..user
+        };
+        assert!(verify_user_password(&invalid, "pwd123").await.is_err());
+    }
+}
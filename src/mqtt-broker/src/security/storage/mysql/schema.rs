@@ -20,6 +20,7 @@ pub struct TAuthUser {
     pub salt: String,
     pub is_superuser: String,
     pub created: u64,
+    pub hash_algorithm: String,
 }
 
 #[derive(Debug, PartialEq, Eq, Default)]
@@ -31,4 +32,5 @@ pub struct TAuthAcl {
     pub client_id: String,
     pub access: u64,
     pub topic: String,
+    pub priority: u32,
 }
@@ -5,6 +5,7 @@ CREATE TABLE `mqtt_user` (
 `salt` varchar(35) DEFAULT NULL,
 `is_superuser` tinyint(1) DEFAULT 0,
 `created` datetime DEFAULT NULL,
+`hash_algorithm` varchar(20) DEFAULT NULL COMMENT 'plain, bcrypt, pbkdf2 or sha256, NULL means plain',
 PRIMARY KEY (`id`),
 UNIQUE KEY `mqtt_username` (`username`)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -17,8 +18,24 @@ CREATE TABLE `mqtt_acl` (
 `clientid` varchar(100) DEFAULT NULL COMMENT 'ClientId',
 `access` int(2) NOT NULL COMMENT '0:All, 1: subscribe, 2: publish, 3: pubsub, 4: retain, 5: qos',
 `topic` varchar(100) NOT NULL DEFAULT '' COMMENT 'Topic Filter', 
+`priority` int(11) unsigned NOT NULL DEFAULT 0 COMMENT 'lower priorities are checked first, then by id',
 PRIMARY KEY (`id`)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
 
+CREATE TABLE `mqtt_psk` (
+`id` int(11) unsigned NOT NULL AUTO_INCREMENT,
+`identity` varchar(128) NOT NULL COMMENT 'PSK identity, also the username of the client',
+`psk` varchar(512) NOT NULL COMMENT 'Pre-shared key, hex encoded',
+`create_time` bigint(20) unsigned NOT NULL DEFAULT 0,
+PRIMARY KEY (`id`),
+UNIQUE KEY `mqtt_psk_identity` (`identity`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- mqtt_user tables created before hash_algorithm existed are migrated by the broker when it
+-- starts, or by hand with:
+-- ALTER TABLE `mqtt_user` ADD COLUMN `hash_algorithm` varchar(20) DEFAULT NULL;
+-- and mqtt_acl tables created before priority existed with:
+-- ALTER TABLE `mqtt_acl` ADD COLUMN `priority` int(11) unsigned NOT NULL DEFAULT 0;
+
 INSERT INTO `mqtt_user` ( `username`, `password`, `salt`) VALUES
 ('robustmq', 'robustmq@2024', NULL);
\ No newline at end of file
@@ -19,12 +19,14 @@
 use grpc_clients::pool::ClientPool;
 use metadata_struct::acl::mqtt_acl::MqttAcl;
 use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::psk::MqttPsk;
 use metadata_struct::mqtt::user::MqttUser;
 
 use crate::handler::error::MqttBrokerError;
 use crate::security::AuthStorageAdapter;
 use crate::storage::acl::AclStorage;
 use crate::storage::blacklist::BlackListStorage;
+use crate::storage::psk::PskStorage;
 use crate::storage::user::UserStorage;
 
 pub struct PlacementAuthStorageAdapter {
@@ -88,4 +90,19 @@ async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), Mqtt
         let blacklist_storage = BlackListStorage::new(self.client_pool.clone());
         blacklist_storage.delete_blacklist(blacklist).await
     }
+
+    async fn read_all_psk(&self) -> Result<Vec<MqttPsk>, MqttBrokerError> {
+        let psk_storage = PskStorage::new(self.client_pool.clone());
+        psk_storage.list_psk().await
+    }
+
+    async fn save_psk(&self, psk: MqttPsk) -> Result<(), MqttBrokerError> {
+        let psk_storage = PskStorage::new(self.client_pool.clone());
+        psk_storage.save_psk(psk).await
+    }
+
+    async fn delete_psk(&self, identity: String) -> Result<(), MqttBrokerError> {
+        let psk_storage = PskStorage::new(self.client_pool.clone());
+        psk_storage.delete_psk(identity).await
+    }
 }
@@ -11,3 +11,847 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::collections::HashMap;
+
+use axum::async_trait;
+use common_base::config::common::Auth;
+use common_base::error::common::CommonError;
+use dashmap::DashMap;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::psk::MqttPsk;
+use metadata_struct::mqtt::user::{build_password_hash_algorithm, MqttUser};
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, AsyncIter, Client};
+use tokio::sync::OnceCell;
+
+use crate::handler::error::MqttBrokerError;
+use crate::security::AuthStorageAdapter;
+
+const USERNAME_PLACEHOLDER: &str = "${username}";
+const RESOURCE_TYPE_PLACEHOLDER: &str = "${resource_type}";
+const RESOURCE_NAME_PLACEHOLDER: &str = "${resource_name}";
+const BLACKLIST_TYPE_PLACEHOLDER: &str = "${blacklist_type}";
+const IDENTITY_PLACEHOLDER: &str = "${identity}";
+
+const DEFAULT_USER_KEY: &str = "mqtt_user:${username}";
+const DEFAULT_ACL_KEY: &str = "mqtt_acl:${resource_type}:${resource_name}";
+const DEFAULT_BLACKLIST_KEY: &str = "mqtt_blacklist:${blacklist_type}:${resource_name}";
+const DEFAULT_PSK_KEY: &str = "mqtt_psk:${identity}";
+
+// KEYS[1] is the acl list and ARGV[1] the rule, a rule with the same permission, action, topic
+// and ip is replaced in place so that a new priority is kept without adding the rule again
+pub(crate) const SAVE_ACL_SCRIPT: &str = r#"
+local rule = cjson.decode(ARGV[1])
+for index, raw in ipairs(redis.call('LRANGE', KEYS[1], 0, -1)) do
+    local saved = cjson.decode(raw)
+    if saved.permission == rule.permission and saved.action == rule.action
+        and saved.topic == rule.topic and saved.ip == rule.ip then
+        redis.call('LSET', KEYS[1], index - 1, ARGV[1])
+        return 0
+    end
+end
+redis.call('RPUSH', KEYS[1], ARGV[1])
+return 1
+"#;
+
+// rewrites the list without the rules that have the permission, action, topic and ip of ARGV[1],
+// so that the remaining rules keep their order
+pub(crate) const DELETE_ACL_SCRIPT: &str = r#"
+local rule = cjson.decode(ARGV[1])
+local kept = {}
+local removed = 0
+for _, raw in ipairs(redis.call('LRANGE', KEYS[1], 0, -1)) do
+    local saved = cjson.decode(raw)
+    if saved.permission == rule.permission and saved.action == rule.action
+        and saved.topic == rule.topic and saved.ip == rule.ip then
+        removed = removed + 1
+    else
+        table.insert(kept, raw)
+    end
+end
+if removed > 0 then
+    redis.call('DEL', KEYS[1])
+    for _, raw in ipairs(kept) do
+        redis.call('RPUSH', KEYS[1], raw)
+    end
+end
+return removed
+"#;
+
+/// Users are stored as hashes with the password, salt, is_superuser and hash_algorithm fields,
+/// the acl of a resource as a list of JSON rules in the order they apply, and every blacklist
+/// entry and TLS-PSK identity as a JSON string. The keys follow the layouts configured in `[auth]`.
+///
+/// One multiplexed connection is opened on first use and shared by every call of the adapter.
+pub struct RedisAuthStorageAdapter {
+    client: Client,
+    conn: OnceCell<MultiplexedConnection>,
+    user_key: String,
+    acl_key: String,
+    blacklist_key: String,
+    psk_key: String,
+}
+
+impl RedisAuthStorageAdapter {
+    pub fn new(auth: &Auth) -> Result<Self, MqttBrokerError> {
+        let user_key = build_key_layout("redis_user_key", &auth.redis_user_key, DEFAULT_USER_KEY)?;
+        if user_key.matches(USERNAME_PLACEHOLDER).count() != 1 {
+            return Err(CommonError::InvalidParameterFormat(
+                "redis_user_key".to_string(),
+                user_key,
+            )
+            .into());
+        }
+        let acl_key = build_key_layout("redis_acl_key", &auth.redis_acl_key, DEFAULT_ACL_KEY)?;
+        let blacklist_key = build_key_layout(
+            "redis_blacklist_key",
+            &auth.redis_blacklist_key,
+            DEFAULT_BLACKLIST_KEY,
+        )?;
+        let psk_key = build_key_layout("redis_psk_key", &auth.redis_psk_key, DEFAULT_PSK_KEY)?;
+        check_key_prefixes(&[
+            ("redis_user_key", &user_key),
+            ("redis_acl_key", &acl_key),
+            ("redis_blacklist_key", &blacklist_key),
+            ("redis_psk_key", &psk_key),
+        ])?;
+
+        Ok(RedisAuthStorageAdapter {
+            client: Client::open(auth.redis_addr.as_str())?,
+            conn: OnceCell::new(),
+            user_key,
+            acl_key,
+            blacklist_key,
+            psk_key,
+        })
+    }
+
+    async fn connection(&self) -> Result<MultiplexedConnection, MqttBrokerError> {
+        let conn = self
+            .conn
+            .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+            .await?;
+        Ok(conn.clone())
+    }
+
+    fn user_key(&self, username: &str) -> String {
+        self.user_key.replace(USERNAME_PLACEHOLDER, username)
+    }
+
+    fn acl_key(&self, acl: &MqttAcl) -> String {
+        self.acl_key
+            .replace(RESOURCE_TYPE_PLACEHOLDER, &acl.resource_type.to_string())
+            .replace(RESOURCE_NAME_PLACEHOLDER, &acl.resource_name)
+    }
+
+    fn blacklist_key(&self, blacklist: &MqttAclBlackList) -> String {
+        self.blacklist_key
+            .replace(
+                BLACKLIST_TYPE_PLACEHOLDER,
+                &blacklist.blacklist_type.to_string(),
+            )
+            .replace(RESOURCE_NAME_PLACEHOLDER, &blacklist.resource_name)
+    }
+
+    fn psk_key(&self, identity: &str) -> String {
+        self.psk_key.replace(IDENTITY_PLACEHOLDER, identity)
+    }
+
+    fn username_from_key<'a>(&self, key: &'a str) -> Option<&'a str> {
+        let (prefix, suffix) = self.user_key.split_once(USERNAME_PLACEHOLDER)?;
+        key.strip_prefix(prefix)?.strip_suffix(suffix)
+    }
+
+    async fn read_acl_list(
+        &self,
+        conn: &mut MultiplexedConnection,
+        key: &str,
+    ) -> Result<Vec<MqttAcl>, MqttBrokerError> {
+        let data: Vec<Vec<u8>> = conn.lrange(key, 0, -1).await?;
+        let mut results = Vec::with_capacity(data.len());
+        for raw in data {
+            results.push(MqttAcl::decode(&raw)?);
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl AuthStorageAdapter for RedisAuthStorageAdapter {
+    async fn read_all_user(&self) -> Result<DashMap<String, MqttUser>, MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let results = DashMap::with_capacity(2);
+        for key in scan_keys(&mut conn, &self.user_key).await? {
+            let Some(username) = self.username_from_key(&key) else {
+                continue;
+            };
+            let data: HashMap<String, String> = conn.hgetall(&key).await?;
+            if let Some(user) = build_user(username, data)? {
+                results.insert(user.username.clone(), user);
+            }
+        }
+        return Ok(results);
+    }
+
+    async fn read_all_acl(&self) -> Result<Vec<MqttAcl>, MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let mut results = Vec::new();
+        for key in scan_keys(&mut conn, &self.acl_key).await? {
+            results.extend(self.read_acl_list(&mut conn, &key).await?);
+        }
+        return Ok(results);
+    }
+
+    async fn read_all_blacklist(&self) -> Result<Vec<MqttAclBlackList>, MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let mut results = Vec::new();
+        for key in scan_keys(&mut conn, &self.blacklist_key).await? {
+            let data: Option<Vec<u8>> = conn.get(&key).await?;
+            if let Some(raw) = data {
+                results.push(MqttAclBlackList::decode(&raw)?);
+            }
+        }
+        return Ok(results);
+    }
+
+    async fn get_user(&self, username: String) -> Result<Option<MqttUser>, MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let data: HashMap<String, String> = conn.hgetall(self.user_key(&username)).await?;
+        return build_user(&username, data);
+    }
+
+    async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let is_superuser = if user_info.is_superuser { "1" } else { "0" };
+        let fields = [
+            ("password", user_info.password.clone()),
+            ("salt", user_info.salt.clone()),
+            ("is_superuser", is_superuser.to_string()),
+            ("hash_algorithm", user_info.hash_algorithm.to_string()),
+        ];
+        let _: () = conn
+            .hset_multiple(self.user_key(&user_info.username), &fields)
+            .await?;
+        return Ok(());
+    }
+
+    async fn delete_user(&self, username: String) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let _: () = conn.del(self.user_key(&username)).await?;
+        return Ok(());
+    }
+
+    async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let _: i64 = redis::cmd("EVAL")
+            .arg(SAVE_ACL_SCRIPT)
+            .arg(1)
+            .arg(self.acl_key(&acl))
+            .arg(acl.encode()?)
+            .query_async(&mut conn)
+            .await?;
+        return Ok(());
+    }
+
+    async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let _: i64 = redis::cmd("EVAL")
+            .arg(DELETE_ACL_SCRIPT)
+            .arg(1)
+            .arg(self.acl_key(&acl))
+            .arg(acl.encode()?)
+            .query_async(&mut conn)
+            .await?;
+        return Ok(());
+    }
+
+    async fn save_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let _: () = conn
+            .set(self.blacklist_key(&blacklist), blacklist.encode()?)
+            .await?;
+        return Ok(());
+    }
+
+    async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let _: () = conn.del(self.blacklist_key(&blacklist)).await?;
+        return Ok(());
+    }
+
+    async fn read_all_psk(&self) -> Result<Vec<MqttPsk>, MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let mut results = Vec::new();
+        for key in scan_keys(&mut conn, &self.psk_key).await? {
+            let data: Option<Vec<u8>> = conn.get(&key).await?;
+            if let Some(raw) = data {
+                results.push(MqttPsk::decode(&raw)?);
+            }
+        }
+        return Ok(results);
+    }
+
+    async fn save_psk(&self, psk: MqttPsk) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let _: () = conn.set(self.psk_key(&psk.identity), psk.encode()?).await?;
+        return Ok(());
+    }
+
+    async fn delete_psk(&self, identity: String) -> Result<(), MqttBrokerError> {
+        let mut conn = self.connection().await?;
+        let _: () = conn.del(self.psk_key(&identity)).await?;
+        return Ok(());
+    }
+}
+
+fn build_key_layout(name: &str, layout: &str, default: &str) -> Result<String, CommonError> {
+    if layout.is_empty() {
+        return Ok(default.to_string());
+    }
+    if !layout.contains("${") {
+        return Err(CommonError::InvalidParameterFormat(
+            name.to_string(),
+            layout.to_string(),
+        ));
+    }
+    Ok(layout.to_string())
+}
+
+/// SCAN of one layout must not return the keys of another, the users would otherwise be read
+/// from the ACL lists and fail with WRONGTYPE, so the fixed prefixes may not overlap
+fn check_key_prefixes(layouts: &[(&str, &String)]) -> Result<(), CommonError> {
+    for (index, (name, layout)) in layouts.iter().enumerate() {
+        let prefix = key_prefix(layout);
+        for (_, other) in &layouts[..index] {
+            let other_prefix = key_prefix(other);
+            if prefix.starts_with(other_prefix) || other_prefix.starts_with(prefix) {
+                return Err(CommonError::InvalidParameterFormat(
+                    name.to_string(),
+                    layout.to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn key_prefix(layout: &str) -> &str {
+    match layout.find("${") {
+        Some(index) => &layout[..index],
+        None => layout,
+    }
+}
+
+/// the SCAN pattern of a key layout, every placeholder matches anything
+fn key_pattern(layout: &str) -> String {
+    let mut pattern = String::new();
+    let mut rest = layout;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        pattern.push_str(&rest[..start]);
+        pattern.push('*');
+        rest = &rest[start + end + 1..];
+    }
+    pattern.push_str(rest);
+    pattern
+}
+
+async fn scan_keys(
+    conn: &mut MultiplexedConnection,
+    layout: &str,
+) -> Result<Vec<String>, MqttBrokerError> {
+    let mut iter: AsyncIter<String> = conn.scan_match(key_pattern(layout)).await?;
+    let mut keys = Vec::new();
+    while let Some(key) = iter.next_item().await {
+        keys.push(key);
+    }
+    // SCAN may return a key more than once
+    keys.sort();
+    keys.dedup();
+    Ok(keys)
+}
+
+fn build_user(
+    username: &str,
+    mut data: HashMap<String, String>,
+) -> Result<Option<MqttUser>, MqttBrokerError> {
+    let Some(password) = data.remove("password") else {
+        return Ok(None);
+    };
+    let is_superuser = data.get("is_superuser").map(String::as_str);
+    Ok(Some(MqttUser {
+        username: username.to_string(),
+        password,
+        salt: data.remove("salt").unwrap_or_default(),
+        is_superuser: matches!(is_superuser, Some("1") | Some("true")),
+        hash_algorithm: build_password_hash_algorithm(
+            data.get("hash_algorithm")
+                .map(String::as_str)
+                .unwrap_or_default(),
+        )?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use common_base::config::common::Auth;
+    use common_base::tools::{now_second, unique_id};
+    use metadata_struct::acl::mqtt_acl::{
+        MqttAcl, MqttAclAction, MqttAclPermission, MqttAclResourceType,
+    };
+    use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
+    use metadata_struct::mqtt::psk::MqttPsk;
+    use metadata_struct::mqtt::user::{MqttUser, PasswordHashAlgorithm};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::{key_pattern, RedisAuthStorageAdapter, DELETE_ACL_SCRIPT, SAVE_ACL_SCRIPT};
+    use crate::security::AuthStorageAdapter;
+
+    enum Entry {
+        String(Vec<u8>),
+        Hash(HashMap<String, Vec<u8>>),
+        List(Vec<Vec<u8>>),
+    }
+
+    type Store = Arc<Mutex<HashMap<String, Entry>>>;
+
+    // a RESP server in the test process that understands the commands of the adapter
+    async fn start_resp_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let store: Store = Arc::new(Mutex::new(HashMap::new()));
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_resp(stream, store.clone()));
+            }
+        });
+        format!("redis://{}", addr)
+    }
+
+    async fn serve_resp(stream: TcpStream, store: Store) {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut queued: Option<Vec<Vec<Vec<u8>>>> = None;
+        while let Some(args) = read_command(&mut reader).await {
+            let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+            let reply = if name == "MULTI" {
+                queued = Some(Vec::new());
+                b"+OK\r\n".to_vec()
+            } else if name == "EXEC" {
+                let commands = queued.take().unwrap_or_default();
+                let mut reply = format!("*{}\r\n", commands.len()).into_bytes();
+                for command in commands {
+                    reply.extend(execute(&store, command));
+                }
+                reply
+            } else if let Some(commands) = queued.as_mut() {
+                commands.push(args);
+                b"+QUEUED\r\n".to_vec()
+            } else {
+                execute(&store, args)
+            };
+            if writer.write_all(&reply).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    async fn read_command(
+        reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+    ) -> Option<Vec<Vec<u8>>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.ok()? == 0 {
+            return None;
+        }
+        let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).await.ok()?;
+            let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+            let mut arg = vec![0u8; len + 2];
+            reader.read_exact(&mut arg).await.ok()?;
+            arg.truncate(len);
+            args.push(arg);
+        }
+        Some(args)
+    }
+
+    fn execute(store: &Store, args: Vec<Vec<u8>>) -> Vec<u8> {
+        let mut store = store.lock().unwrap();
+        let text = |raw: &Vec<u8>| String::from_utf8_lossy(raw).to_string();
+        let key = args.get(1).map(text).unwrap_or_default();
+        let name = text(&args[0]).to_uppercase();
+        match name.as_str() {
+            "CLIENT" | "SELECT" | "PING" => b"+OK\r\n".to_vec(),
+            "SET" => {
+                store.insert(key, Entry::String(args[2].clone()));
+                b"+OK\r\n".to_vec()
+            }
+            "GET" => match store.get(&key) {
+                Some(Entry::String(value)) => bulk(value),
+                _ => b"$-1\r\n".to_vec(),
+            },
+            "DEL" => {
+                let count = args[1..]
+                    .iter()
+                    .map(text)
+                    .filter(|key| store.remove(key).is_some())
+                    .count();
+                format!(":{}\r\n", count).into_bytes()
+            }
+            "HSET" | "HMSET" => {
+                let entry = store
+                    .entry(key)
+                    .or_insert_with(|| Entry::Hash(HashMap::new()));
+                if let Entry::Hash(hash) = entry {
+                    for pair in args[2..].chunks(2) {
+                        hash.insert(text(&pair[0]), pair[1].clone());
+                    }
+                }
+                b"+OK\r\n".to_vec()
+            }
+            "HGETALL" => match store.get(&key) {
+                Some(Entry::Hash(hash)) => {
+                    let mut reply = format!("*{}\r\n", hash.len() * 2).into_bytes();
+                    for (field, value) in hash {
+                        reply.extend(bulk(field.as_bytes()));
+                        reply.extend(bulk(value));
+                    }
+                    reply
+                }
+                _ => b"*0\r\n".to_vec(),
+            },
+            "RPUSH" => {
+                let entry = store.entry(key).or_insert_with(|| Entry::List(Vec::new()));
+                let mut len = 0;
+                if let Entry::List(list) = entry {
+                    list.extend(args[2..].iter().cloned());
+                    len = list.len();
+                }
+                format!(":{}\r\n", len).into_bytes()
+            }
+            "LRANGE" => match store.get(&key) {
+                Some(Entry::List(list)) => {
+                    let mut reply = format!("*{}\r\n", list.len()).into_bytes();
+                    for value in list {
+                        reply.extend(bulk(value));
+                    }
+                    reply
+                }
+                _ => b"*0\r\n".to_vec(),
+            },
+            // the Rust equivalent of the scripts of the adapter
+            "EVAL" => {
+                let rule = MqttAcl::decode(&args[4]).unwrap();
+                let is_same = |raw: &Vec<u8>| is_same_acl(&MqttAcl::decode(raw).unwrap(), &rule);
+                let key = text(&args[3]);
+                let entry = store
+                    .entry(key.clone())
+                    .or_insert_with(|| Entry::List(Vec::new()));
+                let Entry::List(list) = entry else {
+                    return b"-WRONGTYPE\r\n".to_vec();
+                };
+                let count = if text(&args[1]) == SAVE_ACL_SCRIPT {
+                    if let Some(index) = list.iter().position(is_same) {
+                        list[index] = args[4].clone();
+                        0
+                    } else {
+                        list.push(args[4].clone());
+                        1
+                    }
+                } else if text(&args[1]) == DELETE_ACL_SCRIPT {
+                    let len = list.len();
+                    list.retain(|raw| !is_same(raw));
+                    len - list.len()
+                } else {
+                    return b"-ERR unknown script\r\n".to_vec();
+                };
+                if list.is_empty() {
+                    store.remove(&key);
+                }
+                format!(":{}\r\n", count).into_bytes()
+            }
+            "SCAN" => {
+                let pattern = args
+                    .iter()
+                    .position(|raw| text(raw).eq_ignore_ascii_case("MATCH"))
+                    .map(|index| text(&args[index + 1]))
+                    .unwrap_or("*".to_string());
+                let keys: Vec<&String> = store
+                    .keys()
+                    .filter(|key| glob_match(&pattern, key))
+                    .collect();
+                let mut reply = format!("*2\r\n$1\r\n0\r\n*{}\r\n", keys.len()).into_bytes();
+                for key in keys {
+                    reply.extend(bulk(key.as_bytes()));
+                }
+                reply
+            }
+            _ => format!("-ERR unknown command '{}'\r\n", name).into_bytes(),
+        }
+    }
+
+    fn is_same_acl(raw: &MqttAcl, acl: &MqttAcl) -> bool {
+        raw.permission == acl.permission
+            && raw.action == acl.action
+            && raw.topic == acl.topic
+            && raw.ip == acl.ip
+    }
+
+    fn bulk(value: &[u8]) -> Vec<u8> {
+        let mut reply = format!("${}\r\n", value.len()).into_bytes();
+        reply.extend_from_slice(value);
+        reply.extend_from_slice(b"\r\n");
+        reply
+    }
+
+    fn glob_match(pattern: &str, key: &str) -> bool {
+        match pattern.split_once('*') {
+            None => pattern == key,
+            Some((prefix, rest)) => {
+                let Some(key) = key.strip_prefix(prefix) else {
+                    return false;
+                };
+                (0..=key.len())
+                    .filter(|index| key.is_char_boundary(*index))
+                    .any(|index| glob_match(rest, &key[index..]))
+            }
+        }
+    }
+
+    async fn build_adapter() -> RedisAuthStorageAdapter {
+        let auth = Auth {
+            redis_addr: start_resp_server().await,
+            ..Default::default()
+        };
+        RedisAuthStorageAdapter::new(&auth).unwrap()
+    }
+
+    fn build_acl(resource_name: &str, topic: &str) -> MqttAcl {
+        MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: resource_name.to_string(),
+            topic: topic.to_string(),
+            ip: "*".to_string(),
+            action: MqttAclAction::Publish,
+            permission: MqttAclPermission::Deny,
+            priority: 0,
+        }
+    }
+
+    #[test]
+    fn key_pattern_test() {
+        assert_eq!(key_pattern("mqtt_user:${username}"), "mqtt_user:*");
+        assert_eq!(
+            key_pattern("mqtt_acl:${resource_type}:${resource_name}"),
+            "mqtt_acl:*:*"
+        );
+        assert_eq!(key_pattern("{app}:${username}:auth"), "{app}:*:auth");
+    }
+
+    #[test]
+    fn key_layout_test() {
+        let auth = Auth {
+            redis_addr: "redis://127.0.0.1:6379".to_string(),
+            redis_user_key: "mqtt_user".to_string(),
+            ..Default::default()
+        };
+        assert!(RedisAuthStorageAdapter::new(&auth).is_err());
+
+        let auth = Auth {
+            redis_addr: "redis://127.0.0.1:6379".to_string(),
+            redis_user_key: "app:${username}:auth".to_string(),
+            ..Default::default()
+        };
+        let adapter = RedisAuthStorageAdapter::new(&auth).unwrap();
+        assert_eq!(adapter.user_key("lobo"), "app:lobo:auth");
+        assert_eq!(adapter.username_from_key("app:lobo:auth"), Some("lobo"));
+        assert_eq!(adapter.username_from_key("mqtt_user:lobo"), None);
+        assert_eq!(
+            adapter.acl_key(&build_acl("lobo", "t1")),
+            "mqtt_acl:User:lobo"
+        );
+
+        // SCAN of the users would return the ACL lists
+        let auth = Auth {
+            redis_addr: "redis://127.0.0.1:6379".to_string(),
+            redis_user_key: "mqtt:${username}".to_string(),
+            redis_acl_key: "mqtt:acl:${resource_type}:${resource_name}".to_string(),
+            ..Default::default()
+        };
+        assert!(RedisAuthStorageAdapter::new(&auth).is_err());
+    }
+
+    #[tokio::test]
+    async fn user_test() {
+        let adapter = build_adapter().await;
+        let user = MqttUser {
+            username: "lobo".to_string(),
+            password: "hash".to_string(),
+            salt: "salt".to_string(),
+            hash_algorithm: PasswordHashAlgorithm::Sha256,
+            is_superuser: true,
+        };
+        adapter.save_user(user.clone()).await.unwrap();
+        adapter
+            .save_user(MqttUser {
+                username: "robustmq".to_string(),
+                password: "pwd123".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            adapter.get_user("lobo".to_string()).await.unwrap(),
+            Some(user.clone())
+        );
+        assert_eq!(adapter.get_user("nobody".to_string()).await.unwrap(), None);
+
+        let users = adapter.read_all_user().await.unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(users.get("lobo").unwrap().clone(), user);
+        assert!(!users.get("robustmq").unwrap().is_superuser);
+
+        adapter.delete_user("lobo".to_string()).await.unwrap();
+        assert_eq!(adapter.get_user("lobo".to_string()).await.unwrap(), None);
+        assert_eq!(adapter.read_all_user().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn acl_test() {
+        let adapter = build_adapter().await;
+        adapter.save_acl(build_acl("lobo", "t1")).await.unwrap();
+        adapter.save_acl(build_acl("lobo", "t2")).await.unwrap();
+        adapter.save_acl(build_acl("lobo", "t3")).await.unwrap();
+        // a duplicate rule is not saved again, its new priority replaces the stored one
+        let mut acl = build_acl("lobo", "t1");
+        acl.priority = 5;
+        adapter.save_acl(acl).await.unwrap();
+        adapter.save_acl(build_acl("robustmq", "t1")).await.unwrap();
+
+        let acl_list = adapter.read_all_acl().await.unwrap();
+        assert_eq!(acl_list.len(), 4);
+        let rules: Vec<(&str, u32)> = acl_list
+            .iter()
+            .filter(|acl| acl.resource_name == "lobo")
+            .map(|acl| (acl.topic.as_str(), acl.priority))
+            .collect();
+        assert_eq!(rules, vec![("t1", 5), ("t2", 0), ("t3", 0)]);
+
+        // the remaining rules keep their order
+        adapter.delete_acl(build_acl("lobo", "t2")).await.unwrap();
+        let topics: Vec<String> = adapter
+            .read_all_acl()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|acl| acl.resource_name == "lobo")
+            .map(|acl| acl.topic)
+            .collect();
+        assert_eq!(topics, vec!["t1".to_string(), "t3".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn blacklist_test() {
+        let adapter = build_adapter().await;
+        let blacklist = MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::ClientId,
+            resource_name: "c1".to_string(),
+            end_time: now_second() + 100,
+            desc: "flapping".to_string(),
+        };
+        adapter.save_blacklist(blacklist.clone()).await.unwrap();
+        assert_eq!(
+            adapter.read_all_blacklist().await.unwrap(),
+            vec![blacklist.clone()]
+        );
+
+        adapter.delete_blacklist(blacklist).await.unwrap();
+        assert!(adapter.read_all_blacklist().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn psk_test() {
+        let adapter = build_adapter().await;
+        let psk = MqttPsk {
+            identity: "sensor-1".to_string(),
+            psk: "0123456789abcdef".to_string(),
+            create_time: now_second(),
+        };
+        adapter.save_psk(psk.clone()).await.unwrap();
+        assert_eq!(adapter.read_all_psk().await.unwrap(), vec![psk]);
+
+        adapter.delete_psk("sensor-1".to_string()).await.unwrap();
+        assert!(adapter.read_all_psk().await.unwrap().is_empty());
+    }
+
+    async fn read_rules(
+        adapter: &RedisAuthStorageAdapter,
+        resource_name: &str,
+    ) -> Vec<(String, u32)> {
+        adapter
+            .read_all_acl()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|acl| acl.resource_name == resource_name)
+            .map(|acl| (acl.topic, acl.priority))
+            .collect()
+    }
+
+    // the RESP server above only imitates the Lua scripts, this runs them on a real Redis
+    #[tokio::test]
+    #[ignore]
+    async fn acl_script_test() {
+        let auth = Auth {
+            redis_addr: "redis://127.0.0.1:6379".to_string(),
+            ..Default::default()
+        };
+        let adapter = RedisAuthStorageAdapter::new(&auth).unwrap();
+        let resource_name = format!("acl-script-{}", unique_id());
+        for topic in ["t1", "t2", "t3"] {
+            adapter
+                .save_acl(build_acl(&resource_name, topic))
+                .await
+                .unwrap();
+        }
+        let mut acl = build_acl(&resource_name, "t2");
+        acl.priority = 5;
+        adapter.save_acl(acl).await.unwrap();
+        assert_eq!(
+            read_rules(&adapter, &resource_name).await,
+            vec![
+                ("t1".to_string(), 0),
+                ("t2".to_string(), 5),
+                ("t3".to_string(), 0)
+            ]
+        );
+
+        adapter
+            .delete_acl(build_acl(&resource_name, "t1"))
+            .await
+            .unwrap();
+        assert_eq!(
+            read_rules(&adapter, &resource_name).await,
+            vec![("t2".to_string(), 5), ("t3".to_string(), 0)]
+        );
+
+        for topic in ["t2", "t3"] {
+            adapter
+                .delete_acl(build_acl(&resource_name, topic))
+                .await
+                .unwrap();
+        }
+        assert!(read_rules(&adapter, &resource_name).await.is_empty());
+    }
+}
@@ -20,6 +20,9 @@
 use protocol::mqtt::common::MqttProtocol;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
+
+use crate::security::login::x509::X509Identity;
+
 static CONNECTION_ID_BUILD: AtomicU64 = AtomicU64::new(1);
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
@@ -53,6 +56,10 @@ pub struct NetworkConnection {
     pub connection_id: u64,
     pub protocol: Option<MqttProtocol>,
     pub addr: SocketAddr,
+    // the verified client certificate of a mutual TLS connection
+    pub peer_cert: Option<X509Identity>,
+    // the identity of a TLS-PSK connection
+    pub psk_identity: Option<String>,
     #[serde(skip_serializing, skip_deserializing)]
     pub connection_stop_sx: Option<mpsc::Sender<bool>>,
 }
@@ -69,6 +76,8 @@ pub fn new(
             connection_id,
             protocol: None,
             addr,
+            peer_cert: None,
+            psk_identity: None,
             connection_stop_sx,
         }
     }
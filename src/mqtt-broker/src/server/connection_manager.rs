@@ -27,23 +27,20 @@
 
 use super::connection::{NetworkConnection, NetworkConnectionType};
 use crate::handler::cache::CacheManager;
+use crate::handler::enhanced_auth::EnhancedAuthExchange;
 use crate::handler::error::MqttBrokerError;
 use crate::observability::metrics::packets::record_sent_metrics;
 use crate::server::quic::quic_stream_wrapper::QuicFramedWriteStream;
+use crate::server::tcp::tls_server::TlsServerStream;
 
 pub struct ConnectionManager {
     connections: DashMap<u64, NetworkConnection>,
     tcp_write_list:
         DashMap<u64, FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, MqttCodec>>,
-    tcp_tls_write_list: DashMap<
-        u64,
-        FramedWrite<
-            tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-            MqttCodec,
-        >,
-    >,
+    tcp_tls_write_list: DashMap<u64, FramedWrite<tokio::io::WriteHalf<TlsServerStream>, MqttCodec>>,
     websocket_write_list: DashMap<u64, SplitSink<WebSocket, Message>>,
     quic_write_list: DashMap<u64, QuicFramedWriteStream>,
+    enhanced_auth_list: DashMap<u64, EnhancedAuthExchange>,
     cache_manager: Arc<CacheManager>,
 }
 
@@ -54,6 +51,7 @@ pub fn new(cache_manager: Arc<CacheManager>) -> ConnectionManager {
         let tcp_tls_write_list = DashMap::with_capacity(64);
         let websocket_write_list = DashMap::with_capacity(64);
         let quic_write_list = DashMap::with_capacity(64);
+        let enhanced_auth_list = DashMap::with_capacity(8);
         ConnectionManager {
             connections,
             tcp_write_list,
@@ -61,6 +59,7 @@ pub fn new(cache_manager: Arc<CacheManager>) -> ConnectionManager {
             cache_manager,
             websocket_write_list,
             quic_write_list,
+            enhanced_auth_list,
         }
     }
 
@@ -84,10 +83,7 @@ pub fn add_tcp_write(
     pub fn add_tcp_tls_write(
         &self,
         connection_id: u64,
-        write: FramedWrite<
-            tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-            MqttCodec,
-        >,
+        write: FramedWrite<tokio::io::WriteHalf<TlsServerStream>, MqttCodec>,
     ) {
         self.tcp_tls_write_list.insert(connection_id, write);
     }
@@ -105,6 +101,16 @@ pub fn add_quic_write(
             .insert(connection_id, quic_framed_write_stream);
     }
 
+    pub fn add_enhanced_auth(&self, connection_id: u64, exchange: EnhancedAuthExchange) {
+        self.enhanced_auth_list.insert(connection_id, exchange);
+    }
+
+    pub fn take_enhanced_auth(&self, connection_id: u64) -> Option<EnhancedAuthExchange> {
+        self.enhanced_auth_list
+            .remove(&connection_id)
+            .map(|(_, exchange)| exchange)
+    }
+
     pub async fn close_all_connect(&self) {
         for (connect_id, _) in self.connections.clone() {
             self.close_connect(connect_id).await;
@@ -115,6 +121,7 @@ pub async fn close_connect(&self, connection_id: u64) {
         if let Some((_, connection)) = self.connections.remove(&connection_id) {
             connection.stop_connection().await;
         }
+        self.enhanced_auth_list.remove(&connection_id);
 
         if let Some((id, mut stream)) = self.tcp_write_list.remove(&connection_id) {
             if stream.close().await.is_ok() {
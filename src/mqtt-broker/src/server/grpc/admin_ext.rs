@@ -0,0 +1,177 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use grpc_clients::pool::ClientPool;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::mqtt_broker_admin_ext_service_server::MqttBrokerAdminExtService;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::{
+    MqttCheckAclReply, MqttCheckAclRequest, MqttCleanSessionReply, MqttCleanSessionRequest,
+    MqttCreatePskReply, MqttCreatePskRequest, MqttDeletePskReply, MqttDeletePskRequest,
+    MqttDeleteRetainMessageReply, MqttDeleteRetainMessageRequest, MqttGetClientDetailReply,
+    MqttGetClientDetailRequest, MqttGetRetainMessageReply, MqttGetRetainMessageRequest,
+    MqttKickClientReply, MqttKickClientRequest, MqttListPskReply, MqttListPskRequest,
+    MqttListRetainMessageReply, MqttListRetainMessageRequest, MqttListSessionReply,
+    MqttListSessionRequest, MqttRetainMessageStatsReply, MqttRetainMessageStatsRequest,
+};
+use tonic::{Request, Response, Status};
+
+use crate::admin::acl::mqtt_check_acl_by_req;
+use crate::admin::psk::{mqtt_create_psk_by_req, mqtt_delete_psk_by_req, mqtt_list_psk_by_req};
+use crate::admin::retain::{
+    mqtt_delete_retain_message_by_req, mqtt_get_retain_message_by_req,
+    mqtt_list_retain_message_by_req, mqtt_retain_message_stats_by_req,
+};
+use crate::admin::session::{
+    mqtt_clean_session_by_req, mqtt_get_client_detail_by_req, mqtt_kick_client_by_req,
+    mqtt_list_session_by_req,
+};
+use crate::handler::cache::CacheManager;
+use crate::server::connection_manager::ConnectionManager;
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+pub struct GrpcAdminExtServices {
+    client_pool: Arc<ClientPool>,
+    cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+}
+
+impl GrpcAdminExtServices {
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        cache_manager: Arc<CacheManager>,
+        connection_manager: Arc<ConnectionManager>,
+        subscribe_manager: Arc<SubscribeManager>,
+    ) -> Self {
+        GrpcAdminExtServices {
+            client_pool,
+            cache_manager,
+            connection_manager,
+            subscribe_manager,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl MqttBrokerAdminExtService for GrpcAdminExtServices {
+    // --- acl ---
+    async fn mqtt_check_acl(
+        &self,
+        request: Request<MqttCheckAclRequest>,
+    ) -> Result<Response<MqttCheckAclReply>, Status> {
+        mqtt_check_acl_by_req(&self.cache_manager, request)
+    }
+
+    // --- psk ---
+    async fn mqtt_list_psk(
+        &self,
+        _: Request<MqttListPskRequest>,
+    ) -> Result<Response<MqttListPskReply>, Status> {
+        mqtt_list_psk_by_req(&self.cache_manager, &self.client_pool).await
+    }
+
+    async fn mqtt_create_psk(
+        &self,
+        request: Request<MqttCreatePskRequest>,
+    ) -> Result<Response<MqttCreatePskReply>, Status> {
+        mqtt_create_psk_by_req(&self.cache_manager, &self.client_pool, request).await
+    }
+
+    async fn mqtt_delete_psk(
+        &self,
+        request: Request<MqttDeletePskRequest>,
+    ) -> Result<Response<MqttDeletePskReply>, Status> {
+        mqtt_delete_psk_by_req(&self.cache_manager, &self.client_pool, request).await
+    }
+
+    // --- session ---
+    async fn mqtt_list_session(
+        &self,
+        _: Request<MqttListSessionRequest>,
+    ) -> Result<Response<MqttListSessionReply>, Status> {
+        mqtt_list_session_by_req(&self.client_pool).await
+    }
+
+    async fn mqtt_get_client_detail(
+        &self,
+        request: Request<MqttGetClientDetailRequest>,
+    ) -> Result<Response<MqttGetClientDetailReply>, Status> {
+        mqtt_get_client_detail_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            request,
+        )
+        .await
+    }
+
+    async fn mqtt_kick_client(
+        &self,
+        request: Request<MqttKickClientRequest>,
+    ) -> Result<Response<MqttKickClientReply>, Status> {
+        mqtt_kick_client_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            request,
+        )
+        .await
+    }
+
+    async fn mqtt_clean_session(
+        &self,
+        request: Request<MqttCleanSessionRequest>,
+    ) -> Result<Response<MqttCleanSessionReply>, Status> {
+        mqtt_clean_session_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            request,
+        )
+        .await
+    }
+
+    // --- retain ---
+    async fn mqtt_list_retain_message(
+        &self,
+        request: Request<MqttListRetainMessageRequest>,
+    ) -> Result<Response<MqttListRetainMessageReply>, Status> {
+        mqtt_list_retain_message_by_req(&self.client_pool, request).await
+    }
+
+    async fn mqtt_get_retain_message(
+        &self,
+        request: Request<MqttGetRetainMessageRequest>,
+    ) -> Result<Response<MqttGetRetainMessageReply>, Status> {
+        mqtt_get_retain_message_by_req(&self.client_pool, request).await
+    }
+
+    async fn mqtt_delete_retain_message(
+        &self,
+        request: Request<MqttDeleteRetainMessageRequest>,
+    ) -> Result<Response<MqttDeleteRetainMessageReply>, Status> {
+        mqtt_delete_retain_message_by_req(&self.cache_manager, &self.client_pool, request).await
+    }
+
+    async fn mqtt_retain_message_stats(
+        &self,
+        request: Request<MqttRetainMessageStatsRequest>,
+    ) -> Result<Response<MqttRetainMessageStatsReply>, Status> {
+        mqtt_retain_message_stats_by_req(&self.client_pool, request).await
+    }
+}
@@ -16,7 +16,7 @@
 
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
-use log::{debug, info};
+use log::{debug, error, info};
 use metadata_struct::mqtt::lastwill::LastWillData;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_server::MqttBrokerInnerService;
 use protocol::broker_mqtt::broker_mqtt_inner::{
@@ -30,6 +30,7 @@
 use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
 use crate::handler::cache_update::update_cache_metadata;
+use crate::handler::inflight::inflight_clear;
 use crate::handler::lastwill::send_last_will_message;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
@@ -104,6 +105,13 @@ async fn delete_session(
         for client_id in req.client_id {
             self.subscribe_manager.remove_client_id(&client_id);
             self.cache_manager.remove_session(&client_id);
+            if let Err(e) = inflight_clear(&self.cache_manager, &self.client_pool, &client_id).await
+            {
+                error!(
+                    "Failed to clear the inflight window of the expired session {}, error message: {}",
+                    client_id, e
+                );
+            }
         }
 
         return Ok(Response::new(DeleteSessionReply::default()));
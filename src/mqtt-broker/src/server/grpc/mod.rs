@@ -13,5 +13,6 @@
 // limitations under the License.
 
 mod admin;
+mod admin_ext;
 mod inner;
 pub mod server;
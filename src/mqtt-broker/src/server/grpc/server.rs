@@ -18,11 +18,13 @@
 use grpc_clients::pool::ClientPool;
 use log::info;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::MqttBrokerAdminServiceServer;
+use protocol::broker_mqtt::broker_mqtt_admin_ext::mqtt_broker_admin_ext_service_server::MqttBrokerAdminExtServiceServer;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_server::MqttBrokerInnerServiceServer;
 use schema_register::schema::SchemaRegisterManager;
 use storage_adapter::storage::StorageAdapter;
 use tonic::transport::Server;
 
+use super::admin_ext::GrpcAdminExtServices;
 use super::inner::GrpcInnerServices;
 use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
@@ -83,12 +85,19 @@ pub async fn start(&self) -> Result<(), CommonError> {
             self.metadata_cache.clone(),
             self.connection_manager.clone(),
         );
+        let admin_ext_handler = GrpcAdminExtServices::new(
+            self.client_pool.clone(),
+            self.metadata_cache.clone(),
+            self.connection_manager.clone(),
+            self.subscribe_manager.clone(),
+        );
         Server::builder()
             .accept_http1(true)
             .layer(tower_http::cors::CorsLayer::very_permissive())
             .layer(tonic_web::GrpcWebLayer::new())
             .add_service(MqttBrokerInnerServiceServer::new(inner_handler))
             .add_service(MqttBrokerAdminServiceServer::new(admin_handler))
+            .add_service(MqttBrokerAdminExtServiceServer::new(admin_ext_handler))
             .serve(addr)
             .await?;
         Ok(())
@@ -0,0 +1,862 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::bridge::connector::MQTTConnector;
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
+use metadata_struct::mqtt::connection::MQTTConnection;
+use metadata_struct::mqtt::psk::MqttPsk;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+use metadata_struct::mqtt::user::MqttUser;
+use metadata_struct::schema::SchemaData;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    CreateTopicRewriteRuleRequest, DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest,
+    DeleteTopicRewriteRuleRequest, MqttBindSchemaRequest, MqttConnectorType,
+    MqttCreateConnectorRequest, MqttCreateSchemaRequest, MqttDeleteConnectorRequest,
+    MqttDeleteSchemaRequest, MqttListBindSchemaRequest, MqttListConnectorRequest,
+    MqttListSchemaRequest, MqttUnbindSchemaRequest, MqttUpdateConnectorRequest,
+    MqttUpdateSchemaRequest, SetAutoSubscribeRuleRequest,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tonic::Request;
+
+use super::response::{paginate, HttpError, HttpResult, PageQuery, PageReply};
+use super::server::HttpServerState;
+use crate::admin::acl::{check_acl_by_req, delete_blacklist_by_req, CheckAclRequest};
+use crate::admin::cluster_status_by_req;
+use crate::admin::connector::{
+    create_connector_by_req, delete_connector_by_req, list_connector_by_req,
+    update_connector_by_req,
+};
+use crate::admin::psk::{
+    create_psk_by_req, delete_psk_by_req, list_psk_by_req, CreatePskRequest, DeletePskRequest,
+};
+use crate::admin::retain::{
+    delete_retain_message_by_req, get_retain_message_by_req, list_retain_message_by_req,
+    retain_message_stats_by_req, DeleteRetainMessageRequest, DeleteRetainMessageResult,
+    RetainMessageDetail, RetainMessageRaw, RetainMessageStats,
+};
+use crate::admin::session::{
+    clean_session_by_req, get_client_detail_by_req, get_session_by_req, kick_client_by_req,
+    list_session_by_req, CleanSessionRequest, ClientDetail, KickClientRequest,
+};
+use crate::admin::subscribe;
+use crate::admin::topic::{create_topic_rewrite_rule_by_req, delete_topic_rewrite_rule_by_req};
+use crate::security::acl::auth::AclCheckResult;
+use crate::security::AuthDriver;
+use crate::server::connection::NetworkConnection;
+use crate::storage::schema::{
+    bind_schema_by_req, create_schema_by_req, delete_schema_by_req, list_bind_schema_by_req,
+    list_schema_by_req, unbind_schema_by_req, update_schema_by_req,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClusterStatus {
+    pub cluster_name: String,
+    pub nodes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UserBody {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub is_superuser: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConnectionRaw {
+    pub network: NetworkConnection,
+    pub mqtt: MQTTConnection,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SessionQuery {
+    // sessions whose client id contains it, all sessions when empty
+    #[serde(default)]
+    pub client_id: String,
+}
+
+/// `?topic_name=&match_option=`, `match_option` is exact, prefix or contains (the default)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TopicQuery {
+    #[serde(default)]
+    pub topic_name: String,
+    #[serde(default)]
+    pub match_option: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TopicRaw {
+    pub topic_id: String,
+    pub topic_name: String,
+    pub cluster_name: String,
+    pub is_contain_retain_message: bool,
+    pub create_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RetainMessageQuery {
+    // a topic name or a filter with + and #, all retained messages when empty
+    #[serde(default)]
+    pub topic_filter: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetainMessageKey {
+    pub topic_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteRetainMessageReply {
+    pub deleted: usize,
+    // one entry per matching topic, including the ones that failed
+    pub results: Vec<DeleteRetainMessageResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TopicRewriteRuleBody {
+    pub action: String,
+    pub source_topic: String,
+    #[serde(default)]
+    pub dest_topic: String,
+    #[serde(default)]
+    pub regex: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConnectorQuery {
+    #[serde(default)]
+    pub connector_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectorBody {
+    pub connector_name: String,
+    // file or kafka
+    pub connector_type: String,
+    pub config: String,
+    pub topic_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SchemaQuery {
+    #[serde(default)]
+    pub schema_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SchemaBody {
+    // taken from the path on update
+    #[serde(default)]
+    pub schema_name: String,
+    // json, avro or protobuf, empty means json
+    #[serde(default)]
+    pub schema_type: String,
+    pub schema: String,
+    #[serde(default)]
+    pub desc: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SchemaBindBody {
+    #[serde(default)]
+    pub schema_name: String,
+    #[serde(default)]
+    pub resource_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AutoSubscribeRuleBody {
+    pub topic: String,
+    #[serde(default)]
+    pub qos: u32,
+    #[serde(default)]
+    pub no_local: bool,
+    #[serde(default)]
+    pub retain_as_published: bool,
+    #[serde(default)]
+    pub retained_handling: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AutoSubscribeRuleKey {
+    pub topic: String,
+}
+
+pub async fn cluster_status(State(state): State<HttpServerState>) -> HttpResult<ClusterStatus> {
+    let reply = cluster_status_by_req(&state.client_pool).await?;
+    Ok(Json(ClusterStatus {
+        cluster_name: reply.cluster_name,
+        nodes: reply.nodes,
+    }))
+}
+
+pub async fn cluster_config(
+    State(state): State<HttpServerState>,
+) -> HttpResult<MqttClusterDynamicConfig> {
+    Ok(Json(state.cache_manager.get_cluster_info()))
+}
+
+/// replace one section of the dynamic configuration of the cluster
+pub async fn set_cluster_config(
+    State(state): State<HttpServerState>,
+    Path(section): Path<String>,
+    Json(config): Json<Value>,
+) -> HttpResult<MqttClusterDynamicConfig> {
+    let cache_manager = &state.cache_manager;
+    match section.as_str() {
+        "flapping-detect" => {
+            cache_manager
+                .set_flapping_detect_config(serde_json::from_value(config)?)
+                .await?
+        }
+        "slow-sub" => {
+            cache_manager
+                .set_slow_sub_config(serde_json::from_value(config)?)
+                .await?
+        }
+        "security" => {
+            cache_manager
+                .set_security_config(serde_json::from_value(config)?)
+                .await?
+        }
+        "feature" => {
+            cache_manager
+                .set_feature_config(serde_json::from_value(config)?)
+                .await?
+        }
+        "network" => {
+            cache_manager
+                .set_network_config(serde_json::from_value(config)?)
+                .await?
+        }
+        _ => {
+            return Err(HttpError::not_found(format!(
+                "unknown cluster config section {}",
+                section
+            )))
+        }
+    }
+    Ok(Json(cache_manager.get_cluster_info()))
+}
+
+pub async fn list_user(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+) -> HttpResult<PageReply<MqttUser>> {
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    let mut users: Vec<MqttUser> = auth_driver
+        .read_all_user()
+        .await?
+        .into_iter()
+        .map(|(_, user)| MqttUser {
+            password: "".to_string(),
+            salt: "".to_string(),
+            ..user
+        })
+        .collect();
+    users.sort_by(|a, b| a.username.cmp(&b.username));
+    Ok(Json(paginate(users, &page)))
+}
+
+pub async fn create_user(
+    State(state): State<HttpServerState>,
+    Json(body): Json<UserBody>,
+) -> Result<StatusCode, HttpError> {
+    let user = MqttUser {
+        username: body.username,
+        password: body.password,
+        is_superuser: body.is_superuser,
+        ..Default::default()
+    };
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    auth_driver.save_user(user).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn delete_user(
+    State(state): State<HttpServerState>,
+    Path(username): Path<String>,
+) -> Result<StatusCode, HttpError> {
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    auth_driver.delete_user(username).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_acl(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+) -> HttpResult<PageReply<MqttAcl>> {
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    let mut acls = auth_driver.read_all_acl().await?;
+    acls.sort_by(|a, b| {
+        (&a.resource_name, &a.topic, &a.ip).cmp(&(&b.resource_name, &b.topic, &b.ip))
+    });
+    Ok(Json(paginate(acls, &page)))
+}
+
+pub async fn create_acl(
+    State(state): State<HttpServerState>,
+    Json(acl): Json<MqttAcl>,
+) -> Result<StatusCode, HttpError> {
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    auth_driver.save_acl(acl).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn delete_acl(
+    State(state): State<HttpServerState>,
+    Json(acl): Json<MqttAcl>,
+) -> Result<StatusCode, HttpError> {
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    auth_driver.delete_acl(acl).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn check_acl(
+    State(state): State<HttpServerState>,
+    Json(request): Json<CheckAclRequest>,
+) -> HttpResult<AclCheckResult> {
+    Ok(Json(check_acl_by_req(&state.cache_manager, request)))
+}
+
+pub async fn list_blacklist(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+) -> HttpResult<PageReply<MqttAclBlackList>> {
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    let mut blacklists = auth_driver.read_all_blacklist().await?;
+    blacklists.sort_by(|a, b| a.resource_name.cmp(&b.resource_name));
+    Ok(Json(paginate(blacklists, &page)))
+}
+
+pub async fn create_blacklist(
+    State(state): State<HttpServerState>,
+    Json(blacklist): Json<MqttAclBlackList>,
+) -> Result<StatusCode, HttpError> {
+    let auth_driver = AuthDriver::new(state.cache_manager.clone(), state.client_pool.clone());
+    auth_driver.save_blacklist(blacklist).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn delete_blacklist(
+    State(state): State<HttpServerState>,
+    Path((blacklist_type, resource_name)): Path<(String, String)>,
+) -> Result<StatusCode, HttpError> {
+    let request = DeleteBlacklistRequest {
+        blacklist_type,
+        resource_name,
+    };
+    delete_blacklist_by_req(
+        &state.cache_manager,
+        &state.client_pool,
+        Request::new(request),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_psk(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+) -> HttpResult<PageReply<MqttPsk>> {
+    let mut psks = list_psk_by_req(&state.cache_manager, &state.client_pool).await?;
+    psks.sort_by(|a, b| a.identity.cmp(&b.identity));
+    Ok(Json(paginate(psks, &page)))
+}
+
+pub async fn create_psk(
+    State(state): State<HttpServerState>,
+    Json(request): Json<CreatePskRequest>,
+) -> Result<StatusCode, HttpError> {
+    create_psk_by_req(&state.cache_manager, &state.client_pool, request).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn delete_psk(
+    State(state): State<HttpServerState>,
+    Path(identity): Path<String>,
+) -> Result<StatusCode, HttpError> {
+    let request = DeletePskRequest { identity };
+    delete_psk_by_req(&state.cache_manager, &state.client_pool, request).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_connection(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+) -> HttpResult<PageReply<ConnectionRaw>> {
+    let mut connections: Vec<ConnectionRaw> = state
+        .connection_manager
+        .list_connect()
+        .into_iter()
+        .filter_map(|(connect_id, network)| {
+            state
+                .cache_manager
+                .get_connection(connect_id)
+                .map(|mqtt| ConnectionRaw { network, mqtt })
+        })
+        .collect();
+    connections.sort_by_key(|raw| raw.network.connection_id);
+    Ok(Json(paginate(connections, &page)))
+}
+
+pub async fn list_session(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<SessionQuery>,
+) -> HttpResult<PageReply<MqttSession>> {
+    let sessions = list_session_by_req(&state.client_pool)
+        .await?
+        .into_iter()
+        .filter(|session| session.client_id.contains(&query.client_id))
+        .collect();
+    Ok(Json(paginate(sessions, &page)))
+}
+
+pub async fn get_session(
+    State(state): State<HttpServerState>,
+    Path(client_id): Path<String>,
+) -> HttpResult<MqttSession> {
+    Ok(Json(
+        get_session_by_req(&state.client_pool, &client_id).await?,
+    ))
+}
+
+pub async fn clean_session(
+    State(state): State<HttpServerState>,
+    Path(client_id): Path<String>,
+) -> Result<StatusCode, HttpError> {
+    let request = CleanSessionRequest { client_id };
+    clean_session_by_req(
+        &state.cache_manager,
+        &state.client_pool,
+        &state.connection_manager,
+        &state.subscribe_manager,
+        request,
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_client_detail(
+    State(state): State<HttpServerState>,
+    Path(client_id): Path<String>,
+) -> HttpResult<ClientDetail> {
+    let detail = get_client_detail_by_req(
+        &state.cache_manager,
+        &state.client_pool,
+        &state.connection_manager,
+        &state.subscribe_manager,
+        &client_id,
+    )
+    .await?;
+    Ok(Json(detail))
+}
+
+pub async fn kick_client(
+    State(state): State<HttpServerState>,
+    Path(client_id): Path<String>,
+) -> Result<StatusCode, HttpError> {
+    let request = KickClientRequest { client_id };
+    kick_client_by_req(
+        &state.cache_manager,
+        &state.client_pool,
+        &state.connection_manager,
+        &state.subscribe_manager,
+        request,
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_topic(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<TopicQuery>,
+) -> HttpResult<PageReply<TopicRaw>> {
+    let mut topics = Vec::new();
+    for entry in state.cache_manager.topic_info.iter() {
+        let topic = entry.value();
+        if !topic_name_match(&query, &topic.topic_name)? {
+            continue;
+        }
+        topics.push(TopicRaw {
+            topic_id: topic.topic_id.clone(),
+            topic_name: topic.topic_name.clone(),
+            cluster_name: topic.cluster_name.clone(),
+            is_contain_retain_message: topic.retain_message.is_some(),
+            create_time: topic.create_time,
+        });
+    }
+    topics.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+    Ok(Json(paginate(topics, &page)))
+}
+
+fn topic_name_match(query: &TopicQuery, topic_name: &str) -> Result<bool, HttpError> {
+    if query.topic_name.is_empty() {
+        return Ok(true);
+    }
+    match query.match_option.as_str() {
+        "exact" => Ok(topic_name == query.topic_name),
+        "prefix" => Ok(topic_name.starts_with(&query.topic_name)),
+        "" | "contains" => Ok(topic_name.contains(&query.topic_name)),
+        option => Err(HttpError::bad_request(format!(
+            "invalid match_option {}, expected exact, prefix or contains",
+            option
+        ))),
+    }
+}
+
+pub async fn list_retain_message(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<RetainMessageQuery>,
+) -> HttpResult<PageReply<RetainMessageRaw>> {
+    let reply = list_retain_message_by_req(&state.client_pool, &query.topic_filter, &page).await?;
+    Ok(Json(reply))
+}
+
+pub async fn get_retain_message(
+    State(state): State<HttpServerState>,
+    Query(query): Query<RetainMessageKey>,
+) -> HttpResult<RetainMessageDetail> {
+    let detail = get_retain_message_by_req(&state.client_pool, &query.topic_name).await?;
+    Ok(Json(detail))
+}
+
+/// `topic_filter` is required, `#` deletes every retained message
+pub async fn delete_retain_message(
+    State(state): State<HttpServerState>,
+    Query(query): Query<RetainMessageQuery>,
+) -> HttpResult<DeleteRetainMessageReply> {
+    if query.topic_filter.is_empty() {
+        return Err(HttpError::bad_request("topic_filter is required"));
+    }
+    let request = DeleteRetainMessageRequest {
+        topic_filter: query.topic_filter,
+    };
+    let results =
+        delete_retain_message_by_req(&state.cache_manager, &state.client_pool, request).await?;
+    let deleted = results
+        .iter()
+        .filter(|result| result.error.is_empty())
+        .count();
+    Ok(Json(DeleteRetainMessageReply { deleted, results }))
+}
+
+pub async fn retain_message_stats(
+    State(state): State<HttpServerState>,
+    Query(query): Query<RetainMessageQuery>,
+) -> HttpResult<RetainMessageStats> {
+    let stats = retain_message_stats_by_req(&state.client_pool, &query.topic_filter).await?;
+    Ok(Json(stats))
+}
+
+pub async fn list_topic_rewrite_rule(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+) -> HttpResult<PageReply<MqttTopicRewriteRule>> {
+    let mut rules: Vec<MqttTopicRewriteRule> = state
+        .cache_manager
+        .topic_rewrite_rule
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect();
+    rules.sort_by(|a, b| (&a.action, &a.source_topic).cmp(&(&b.action, &b.source_topic)));
+    Ok(Json(paginate(rules, &page)))
+}
+
+pub async fn create_topic_rewrite_rule(
+    State(state): State<HttpServerState>,
+    Json(body): Json<TopicRewriteRuleBody>,
+) -> Result<StatusCode, HttpError> {
+    let request = CreateTopicRewriteRuleRequest {
+        action: body.action,
+        source_topic: body.source_topic,
+        dest_topic: body.dest_topic,
+        regex: body.regex,
+    };
+    create_topic_rewrite_rule_by_req(
+        &state.client_pool,
+        &state.cache_manager,
+        Request::new(request),
+    )
+    .await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn delete_topic_rewrite_rule(
+    State(state): State<HttpServerState>,
+    Json(body): Json<TopicRewriteRuleBody>,
+) -> Result<StatusCode, HttpError> {
+    let request = DeleteTopicRewriteRuleRequest {
+        action: body.action,
+        source_topic: body.source_topic,
+    };
+    delete_topic_rewrite_rule_by_req(
+        &state.client_pool,
+        &state.cache_manager,
+        Request::new(request),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_connector(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<ConnectorQuery>,
+) -> HttpResult<PageReply<MQTTConnector>> {
+    let request = MqttListConnectorRequest {
+        connector_name: query.connector_name,
+    };
+    let reply = list_connector_by_req(&state.client_pool, Request::new(request))
+        .await?
+        .into_inner();
+    let mut connectors = Vec::new();
+    for raw in reply.connectors {
+        connectors
+            .push(serde_json::from_slice::<MQTTConnector>(&raw).map_err(HttpError::internal)?);
+    }
+    connectors.sort_by(|a, b| a.connector_name.cmp(&b.connector_name));
+    Ok(Json(paginate(connectors, &page)))
+}
+
+pub async fn create_connector(
+    State(state): State<HttpServerState>,
+    Json(body): Json<ConnectorBody>,
+) -> Result<StatusCode, HttpError> {
+    let connector_type = match body.connector_type.as_str() {
+        "file" => MqttConnectorType::File,
+        "kafka" => MqttConnectorType::Kafka,
+        connector_type => {
+            return Err(HttpError::bad_request(format!(
+                "invalid connector_type {}, expected file or kafka",
+                connector_type
+            )))
+        }
+    };
+    let request = MqttCreateConnectorRequest {
+        connector_name: body.connector_name,
+        connector_type: connector_type as i32,
+        config: body.config,
+        topic_id: body.topic_id,
+    };
+    create_connector_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn update_connector(
+    State(state): State<HttpServerState>,
+    Path(connector_name): Path<String>,
+    Json(connector): Json<MQTTConnector>,
+) -> Result<StatusCode, HttpError> {
+    if connector.connector_name != connector_name {
+        return Err(HttpError::bad_request(
+            "connector_name of the body does not match the path",
+        ));
+    }
+    let request = MqttUpdateConnectorRequest {
+        connector: connector.encode(),
+    };
+    update_connector_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_connector(
+    State(state): State<HttpServerState>,
+    Path(connector_name): Path<String>,
+) -> Result<StatusCode, HttpError> {
+    let request = MqttDeleteConnectorRequest { connector_name };
+    delete_connector_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_schema(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<SchemaQuery>,
+) -> HttpResult<PageReply<SchemaData>> {
+    let request = MqttListSchemaRequest {
+        schema_name: query.schema_name,
+    };
+    let reply = list_schema_by_req(&state.client_pool, Request::new(request))
+        .await?
+        .into_inner();
+    let mut schemas = Vec::new();
+    for raw in reply.schemas {
+        schemas.push(serde_json::from_slice::<SchemaData>(&raw).map_err(HttpError::internal)?);
+    }
+    schemas.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Json(paginate(schemas, &page)))
+}
+
+pub async fn create_schema(
+    State(state): State<HttpServerState>,
+    Json(body): Json<SchemaBody>,
+) -> Result<StatusCode, HttpError> {
+    let request = MqttCreateSchemaRequest {
+        schema_name: body.schema_name,
+        schema_type: body.schema_type,
+        schema: body.schema,
+        desc: body.desc,
+    };
+    create_schema_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn update_schema(
+    State(state): State<HttpServerState>,
+    Path(schema_name): Path<String>,
+    Json(body): Json<SchemaBody>,
+) -> Result<StatusCode, HttpError> {
+    let request = MqttUpdateSchemaRequest {
+        schema_name,
+        schema_type: body.schema_type,
+        schema: body.schema,
+        desc: body.desc,
+    };
+    update_schema_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_schema(
+    State(state): State<HttpServerState>,
+    Path(schema_name): Path<String>,
+) -> Result<StatusCode, HttpError> {
+    let request = MqttDeleteSchemaRequest { schema_name };
+    delete_schema_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_bind_schema(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<SchemaBindBody>,
+) -> HttpResult<PageReply<Value>> {
+    let request = MqttListBindSchemaRequest {
+        schema_name: query.schema_name,
+        resource_name: query.resource_name,
+    };
+    let reply = list_bind_schema_by_req(&state.client_pool, Request::new(request))
+        .await?
+        .into_inner();
+    let mut binds = Vec::new();
+    for raw in reply.schema_binds {
+        binds.push(serde_json::from_slice::<Value>(&raw).map_err(HttpError::internal)?);
+    }
+    Ok(Json(paginate(binds, &page)))
+}
+
+pub async fn bind_schema(
+    State(state): State<HttpServerState>,
+    Json(body): Json<SchemaBindBody>,
+) -> Result<StatusCode, HttpError> {
+    let request = MqttBindSchemaRequest {
+        schema_name: body.schema_name,
+        resource_name: body.resource_name,
+    };
+    bind_schema_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn unbind_schema(
+    State(state): State<HttpServerState>,
+    Json(body): Json<SchemaBindBody>,
+) -> Result<StatusCode, HttpError> {
+    let request = MqttUnbindSchemaRequest {
+        schema_name: body.schema_name,
+        resource_name: body.resource_name,
+    };
+    unbind_schema_by_req(&state.client_pool, Request::new(request)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_auto_subscribe_rule(
+    State(state): State<HttpServerState>,
+    Query(page): Query<PageQuery>,
+) -> HttpResult<PageReply<MqttAutoSubscribeRule>> {
+    let mut rules: Vec<MqttAutoSubscribeRule> = state
+        .cache_manager
+        .auto_subscribe_rule
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect();
+    rules.sort_by(|a, b| a.topic.cmp(&b.topic));
+    Ok(Json(paginate(rules, &page)))
+}
+
+pub async fn set_auto_subscribe_rule(
+    State(state): State<HttpServerState>,
+    Json(body): Json<AutoSubscribeRuleBody>,
+) -> Result<StatusCode, HttpError> {
+    let request = SetAutoSubscribeRuleRequest {
+        topic: body.topic,
+        qos: body.qos,
+        no_local: body.no_local,
+        retain_as_published: body.retain_as_published,
+        retained_handling: body.retained_handling,
+    };
+    subscribe::set_auto_subscribe_rule(
+        &state.client_pool,
+        &state.cache_manager,
+        Request::new(request),
+    )
+    .await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn delete_auto_subscribe_rule(
+    State(state): State<HttpServerState>,
+    Json(body): Json<AutoSubscribeRuleKey>,
+) -> Result<StatusCode, HttpError> {
+    let request = DeleteAutoSubscribeRuleRequest { topic: body.topic };
+    subscribe::delete_auto_subscribe_rule(
+        &state.client_pool,
+        &state.cache_manager,
+        Request::new(request),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{topic_name_match, TopicQuery};
+
+    #[test]
+    fn topic_name_match_test() {
+        let query = |topic_name: &str, match_option: &str| TopicQuery {
+            topic_name: topic_name.to_string(),
+            match_option: match_option.to_string(),
+        };
+
+        assert!(topic_name_match(&query("", ""), "/a/b").unwrap());
+        assert!(topic_name_match(&query("/a/b", "exact"), "/a/b").unwrap());
+        assert!(!topic_name_match(&query("/a", "exact"), "/a/b").unwrap());
+        assert!(topic_name_match(&query("/a", "prefix"), "/a/b").unwrap());
+        assert!(!topic_name_match(&query("/b", "prefix"), "/a/b").unwrap());
+        assert!(topic_name_match(&query("/b", ""), "/a/b").unwrap());
+        assert!(topic_name_match(&query("/b", "regex"), "/a/b").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tonic::{Code, Status};
+
+use crate::handler::error::MqttBrokerError;
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 1000;
+
+pub type HttpResult<T> = Result<Json<T>, HttpError>;
+
+/// an error of the admin API, returned as `{"error": "..."}`
+#[derive(Debug)]
+pub struct HttpError {
+    status: StatusCode,
+    message: String,
+}
+
+impl HttpError {
+    pub fn bad_request(message: impl ToString) -> Self {
+        HttpError {
+            status: StatusCode::BAD_REQUEST,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn not_found(message: impl ToString) -> Self {
+        HttpError {
+            status: StatusCode::NOT_FOUND,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn conflict(message: impl ToString) -> Self {
+        HttpError {
+            status: StatusCode::CONFLICT,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn unauthorized() -> Self {
+        HttpError {
+            status: StatusCode::UNAUTHORIZED,
+            message: "missing or invalid credentials".to_string(),
+        }
+    }
+
+    pub fn internal(message: impl ToString) -> Self {
+        HttpError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for HttpError {
+    fn into_response(self) -> Response {
+        let body = Json(json!({ "error": self.message }));
+        if self.status == StatusCode::UNAUTHORIZED {
+            return (
+                self.status,
+                [(header::WWW_AUTHENTICATE, "Basic realm=\"robustmq\"")],
+                body,
+            )
+                .into_response();
+        }
+        (self.status, body).into_response()
+    }
+}
+
+impl From<Status> for HttpError {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            Code::InvalidArgument => HttpError::bad_request(status.message()),
+            Code::NotFound => HttpError::not_found(status.message()),
+            Code::AlreadyExists => HttpError::conflict(status.message()),
+            _ => HttpError::internal(status.message()),
+        }
+    }
+}
+
+impl From<MqttBrokerError> for HttpError {
+    fn from(e: MqttBrokerError) -> Self {
+        match e {
+            MqttBrokerError::SessionDoesNotExist
+            | MqttBrokerError::ClientNoAvailableCOnnection(_)
+            | MqttBrokerError::TopicDoesNotExist(_)
+            | MqttBrokerError::RetainMessageDoesNotExist(_)
+            | MqttBrokerError::UserDoesNotExist
+            | MqttBrokerError::PskIdentityDoesNotExist => HttpError::not_found(e),
+            MqttBrokerError::UserAlreadyExist
+            | MqttBrokerError::PskIdentityAlreadyExist
+            | MqttBrokerError::TopicRewriteRuleAlreadyExist => HttpError::conflict(e),
+            MqttBrokerError::TopicNameIsEmpty
+            | MqttBrokerError::TopicNameInvalid()
+            | MqttBrokerError::TopicNameIncorrectlyFormatted(_)
+            | MqttBrokerError::InvalidAclAction
+            | MqttBrokerError::InvalidAclPermission
+            | MqttBrokerError::InvalidSchemaType(_)
+            | MqttBrokerError::InvalidPsk(_)
+            | MqttBrokerError::SerdeJsonError(_) => HttpError::bad_request(e),
+            _ => HttpError::internal(e),
+        }
+    }
+}
+
+impl From<CommonError> for HttpError {
+    fn from(e: CommonError) -> Self {
+        HttpError::internal(e)
+    }
+}
+
+impl From<serde_json::Error> for HttpError {
+    fn from(e: serde_json::Error) -> Self {
+        HttpError::bad_request(e)
+    }
+}
+
+/// `?page=1&page_size=20` of the list endpoints, pages start at 1
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PageQuery {
+    #[serde(default)]
+    pub page: usize,
+    #[serde(default)]
+    pub page_size: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PageReply<T> {
+    pub data: Vec<T>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+/// the requested page of a list, which callers sort so that pages are stable
+pub fn paginate<T>(list: Vec<T>, query: &PageQuery) -> PageReply<T> {
+    let page = query.page.max(1);
+    let page_size = match query.page_size {
+        0 => DEFAULT_PAGE_SIZE,
+        size => size.min(MAX_PAGE_SIZE),
+    };
+    let total = list.len();
+    let data = list
+        .into_iter()
+        .skip((page - 1).saturating_mul(page_size))
+        .take(page_size)
+        .collect();
+    PageReply {
+        data,
+        total,
+        page,
+        page_size,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::StatusCode;
+    use tonic::Status;
+
+    use super::{paginate, HttpError, PageQuery};
+    use crate::handler::error::MqttBrokerError;
+
+    #[test]
+    fn http_error_status_test() {
+        let status = |e: MqttBrokerError| HttpError::from(e).status;
+        assert_eq!(
+            status(MqttBrokerError::UserAlreadyExist),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(MqttBrokerError::UserDoesNotExist),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(MqttBrokerError::InvalidAclAction),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(MqttBrokerError::CommonError("e".to_string())),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            HttpError::from(Status::already_exists("e")).status,
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn paginate_test() {
+        let list: Vec<u32> = (0..45).collect();
+
+        let reply = paginate(list.clone(), &PageQuery::default());
+        assert_eq!(reply.data, (0..20).collect::<Vec<u32>>());
+        assert_eq!(reply.total, 45);
+        assert_eq!(reply.page, 1);
+        assert_eq!(reply.page_size, 20);
+
+        let query = PageQuery {
+            page: 3,
+            page_size: 20,
+        };
+        assert_eq!(
+            paginate(list.clone(), &query).data,
+            vec![40, 41, 42, 43, 44]
+        );
+
+        let query = PageQuery {
+            page: 4,
+            page_size: 20,
+        };
+        assert!(paginate(list.clone(), &query).data.is_empty());
+
+        let query = PageQuery {
+            page: 1,
+            page_size: 100000,
+        };
+        let reply = paginate(list, &query);
+        assert_eq!(reply.page_size, 1000);
+        assert_eq!(reply.data.len(), 45);
+    }
+}
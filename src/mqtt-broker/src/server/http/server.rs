@@ -0,0 +1,292 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::Request;
+use axum::http::{header, HeaderMap};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::Router;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common_base::config::broker_mqtt::{broker_mqtt_conf, HttpAdmin};
+use grpc_clients::pool::ClientPool;
+use log::info;
+use subtle::ConstantTimeEq;
+
+use super::admin::{
+    bind_schema, check_acl, clean_session, cluster_config, cluster_status, create_acl,
+    create_blacklist, create_connector, create_psk, create_schema, create_topic_rewrite_rule,
+    create_user, delete_acl, delete_auto_subscribe_rule, delete_blacklist, delete_connector,
+    delete_psk, delete_retain_message, delete_schema, delete_topic_rewrite_rule, delete_user,
+    get_client_detail, get_retain_message, get_session, kick_client, list_acl,
+    list_auto_subscribe_rule, list_bind_schema, list_blacklist, list_connection, list_connector,
+    list_psk, list_retain_message, list_schema, list_session, list_topic, list_topic_rewrite_rule,
+    list_user, retain_message_stats, set_auto_subscribe_rule, set_cluster_config, unbind_schema,
+    update_connector, update_schema,
+};
+use super::response::HttpError;
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::server::connection_manager::ConnectionManager;
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+pub const ROUTE_ROOT: &str = "/api/v1";
+
+#[derive(Clone)]
+pub struct HttpServerState {
+    pub client_pool: Arc<ClientPool>,
+    pub cache_manager: Arc<CacheManager>,
+    pub connection_manager: Arc<ConnectionManager>,
+    pub subscribe_manager: Arc<SubscribeManager>,
+}
+
+impl HttpServerState {
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        cache_manager: Arc<CacheManager>,
+        connection_manager: Arc<ConnectionManager>,
+        subscribe_manager: Arc<SubscribeManager>,
+    ) -> Self {
+        Self {
+            client_pool,
+            cache_manager,
+            connection_manager,
+            subscribe_manager,
+        }
+    }
+}
+
+/// the REST admin API, a JSON mirror of the admin gRPC service
+pub async fn http_admin_server(state: HttpServerState) -> Result<(), MqttBrokerError> {
+    let config = broker_mqtt_conf();
+    if !has_basic_auth(&config.http_admin) && config.http_admin.api_key.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "[http_admin] requires a username and password or an api_key".to_string(),
+        ));
+    }
+
+    let ip: SocketAddr = format!("{}:{}", config.http_admin.bind, config.http_admin.port)
+        .parse()
+        .map_err(|e| {
+            MqttBrokerError::CommonError(format!(
+                "Invalid [http_admin] bind address {}, error message: {}",
+                config.http_admin.bind, e
+            ))
+        })?;
+    let app = routes_v1(state);
+    info!("Broker HTTP Admin Server start success. addr:{}", ip);
+    axum_server::bind(ip).serve(app.into_make_service()).await?;
+    Ok(())
+}
+
+fn routes_v1(state: HttpServerState) -> Router {
+    let admin = Router::new()
+        // cluster
+        .route("/cluster/status", get(cluster_status))
+        .route("/cluster/config", get(cluster_config))
+        .route("/cluster/config/:section", put(set_cluster_config))
+        // user
+        .route("/users", get(list_user).post(create_user))
+        .route("/users/:username", delete(delete_user))
+        // acl
+        .route("/acls", get(list_acl).post(create_acl).delete(delete_acl))
+        .route("/acls/check", post(check_acl))
+        .route("/blacklists", get(list_blacklist).post(create_blacklist))
+        .route(
+            "/blacklists/:blacklist_type/:resource_name",
+            delete(delete_blacklist),
+        )
+        .route("/psks", get(list_psk).post(create_psk))
+        .route("/psks/:identity", delete(delete_psk))
+        // connection
+        .route("/connections", get(list_connection))
+        // session
+        .route("/sessions", get(list_session))
+        .route(
+            "/sessions/:client_id",
+            get(get_session).delete(clean_session),
+        )
+        .route("/clients/:client_id", get(get_client_detail))
+        .route("/clients/:client_id/kick", post(kick_client))
+        // topic
+        .route("/topics", get(list_topic))
+        .route(
+            "/retain-messages",
+            get(list_retain_message).delete(delete_retain_message),
+        )
+        .route("/retain-messages/detail", get(get_retain_message))
+        .route("/retain-messages/stats", get(retain_message_stats))
+        .route(
+            "/topic-rewrite-rules",
+            get(list_topic_rewrite_rule)
+                .post(create_topic_rewrite_rule)
+                .delete(delete_topic_rewrite_rule),
+        )
+        // connector
+        .route("/connectors", get(list_connector).post(create_connector))
+        .route(
+            "/connectors/:connector_name",
+            put(update_connector).delete(delete_connector),
+        )
+        // schema
+        .route("/schemas", get(list_schema).post(create_schema))
+        .route(
+            "/schemas/:schema_name",
+            put(update_schema).delete(delete_schema),
+        )
+        .route(
+            "/schema-binds",
+            get(list_bind_schema)
+                .post(bind_schema)
+                .delete(unbind_schema),
+        )
+        // auto subscribe
+        .route(
+            "/auto-subscribe-rules",
+            get(list_auto_subscribe_rule)
+                .post(set_auto_subscribe_rule)
+                .delete(delete_auto_subscribe_rule),
+        )
+        .layer(middleware::from_fn(admin_auth));
+
+    Router::new().nest(ROUTE_ROOT, admin).with_state(state)
+}
+
+async fn admin_auth(request: Request, next: Next) -> Response {
+    if !is_authorized(&broker_mqtt_conf().http_admin, request.headers()) {
+        return HttpError::unauthorized().into_response();
+    }
+    next.run(request).await
+}
+
+/// whether the request carries the API key or the basic auth credentials of `[http_admin]`
+fn is_authorized(config: &HttpAdmin, headers: &HeaderMap) -> bool {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if !config.api_key.is_empty() {
+        let api_key = headers
+            .get("x-api-key")
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| authorization.strip_prefix("Bearer "));
+        if let Some(api_key) = api_key {
+            if secure_eq(api_key, &config.api_key) {
+                return true;
+            }
+        }
+    }
+
+    if has_basic_auth(config) {
+        let credentials = authorization
+            .strip_prefix("Basic ")
+            .and_then(|encoded| STANDARD.decode(encoded.trim()).ok())
+            .and_then(|decoded| String::from_utf8(decoded).ok());
+        if let Some((username, password)) = credentials.as_ref().and_then(|c| c.split_once(':')) {
+            return secure_eq(username, &config.username) & secure_eq(password, &config.password);
+        }
+    }
+    false
+}
+
+// basic auth needs both a username and a password, an empty password would let anyone in who
+// knows the username
+fn has_basic_auth(config: &HttpAdmin) -> bool {
+    !config.username.is_empty() && !config.password.is_empty()
+}
+
+fn secure_eq(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::header::{self, IntoHeaderName};
+    use axum::http::{HeaderMap, HeaderValue};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use common_base::config::broker_mqtt::HttpAdmin;
+
+    use super::is_authorized;
+
+    fn headers(name: impl IntoHeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn basic_auth_test() {
+        let config = HttpAdmin {
+            username: "admin".to_string(),
+            password: "pwd123".to_string(),
+            ..Default::default()
+        };
+        let basic = |credentials: &str| format!("Basic {}", STANDARD.encode(credentials));
+
+        assert!(is_authorized(
+            &config,
+            &headers(header::AUTHORIZATION, &basic("admin:pwd123"))
+        ));
+        assert!(!is_authorized(
+            &config,
+            &headers(header::AUTHORIZATION, &basic("admin:pwd1234"))
+        ));
+        assert!(!is_authorized(
+            &config,
+            &headers(header::AUTHORIZATION, &basic("admin"))
+        ));
+        assert!(!is_authorized(&config, &HeaderMap::new()));
+
+        // api keys are disabled
+        assert!(!is_authorized(&config, &headers("x-api-key", "")));
+
+        // basic auth is disabled without a password
+        let config = HttpAdmin {
+            username: "admin".to_string(),
+            ..Default::default()
+        };
+        assert!(!is_authorized(
+            &config,
+            &headers(header::AUTHORIZATION, &basic("admin:"))
+        ));
+    }
+
+    #[test]
+    fn api_key_test() {
+        let config = HttpAdmin {
+            api_key: "key-123".to_string(),
+            ..Default::default()
+        };
+
+        assert!(is_authorized(&config, &headers("x-api-key", "key-123")));
+        assert!(is_authorized(
+            &config,
+            &headers(header::AUTHORIZATION, "Bearer key-123")
+        ));
+        assert!(!is_authorized(&config, &headers("x-api-key", "key-1234")));
+
+        // basic auth is disabled
+        let basic = format!("Basic {}", STANDARD.encode(":"));
+        assert!(!is_authorized(
+            &config,
+            &headers(header::AUTHORIZATION, &basic)
+        ));
+    }
+}
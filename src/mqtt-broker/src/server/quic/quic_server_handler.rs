@@ -13,10 +13,14 @@
 // limitations under the License.
 
 use crate::handler::cache::CacheManager;
+use crate::handler::flow_control::{
+    throttle_client_packet, throttle_new_connection, ClientRateLimiter, RateLimiter,
+};
 use crate::observability::metrics::packets::{
     record_received_error_metrics, record_received_metrics,
 };
 use crate::observability::slow::request::try_record_total_request_ms;
+use crate::security::login::x509::X509Identity;
 use crate::server::connection::{NetworkConnection, NetworkConnectionType};
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::RequestPackage;
@@ -24,6 +28,7 @@
 use log::{debug, error, info};
 use protocol::mqtt::codec::MqttCodec;
 use quinn::Endpoint;
+use rustls_pki_types::CertificateDer;
 use std::sync::Arc;
 use tokio::select;
 use tokio::sync::broadcast;
@@ -39,6 +44,7 @@ pub(crate) async fn acceptor_process(
     cache_manager: Arc<CacheManager>,
     network_connection_type: NetworkConnectionType,
 ) {
+    let connection_limiter = Arc::new(RateLimiter::new());
     for index in 1..=accept_thread_num {
         let endpoint = endpoint_arc.clone();
         let connection_manager = connection_manager.clone();
@@ -46,6 +52,7 @@ pub(crate) async fn acceptor_process(
         let raw_request_queue_sx = request_queue_sx.clone();
         let network_type = network_connection_type.clone();
         let cache_manager = cache_manager.clone();
+        let connection_limiter = connection_limiter.clone();
         tokio::spawn(async move {
             debug!("Quic Server acceptor thread {} start successfully.", index);
             loop {
@@ -65,7 +72,12 @@ pub(crate) async fn acceptor_process(
                                 match incoming.await {
                                 Ok(connection) => {
                                         info!("accept quic connection:{:?}",connection.remote_address());
+                                        throttle_new_connection(&connection_limiter, &cache_manager, &network_type).await;
                                         let client_addr = connection.remote_address();
+                                        let peer_cert = connection
+                                            .peer_identity()
+                                            .and_then(|identity| identity.downcast::<Vec<CertificateDer<'static>>>().ok())
+                                            .and_then(|certs| X509Identity::from_peer_certs(Some(certs.as_slice())));
                                         match connection.accept_bi().await {
                                             Ok((w_stream, r_stream)) => {
                                                     let codec = MqttCodec::new(None);
@@ -74,11 +86,12 @@ pub(crate) async fn acceptor_process(
                                                     // todo we need to add quic_establish_connection_check
 
                                                 let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
-                                                let connection = NetworkConnection::new(
+                                                let mut connection = NetworkConnection::new(
                                                     NetworkConnectionType::Quic,
                                                     client_addr,
                                                     Some(connection_stop_sx.clone())
                                                 );
+                                                connection.peer_cert = peer_cert;
                                                 connection_manager.add_connection(connection.clone());
                                                 connection_manager.add_quic_write(connection.connection_id, quic_framed_write_stream);
                                                 read_frame_process(quic_framed_read_stream, connection.clone(), raw_request_queue_sx.clone(),connection_stop_rx, network_type.clone(), cache_manager.clone())
@@ -114,6 +127,7 @@ fn read_frame_process(
     cache_manager: Arc<CacheManager>,
 ) {
     tokio::spawn(async move {
+        let rate_limiter = ClientRateLimiter::default();
         loop {
             select! {
                 val = connection_stop_rx.recv() =>{
@@ -131,6 +145,11 @@ fn read_frame_process(
                                     record_received_metrics(&connection, &packet, &network_type);
 
                                     info!("revc quic packet:{:?}", packet);
+                                    let stop = async { matches!(connection_stop_rx.recv().await, Some(true)) };
+                                    if !throttle_client_packet(&rate_limiter, &packet, &cache_manager, &network_type, stop).await {
+                                        debug!("Quic connection 【{}】 acceptor thread stopped successfully.",connection.connection_id);
+                                        break;
+                                    }
                                     let package =
                                         RequestPackage::new(connection.connection_id, connection.addr, packet);
 
@@ -22,11 +22,13 @@
 use crate::server::quic::handler::handler_process;
 use crate::server::quic::quic_server_handler::acceptor_process;
 use crate::server::quic::response::response_process;
+use crate::server::tcp::tls_server::build_tls_server_config;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use delay_message::DelayMessageManager;
 use grpc_clients::pool::ClientPool;
 use log::info;
+use quinn::crypto::rustls::QuicServerConfig as QuicRustlsServerConfig;
 use quinn::{Connection, Endpoint, ServerConfig, VarInt};
 use rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer};
 use rustls_pki_types::PrivateKeyDer;
@@ -42,6 +44,16 @@ pub fn generate_self_signed_cert() -> (Vec<CertificateDer<'static>>, PrivateKeyD
     let priv_key = PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
     (vec![cert_der.clone()], priv_key.into())
 }
+
+/// the server config with the certificate in `tls_cert`, which verifies client certificates
+/// against `tls_ca`
+pub fn build_mutual_tls_server_config() -> Result<ServerConfig, MqttBrokerError> {
+    let tls_config = build_tls_server_config()?;
+    let crypto = QuicRustlsServerConfig::try_from(tls_config)
+        .map_err(|e| MqttBrokerError::InvalidCertificate(e.to_string()))?;
+    Ok(ServerConfig::with_crypto(Arc::new(crypto)))
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn start_quic_server<S>(
     subscribe_manager: Arc<SubscribeManager>,
@@ -68,10 +80,20 @@ pub async fn start_quic_server<S>(
         auth_driver.clone(),
     );
 
-    let mut server = QuicServer::new(SocketAddr::new(
+    let addr = SocketAddr::new(
         IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
         conf.network.quic_port as u16,
-    ));
+    );
+    let mut server = if conf.network.tls_ca.is_empty() {
+        QuicServer::new(addr)
+    } else {
+        match build_mutual_tls_server_config() {
+            Ok(server_config) => QuicServer::with_server_config(addr, server_config),
+            Err(e) => {
+                panic!("{}", e.to_string());
+            }
+        }
+    };
     server.start();
 
     let quic_endpoint = server.get_endpoint();
@@ -176,6 +198,16 @@ pub fn new(addr: SocketAddr) -> Self {
         }
     }
 
+    pub fn with_server_config(addr: SocketAddr, server_config: ServerConfig) -> Self {
+        QuicServer {
+            quic_server_config: QuicServerConfig {
+                server_config,
+                bind_addr: addr,
+            },
+            endpoint: None,
+        }
+    }
+
     pub fn start(&mut self) {
         let endpoint = self.create_quinn_endpoint_as_a_quic_server();
         self.bind_address_for_quic_server_config(endpoint);
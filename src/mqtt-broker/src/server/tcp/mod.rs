@@ -16,4 +16,4 @@
 mod response;
 pub mod server;
 mod tcp_server;
-mod tls_server;
+pub(crate) mod tls_server;
@@ -205,6 +205,7 @@ pub async fn start_tls(&mut self, port: u32) {
             self.stop_sx.clone(),
             self.network_connection_type.clone(),
             self.connection_manager.clone(),
+            self.cache_manager.clone(),
             request_queue_sx,
         )
         .await;
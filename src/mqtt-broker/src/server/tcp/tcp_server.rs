@@ -26,6 +26,9 @@
 use tokio_util::codec::{FramedRead, FramedWrite};
 
 use crate::handler::cache::CacheManager;
+use crate::handler::flow_control::{
+    throttle_client_packet, throttle_new_connection, ClientRateLimiter, RateLimiter,
+};
 use crate::handler::validator::tcp_establish_connection_check;
 use crate::observability::metrics::packets::{
     record_received_error_metrics, record_received_metrics,
@@ -48,6 +51,8 @@
 /// - `cache_manager`: An `Arc`-wrapped `CacheManager` for managing cache operations.
 /// - `network_connection_type`: An enum indicating the type of network connection.
 ///
+/// All acceptor threads share one `RateLimiter`, which holds them back once the listener
+/// accepts new connections faster than `max_connection_rate`.
 pub(crate) async fn acceptor_process(
     accept_thread_num: usize,
     connection_manager: Arc<ConnectionManager>,
@@ -57,6 +62,7 @@ pub(crate) async fn acceptor_process(
     cache_manager: Arc<CacheManager>,
     network_connection_type: NetworkConnectionType,
 ) {
+    let connection_limiter = Arc::new(RateLimiter::new());
     for index in 1..=accept_thread_num {
         let listener = listener_arc.clone();
         let connection_manager = connection_manager.clone();
@@ -64,6 +70,7 @@ pub(crate) async fn acceptor_process(
         let raw_request_queue_sx = request_queue_sx.clone();
         let network_type = network_connection_type.clone();
         let cache_manager = cache_manager.clone();
+        let connection_limiter = connection_limiter.clone();
         tokio::spawn(async move {
             debug!("TCP Server acceptor thread {} start successfully.", index);
             loop {
@@ -81,6 +88,7 @@ pub(crate) async fn acceptor_process(
                         match val{
                             Ok((stream, addr)) => {
                                 info!("accept tcp connection:{:?}",addr);
+                                throttle_new_connection(&connection_limiter, &cache_manager, &network_type).await;
 
                                 let (r_stream, w_stream) = io::split(stream);
                                 let codec = MqttCodec::new(None);
@@ -123,6 +131,7 @@ fn read_frame_process(
     cache_manager: Arc<CacheManager>,
 ) {
     tokio::spawn(async move {
+        let rate_limiter = ClientRateLimiter::default();
         loop {
             select! {
                 val = connection_stop_rx.recv() =>{
@@ -140,6 +149,11 @@ fn read_frame_process(
                                 record_received_metrics(&connection, &pack, &network_type);
 
                                 info!("revc tcp packet:{:?}", pack);
+                                let stop = async { matches!(connection_stop_rx.recv().await, Some(true)) };
+                                if !throttle_client_packet(&rate_limiter, &pack, &cache_manager, &network_type, stop).await {
+                                    debug!("TCP connection 【{}】 acceptor thread stopped successfully.",connection.connection_id);
+                                    break;
+                                }
                                 let package =
                                     RequestPackage::new(connection.connection_id, connection.addr, pack);
 
@@ -21,23 +21,34 @@
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use futures_util::StreamExt;
 use log::{debug, error, info};
+use openssl::ssl::SslAcceptor;
 use protocol::mqtt::codec::MqttCodec;
 use rustls_pemfile::{certs, private_key};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::select;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{broadcast, mpsc};
 use tokio::time::sleep;
 
+use tokio_openssl::SslStream;
 use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
 use tokio_rustls::TlsAcceptor;
 use tokio_util::codec::{FramedRead, FramedWrite};
+use tokio_util::either::Either;
 
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::flow_control::{
+    throttle_client_packet, throttle_new_connection, ClientRateLimiter, RateLimiter,
+};
 use crate::handler::validator::tcp_tls_establish_connection_check;
 use crate::observability::metrics::packets::{
     record_received_error_metrics, record_received_metrics,
 };
+use crate::security::login::psk::{accept_psk_stream, build_psk_acceptor, psk_identity};
+use crate::security::login::x509::{build_client_verifier, X509Identity};
 use crate::server::connection::{NetworkConnection, NetworkConnectionType};
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::RequestPackage;
@@ -55,40 +66,94 @@ pub(crate) fn load_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
         ))
 }
 
+/// the TLS config of the tcps, wss and QUIC listeners
+///
+/// Client certificates are requested and verified against `tls_ca` when it is configured.
+pub(crate) fn build_tls_server_config() -> Result<ServerConfig, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let certs = load_certs(Path::new(&conf.network.tls_cert)).map_err(|e| {
+        MqttBrokerError::InvalidCertificate(format!("load certs {}: {}", conf.network.tls_cert, e))
+    })?;
+    let key = load_key(Path::new(&conf.network.tls_key)).map_err(|e| {
+        MqttBrokerError::InvalidCertificate(format!("load key {}: {}", conf.network.tls_key, e))
+    })?;
+
+    let builder = ServerConfig::builder();
+    let builder = if conf.network.tls_ca.is_empty() {
+        builder.with_no_client_auth()
+    } else {
+        builder.with_client_cert_verifier(build_client_verifier(&conf.network)?)
+    };
+    builder
+        .with_single_cert(certs, key)
+        .map_err(|e| MqttBrokerError::InvalidCertificate(e.to_string()))
+}
+
+/// the stream of a tcps connection, served by OpenSSL when TLS-PSK is enabled
+pub(crate) type TlsServerStream = Either<TlsStream<TcpStream>, SslStream<TcpStream>>;
+
+#[derive(Clone)]
+enum TlsServerAcceptor {
+    Rustls(TlsAcceptor),
+    Psk(Arc<SslAcceptor>),
+}
+
+impl TlsServerAcceptor {
+    fn new(cache_manager: Arc<CacheManager>) -> Result<Self, MqttBrokerError> {
+        let conf = broker_mqtt_conf();
+        if conf.network.tls_psk_enable {
+            let acceptor = build_psk_acceptor(&conf.network, cache_manager)?;
+            return Ok(TlsServerAcceptor::Psk(Arc::new(acceptor)));
+        }
+        let config = build_tls_server_config()?;
+        Ok(TlsServerAcceptor::Rustls(TlsAcceptor::from(Arc::new(
+            config,
+        ))))
+    }
+
+    // the stream, the client certificate and the PSK identity of a connection
+    async fn accept(
+        &self,
+        stream: TcpStream,
+    ) -> Result<(TlsServerStream, Option<X509Identity>, Option<String>), MqttBrokerError> {
+        match self {
+            TlsServerAcceptor::Rustls(acceptor) => {
+                let stream = acceptor.accept(stream).await?;
+                let peer_cert =
+                    X509Identity::from_peer_certs(stream.get_ref().1.peer_certificates());
+                Ok((Either::Left(stream), peer_cert, None))
+            }
+            TlsServerAcceptor::Psk(acceptor) => {
+                let stream = accept_psk_stream(acceptor, stream).await?;
+                let peer_cert = stream
+                    .ssl()
+                    .peer_certificate()
+                    .and_then(|cert| cert.to_der().ok())
+                    .and_then(|der| X509Identity::from_der(&der).ok());
+                let identity = psk_identity(stream.ssl());
+                Ok((Either::Right(stream), peer_cert, identity))
+            }
+        }
+    }
+}
+
 pub(crate) async fn acceptor_tls_process(
     accept_thread_num: usize,
     listener_arc: Arc<TcpListener>,
     stop_sx: broadcast::Sender<bool>,
     network_connection_type: NetworkConnectionType,
     connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
     request_queue_sx: Sender<RequestPackage>,
 ) {
-    let conf = broker_mqtt_conf();
-    let certs = match load_certs(Path::new(&conf.network.tls_cert)) {
-        Ok(data) => data,
-        Err(e) => {
-            panic!("load certs: {}", e);
-        }
-    };
-
-    let key = match load_key(Path::new(&conf.network.tls_key)) {
-        Ok(data) => data,
-        Err(e) => {
-            panic!("load key: {}", e);
-        }
-    };
-
-    let config = match ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)
-    {
+    let tls_acceptor = match TlsServerAcceptor::new(cache_manager.clone()) {
         Ok(data) => data,
         Err(e) => {
-            panic!("ssl build cert:{}", e);
+            panic!("{}", e);
         }
     };
-    let tls_acceptor = TlsAcceptor::from(Arc::new(config));
 
+    let connection_limiter = Arc::new(RateLimiter::new());
     for index in 1..=accept_thread_num {
         let listener = listener_arc.clone();
         let connection_manager = connection_manager.clone();
@@ -96,6 +161,8 @@ pub(crate) async fn acceptor_tls_process(
         let raw_request_queue_sx = request_queue_sx.clone();
         let raw_tls_acceptor = tls_acceptor.clone();
         let network_type = network_connection_type.clone();
+        let cache_manager = cache_manager.clone();
+        let connection_limiter = connection_limiter.clone();
         tokio::spawn(async move {
             debug!("TCP Server acceptor thread {} start successfully.", index);
             loop {
@@ -112,7 +179,8 @@ pub(crate) async fn acceptor_tls_process(
                         match val{
                             Ok((stream, addr)) => {
                                 info!("accept tcp tls connection:{:?}",addr);
-                                let stream = match raw_tls_acceptor.accept(stream).await{
+                                throttle_new_connection(&connection_limiter, &cache_manager, &network_type).await;
+                                let (stream, peer_cert, psk_identity) = match raw_tls_acceptor.accept(stream).await{
                                     Ok(da) => da,
                                     Err(e) => {
                                         error!("Tls Accepter failed to read Stream with error message :{e:?}");
@@ -129,15 +197,17 @@ pub(crate) async fn acceptor_tls_process(
                                 }
 
                                 let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
-                                let connection = NetworkConnection::new(
+                                let mut connection = NetworkConnection::new(
                                     crate::server::connection::NetworkConnectionType::Tls,
                                     addr,
                                     Some(connection_stop_sx.clone())
                                 );
+                                connection.peer_cert = peer_cert;
+                                connection.psk_identity = psk_identity;
                                 connection_manager.add_connection(connection.clone());
                                 connection_manager.add_tcp_tls_write(connection.connection_id, write_frame_stream);
 
-                                read_tls_frame_process(read_frame_stream,connection,raw_request_queue_sx.clone(),connection_stop_rx, network_type.clone());
+                                read_tls_frame_process(read_frame_stream,connection,raw_request_queue_sx.clone(),connection_stop_rx, network_type.clone(), cache_manager.clone());
                             }
                             Err(e) => {
                                 error!("TCP accept failed to create connection with error message :{:?}",e);
@@ -151,16 +221,15 @@ pub(crate) async fn acceptor_tls_process(
 }
 
 pub(crate) fn read_tls_frame_process(
-    mut read_frame_stream: FramedRead<
-        tokio::io::ReadHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-        MqttCodec,
-    >,
+    mut read_frame_stream: FramedRead<tokio::io::ReadHalf<TlsServerStream>, MqttCodec>,
     connection: NetworkConnection,
     request_queue_sx: Sender<RequestPackage>,
     mut connection_stop_rx: Receiver<bool>,
     network_type: NetworkConnectionType,
+    cache_manager: Arc<CacheManager>,
 ) {
     tokio::spawn(async move {
+        let rate_limiter = ClientRateLimiter::default();
         loop {
             select! {
                 val = connection_stop_rx.recv() =>{
@@ -177,6 +246,11 @@ pub(crate) fn read_tls_frame_process(
                             Ok(pack) => {
                                 record_received_metrics(&connection, &pack, &network_type);
                                 info!("revc tcp tls packet:{:?}", pack);
+                                let stop = async { matches!(connection_stop_rx.recv().await, Some(true)) };
+                                if !throttle_client_packet(&rate_limiter, &pack, &cache_manager, &network_type, stop).await {
+                                    debug!("TCP connection 【{}】 acceptor thread stopped successfully.",connection.connection_id);
+                                    break;
+                                }
                                 let package =
                                     RequestPackage::new(connection.connection_id, connection.addr, pack);
                                 match request_queue_sx.send(package).await {
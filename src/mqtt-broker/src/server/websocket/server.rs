@@ -12,21 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io;
 use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::sync::Arc;
 
 use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{ConnectInfo, State, WebSocketUpgrade};
+use axum::middleware::AddExtension;
 use axum::response::Response;
 use axum::routing::get;
-use axum::Router;
+use axum::{Extension, Router};
 use axum_extra::headers::UserAgent;
 use axum_extra::TypedHeader;
-use axum_server::tls_rustls::RustlsConfig;
+use axum_server::accept::Accept;
+use axum_server::tls_rustls::{RustlsAcceptor, RustlsConfig};
 use bytes::{BufMut, BytesMut};
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use delay_message::DelayMessageManager;
+use futures_util::future::BoxFuture;
 use futures_util::stream::StreamExt;
 use grpc_clients::pool::ClientPool;
 use log::{error, info};
@@ -34,14 +37,22 @@
 use protocol::mqtt::common::{MqttPacket, MqttProtocol};
 use schema_register::schema::SchemaRegisterManager;
 use storage_adapter::storage::StorageAdapter;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::select;
 use tokio::sync::broadcast::{self};
+use tokio_rustls::server::TlsStream;
+use tower::Layer;
 
 use crate::handler::cache::CacheManager;
 use crate::handler::command::Command;
+use crate::handler::flow_control::{
+    throttle_client_packet, throttle_new_connection, ClientRateLimiter, RateLimiter,
+};
+use crate::security::login::x509::X509Identity;
 use crate::security::AuthDriver;
-use crate::server::connection::NetworkConnection;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType};
 use crate::server::connection_manager::ConnectionManager;
+use crate::server::tcp::tls_server::build_tls_server_config;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
 pub const ROUTE_ROOT: &str = "/mqtt";
@@ -57,6 +68,9 @@ pub struct WebSocketServerState<S> {
     connection_manager: Arc<ConnectionManager>,
     schema_manager: Arc<SchemaRegisterManager>,
     auth_driver: Arc<AuthDriver>,
+    // WebSocket, or WebSockets for the wss listener
+    network_type: NetworkConnectionType,
+    connection_limiter: Arc<RateLimiter>,
 }
 
 impl<S> WebSocketServerState<S>
@@ -85,6 +99,8 @@ pub fn new(
             client_pool,
             auth_driver,
             stop_sx,
+            network_type: NetworkConnectionType::WebSocket,
+            connection_limiter: Arc::new(RateLimiter::new()),
         }
     }
 }
@@ -119,15 +135,14 @@ pub async fn websockets_server<S>(state: WebSocketServerState<S>)
     let ip: SocketAddr = format!("0.0.0.0:{}", config.network.websockets_port)
         .parse()
         .unwrap();
+    let state = WebSocketServerState {
+        network_type: NetworkConnectionType::WebSockets,
+        ..state
+    };
     let app = routes_v1(state);
 
-    let tls_config = match RustlsConfig::from_pem_file(
-        PathBuf::from(config.network.tls_cert.clone()),
-        PathBuf::from(config.network.tls_key.clone()),
-    )
-    .await
-    {
-        Ok(cf) => cf,
+    let tls_config = match build_tls_server_config() {
+        Ok(cf) => RustlsConfig::from_config(Arc::new(cf)),
         Err(e) => {
             panic!("{}", e.to_string());
         }
@@ -137,7 +152,9 @@ pub async fn websockets_server<S>(state: WebSocketServerState<S>)
         "Broker WebSocket TLS Server start success. port:{}",
         config.network.websockets_port
     );
-    match axum_server::bind_rustls(ip, tls_config)
+    let acceptor = PeerCertAcceptor::new(RustlsAcceptor::new(tls_config));
+    match axum_server::bind(ip)
+        .acceptor(acceptor)
         .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
     {
@@ -146,6 +163,42 @@ pub async fn websockets_server<S>(state: WebSocketServerState<S>)
     }
 }
 
+/// the client certificate of a wss connection
+#[derive(Clone)]
+struct PeerCert(Option<X509Identity>);
+
+/// Accepts TLS connections and hands the client certificate to `ws_handler`.
+#[derive(Clone)]
+struct PeerCertAcceptor {
+    inner: RustlsAcceptor,
+}
+
+impl PeerCertAcceptor {
+    fn new(inner: RustlsAcceptor) -> Self {
+        PeerCertAcceptor { inner }
+    }
+}
+
+impl<I, S> Accept<I, S> for PeerCertAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = TlsStream<I>;
+    type Service = AddExtension<S, PeerCert>;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let acceptor = self.inner.clone();
+        Box::pin(async move {
+            let (stream, service) = acceptor.accept(stream, service).await?;
+            let peer_cert = X509Identity::from_peer_certs(stream.get_ref().1.peer_certificates());
+            let service = Extension(PeerCert(peer_cert)).layer(service);
+            Ok((stream, service))
+        })
+    }
+}
+
 fn routes_v1<S>(state: WebSocketServerState<S>) -> Router
 where
     S: StorageAdapter + Sync + Send + 'static + Clone,
@@ -161,6 +214,7 @@ async fn ws_handler<S>(
     State(state): State<WebSocketServerState<S>>,
     user_agent: Option<TypedHeader<UserAgent>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    peer_cert: Option<Extension<PeerCert>>,
 ) -> Response
 where
     S: StorageAdapter + Sync + Send + 'static + Clone,
@@ -171,6 +225,13 @@ async fn ws_handler<S>(
         String::from("Unknown Source")
     };
     info!("websocket `{user_agent}` at {addr} connected.");
+    throttle_new_connection(
+        &state.connection_limiter,
+        &state.cache_manager,
+        &state.network_type,
+    )
+    .await;
+    let peer_cert = peer_cert.and_then(|Extension(PeerCert(peer_cert))| peer_cert);
     let command = Command::new(
         state.cache_manager.clone(),
         state.message_storage_adapter.clone(),
@@ -187,9 +248,12 @@ async fn ws_handler<S>(
             handle_socket(
                 socket,
                 addr,
+                peer_cert,
                 command,
                 codec,
                 state.connection_manager.clone(),
+                state.cache_manager.clone(),
+                state.network_type.clone(),
                 state.stop_sx.clone(),
             )
         })
@@ -198,24 +262,25 @@ async fn ws_handler<S>(
 async fn handle_socket<S>(
     socket: WebSocket,
     addr: SocketAddr,
+    peer_cert: Option<X509Identity>,
     mut command: Command<S>,
     mut codec: MqttCodec,
     connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
+    network_type: NetworkConnectionType,
     stop_sx: broadcast::Sender<bool>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     let (sender, mut receiver) = socket.split();
-    let mut tcp_connection = NetworkConnection::new(
-        crate::server::connection::NetworkConnectionType::WebSocket,
-        addr,
-        None,
-    );
+    let mut tcp_connection = NetworkConnection::new(NetworkConnectionType::WebSocket, addr, None);
+    tcp_connection.peer_cert = peer_cert;
 
     connection_manager.add_websocket_write(tcp_connection.connection_id, sender);
     connection_manager.add_connection(tcp_connection.clone());
     let mut protocol_version = MqttProtocol::Mqtt5;
     let mut stop_rx = stop_sx.subscribe();
+    let rate_limiter = ClientRateLimiter::default();
 
     loop {
         select! {
@@ -235,6 +300,10 @@ async fn handle_socket<S>(
                             match codec.decode_data(&mut buf) {
                                 Ok(Some(packet)) => {
                                     info!("recv websocket packet:{packet:?}");
+                                    let stop = async { matches!(stop_rx.recv().await, Ok(true)) };
+                                    if !throttle_client_packet(&rate_limiter, &packet, &cache_manager, &network_type, stop).await {
+                                        break;
+                                    }
                                     if let Some(resp_pkg) = command
                                         .apply(
                                             connection_manager.clone(),
@@ -0,0 +1,107 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{placement_delete, placement_get_prefix, placement_set};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::inflight::MqttInflightMessage;
+use protocol::placement_center::placement_center_kv::{
+    DeleteRequest, GetPrefixRequest, SetRequest,
+};
+
+use crate::handler::error::MqttBrokerError;
+
+/// the inflight window of the persistent sessions, kept in the KV store of the placement center
+pub struct InflightStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl InflightStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        InflightStorage { client_pool }
+    }
+
+    pub async fn save_inflight(
+        &self,
+        inflight: &MqttInflightMessage,
+    ) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = SetRequest {
+            key: inflight_key(&config.cluster_name, &inflight.client_id, inflight.pkid),
+            value: inflight.encode(),
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete_inflight(&self, client_id: &str, pkid: u16) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteRequest {
+            key: inflight_key(&config.cluster_name, client_id, pkid),
+        };
+        placement_delete(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn list_inflight(
+        &self,
+        client_id: &str,
+    ) -> Result<Vec<MqttInflightMessage>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = GetPrefixRequest {
+            prefix: inflight_prefix(&config.cluster_name, client_id),
+        };
+        let reply =
+            placement_get_prefix(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.values {
+            list.push(serde_json::from_str::<MqttInflightMessage>(&raw)?);
+        }
+        Ok(list)
+    }
+
+    pub async fn clear_inflight(&self, client_id: &str) -> Result<(), MqttBrokerError> {
+        for inflight in self.list_inflight(client_id).await? {
+            self.delete_inflight(client_id, inflight.pkid).await?;
+        }
+        Ok(())
+    }
+}
+
+// the client id is hex encoded, so that a client id containing '/' can neither break the key
+// layout nor make the prefix of one client match the keys of another
+fn inflight_prefix(cluster_name: &str, client_id: &str) -> String {
+    format!(
+        "/mqtt/inflight/{}/{}/",
+        cluster_name,
+        hex::encode(client_id)
+    )
+}
+
+fn inflight_key(cluster_name: &str, client_id: &str, pkid: u16) -> String {
+    format!("{}{}", inflight_prefix(cluster_name, client_id), pkid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inflight_key, inflight_prefix};
+
+    #[test]
+    fn inflight_key_test() {
+        assert_eq!(inflight_key("c1", "a/b", 7), "/mqtt/inflight/c1/612f62/7");
+        assert!(!inflight_key("c1", "a/1", 7).starts_with(&inflight_prefix("c1", "a")));
+    }
+}
@@ -17,7 +17,9 @@
 pub mod blacklist;
 pub mod cluster;
 pub mod connector;
+pub mod inflight;
 pub mod message;
+pub mod psk;
 pub mod schema;
 pub mod session;
 pub mod topic;
@@ -0,0 +1,77 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{placement_delete, placement_get_prefix, placement_set};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::psk::MqttPsk;
+use protocol::placement_center::placement_center_kv::{
+    DeleteRequest, GetPrefixRequest, SetRequest,
+};
+
+use crate::handler::error::MqttBrokerError;
+
+/// the TLS-PSK identities of the cluster, kept in the KV store of the placement center
+pub struct PskStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl PskStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        PskStorage { client_pool }
+    }
+
+    pub async fn save_psk(&self, psk: MqttPsk) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = SetRequest {
+            key: psk_key(&config.cluster_name, &psk.identity),
+            value: serde_json::to_string(&psk)?,
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete_psk(&self, identity: String) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteRequest {
+            key: psk_key(&config.cluster_name, &identity),
+        };
+        placement_delete(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn list_psk(&self) -> Result<Vec<MqttPsk>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = GetPrefixRequest {
+            prefix: psk_prefix(&config.cluster_name),
+        };
+        let reply =
+            placement_get_prefix(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.values {
+            list.push(serde_json::from_str::<MqttPsk>(&raw)?);
+        }
+        Ok(list)
+    }
+}
+
+fn psk_prefix(cluster_name: &str) -> String {
+    format!("/mqtt/psk/{}/", cluster_name)
+}
+
+fn psk_key(cluster_name: &str, identity: &str) -> String {
+    format!("{}{}", psk_prefix(cluster_name), identity)
+}
@@ -17,8 +17,10 @@
 
 use bytes::Bytes;
 use common_base::tools::now_second;
+use grpc_clients::pool::ClientPool;
 use log::{error, info, warn};
 use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::inflight::{InflightState, MqttInflightMessage};
 use metadata_struct::mqtt::message::MqttMessage;
 use protocol::mqtt::common::{Publish, PublishProperties, QoS};
 use storage_adapter::storage::StorageAdapter;
@@ -26,6 +28,7 @@
 use tokio::sync::broadcast::{self};
 use tokio::time::sleep;
 
+use super::message_reader::TopicMessageReader;
 use super::sub_common::{
     loop_commit_offset, min_qos, publish_message_qos, qos2_send_pubrel, wait_pub_ack,
     wait_pub_comp, wait_pub_rec,
@@ -34,6 +37,7 @@
 use super::subscriber::Subscriber;
 use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPacketInfo};
 use crate::handler::error::MqttBrokerError;
+use crate::handler::inflight::{inflight_delete, inflight_resume, inflight_save};
 use crate::handler::message::is_message_expire;
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::message::MessageStorage;
@@ -41,6 +45,7 @@
 
 pub struct ExclusivePush<S> {
     cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
     subscribe_manager: Arc<SubscribeManager>,
     connection_manager: Arc<ConnectionManager>,
     message_storage: Arc<S>,
@@ -53,12 +58,14 @@ impl<S> ExclusivePush<S>
     pub fn new(
         message_storage: Arc<S>,
         cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
         subscribe_manager: Arc<SubscribeManager>,
         connection_manager: Arc<ConnectionManager>,
     ) -> Self {
         ExclusivePush {
             message_storage,
             cache_manager,
+            client_pool,
             subscribe_manager,
             connection_manager,
         }
@@ -113,7 +120,13 @@ async fn start_push_thread(&self) {
             let (sub_thread_stop_sx, mut sub_thread_stop_rx) = broadcast::channel(1);
 
             let message_storage = MessageStorage::new(self.message_storage.clone());
+            let mut message_reader = TopicMessageReader::new(
+                &self.subscribe_manager,
+                message_storage.clone(),
+                &subscriber.topic_id,
+            );
             let cache_manager = self.cache_manager.clone();
+            let client_pool = self.client_pool.clone();
             let connection_manager = self.connection_manager.clone();
             let subscribe_manager = self.subscribe_manager.clone();
 
@@ -141,6 +154,30 @@ async fn start_push_thread(&self) {
                     }
                 };
 
+                // resume the message that was not fully acknowledged when the session was
+                // pushed last time, by this or another broker
+                let resume_result = inflight_resume(
+                    &cache_manager,
+                    &client_pool,
+                    &subscriber.client_id,
+                    &group_id,
+                )
+                .await;
+                let mut resume = match resume_result {
+                    Ok(inflight) => inflight,
+                    Err(e) => {
+                        error!(
+                            "Failed to read the inflight messages of client [{}], error message: {}",
+                            subscriber.client_id, e
+                        );
+                        None
+                    }
+                };
+                if let Some(inflight) = resume.take_if(|inflight| inflight.offset < offset) {
+                    // the message was committed already
+                    inflight_delete(&cache_manager, &inflight.client_id, inflight.pkid);
+                }
+
                 loop {
                     select! {
                         val = sub_thread_stop_rx.recv() =>{
@@ -161,20 +198,20 @@ async fn start_push_thread(&self) {
                         val = pub_message(
                                 &connection_manager,
                                 &message_storage,
+                                &mut message_reader,
                                 &cache_manager,
                                 &subscriber,
                                 &group_id,
                                 &qos,
                                 &sub_ids,
                                 offset,
+                                &mut resume,
                                 &sub_thread_stop_sx
                             ) => {
                                 match val{
                                     Ok(offset_op) => {
                                         if let Some(off) = offset_op{
                                             offset = off + 1;
-                                        }else{
-                                            sleep(Duration::from_millis(100)).await;
                                         }
                                     }
                                     Err(e) => {
@@ -199,12 +236,14 @@ async fn start_push_thread(&self) {
 async fn pub_message<S>(
     connection_manager: &Arc<ConnectionManager>,
     message_storage: &MessageStorage<S>,
+    message_reader: &mut TopicMessageReader<S>,
     cache_manager: &Arc<CacheManager>,
     subscriber: &Subscriber,
     group_id: &str,
     qos: &QoS,
     sub_ids: &[usize],
     offset: u64,
+    resume: &mut Option<MqttInflightMessage>,
     sub_thread_stop_sx: &broadcast::Sender<bool>,
 ) -> Result<Option<u64>, MqttBrokerError>
 where
@@ -213,9 +252,7 @@ async fn pub_message<S>(
     let record_num = 5;
     let client_id = subscriber.client_id.clone();
 
-    let results = message_storage
-        .read_topic_message(&subscriber.topic_id, offset, record_num)
-        .await?;
+    let results = message_reader.read(offset, record_num).await?;
 
     if results.is_empty() {
         return Ok(None);
@@ -223,6 +260,14 @@ async fn pub_message<S>(
 
     for record in results.iter() {
         let record_offset = record.offset.unwrap();
+        let resume_inflight = if resume
+            .as_ref()
+            .is_some_and(|inflight| inflight.offset == record_offset)
+        {
+            resume.take()
+        } else {
+            None
+        };
 
         // build publish params
         let sub_pub_param = if let Some(params) = build_pub_message(
@@ -232,17 +277,31 @@ async fn pub_message<S>(
             subscriber,
             cache_manager,
             sub_ids,
+            resume_inflight.as_ref(),
         )
         .await?
         {
             params
         } else {
+            if let Some(inflight) = resume_inflight {
+                inflight_delete(cache_manager, &inflight.client_id, inflight.pkid);
+            }
             continue;
         };
 
         let pkid = sub_pub_param.pkid;
+        let mut inflight = MqttInflightMessage::new(
+            client_id.clone(),
+            pkid,
+            group_id.to_owned(),
+            subscriber.topic_id.clone(),
+            record_offset,
+        );
         match qos {
             QoS::AtMostOnce => {
+                if let Some(inflight) = resume_inflight {
+                    inflight_delete(cache_manager, &inflight.client_id, inflight.pkid);
+                }
                 publish_message_qos(
                     cache_manager,
                     connection_manager,
@@ -263,14 +322,20 @@ async fn pub_message<S>(
                     },
                 );
 
-                exclusive_publish_message_qos1(
+                inflight_save(cache_manager, &inflight);
+                if !exclusive_publish_message_qos1(
                     cache_manager,
                     connection_manager,
                     &sub_pub_param,
                     sub_thread_stop_sx,
                     &wait_puback_sx,
                 )
-                .await;
+                .await
+                {
+                    // stopped, the message stays in the inflight window
+                    return Ok(None);
+                }
+                inflight_delete(cache_manager, &inflight.client_id, inflight.pkid);
 
                 cache_manager.remove_pkid_info(&client_id, pkid);
                 cache_manager.remove_ack_packet(&client_id, pkid);
@@ -287,14 +352,61 @@ async fn pub_message<S>(
                     },
                 );
 
-                exclusive_publish_message_qos2(
+                // the client has received the message already when the PubRel was sent
+                let released = resume_inflight
+                    .as_ref()
+                    .is_some_and(|inflight| inflight.state == InflightState::PubRel);
+                if !released {
+                    inflight_save(cache_manager, &inflight);
+
+                    // 1. send Publish to Client
+                    publish_message_qos(
+                        cache_manager,
+                        connection_manager,
+                        &sub_pub_param,
+                        sub_thread_stop_sx,
+                    )
+                    .await;
+
+                    // 2. wait PubRec ack
+                    if !wait_pub_rec(
+                        cache_manager,
+                        connection_manager,
+                        &sub_pub_param,
+                        sub_thread_stop_sx,
+                        &wait_ack_sx,
+                    )
+                    .await
+                    {
+                        return Ok(None);
+                    }
+
+                    inflight.state = InflightState::PubRel;
+                    inflight_save(cache_manager, &inflight);
+                }
+
+                // 3. send PubRel to Client
+                qos2_send_pubrel(
+                    cache_manager,
+                    &sub_pub_param,
+                    connection_manager,
+                    sub_thread_stop_sx,
+                )
+                .await;
+
+                // 4. wait PubComp ack
+                if !wait_pub_comp(
                     cache_manager,
                     connection_manager,
                     &sub_pub_param,
                     sub_thread_stop_sx,
                     &wait_ack_sx,
                 )
-                .await;
+                .await
+                {
+                    return Ok(None);
+                }
+                inflight_delete(cache_manager, &inflight.client_id, inflight.pkid);
 
                 cache_manager.remove_pkid_info(&client_id, pkid);
                 cache_manager.remove_ack_packet(&client_id, pkid);
@@ -321,6 +433,7 @@ async fn build_pub_message(
     subscriber: &Subscriber,
     cache_manager: &Arc<CacheManager>,
     sub_ids: &[usize],
+    resume_inflight: Option<&MqttInflightMessage>,
 ) -> Result<Option<SubPublishParam>, MqttBrokerError> {
     let msg = MqttMessage::decode_record(record.clone())?;
 
@@ -363,10 +476,15 @@ async fn build_pub_message(
         content_type: msg.content_type,
     };
 
-    let pkid = if *qos != QoS::AtMostOnce {
-        cache_manager.get_pkid(&subscriber.client_id).await
-    } else {
+    let pkid = if *qos == QoS::AtMostOnce {
         0
+    } else if let Some(inflight) = resume_inflight {
+        // a resumed message keeps its pkid and is flagged as a duplicate
+        cache_manager.add_pkid_info(&subscriber.client_id, inflight.pkid);
+        publish.dup = true;
+        inflight.pkid
+    } else {
+        cache_manager.get_pkid(&subscriber.client_id).await
     };
     publish.pkid = pkid;
 
@@ -384,13 +502,14 @@ async fn build_pub_message(
 // When the subscribed QOS is 1, we need to keep retrying to send the message to the client.
 // To avoid messages that are not successfully pushed to the client. When the client Session expires,
 // the push thread will exit automatically and will not attempt to push again.
+// Returns false if the push thread is stopped before the PubAck arrives.
 pub async fn exclusive_publish_message_qos1(
     metadata_cache: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     sub_pub_param: &SubPublishParam,
     stop_sx: &broadcast::Sender<bool>,
     wait_puback_sx: &broadcast::Sender<QosAckPackageData>,
-) {
+) -> bool {
     // 1. send Publish to Client
     publish_message_qos(metadata_cache, connection_manager, sub_pub_param, stop_sx).await;
 
@@ -402,7 +521,7 @@ pub async fn exclusive_publish_message_qos1(
         stop_sx,
         wait_puback_sx,
     )
-    .await;
+    .await
 }
 
 // send publish message
@@ -415,19 +534,22 @@ pub async fn exclusive_publish_message_qos2(
     sub_pub_param: &SubPublishParam,
     stop_sx: &broadcast::Sender<bool>,
     wait_ack_sx: &broadcast::Sender<QosAckPackageData>,
-) {
+) -> bool {
     // 1. send Publish to Client
     publish_message_qos(metadata_cache, connection_manager, sub_pub_param, stop_sx).await;
 
     // 2. wait PubRec ack
-    wait_pub_rec(
+    if !wait_pub_rec(
         metadata_cache,
         connection_manager,
         sub_pub_param,
         stop_sx,
         wait_ack_sx,
     )
-    .await;
+    .await
+    {
+        return false;
+    }
 
     // 3. send PubRel to Client
     qos2_send_pubrel(metadata_cache, sub_pub_param, connection_manager, stop_sx).await;
@@ -440,7 +562,7 @@ pub async fn exclusive_publish_message_qos2(
         stop_sx,
         wait_ack_sx,
     )
-    .await;
+    .await
 }
 
 fn build_group_name(subscriber: &Subscriber) -> String {
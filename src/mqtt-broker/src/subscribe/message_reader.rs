@@ -0,0 +1,215 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::error::common::CommonError;
+use metadata_struct::adapter::record::Record;
+use storage_adapter::storage::StorageAdapter;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::broadcast::Receiver;
+use tokio::time::timeout;
+
+use super::subscribe_manager::SubscribeManager;
+use crate::storage::message::MessageStorage;
+
+// How long a read waits for a notification before it checks the storage, which also bounds how
+// long the caller goes without reporting its heartbeat
+const READ_WAIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reads the messages of one topic in offset order.
+///
+/// Messages saved by this broker arrive in memory from `SubscribeManager::notify_topic_message`.
+/// The storage is read to catch up: when the reader starts, when it lags behind or loses the
+/// notification channel, and when a notification skips offsets. A wait that times out also checks
+/// the storage once, for the messages saved without a notification: those published on other
+/// brokers, last wills, `$SYS` messages and delayed messages.
+pub struct TopicMessageReader<S> {
+    message_storage: MessageStorage<S>,
+    topic_id: String,
+    message_rx: Receiver<Record>,
+    caught_up: bool,
+}
+
+impl<S> TopicMessageReader<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        subscribe_manager: &Arc<SubscribeManager>,
+        message_storage: MessageStorage<S>,
+        topic_id: &str,
+    ) -> Self {
+        // subscribe before the first storage read, so no message falls in between
+        let message_rx = subscribe_manager.subscribe_topic_message(topic_id);
+        TopicMessageReader {
+            message_storage,
+            topic_id: topic_id.to_owned(),
+            message_rx,
+            caught_up: false,
+        }
+    }
+
+    /// at most `record_num` messages starting at `offset`, empty when none arrived in time
+    pub async fn read(&mut self, offset: u64, record_num: u64) -> Result<Vec<Record>, CommonError> {
+        if !self.caught_up {
+            let records = self
+                .message_storage
+                .read_topic_message(&self.topic_id, offset, record_num)
+                .await?;
+            if !records.is_empty() {
+                return Ok(records);
+            }
+            self.caught_up = true;
+        }
+
+        loop {
+            let record = match timeout(READ_WAIT_INTERVAL, self.message_rx.recv()).await {
+                Ok(Ok(record)) => record,
+                Ok(Err(RecvError::Lagged(_))) | Ok(Err(RecvError::Closed)) => {
+                    self.caught_up = false;
+                    return Ok(Vec::new());
+                }
+                Err(_) => {
+                    return self
+                        .message_storage
+                        .read_topic_message(&self.topic_id, offset, record_num)
+                        .await;
+                }
+            };
+
+            let Some(record_offset) = record.offset else {
+                continue;
+            };
+            if record_offset < offset {
+                continue;
+            }
+            if record_offset > offset {
+                // messages in between were missed, read them from the storage
+                self.caught_up = false;
+                return Ok(Vec::new());
+            }
+
+            let mut records = vec![record];
+            while (records.len() as u64) < record_num {
+                match self.message_rx.try_recv() {
+                    Ok(record) if record.offset == Some(offset + records.len() as u64) => {
+                        records.push(record)
+                    }
+                    Ok(_) | Err(TryRecvError::Lagged(_)) => {
+                        self.caught_up = false;
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            }
+            return Ok(records);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use common_base::config::broker_mqtt::init_broker_mqtt_conf_by_path;
+    use metadata_struct::adapter::record::Record;
+    use storage_adapter::memory::MemoryStorageAdapter;
+
+    use super::TopicMessageReader;
+    use crate::storage::message::MessageStorage;
+    use crate::subscribe::subscribe_manager::SubscribeManager;
+
+    #[tokio::test]
+    async fn topic_message_reader_test() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        let message_storage = MessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+        let topic_id = "t1";
+
+        // messages saved before the reader starts are read from the storage
+        let offsets = message_storage
+            .append_topic_message(topic_id, vec![Record::build_str("m0".to_string())])
+            .await
+            .unwrap();
+        assert_eq!(offsets, vec![0]);
+
+        let mut reader =
+            TopicMessageReader::new(&subscribe_manager, message_storage.clone(), topic_id);
+        let records = reader.read(0, 10).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].offset, Some(0));
+
+        // later messages arrive from the notification
+        for (offset, data) in [(1, "m1"), (2, "m2")] {
+            let mut record = Record::build_str(data.to_string());
+            record.offset = Some(offset);
+            subscribe_manager.notify_topic_message(topic_id, record);
+        }
+        let records = reader.read(1, 10).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].data, b"m2".to_vec());
+
+        // an idle topic keeps the reader on the notification
+        assert!(reader.read(3, 10).await.unwrap().is_empty());
+        assert!(reader.caught_up);
+
+        // a notification that skips an offset sends the reader back to the storage
+        let mut record = Record::build_str("m4".to_string());
+        record.offset = Some(4);
+        subscribe_manager.notify_topic_message(topic_id, record);
+        assert!(reader.read(3, 10).await.unwrap().is_empty());
+        assert!(!reader.caught_up);
+
+        // the channel of a topic is removed once it has no reader
+        drop(reader);
+        subscribe_manager.notify_topic_message(topic_id, Record::build_str("m5".to_string()));
+        assert!(!subscribe_manager.topic_message_sx.contains_key(topic_id));
+    }
+
+    #[tokio::test]
+    async fn topic_message_reader_without_notification_test() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        let message_storage = MessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+        let topic_id = "t1";
+
+        let mut reader =
+            TopicMessageReader::new(&subscribe_manager, message_storage.clone(), topic_id);
+        assert!(reader.read(0, 10).await.unwrap().is_empty());
+        assert!(reader.caught_up);
+
+        // a message saved without a notification, as by another broker, is read from the
+        // storage once the wait times out
+        message_storage
+            .append_topic_message(topic_id, vec![Record::build_str("m0".to_string())])
+            .await
+            .unwrap();
+        let records = reader.read(0, 10).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].offset, Some(0));
+        assert!(reader.caught_up);
+    }
+}
@@ -13,8 +13,11 @@
 // limitations under the License.
 
 pub mod exclusive_push;
+pub mod message_reader;
 pub mod share_follower_resub;
 pub mod share_leader_push;
+pub mod share_strategy;
 pub mod sub_common;
 pub mod subscribe_manager;
 pub mod subscriber;
+pub mod topic_trie;
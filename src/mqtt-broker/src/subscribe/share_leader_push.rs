@@ -25,9 +25,11 @@
 use tokio::sync::broadcast::{self, Sender};
 use tokio::time::sleep;
 
+use super::message_reader::TopicMessageReader;
+use super::share_strategy::{share_group_name, ShareSubDispatcher};
 use super::sub_common::{
     loop_commit_offset, min_qos, publish_message_qos, publish_message_to_client, qos2_send_pubrel,
-    wait_packet_ack,
+    wait_packet_ack, wait_packet_ack_while_connected,
 };
 use super::subscribe_manager::{ShareLeaderSubscribeData, SubscribeManager};
 use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType, QosAckPacketInfo};
@@ -118,17 +120,13 @@ pub async fn start_push_thread(&self) {
                 .share_leader_push_thread
                 .contains_key(&share_leader_key)
             {
-                self.push_by_round_robin(
-                    share_leader_key,
-                    sub_data,
-                    self.subscribe_manager.clone(),
-                )
-                .await;
+                self.push_by_strategy(share_leader_key, sub_data, self.subscribe_manager.clone())
+                    .await;
             }
         }
     }
 
-    async fn push_by_round_robin(
+    async fn push_by_strategy(
         &self,
         share_leader_key: String,
         sub_data: ShareLeaderSubscribeData,
@@ -140,9 +138,13 @@ async fn push_by_round_robin(
             "system_sub_{}_{}_{}",
             sub_data.group_name, sub_data.sub_name, sub_data.topic_id
         );
-        let cursor_point = 0;
 
         let message_storage = MessageStorage::new(self.message_storage.clone());
+        let mut message_reader = TopicMessageReader::new(
+            &subscribe_manager,
+            message_storage.clone(),
+            &sub_data.topic_id,
+        );
 
         // get current offset by group
         let mut offset = match message_storage.get_group_offset(&group_id).await {
@@ -169,6 +171,7 @@ async fn push_by_round_robin(
 
             let mut sub_list: Vec<Subscriber> =
                 build_share_leader_sub_list(&subscribe_manager, &share_leader_key);
+            let mut dispatcher = ShareSubDispatcher::new();
             let mut pre_times = now_second();
             loop {
                 select! {
@@ -187,10 +190,11 @@ async fn push_by_round_robin(
                         &connection_manager,
                         &cache_manager,
                         &message_storage,
+                        &mut message_reader,
                         &sub_data,
                         &sub_list,
                         &group_id,
-                        cursor_point,
+                        &mut dispatcher,
                         offset,
                         &sub_thread_stop_sx
                     ) =>{
@@ -233,10 +237,11 @@ async fn read_message_process<S>(
     connection_manager: &Arc<ConnectionManager>,
     cache_manager: &Arc<CacheManager>,
     message_storage: &MessageStorage<S>,
+    message_reader: &mut TopicMessageReader<S>,
     sub_data: &ShareLeaderSubscribeData,
     sub_list: &[Subscriber],
     group_id: &str,
-    mut cursor_point: usize,
+    dispatcher: &mut ShareSubDispatcher,
     offset: u64,
     stop_sx: &Sender<bool>,
 ) -> Result<Option<u64>, MqttBrokerError>
@@ -245,9 +250,7 @@ async fn read_message_process<S>(
 {
     let record_num = calc_record_num(sub_list.len());
 
-    let results = message_storage
-        .read_topic_message(&sub_data.topic_id, offset, record_num as u64)
-        .await?;
+    let results = message_reader.read(offset, record_num as u64).await?;
 
     if results.is_empty() {
         return Ok(None);
@@ -260,19 +263,28 @@ async fn read_message_process<S>(
             continue;
         }
 
-        let mut loop_times = 0;
+        let strategy = cache_manager.get_shared_subscription_strategy(&share_group_name(
+            sub_list.first().map_or("", |sub| sub.sub_path.as_str()),
+        ));
+
+        // members that failed to take the message, it goes to another member of the group
+        let mut tried: Vec<String> = Vec::new();
         loop {
-            if loop_times > try_loop_times(sub_list.len()) {
+            let Some(index) = dispatcher.choose(
+                strategy,
+                sub_list,
+                &msg.client_id,
+                &sub_data.topic_name,
+                |sub| {
+                    !tried.contains(&sub.client_id)
+                        && cache_manager.get_connect_id(&sub.client_id).is_some()
+                },
+                |sub| cache_manager.get_inflight_num(&sub.client_id),
+            ) else {
                 error!("Share subscription push message fails, dropping the message, possibly because no subscriber is available");
                 break;
-            }
-
-            cursor_point = choose_available_sub(cursor_point, sub_list);
-            let subscribe = if let Some(sub) = sub_list.get(cursor_point) {
-                sub.clone()
-            } else {
-                continue;
             };
+            let subscribe = sub_list[index].clone();
 
             if let Some((mut publish, properties)) =
                 build_publish(cache_manager, &subscribe, &sub_data.topic_name, &msg)
@@ -307,7 +319,7 @@ async fn read_message_process<S>(
                     break;
                 }
             }
-            loop_times += 1;
+            tried.push(subscribe.client_id);
         }
 
         // commit offset
@@ -322,19 +334,6 @@ async fn read_message_process<S>(
     Ok(results.last().unwrap().offset)
 }
 
-fn try_loop_times(sub_len: usize) -> usize {
-    sub_len * 2
-}
-
-fn choose_available_sub(cursor_point: usize, sub_list: &[Subscriber]) -> usize {
-    let current_point = cursor_point + 1;
-    if current_point < sub_list.len() {
-        current_point
-    } else {
-        0
-    }
-}
-
 async fn qos_publish<S>(
     connection_manager: &Arc<ConnectionManager>,
     cache_manager: &Arc<CacheManager>,
@@ -385,6 +384,10 @@ async fn qos_publish<S>(
                         sub_pub_param.subscribe.client_id.clone(),
                         e.to_string()
                     );
+                    cache_manager
+                        .remove_pkid_info(&sub_pub_param.subscribe.client_id, sub_pub_param.pkid);
+                    cache_manager
+                        .remove_ack_packet(&sub_pub_param.subscribe.client_id, sub_pub_param.pkid);
                     false
                 }
             }
@@ -414,7 +417,15 @@ async fn qos_publish<S>(
             {
                 Ok(()) => true,
                 Err(e) => {
-                    error!("{}", e);
+                    error!(
+                        "SharSub Leader failed to send QOS2 message to {}, error message :{},trying to deliver the message to another client.",
+                        sub_pub_param.subscribe.client_id.clone(),
+                        e.to_string()
+                    );
+                    cache_manager
+                        .remove_pkid_info(&sub_pub_param.subscribe.client_id, sub_pub_param.pkid);
+                    cache_manager
+                        .remove_ack_packet(&sub_pub_param.subscribe.client_id, sub_pub_param.pkid);
                     false
                 }
             }
@@ -525,7 +536,13 @@ async fn share_leader_publish_message_qos1(
     .await
     {
         Ok(_) => {
-            if let Some(data) = wait_packet_ack(wait_puback_sx).await {
+            if let Some(data) = wait_packet_ack_while_connected(
+                metadata_cache,
+                &sub_pub_param.subscribe.client_id,
+                wait_puback_sx,
+            )
+            .await
+            {
                 if data.ack_type == QosAckPackageType::PubAck && data.pkid == sub_pub_param.pkid {
                     return Ok(());
                 }
@@ -569,7 +586,13 @@ async fn share_leader_publish_message_qos2<S>(
                 return Ok(());
             }
         }
-        if let Some(data) = wait_packet_ack(wait_ack_sx).await {
+        if let Some(data) = wait_packet_ack_while_connected(
+            cache_manager,
+            &sub_pub_param.subscribe.client_id,
+            wait_ack_sx,
+        )
+        .await
+        {
             if data.ack_type == QosAckPackageType::PubRec && data.pkid == sub_pub_param.pkid {
                 // When sending a QOS2 message, as long as the pubrec is received, the offset can be submitted,
                 // the pubrel is sent asynchronously, and the pubcomp is waited for. Push the next message at the same time.
@@ -630,6 +653,8 @@ fn build_share_leader_sub_list(
     for (_, sub) in sub_list {
         result.push(sub);
     }
+    // keep the order stable, so that the hash strategies map to the same member
+    result.sort_by(|a, b| a.client_id.cmp(&b.client_id));
     result
 }
 
@@ -0,0 +1,259 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use metadata_struct::mqtt::cluster::SharedSubscriptionStrategy;
+use rand::Rng;
+
+use super::sub_common::{decode_share_info, is_share_sub};
+use super::subscriber::Subscriber;
+
+/// Chooses the member of a shared subscription group that receives a message. One dispatcher
+/// lives as long as the push thread of the group, so round robin and sticky keep their state
+/// across messages.
+#[derive(Default)]
+pub struct ShareSubDispatcher {
+    cursor: usize,
+    sticky_client_id: Option<String>,
+}
+
+impl ShareSubDispatcher {
+    pub fn new() -> Self {
+        ShareSubDispatcher::default()
+    }
+
+    /// the index in `sub_list` of the member that receives a message of `publisher` on
+    /// `topic_name`, chosen among the members for which `available` holds
+    pub fn choose(
+        &mut self,
+        strategy: SharedSubscriptionStrategy,
+        sub_list: &[Subscriber],
+        publisher: &str,
+        topic_name: &str,
+        available: impl Fn(&Subscriber) -> bool,
+        inflight: impl Fn(&Subscriber) -> usize,
+    ) -> Option<usize> {
+        let candidates: Vec<usize> = (0..sub_list.len())
+            .filter(|index| available(&sub_list[*index]))
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let index = match strategy {
+            SharedSubscriptionStrategy::RoundRobin => {
+                self.cursor = self.cursor.wrapping_add(1);
+                candidates[self.cursor % candidates.len()]
+            }
+            SharedSubscriptionStrategy::Random => {
+                candidates[rand::thread_rng().gen_range(0..candidates.len())]
+            }
+            SharedSubscriptionStrategy::HashClientId => {
+                candidates[hash_index(publisher, candidates.len())]
+            }
+            SharedSubscriptionStrategy::HashTopic => {
+                candidates[hash_index(topic_name, candidates.len())]
+            }
+            SharedSubscriptionStrategy::Sticky => {
+                let sticky = self.sticky_client_id.as_ref().and_then(|client_id| {
+                    candidates
+                        .iter()
+                        .find(|index| sub_list[**index].client_id == *client_id)
+                });
+                match sticky {
+                    Some(index) => *index,
+                    None => {
+                        let index = candidates[rand::thread_rng().gen_range(0..candidates.len())];
+                        self.sticky_client_id = Some(sub_list[index].client_id.clone());
+                        index
+                    }
+                }
+            }
+            SharedSubscriptionStrategy::LeastInflight => {
+                // start from the cursor, so members with the same inflight take turns
+                self.cursor = self.cursor.wrapping_add(1);
+                let start = self.cursor % candidates.len();
+                let mut rotated = candidates[start..].to_vec();
+                rotated.extend_from_slice(&candidates[..start]);
+                *rotated
+                    .iter()
+                    .min_by_key(|index| inflight(&sub_list[**index]))
+                    .unwrap()
+            }
+        };
+        Some(index)
+    }
+}
+
+/// the group name that selects the strategy of a `$share/{group}/...` subscription
+pub fn share_group_name(sub_path: &str) -> String {
+    if is_share_sub(sub_path) {
+        let (group_name, _) = decode_share_info(sub_path);
+        return group_name;
+    }
+    "".to_string()
+}
+
+fn hash_index(value: &str, len: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    (hasher.finish() % len as u64) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use metadata_struct::mqtt::cluster::SharedSubscriptionStrategy;
+
+    use super::{share_group_name, ShareSubDispatcher};
+    use crate::subscribe::subscriber::Subscriber;
+
+    fn build_sub_list() -> Vec<Subscriber> {
+        ["c1", "c2", "c3"]
+            .iter()
+            .map(|client_id| Subscriber {
+                client_id: client_id.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn choose(
+        dispatcher: &mut ShareSubDispatcher,
+        strategy: SharedSubscriptionStrategy,
+        sub_list: &[Subscriber],
+        publisher: &str,
+        offline: &str,
+    ) -> String {
+        let index = dispatcher
+            .choose(
+                strategy,
+                sub_list,
+                publisher,
+                "/sensor/1",
+                |sub| sub.client_id != offline,
+                |sub| if sub.client_id == "c1" { 0 } else { 5 },
+            )
+            .unwrap();
+        sub_list[index].client_id.clone()
+    }
+
+    #[test]
+    fn round_robin_test() {
+        let sub_list = build_sub_list();
+        let mut dispatcher = ShareSubDispatcher::new();
+        let strategy = SharedSubscriptionStrategy::RoundRobin;
+        let chosen: Vec<String> = (0..6)
+            .map(|_| choose(&mut dispatcher, strategy, &sub_list, "p1", ""))
+            .collect();
+        assert_eq!(chosen, vec!["c2", "c3", "c1", "c2", "c3", "c1"]);
+
+        // an unavailable member is skipped
+        for _ in 0..6 {
+            assert_ne!(
+                choose(&mut dispatcher, strategy, &sub_list, "p1", "c2"),
+                "c2"
+            );
+        }
+    }
+
+    #[test]
+    fn hash_test() {
+        let sub_list = build_sub_list();
+        let mut dispatcher = ShareSubDispatcher::new();
+        for strategy in [
+            SharedSubscriptionStrategy::HashClientId,
+            SharedSubscriptionStrategy::HashTopic,
+        ] {
+            let first = choose(&mut dispatcher, strategy, &sub_list, "p1", "");
+            for _ in 0..10 {
+                assert_eq!(
+                    choose(&mut dispatcher, strategy, &sub_list, "p1", ""),
+                    first
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sticky_test() {
+        let sub_list = build_sub_list();
+        let mut dispatcher = ShareSubDispatcher::new();
+        let strategy = SharedSubscriptionStrategy::Sticky;
+        let first = choose(&mut dispatcher, strategy, &sub_list, "p1", "");
+        for _ in 0..10 {
+            assert_eq!(
+                choose(&mut dispatcher, strategy, &sub_list, "p2", ""),
+                first
+            );
+        }
+
+        // the member sticks until it goes away
+        let second = choose(&mut dispatcher, strategy, &sub_list, "p1", &first);
+        assert_ne!(second, first);
+        assert_eq!(
+            choose(&mut dispatcher, strategy, &sub_list, "p1", ""),
+            second
+        );
+    }
+
+    #[test]
+    fn least_inflight_test() {
+        let sub_list = build_sub_list();
+        let mut dispatcher = ShareSubDispatcher::new();
+        let strategy = SharedSubscriptionStrategy::LeastInflight;
+        for _ in 0..3 {
+            assert_eq!(choose(&mut dispatcher, strategy, &sub_list, "p1", ""), "c1");
+        }
+        assert_ne!(
+            choose(&mut dispatcher, strategy, &sub_list, "p1", "c1"),
+            "c1"
+        );
+    }
+
+    #[test]
+    fn random_test() {
+        let sub_list = build_sub_list();
+        let mut dispatcher = ShareSubDispatcher::new();
+        for _ in 0..10 {
+            let client_id = choose(
+                &mut dispatcher,
+                SharedSubscriptionStrategy::Random,
+                &sub_list,
+                "p1",
+                "c3",
+            );
+            assert_ne!(client_id, "c3");
+        }
+
+        let mut dispatcher = ShareSubDispatcher::new();
+        assert!(dispatcher
+            .choose(
+                SharedSubscriptionStrategy::Random,
+                &sub_list,
+                "p1",
+                "/sensor/1",
+                |_| false,
+                |_| 0,
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn share_group_name_test() {
+        assert_eq!(share_group_name("$share/g1/sensor/+"), "g1");
+        assert_eq!(share_group_name("$queue/sensor/+"), "");
+    }
+}
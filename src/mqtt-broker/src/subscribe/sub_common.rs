@@ -19,7 +19,7 @@
 use bytes::BytesMut;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::error::common::CommonError;
-use common_base::tools::now_mills;
+use common_base::tools::{now_mills, now_second};
 use grpc_clients::placement::mqtt::call::placement_get_share_sub_leader;
 use grpc_clients::pool::ClientPool;
 use log::{error, warn};
@@ -35,6 +35,7 @@
 use tokio::time::{sleep, timeout};
 
 use super::subscriber::SubPublishParam;
+use super::topic_trie::topic_match_filter;
 use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType};
 use crate::handler::error::MqttBrokerError;
 use crate::observability::slow::sub::{record_slow_sub_data, SlowSubData};
@@ -69,16 +70,9 @@ pub fn sub_path_validator(sub_path: String) -> bool {
     true
 }
 
+/// whether the topic matches the subscription path, either of which may carry a `$share` or
+/// `$queue` prefix
 pub fn path_regex_match(topic_name: &str, sub_path: &str) -> bool {
-    let path = if is_share_sub(sub_path) {
-        let (_, group_path) = decode_share_info(sub_path);
-        group_path
-    } else if is_queue_sub(sub_path) {
-        decode_queue_info(sub_path)
-    } else {
-        sub_path.to_owned()
-    };
-
     let topic = if is_share_sub(topic_name) {
         let (_, group_path) = decode_share_info(topic_name);
         group_path
@@ -88,27 +82,7 @@ pub fn path_regex_match(topic_name: &str, sub_path: &str) -> bool {
         topic_name.to_owned()
     };
 
-    // Path perfect matching
-    if topic == path {
-        return true;
-    }
-
-    if path.contains("+") {
-        let sub_regex = path.replace("+", "[^+*/]+");
-        let re = Regex::new(&sub_regex.to_string()).unwrap();
-        return re.is_match(&topic);
-    }
-
-    if path.contains("#") {
-        if path.split("/").last().unwrap() != "#" {
-            return false;
-        }
-        let sub_regex = path.replace("#", "[^+#]+");
-        let re = Regex::new(&sub_regex.to_string()).unwrap();
-        return re.is_match(&topic);
-    }
-
-    false
+    topic_match_filter(&topic, sub_path)
 }
 
 pub fn min_qos(qos: QoS, sub_qos: QoS) -> QoS {
@@ -122,13 +96,7 @@ pub async fn get_sub_topic_id_list(
     metadata_cache: &Arc<CacheManager>,
     sub_path: &str,
 ) -> Vec<String> {
-    let mut result = Vec::new();
-    for (topic_id, topic_name) in metadata_cache.topic_id_name.clone() {
-        if path_regex_match(&topic_name, sub_path) {
-            result.push(topic_id);
-        }
-    }
-    result
+    metadata_cache.topic_trie.match_filter(sub_path)
 }
 
 pub fn is_share_sub(sub_name: &str) -> bool {
@@ -179,6 +147,32 @@ pub async fn wait_packet_ack(sx: &Sender<QosAckPackageData>) -> Option<QosAckPac
     (res.await).unwrap_or_default()
 }
 
+/// Like `wait_packet_ack`, but gives up as soon as the client disconnects, so that a shared
+/// subscription can deliver the message to another member of the group.
+pub async fn wait_packet_ack_while_connected(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    sx: &Sender<QosAckPackageData>,
+) -> Option<QosAckPackageData> {
+    let mut ack_rx = sx.subscribe();
+    let res = timeout(Duration::from_secs(120), async {
+        loop {
+            select! {
+                val = ack_rx.recv() => {
+                    return val.ok();
+                }
+                _ = sleep(Duration::from_secs(1)) => {
+                    if cache_manager.get_connect_id(client_id).is_none() {
+                        return None;
+                    }
+                }
+            }
+        }
+    });
+
+    (res.await).unwrap_or_default()
+}
+
 pub async fn publish_message_to_client(
     resp: ResponsePackage,
     sub_pub_param: &SubPublishParam,
@@ -224,15 +218,22 @@ pub async fn publish_message_to_client(
     Ok(())
 }
 
+/// false if the push thread is stopped before the PubAck arrives
 pub async fn wait_pub_ack(
     metadata_cache: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     sub_pub_param: &SubPublishParam,
     stop_sx: &broadcast::Sender<bool>,
     wait_ack_sx: &broadcast::Sender<QosAckPackageData>,
-) {
+) -> bool {
     let wait_pub_rec_fn = async || -> Result<(), MqttBrokerError> {
-        match timeout(Duration::from_secs(30), wait_packet_ack(wait_ack_sx)).await {
+        match wait_packet_ack_or_resend(
+            metadata_cache,
+            &sub_pub_param.subscribe.client_id,
+            wait_ack_sx,
+        )
+        .await
+        {
             Ok(Some(data)) => {
                 if data.ack_type == QosAckPackageType::PubAck && data.pkid == sub_pub_param.pkid {
                     return Ok(());
@@ -240,15 +241,17 @@ pub async fn wait_pub_ack(
             }
             Ok(None) => {}
             Err(e) => {
-                publish_message_qos(metadata_cache, connection_manager, sub_pub_param, stop_sx)
-                    .await;
-                return Err(MqttBrokerError::CommonError(
-                    format!(
-                        "Push QOS1 Publish message to client {}, wait PubAck timeout, more than 30s, error message :{:?}",
-                        sub_pub_param.subscribe.client_id,
-                        e
-                    )
-                ));
+                publish_message_qos(
+                    metadata_cache,
+                    connection_manager,
+                    &retransmit_param(sub_pub_param),
+                    stop_sx,
+                )
+                .await;
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Push QOS1 Publish message to client {}, wait PubAck failed, {}",
+                    sub_pub_param.subscribe.client_id, e
+                )));
             }
         };
 
@@ -264,7 +267,7 @@ pub async fn wait_pub_ack(
             val = stop_recv.recv() => {
                 if let Ok(flag) = val {
                     if flag {
-                        return;
+                        return false;
                     }
                 }
             }
@@ -274,21 +277,28 @@ pub async fn wait_pub_ack(
                     sleep(Duration::from_secs(1)).await;
                     continue;
                 }
-                break;
+                return true;
             }
         }
     }
 }
 
+/// false if the push thread is stopped before the PubRec arrives
 pub async fn wait_pub_rec(
     metadata_cache: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     sub_pub_param: &SubPublishParam,
     stop_sx: &broadcast::Sender<bool>,
     wait_ack_sx: &broadcast::Sender<QosAckPackageData>,
-) {
+) -> bool {
     let wait_pub_rec_fn = async || -> Result<(), MqttBrokerError> {
-        match timeout(Duration::from_secs(30), wait_packet_ack(wait_ack_sx)).await {
+        match wait_packet_ack_or_resend(
+            metadata_cache,
+            &sub_pub_param.subscribe.client_id,
+            wait_ack_sx,
+        )
+        .await
+        {
             Ok(Some(data)) => {
                 if data.ack_type == QosAckPackageType::PubRec && data.pkid == sub_pub_param.pkid {
                     return Ok(());
@@ -296,15 +306,17 @@ pub async fn wait_pub_rec(
             }
             Ok(None) => {}
             Err(e) => {
-                publish_message_qos(metadata_cache, connection_manager, sub_pub_param, stop_sx)
-                    .await;
-                return Err(MqttBrokerError::CommonError(
-                    format!(
-                        "Push QOS2 Publish message to client {}, wait pubrec timeout, more than 30s, error message :{:?}",
-                        sub_pub_param.subscribe.client_id,
-                        e
-                    )
-                ));
+                publish_message_qos(
+                    metadata_cache,
+                    connection_manager,
+                    &retransmit_param(sub_pub_param),
+                    stop_sx,
+                )
+                .await;
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Push QOS2 Publish message to client {}, wait PubRec failed, {}",
+                    sub_pub_param.subscribe.client_id, e
+                )));
             }
         };
 
@@ -320,7 +332,7 @@ pub async fn wait_pub_rec(
             val = stop_recv.recv() => {
                 if let Ok(flag) = val {
                     if flag {
-                        return;
+                        return false;
                     }
                 }
             }
@@ -330,21 +342,28 @@ pub async fn wait_pub_rec(
                     sleep(Duration::from_secs(1)).await;
                     continue;
                 }
-                break;
+                return true;
             }
         }
     }
 }
 
+/// false if the push thread is stopped before the PubComp arrives
 pub async fn wait_pub_comp(
     metadata_cache: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     sub_pub_param: &SubPublishParam,
     stop_sx: &broadcast::Sender<bool>,
     wait_ack_sx: &broadcast::Sender<QosAckPackageData>,
-) {
+) -> bool {
     let wait_pub_rec_fn = async || -> Result<(), MqttBrokerError> {
-        match timeout(Duration::from_secs(30), wait_packet_ack(wait_ack_sx)).await {
+        match wait_packet_ack_or_resend(
+            metadata_cache,
+            &sub_pub_param.subscribe.client_id,
+            wait_ack_sx,
+        )
+        .await
+        {
             Ok(Some(data)) => {
                 if data.ack_type == QosAckPackageType::PubComp && data.pkid == sub_pub_param.pkid {
                     return Ok(());
@@ -353,13 +372,10 @@ pub async fn wait_pub_comp(
             Ok(None) => {}
             Err(e) => {
                 qos2_send_pubrel(metadata_cache, sub_pub_param, connection_manager, stop_sx).await;
-                return Err(MqttBrokerError::CommonError(
-                    format!(
-                        "Push QOS2 Publish message to client {}, wait PubComp timeout, more than 30s, error message :{:?}",
-                        sub_pub_param.subscribe.client_id,
-                        e
-                    )
-                ));
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Push QOS2 Publish message to client {}, wait PubComp failed, {}",
+                    sub_pub_param.subscribe.client_id, e
+                )));
             }
         };
 
@@ -375,7 +391,7 @@ pub async fn wait_pub_comp(
             val = stop_recv.recv() => {
                 if let Ok(flag) = val {
                     if flag {
-                        return;
+                        return false;
                     }
                 }
             }
@@ -385,12 +401,47 @@ pub async fn wait_pub_comp(
                     sleep(Duration::from_secs(1)).await;
                     continue;
                 }
-                break;
+                return true;
+            }
+        }
+    }
+}
+
+/// Waits for the ack of a packet pushed to the client. Fails after 30s, or as soon as the client
+/// has reconnected, so that the packet is sent again without waiting for the timeout.
+async fn wait_packet_ack_or_resend(
+    metadata_cache: &Arc<CacheManager>,
+    client_id: &str,
+    sx: &Sender<QosAckPackageData>,
+) -> Result<Option<QosAckPackageData>, String> {
+    let connect_id = metadata_cache.get_connect_id(client_id);
+    let start_time = now_second();
+    let mut ack_rx = sx.subscribe();
+    loop {
+        select! {
+            val = ack_rx.recv() => {
+                return Ok(val.ok());
+            }
+            _ = sleep(Duration::from_secs(1)) => {
+                let current_connect_id = metadata_cache.get_connect_id(client_id);
+                if current_connect_id.is_some() && current_connect_id != connect_id {
+                    return Err("the client has reconnected".to_string());
+                }
+                if now_second() - start_time >= 30 {
+                    return Err("timeout, more than 30s".to_string());
+                }
             }
         }
     }
 }
 
+// A PUBLISH that is sent again carries the DUP flag.
+fn retransmit_param(sub_pub_param: &SubPublishParam) -> SubPublishParam {
+    let mut param = sub_pub_param.clone();
+    param.publish.dup = true;
+    param
+}
+
 pub async fn qos2_send_pubrel(
     metadata_cache: &Arc<CacheManager>,
     sub_pub_param: &SubPublishParam,
@@ -562,17 +613,22 @@ pub async fn publish_message_qos(
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
+    use std::time::Duration;
 
     use common_base::tools::unique_id;
     use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
     use metadata_struct::mqtt::topic::MqttTopic;
     use protocol::mqtt::common::QoS;
+    use tokio::sync::broadcast;
+    use tokio::time::sleep;
 
-    use crate::handler::cache::CacheManager;
+    use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType};
     use crate::subscribe::sub_common::{
         decode_share_info, get_sub_topic_id_list, is_share_sub, min_qos, path_regex_match,
-        sub_path_validator,
+        retransmit_param, sub_path_validator, wait_packet_ack_or_resend,
     };
+    use crate::subscribe::subscriber::SubPublishParam;
 
     #[tokio::test]
     async fn is_share_sub_test() {
@@ -647,27 +703,27 @@ fn path_regex_match_test() {
         let sub_regex = r"/sensor/#".to_string();
         assert!(path_regex_match(&topic_name, &sub_regex));
 
-        let topic_name = "/topic/test".to_string();
+        let topic_name = "topic/test".to_string();
         let sub_regex = "$share/groupname/topic/test".to_string();
         assert!(path_regex_match(&topic_name, &sub_regex));
 
-        let topic_name = r"/sensor/1/temperature".to_string();
+        let topic_name = r"sensor/1/temperature".to_string();
         let sub_regex = r"$share/groupname/sensor/+/temperature".to_string();
         assert!(path_regex_match(&topic_name, &sub_regex));
 
-        let topic_name = r"/sensor/1/2/temperature3".to_string();
+        let topic_name = r"sensor/1/2/temperature3".to_string();
         let sub_regex = r"$share/groupname/sensor/+/temperature".to_string();
         assert!(!path_regex_match(&topic_name, &sub_regex));
 
-        let topic_name = r"/sensor/temperature3".to_string();
+        let topic_name = r"sensor/temperature3".to_string();
         let sub_regex = r"$share/groupname/sensor/+/temperature".to_string();
         assert!(!path_regex_match(&topic_name, &sub_regex));
 
-        let topic_name = r"/sensor/temperature3".to_string();
+        let topic_name = r"sensor/temperature3".to_string();
         let sub_regex = r"$share/groupname/sensor/+".to_string();
         assert!(path_regex_match(&topic_name, &sub_regex));
 
-        let topic_name = r"/sensor/temperature3/tmpq".to_string();
+        let topic_name = r"sensor/temperature3/tmpq".to_string();
         let sub_regex = r"$share/groupname/sensor/#".to_string();
         assert!(path_regex_match(&topic_name, &sub_regex));
     }
@@ -695,6 +751,13 @@ async fn get_sub_topic_list_test() {
         let result = get_sub_topic_id_list(&metadata_cache, &sub_path).await;
         assert!(result.len() == 1);
         assert_eq!(result.first().unwrap().clone(), topic.topic_id);
+
+        let result = get_sub_topic_id_list(&metadata_cache, "/test/+").await;
+        assert_eq!(result, vec![topic.topic_id.clone()]);
+
+        metadata_cache.delete_topic(&topic_name, &topic);
+        let result = get_sub_topic_id_list(&metadata_cache, "/test/#").await;
+        assert!(result.is_empty());
     }
 
     #[tokio::test]
@@ -726,4 +789,50 @@ async fn path_validator_test() {
         let path = "$share/loboxu/*test".to_string();
         assert!(!sub_path_validator(path));
     }
+
+    #[tokio::test]
+    async fn wait_packet_ack_or_resend_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(100));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test-cluster".to_string()));
+        let client_id = "c1".to_string();
+        let mut session = MqttSession::new(client_id.clone(), 60, false, None);
+        session.update_connnction_id(Some(1));
+        cache_manager.add_session(client_id.clone(), session);
+
+        let (sx, _) = broadcast::channel(1);
+        let ack_sx = sx.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            ack_sx
+                .send(QosAckPackageData {
+                    ack_type: QosAckPackageType::PubAck,
+                    pkid: 1,
+                })
+                .unwrap();
+        });
+        let data = wait_packet_ack_or_resend(&cache_manager, &client_id, &sx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.ack_type, QosAckPackageType::PubAck);
+        assert_eq!(data.pkid, 1);
+
+        // the client reconnects before acking, the packet is to be sent again
+        let reconnect_cache = cache_manager.clone();
+        let reconnect_client_id = client_id.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            reconnect_cache.update_session_connect_id(&reconnect_client_id, Some(2));
+        });
+        assert!(wait_packet_ack_or_resend(&cache_manager, &client_id, &sx)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn retransmit_param_test() {
+        let param = SubPublishParam::default();
+        assert!(!param.publish.dup);
+        assert!(retransmit_param(&param).publish.dup);
+    }
 }
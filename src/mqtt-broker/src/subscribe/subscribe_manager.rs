@@ -13,11 +13,15 @@
 // limitations under the License.
 
 use crate::subscribe::subscriber::Subscriber;
+use crate::subscribe::topic_trie::TopicTrie;
 use dashmap::DashMap;
+use metadata_struct::adapter::record::Record;
 use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
 use protocol::mqtt::common::{Filter, MqttProtocol};
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast::Sender;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+const TOPIC_MESSAGE_CHANNEL_SIZE: usize = 128;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ShareSubShareSub {
@@ -51,6 +55,9 @@ pub struct SubscribeManager {
     //(client_id_path: MqttSubscribe)
     pub subscribe_list: DashMap<String, MqttSubscribe>,
 
+    // filter levels of all subscribes, valued by client_id_path
+    pub subscribe_trie: TopicTrie,
+
     // (client_id_sub_name_topic_id, Subscriber)
     pub exclusive_push: DashMap<String, Subscriber>,
 
@@ -77,12 +84,16 @@ pub struct SubscribeManager {
 
     //(topic_id, Vec<TopicSubscribeInfo>)
     pub topic_subscribe_list: DashMap<String, Vec<TopicSubscribeInfo>>,
+
+    // (topic_id, Sender<Record>), messages saved by this broker, fanned out to the push threads
+    pub topic_message_sx: DashMap<String, Sender<Record>>,
 }
 
 impl SubscribeManager {
     pub fn new() -> Self {
         SubscribeManager {
             subscribe_list: DashMap::with_capacity(8),
+            subscribe_trie: TopicTrie::new(),
             exclusive_push: DashMap::with_capacity(8),
             share_leader_push: DashMap::with_capacity(8),
             share_follower_resub: DashMap::with_capacity(8),
@@ -92,12 +103,14 @@ pub fn new() -> Self {
             share_follower_resub_thread: DashMap::with_capacity(8),
             exclusive_subscribe: DashMap::with_capacity(8),
             topic_subscribe_list: DashMap::with_capacity(8),
+            topic_message_sx: DashMap::with_capacity(8),
         }
     }
 
     // subscribe info
     pub fn add_subscribe(&self, subscribe: MqttSubscribe) {
         let key = self.subscribe_key(&subscribe.client_id, &subscribe.path);
+        self.subscribe_trie.insert(&subscribe.path, &key);
         self.subscribe_list.insert(key, subscribe);
     }
 
@@ -112,12 +125,14 @@ pub fn get_subscribe(&self, client_id: &str, path: &str) -> Option<MqttSubscribe
     pub fn remove_subscribe(&self, client_id: &str, path: &str) {
         let key = self.subscribe_key(client_id, path);
         self.subscribe_list.remove(&key);
+        self.subscribe_trie.remove(path, &key);
     }
 
     pub fn remove_subscriber_by_client_id(&self, client_id: &str) {
         for (key, subscribe) in self.subscribe_list.clone() {
             if subscribe.client_id == *client_id {
                 self.subscribe_list.remove(&key);
+                self.subscribe_trie.remove(&subscribe.path, &key);
             }
         }
     }
@@ -268,6 +283,27 @@ pub fn remove_client_id(&self, client_id: &str) {
     }
 
     // key
+    // message notify
+    pub fn notify_topic_message(&self, topic_id: &str, record: Record) {
+        let no_receiver = if let Some(sx) = self.topic_message_sx.get(topic_id) {
+            sx.send(record).is_err()
+        } else {
+            false
+        };
+
+        if no_receiver {
+            self.topic_message_sx
+                .remove_if(topic_id, |_, sx| sx.receiver_count() == 0);
+        }
+    }
+
+    pub fn subscribe_topic_message(&self, topic_id: &str) -> Receiver<Record> {
+        self.topic_message_sx
+            .entry(topic_id.to_owned())
+            .or_insert_with(|| broadcast::channel(TOPIC_MESSAGE_CHANNEL_SIZE).0)
+            .subscribe()
+    }
+
     fn subscribe_key(&self, client_id: &str, path: &str) -> String {
         format!("{}_{}", client_id, path)
     }
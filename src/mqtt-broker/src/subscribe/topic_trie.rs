@@ -0,0 +1,312 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+const SINGLE_LEVEL_WILDCARD: &str = "+";
+const MULTI_LEVEL_WILDCARD: &str = "#";
+const SHARE_SUB_PREFIX: &str = "$share/";
+const QUEUE_SUB_PREFIX: &str = "$queue/";
+
+#[derive(Clone, Default)]
+struct TrieNode {
+    children: HashMap<String, TrieNode>,
+    values: HashSet<String>,
+}
+
+impl TrieNode {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.values.is_empty()
+    }
+
+    fn collect(&self, result: &mut Vec<String>) {
+        result.extend(self.values.iter().cloned());
+        for child in self.children.values() {
+            child.collect(result);
+        }
+    }
+
+    // walk the stored topic names that match the filter levels
+    fn match_filter(&self, levels: &[&str], is_root: bool, result: &mut Vec<String>) {
+        let Some((level, rest)) = levels.split_first() else {
+            result.extend(self.values.iter().cloned());
+            return;
+        };
+
+        match *level {
+            MULTI_LEVEL_WILDCARD => {
+                // "sport/#" also matches "sport"
+                result.extend(self.values.iter().cloned());
+                for (name, child) in self.children.iter() {
+                    if !(is_root && is_system_level(name)) {
+                        child.collect(result);
+                    }
+                }
+            }
+            SINGLE_LEVEL_WILDCARD => {
+                for (name, child) in self.children.iter() {
+                    if !(is_root && is_system_level(name)) {
+                        child.match_filter(rest, false, result);
+                    }
+                }
+            }
+            _ => {
+                if let Some(child) = self.children.get(*level) {
+                    child.match_filter(rest, false, result);
+                }
+            }
+        }
+    }
+
+    // walk the stored filters that match the topic levels
+    fn match_topic(&self, levels: &[&str], is_root: bool, result: &mut Vec<String>) {
+        let wildcard = !(is_root && levels.first().is_some_and(|level| is_system_level(level)));
+        if wildcard {
+            if let Some(child) = self.children.get(MULTI_LEVEL_WILDCARD) {
+                result.extend(child.values.iter().cloned());
+            }
+        }
+
+        let Some((level, rest)) = levels.split_first() else {
+            result.extend(self.values.iter().cloned());
+            return;
+        };
+
+        if let Some(child) = self.children.get(*level) {
+            child.match_topic(rest, false, result);
+        }
+        if wildcard {
+            if let Some(child) = self.children.get(SINGLE_LEVEL_WILDCARD) {
+                child.match_topic(rest, false, result);
+            }
+        }
+    }
+}
+
+/// A tree of topic levels, which indexes either topic names or subscription filters.
+///
+/// Topic names are looked up by a filter with `match_filter`, and filters are looked up by a topic
+/// name with `match_topic`. The `$share/{group}` and `$queue` prefixes of a filter are stripped
+/// before it is indexed or matched, and a filter that starts with a wildcard does not match
+/// topics whose first level starts with `$`.
+#[derive(Default)]
+pub struct TopicTrie {
+    root: RwLock<TrieNode>,
+}
+
+impl Clone for TopicTrie {
+    fn clone(&self) -> Self {
+        TopicTrie {
+            root: RwLock::new(self.root.read().unwrap().clone()),
+        }
+    }
+}
+
+impl TopicTrie {
+    pub fn new() -> Self {
+        TopicTrie::default()
+    }
+
+    pub fn insert(&self, path: &str, value: &str) {
+        let path = strip_sub_prefix(path);
+        let mut node = &mut *self.root.write().unwrap();
+        for level in path.split('/') {
+            node = node.children.entry(level.to_string()).or_default();
+        }
+        node.values.insert(value.to_string());
+    }
+
+    pub fn remove(&self, path: &str, value: &str) {
+        let path = strip_sub_prefix(path);
+        let levels: Vec<&str> = path.split('/').collect();
+        let mut root = self.root.write().unwrap();
+        remove_value(&mut root, &levels, value);
+    }
+
+    /// the values of the topic names indexed in the trie that match the filter
+    pub fn match_filter(&self, filter: &str) -> Vec<String> {
+        let filter = strip_sub_prefix(filter);
+        let levels: Vec<&str> = filter.split('/').collect();
+        let mut result = Vec::new();
+        self.root
+            .read()
+            .unwrap()
+            .match_filter(&levels, true, &mut result);
+        result
+    }
+
+    /// the values of the filters indexed in the trie that match the topic name
+    pub fn match_topic(&self, topic_name: &str) -> Vec<String> {
+        let levels: Vec<&str> = topic_name.split('/').collect();
+        let mut result = Vec::new();
+        self.root
+            .read()
+            .unwrap()
+            .match_topic(&levels, true, &mut result);
+        result
+    }
+}
+
+/// whether the topic name matches the filter, compared level by level
+pub fn topic_match_filter(topic_name: &str, filter: &str) -> bool {
+    let filter = strip_sub_prefix(filter);
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic_name.split('/');
+    let mut is_root = true;
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some(MULTI_LEVEL_WILDCARD), level) => {
+                return !(is_root && level.is_some_and(is_system_level));
+            }
+            (Some(SINGLE_LEVEL_WILDCARD), Some(level)) => {
+                if is_root && is_system_level(level) {
+                    return false;
+                }
+            }
+            (Some(filter_level), Some(level)) => {
+                if filter_level != level {
+                    return false;
+                }
+            }
+            (None, None) => return true,
+            _ => return false,
+        }
+        is_root = false;
+    }
+}
+
+fn remove_value(node: &mut TrieNode, levels: &[&str], value: &str) -> bool {
+    match levels.split_first() {
+        None => {
+            node.values.remove(value);
+        }
+        Some((level, rest)) => {
+            if let Some(child) = node.children.get_mut(*level) {
+                if remove_value(child, rest, value) {
+                    node.children.remove(*level);
+                }
+            }
+        }
+    }
+    node.is_empty()
+}
+
+// unlike decode_share_info and decode_queue_info, the filter is returned as it was subscribed,
+// without a "/" added in front of it
+fn strip_sub_prefix(path: &str) -> &str {
+    if let Some(rest) = path.strip_prefix(SHARE_SUB_PREFIX) {
+        return rest.split_once('/').map_or("", |(_, filter)| filter);
+    }
+    path.strip_prefix(QUEUE_SUB_PREFIX).unwrap_or(path)
+}
+
+fn is_system_level(level: &str) -> bool {
+    level.starts_with('$')
+}
+
+#[cfg(test)]
+mod test {
+    use super::{topic_match_filter, TopicTrie};
+
+    fn sorted(mut values: Vec<String>) -> Vec<String> {
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn match_filter_test() {
+        let trie = TopicTrie::new();
+        trie.insert("/sensor/1/temperature", "t1");
+        trie.insert("/sensor/2/temperature", "t2");
+        trie.insert("/sensor/1/humidity", "t3");
+        trie.insert("/sensor", "t4");
+        trie.insert("$SYS/broker/uptime", "t5");
+        trie.insert("sensor/1/temperature", "t6");
+
+        assert_eq!(trie.match_filter("/sensor/1/temperature"), vec!["t1"]);
+        assert_eq!(
+            sorted(trie.match_filter("/sensor/+/temperature")),
+            vec!["t1", "t2"]
+        );
+        assert_eq!(
+            sorted(trie.match_filter("/sensor/#")),
+            vec!["t1", "t2", "t3", "t4"]
+        );
+        assert!(trie.match_filter("/sensor/+").is_empty());
+        assert_eq!(
+            trie.match_filter("$share/g1/sensor/+/temperature"),
+            vec!["t6"]
+        );
+        assert_eq!(
+            sorted(trie.match_filter("$share/g1//sensor/+/temperature")),
+            vec!["t1", "t2"]
+        );
+        assert_eq!(trie.match_filter("$queue/sensor/1/+"), vec!["t6"]);
+
+        // topics starting with $ are not matched by a filter starting with a wildcard
+        assert_eq!(trie.match_filter("#").len(), 5);
+        assert!(trie.match_filter("+/broker/uptime").is_empty());
+        assert_eq!(trie.match_filter("$SYS/#"), vec!["t5"]);
+
+        trie.remove("/sensor/1/temperature", "t1");
+        assert_eq!(trie.match_filter("/sensor/+/temperature"), vec!["t2"]);
+        trie.remove("/sensor/2/temperature", "t2");
+        assert!(trie.match_filter("/sensor/+/temperature").is_empty());
+        assert_eq!(sorted(trie.match_filter("/sensor/#")), vec!["t3", "t4"]);
+    }
+
+    #[test]
+    fn match_topic_test() {
+        let trie = TopicTrie::new();
+        trie.insert("/sensor/1/temperature", "f1");
+        trie.insert("/sensor/+/temperature", "f2");
+        trie.insert("/sensor/#", "f3");
+        trie.insert("#", "f4");
+        trie.insert("$share/g1/sensor/+/+", "f5");
+        trie.insert("$SYS/#", "f6");
+
+        assert_eq!(
+            sorted(trie.match_topic("/sensor/1/temperature")),
+            vec!["f1", "f2", "f3", "f4"]
+        );
+        assert_eq!(
+            sorted(trie.match_topic("sensor/1/temperature")),
+            vec!["f4", "f5"]
+        );
+        assert_eq!(sorted(trie.match_topic("/sensor")), vec!["f3", "f4"]);
+        assert_eq!(trie.match_topic("$SYS/broker/uptime"), vec!["f6"]);
+
+        trie.remove("#", "f4");
+        trie.remove("$share/g1/sensor/+/+", "f5");
+        assert_eq!(
+            sorted(trie.match_topic("/sensor/2/temperature")),
+            vec!["f2", "f3"]
+        );
+    }
+
+    #[test]
+    fn topic_match_filter_test() {
+        assert!(topic_match_filter("/sensor/1", "/sensor/+"));
+        assert!(topic_match_filter("/sensor", "/sensor/#"));
+        assert!(!topic_match_filter("/sensor1/a", "/sensor/#"));
+        assert!(!topic_match_filter("/sensor/1/2", "/sensor/+"));
+        assert!(!topic_match_filter("$SYS/uptime", "#"));
+        assert!(!topic_match_filter("$SYS/uptime", "+/uptime"));
+        assert!(topic_match_filter("$SYS/uptime", "$SYS/+"));
+        assert!(topic_match_filter("sensor/1", "$share/g1/sensor/+"));
+        assert!(!topic_match_filter("/sensor/1", "$share/g1/sensor/+"));
+        assert!(topic_match_filter("sensor/1", "$queue/sensor/+"));
+    }
+}
@@ -12,25 +12,268 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+
+use common_base::metrics::registry::exponential_buckets;
+use openraft::RaftMetrics;
 use prometheus_client::encoding::EncodeLabelSet;
 
+use crate::raft::raft_node::NodeId;
+use crate::raft::typeconfig::TypeConfig;
+
+pub const CONTROLLER_SESSION_EXPIRE: &str = "session_expire";
+pub const CONTROLLER_LASTWILL_EXPIRE: &str = "lastwill_expire";
+pub const CONTROLLER_CONNECTOR_SCHEDULER: &str = "connector_scheduler";
+pub const CONTROLLER_GC_SHARD: &str = "gc_shard";
+pub const CONTROLLER_GC_SEGMENT: &str = "gc_segment";
+
 #[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
 pub struct GrpcMethodLabel {
+    pub service: String,
     pub method: String,
 }
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct RaftNodeLabel {
+    node_id: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct RaftReplicationLabel {
+    node_id: String,
+    follower_id: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ControllerLabel {
+    controller: String,
+}
+
 common_base::register_counter_metric!(
     GRPC_REQUEST_NUM,
     "grpc_request_num",
     "Number of calls to the grpc request",
     GrpcMethodLabel
 );
+common_base::register_histogram_metric!(
+    GRPC_REQUEST_DURATION,
+    "grpc_request_duration_ms",
+    "Time taken to handle a grpc request, in milliseconds",
+    GrpcMethodLabel,
+    exponential_buckets(1.0, 2.0, 14)
+);
+common_base::register_gauge_metric!(
+    RAFT_CURRENT_TERM,
+    "raft_current_term",
+    "Current term of the raft node",
+    RaftNodeLabel
+);
+common_base::register_gauge_metric!(
+    RAFT_CURRENT_LEADER,
+    "raft_current_leader",
+    "Id of the node the raft node considers as the leader, -1 if there is none",
+    RaftNodeLabel
+);
+common_base::register_gauge_metric!(
+    RAFT_LAST_LOG_INDEX,
+    "raft_last_log_index",
+    "Index of the last log entry appended to the log of the raft node",
+    RaftNodeLabel
+);
+common_base::register_gauge_metric!(
+    RAFT_COMMITTED_INDEX,
+    "raft_committed_index",
+    "Index of the last log entry the raft node knows to be committed",
+    RaftNodeLabel
+);
+common_base::register_gauge_metric!(
+    RAFT_LAST_APPLIED_INDEX,
+    "raft_last_applied_index",
+    "Index of the last log entry applied to the state machine",
+    RaftNodeLabel
+);
+common_base::register_gauge_metric!(
+    RAFT_SNAPSHOT_INDEX,
+    "raft_snapshot_index",
+    "Index of the last log entry included in the current snapshot",
+    RaftNodeLabel
+);
+common_base::register_gauge_metric!(
+    RAFT_REPLICATION_LAG,
+    "raft_replication_lag",
+    "Number of log entries of the leader not replicated to the follower yet",
+    RaftReplicationLabel
+);
+common_base::register_histogram_metric!(
+    RAFT_SNAPSHOT_BUILD_DURATION,
+    "raft_snapshot_build_ms",
+    "Time taken to build a snapshot of the state machine, in milliseconds",
+    RaftNodeLabel,
+    exponential_buckets(1.0, 2.0, 16)
+);
+common_base::register_gauge_metric!(
+    RAFT_SNAPSHOT_SIZE,
+    "raft_snapshot_size_bytes",
+    "Size of the last snapshot built from the state machine",
+    RaftNodeLabel
+);
+common_base::register_histogram_metric!(
+    CONTROLLER_LOOP_DURATION,
+    "controller_loop_duration_ms",
+    "Time taken by one round of a controller loop, in milliseconds",
+    ControllerLabel,
+    exponential_buckets(1.0, 2.0, 16)
+);
+
+impl GrpcMethodLabel {
+    /// the service and method of a request path like `/package.Service/Method`
+    fn from_path(path: &str) -> Self {
+        let (service, method) = path
+            .trim_start_matches('/')
+            .split_once('/')
+            .unwrap_or((path, ""));
+        GrpcMethodLabel {
+            service: service.rsplit('.').next().unwrap_or(service).to_string(),
+            method: method.to_string(),
+        }
+    }
+}
 
-pub fn metrics_grpc_request_incr(method: &str) {
-    let label = GrpcMethodLabel {
-        method: method.to_string(),
+impl RaftNodeLabel {
+    fn new(node_id: NodeId) -> Self {
+        RaftNodeLabel {
+            node_id: node_id.to_string(),
+        }
+    }
+}
+
+pub fn metrics_grpc_request_incr(path: &str) {
+    let label = GrpcMethodLabel::from_path(path);
+    common_base::counter_metric_inc!(GRPC_REQUEST_NUM, label)
+}
+
+pub fn metrics_grpc_request_ms(path: &str, ms: u128) {
+    let label = GrpcMethodLabel::from_path(path);
+    common_base::histogram_metric_observe!(GRPC_REQUEST_DURATION, label, ms as f64)
+}
+
+/// export the state of the raft node, called each time the metrics of openraft change
+pub fn record_raft_metrics(metrics: &RaftMetrics<TypeConfig>) {
+    let label = RaftNodeLabel::new(metrics.id);
+    let last_log_index = metrics.last_log_index.unwrap_or_default();
+    let last_applied_index = metrics.last_applied.map(|l| l.index).unwrap_or_default();
+    let leader = metrics.current_leader.map(|id| id as i64).unwrap_or(-1);
+
+    common_base::gauge_metric_set!(RAFT_CURRENT_TERM, label, metrics.current_term as i64);
+    common_base::gauge_metric_set!(RAFT_CURRENT_LEADER, label, leader);
+    common_base::gauge_metric_set!(RAFT_LAST_LOG_INDEX, label, last_log_index as i64);
+    common_base::gauge_metric_set!(RAFT_LAST_APPLIED_INDEX, label, last_applied_index as i64);
+    common_base::gauge_metric_set!(
+        RAFT_SNAPSHOT_INDEX,
+        label,
+        metrics.snapshot.map(|l| l.index).unwrap_or_default() as i64
+    );
+
+    let replication = if let Some(replication) = &metrics.replication {
+        replication
+    } else {
+        // only the leader knows the progress of the followers, a follower knows at least that
+        // the applied logs are committed
+        RAFT_REPLICATION_LAG.write().unwrap().clear();
+        common_base::gauge_metric_set!(RAFT_COMMITTED_INDEX, label, last_applied_index as i64);
+        return;
     };
-    common_base::gauge_metric_inc!(GRPC_REQUEST_NUM, label)
+
+    let mut matched: BTreeMap<NodeId, u64> = replication
+        .iter()
+        .map(|(id, log_id)| (*id, log_id.map(|l| l.index).unwrap_or_default()))
+        .collect();
+    matched.entry(metrics.id).or_insert(last_log_index);
+
+    let voter_ids: Vec<NodeId> = metrics.membership_config.membership().voter_ids().collect();
+    let committed_index = quorum_committed_index(&voter_ids, &matched);
+    common_base::gauge_metric_set!(RAFT_COMMITTED_INDEX, label, committed_index as i64);
+
+    for (follower_id, index) in matched.iter() {
+        if *follower_id == metrics.id {
+            continue;
+        }
+        let replication_label = RaftReplicationLabel {
+            node_id: metrics.id.to_string(),
+            follower_id: follower_id.to_string(),
+        };
+        common_base::gauge_metric_set!(
+            RAFT_REPLICATION_LAG,
+            replication_label,
+            last_log_index.saturating_sub(*index) as i64
+        );
+    }
+}
+
+/// the greatest log index replicated to a majority of the voters
+fn quorum_committed_index(voter_ids: &[NodeId], matched: &BTreeMap<NodeId, u64>) -> u64 {
+    let mut indexes: Vec<u64> = voter_ids
+        .iter()
+        .map(|id| matched.get(id).copied().unwrap_or_default())
+        .collect();
+    if indexes.is_empty() {
+        return 0;
+    }
+    indexes.sort_unstable_by(|a, b| b.cmp(a));
+    indexes[indexes.len() / 2]
 }
 
-pub fn metrics_grpc_request_ms(_: u128) {}
+pub fn record_snapshot_build(node_id: NodeId, ms: u128, size: usize) {
+    let label = RaftNodeLabel::new(node_id);
+    common_base::histogram_metric_observe!(RAFT_SNAPSHOT_BUILD_DURATION, label, ms as f64);
+    common_base::gauge_metric_set!(RAFT_SNAPSHOT_SIZE, label, size as i64);
+}
+
+pub fn record_controller_loop_duration(controller: &str, ms: u128) {
+    let label = ControllerLabel {
+        controller: controller.to_string(),
+    };
+    common_base::histogram_metric_observe!(CONTROLLER_LOOP_DURATION, label, ms as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{
+        metrics_grpc_request_incr, quorum_committed_index, record_snapshot_build, GrpcMethodLabel,
+        RaftNodeLabel, GRPC_REQUEST_NUM, RAFT_SNAPSHOT_SIZE,
+    };
+
+    #[test]
+    fn grpc_method_label_test() {
+        let label = GrpcMethodLabel::from_path("/placement.center.kv.KvService/Set");
+        assert_eq!(label.service, "KvService");
+        assert_eq!(label.method, "Set");
+
+        metrics_grpc_request_incr("/placement.center.kv.KvService/Get");
+        metrics_grpc_request_incr("/placement.center.kv.KvService/Get");
+        let label = GrpcMethodLabel::from_path("/placement.center.kv.KvService/Get");
+        let mut num = 0;
+        common_base::counter_metric_get!(GRPC_REQUEST_NUM, label, num);
+        assert_eq!(num, 2);
+    }
+
+    #[test]
+    fn quorum_committed_index_test() {
+        let matched = BTreeMap::from([(1, 10), (2, 8), (3, 5)]);
+        assert_eq!(quorum_committed_index(&[1, 2, 3], &matched), 8);
+        assert_eq!(quorum_committed_index(&[1, 2, 3, 4], &matched), 5);
+        assert_eq!(quorum_committed_index(&[1], &matched), 10);
+        assert_eq!(quorum_committed_index(&[], &matched), 0);
+    }
+
+    #[test]
+    fn record_snapshot_build_test() {
+        record_snapshot_build(1, 12, 4096);
+        let label = RaftNodeLabel::new(1);
+        let mut size = 0;
+        common_base::gauge_metric_get!(RAFT_SNAPSHOT_SIZE, label, size);
+        assert_eq!(size, 4096);
+    }
+}
@@ -15,6 +15,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use common_base::tools::now_mills;
 use gc::{gc_segment_thread, gc_shard_thread};
 use grpc_clients::pool::ClientPool;
 use log::info;
@@ -23,6 +24,9 @@
 
 use super::cache::JournalCacheManager;
 use crate::core::cache::PlacementCacheManager;
+use crate::core::metrics::{
+    record_controller_loop_duration, CONTROLLER_GC_SEGMENT, CONTROLLER_GC_SHARD,
+};
 use crate::route::apply::RaftMachineApply;
 
 pub mod call_node;
@@ -65,6 +69,7 @@ pub fn delete_shard_gc_thread(&self) {
         let client_pool = self.client_pool.clone();
         tokio::spawn(async move {
             loop {
+                let start = now_mills();
                 gc_shard_thread(
                     raft_machine_apply.clone(),
                     engine_cache.clone(),
@@ -72,6 +77,7 @@ pub fn delete_shard_gc_thread(&self) {
                     client_pool.clone(),
                 )
                 .await;
+                record_controller_loop_duration(CONTROLLER_GC_SHARD, now_mills() - start);
                 sleep(Duration::from_secs(1)).await;
             }
         });
@@ -84,6 +90,7 @@ pub fn delete_segment_gc_thread(&self) {
         let client_pool = self.client_pool.clone();
         tokio::spawn(async move {
             loop {
+                let start = now_mills();
                 gc_segment_thread(
                     raft_machine_apply.clone(),
                     engine_cache.clone(),
@@ -91,6 +98,7 @@ pub fn delete_segment_gc_thread(&self) {
                     client_pool.clone(),
                 )
                 .await;
+                record_controller_loop_duration(CONTROLLER_GC_SEGMENT, now_mills() - start);
                 sleep(Duration::from_secs(1)).await;
             }
         });
@@ -16,6 +16,7 @@
 use std::time::Duration;
 
 use common_base::config::placement_center::placement_center_conf;
+use common_base::metrics::register_prometheus_export;
 use grpc_clients::pool::ClientPool;
 use log::info;
 use mqtt::cache::load_mqtt_cache;
@@ -120,6 +121,8 @@ pub async fn start(&mut self, stop_send: Sender<bool>) {
 
         self.start_grpc_server(placement_center_storage.clone());
 
+        self.start_prometheus();
+
         self.monitoring_leader_transition(openraft_node.clone(), placement_center_storage.clone());
 
         self.awaiting_stop(stop_send).await;
@@ -169,6 +172,16 @@ pub fn start_grpc_server(&self, raft_machine_apply: Arc<RaftMachineApply>) {
         });
     }
 
+    fn start_prometheus(&self) {
+        let config = placement_center_conf();
+        if config.prometheus.enable {
+            let prometheus_port = config.prometheus.port;
+            tokio::spawn(async move {
+                register_prometheus_export(prometheus_port).await;
+            });
+        }
+    }
+
     pub fn start_heartbeat(
         &self,
         raft_machine_apply: Arc<RaftMachineApply>,
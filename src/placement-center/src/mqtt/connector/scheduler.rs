@@ -14,7 +14,10 @@
 
 use std::{collections::HashMap, sync::Arc};
 
-use common_base::{config::placement_center::placement_center_conf, tools::now_second};
+use common_base::{
+    config::placement_center::placement_center_conf,
+    tools::{now_mills, now_second},
+};
 use grpc_clients::pool::ClientPool;
 use log::{info, warn};
 use metadata_struct::mqtt::bridge::status::MQTTStatus;
@@ -22,7 +25,11 @@
 use tokio::{select, sync::broadcast};
 
 use crate::{
-    core::{cache::PlacementCacheManager, error::PlacementCenterError},
+    core::{
+        cache::PlacementCacheManager,
+        error::PlacementCenterError,
+        metrics::{record_controller_loop_duration, CONTROLLER_CONNECTOR_SCHEDULER},
+    },
     mqtt::{
         cache::MqttCacheManager, connector::status::update_connector_status_to_idle,
         controller::call_broker::MQTTInnerCallManager,
@@ -64,6 +71,7 @@ async fn scheduler_thread(
     mqtt_cache: &Arc<MqttCacheManager>,
     placement_cache: &Arc<PlacementCacheManager>,
 ) {
+    let start = now_mills();
     if let Err(e) = check_heartbeat(raft_machine_apply, call_manager, client_pool, mqtt_cache).await
     {
         info!("check heartbeat error: {:?}", e);
@@ -80,6 +88,7 @@ async fn scheduler_thread(
     {
         info!("start stop connector thread error: {:?}", e);
     }
+    record_controller_loop_duration(CONTROLLER_CONNECTOR_SCHEDULER, now_mills() - start);
 }
 
 async fn check_heartbeat(
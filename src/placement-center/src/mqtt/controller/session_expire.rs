@@ -16,13 +16,16 @@
 use std::time::Duration;
 
 use crate::core::cache::PlacementCacheManager;
+use crate::core::metrics::{
+    record_controller_loop_duration, CONTROLLER_LASTWILL_EXPIRE, CONTROLLER_SESSION_EXPIRE,
+};
 use crate::mqtt::cache::MqttCacheManager;
 use crate::storage::keys::storage_key_mqtt_session_cluster_prefix;
 use crate::storage::mqtt::lastwill::MqttLastWillStorage;
 use crate::storage::mqtt::session::MqttSessionStorage;
 use crate::storage::rocksdb::{RocksDBEngine, DB_COLUMN_FAMILY_CLUSTER};
 use common_base::error::common::CommonError;
-use common_base::tools::now_second;
+use common_base::tools::{now_mills, now_second};
 use grpc_clients::mqtt::inner::call::{broker_mqtt_delete_session, send_last_will_message};
 use grpc_clients::pool::ClientPool;
 use log::{debug, error, warn};
@@ -65,14 +68,17 @@ pub fn new(
     }
 
     pub async fn session_expire(&self) {
+        let start = now_mills();
         let sessions = self.get_expire_session_list().await;
         if !sessions.is_empty() {
             self.delete_session(sessions);
         }
+        record_controller_loop_duration(CONTROLLER_SESSION_EXPIRE, now_mills() - start);
         sleep(Duration::from_secs(1)).await;
     }
 
     pub async fn lastwill_expire_send(&self) {
+        let start = now_mills();
         let lastwill_list = self
             .mqtt_cache_manager
             .get_expire_last_wills(&self.cluster_name);
@@ -81,6 +87,7 @@ pub async fn lastwill_expire_send(&self) {
             debug!("Will message due, list:{:?}", lastwill_list);
             self.send_expire_lastwill_message(lastwill_list).await;
         }
+        record_controller_loop_duration(CONTROLLER_LASTWILL_EXPIRE, now_mills() - start);
 
         sleep(Duration::from_secs(1)).await;
     }
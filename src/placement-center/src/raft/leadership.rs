@@ -15,7 +15,7 @@
 use std::sync::Arc;
 
 use crate::{
-    core::cache::PlacementCacheManager,
+    core::{cache::PlacementCacheManager, metrics::record_raft_metrics},
     journal::{cache::JournalCacheManager, controller::StorageEngineController},
     mqtt::{cache::MqttCacheManager, controller::MqttController},
     route::apply::RaftMachineApply,
@@ -46,6 +46,7 @@ pub fn monitoring_leader_transition(
             match metrics_rx.changed().await {
                 Ok(_) => {
                     let mm = metrics_rx.borrow().clone();
+                    record_raft_metrics(&mm);
 
                     if let Some(current_leader) = mm.current_leader {
                         if last_leader != Some(current_leader) {
@@ -15,6 +15,8 @@
 use std::io::Cursor;
 use std::sync::Arc;
 
+use common_base::config::placement_center::placement_center_conf;
+use common_base::tools::now_mills;
 use log::warn;
 use openraft::storage::RaftStateMachine;
 use openraft::{
@@ -24,6 +26,7 @@
 use rocksdb::{BoundColumnFamily, DB};
 
 use super::{cf_raft_store, StorageResult, StoredSnapshot};
+use crate::core::metrics::record_snapshot_build;
 use crate::raft::raft_node::{typ, NodeId};
 use crate::raft::route::AppResponseData;
 use crate::raft::typeconfig::{SnapshotData, TypeConfig};
@@ -58,8 +61,14 @@ async fn build_snapshot(&mut self) -> Result<Snapshot<TypeConfig>, StorageError<
         let last_applied_log = self.data.last_applied_log_id;
         let last_membership = self.data.last_membership.clone();
 
+        let start = now_mills();
         // todo
         let kv_json = self.data.route.build_snapshot();
+        record_snapshot_build(
+            placement_center_conf().node.node_id,
+            now_mills() - start,
+            kv_json.len(),
+        );
 
         let snapshot_id = if let Some(last) = last_applied_log {
             format!("{}-{}-{}", last.leader_id, last.index, self.snapshot_idx)
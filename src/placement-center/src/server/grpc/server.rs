@@ -158,18 +158,19 @@ fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
 
         Box::pin(async move {
             let start_time = now_mills();
+            let path = req.uri().path().to_string();
+            metrics_grpc_request_incr(&path);
 
             // call
-            let response = inner.call(req).await?;
+            let response = inner.call(req).await;
 
-            metrics_grpc_request_ms(now_mills() - start_time);
-            Ok(response)
+            metrics_grpc_request_ms(&path, now_mills() - start_time);
+            response
         })
     }
 }
 
 // See: https://github.com/hyperium/tonic/blob/master/examples/src/interceptor/server.rs
 pub fn grpc_intercept(req: Request<()>) -> Result<Request<()>, Status> {
-    metrics_grpc_request_incr("all");
     Ok(req)
 }
@@ -163,6 +163,7 @@ async fn acl_storage_test() {
             ip: ip.clone(),
             action: action.clone(),
             permission: permission.clone(),
+            priority: 0,
         };
 
         acl_storage.save(&cluster_name, acl.clone()).unwrap();
@@ -180,6 +181,7 @@ async fn acl_storage_test() {
             ip: "localhost2".to_string(),
             action: MqttAclAction::Publish,
             permission: MqttAclPermission::Deny,
+            priority: 0,
         };
 
         acl_storage.save(&cluster_name, acl2.clone()).unwrap();
@@ -14,5 +14,16 @@
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     robustmq_proto_build::setup()?;
+
+    // services that are only used between the nodes of this repository
+    tonic_build::configure().compile_protos(
+        &[
+            "proto/journal/engine_ext.proto",
+            "proto/journal/replica.proto",
+            "proto/mqtt/admin_ext.proto",
+        ],
+        &["proto/"],
+    )?;
+    println!("cargo:rerun-if-changed=proto");
     Ok(())
 }
@@ -19,6 +19,10 @@ pub mod broker_mqtt_admin {
     tonic::include_proto!("broker.mqtt.admin");
 }
 
+pub mod broker_mqtt_admin_ext {
+    tonic::include_proto!("broker.mqtt.admin_ext");
+}
+
 pub mod broker_mqtt_inner {
     tonic::include_proto!("broker.mqtt.inner");
 }
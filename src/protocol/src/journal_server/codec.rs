@@ -27,6 +27,7 @@
     ListShardReqBody, ListShardResp, ListShardRespBody, ReadReq, ReadReqBody, ReadResp,
     ReadRespBody, ReqHeader, RespHeader, WriteReq, WriteReqBody, WriteResp, WriteRespBody,
 };
+use super::journal_engine_ext::{CreateShardReqBodyExt, ReadReqBodyExt, ReadRespBodyExt};
 use super::Error;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -39,8 +40,8 @@ pub enum JournalEnginePacket {
     WriteResp(WriteResp),
 
     // Read
-    ReadReq(ReadReq),
-    ReadResp(ReadResp),
+    ReadReq(ReadReq, ReadReqBodyExt),
+    ReadResp(ReadResp, ReadRespBodyExt),
 
     // GetClusterMetadata
     GetClusterMetadataReq(GetClusterMetadataReq),
@@ -55,7 +56,7 @@ pub enum JournalEnginePacket {
     FetchOffsetResp(FetchOffsetResp),
 
     // CreateShard
-    CreateShardReq(CreateShardReq),
+    CreateShardReq(CreateShardReq, CreateShardReqBodyExt),
     CreateShardResp(CreateShardResp),
 
     // DeleteShard
@@ -72,15 +73,15 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             JournalEnginePacket::WriteReq(_) => write!(f, "WriteReq"),
             JournalEnginePacket::WriteResp(_) => write!(f, "WriteResp"),
-            JournalEnginePacket::ReadReq(_) => write!(f, "ReadReq"),
-            JournalEnginePacket::ReadResp(_) => write!(f, "ReadResp"),
+            JournalEnginePacket::ReadReq(_, _) => write!(f, "ReadReq"),
+            JournalEnginePacket::ReadResp(_, _) => write!(f, "ReadResp"),
             JournalEnginePacket::GetClusterMetadataReq(_) => write!(f, "GetClusterMetadataReq"),
             JournalEnginePacket::GetClusterMetadataResp(_) => write!(f, "GetClusterMetadataResp"),
             JournalEnginePacket::GetShardMetadataReq(_) => write!(f, "GetShardMetadataReq"),
             JournalEnginePacket::GetShardMetadataResp(_) => write!(f, "GetShardMetadataResp"),
             JournalEnginePacket::FetchOffsetReq(_) => write!(f, "FetchOffsetReq"),
             JournalEnginePacket::FetchOffsetResp(_) => write!(f, "FetchOffsetResp"),
-            JournalEnginePacket::CreateShardReq(_) => write!(f, "CreateShardReq"),
+            JournalEnginePacket::CreateShardReq(_, _) => write!(f, "CreateShardReq"),
             JournalEnginePacket::CreateShardResp(_) => write!(f, "CreateShardResp"),
             JournalEnginePacket::DeleteShardReq(_) => write!(f, "DeleteShardReq"),
             JournalEnginePacket::DeleteShardResp(_) => write!(f, "DeleteShardResp"),
@@ -132,18 +133,23 @@ fn encode(
             }
 
             // Read
-            JournalEnginePacket::ReadReq(data) => {
+            JournalEnginePacket::ReadReq(data, ext) => {
                 let header = data.header.unwrap();
                 let body = data.body.unwrap();
                 header_byte = ReqHeader::encode_to_vec(&header);
-                body_byte = ReadReqBody::encode_to_vec(&body);
+                // concatenated messages are decoded as one, see CreateShardReqBodyExt
+                let mut bytes = ReadReqBody::encode_to_vec(&body);
+                bytes.extend(ReadReqBodyExt::encode_to_vec(&ext));
+                body_byte = bytes;
                 req_type = 1;
             }
-            JournalEnginePacket::ReadResp(data) => {
+            JournalEnginePacket::ReadResp(data, ext) => {
                 let header = data.header.unwrap();
                 let body = data.body.unwrap();
                 header_byte = RespHeader::encode_to_vec(&header);
-                body_byte = ReadRespBody::encode_to_vec(&body);
+                let mut bytes = ReadRespBody::encode_to_vec(&body);
+                bytes.extend(ReadRespBodyExt::encode_to_vec(&ext));
+                body_byte = bytes;
             }
 
             // GetClusterMetadata
@@ -191,11 +197,14 @@ fn encode(
             }
 
             // CreateShard
-            JournalEnginePacket::CreateShardReq(data) => {
+            JournalEnginePacket::CreateShardReq(data, ext) => {
                 let header = data.header.unwrap();
                 let body = data.body.unwrap();
                 header_byte = ReqHeader::encode_to_vec(&header);
-                body_byte = CreateShardReqBody::encode_to_vec(&body);
+                // concatenated messages are decoded as one, see CreateShardReqBodyExt
+                let mut bytes = CreateShardReqBody::encode_to_vec(&body);
+                bytes.extend(CreateShardReqBodyExt::encode_to_vec(&ext));
+                body_byte = bytes;
                 req_type = 1;
             }
             JournalEnginePacket::CreateShardResp(data) => {
@@ -396,12 +405,17 @@ fn create_shard_req(
     body_bytes: BytesMut,
     header: ReqHeader,
 ) -> Result<Option<JournalEnginePacket>, Error> {
+    let ext = CreateShardReqBodyExt::decode(body_bytes.as_ref())
+        .map_err(|e| Error::DecodeBodyError("create_shard_req".to_string(), e.to_string()))?;
     match CreateShardReqBody::decode(body_bytes.as_ref()) {
         Ok(body) => {
-            let item = JournalEnginePacket::CreateShardReq(CreateShardReq {
-                header: Some(header),
-                body: Some(body),
-            });
+            let item = JournalEnginePacket::CreateShardReq(
+                CreateShardReq {
+                    header: Some(header),
+                    body: Some(body),
+                },
+                ext,
+            );
             Ok(Some(item))
         }
         Err(e) => Err(Error::DecodeBodyError(
@@ -507,12 +521,17 @@ fn write_resp(
 }
 
 fn read_req(body_bytes: BytesMut, header: ReqHeader) -> Result<Option<JournalEnginePacket>, Error> {
+    let ext = ReadReqBodyExt::decode(body_bytes.as_ref())
+        .map_err(|e| Error::DecodeBodyError("read_req".to_string(), e.to_string()))?;
     match ReadReqBody::decode(body_bytes.as_ref()) {
         Ok(body) => {
-            let item = JournalEnginePacket::ReadReq(ReadReq {
-                header: Some(header),
-                body: Some(body),
-            });
+            let item = JournalEnginePacket::ReadReq(
+                ReadReq {
+                    header: Some(header),
+                    body: Some(body),
+                },
+                ext,
+            );
             Ok(Some(item))
         }
         Err(e) => Err(Error::DecodeBodyError(
@@ -526,12 +545,17 @@ fn read_resp(
     body_bytes: BytesMut,
     header: RespHeader,
 ) -> Result<Option<JournalEnginePacket>, Error> {
+    let ext = ReadRespBodyExt::decode(body_bytes.as_ref())
+        .map_err(|e| Error::DecodeBodyError("read_resp".to_string(), e.to_string()))?;
     match ReadRespBody::decode(body_bytes.as_ref()) {
         Ok(body) => {
-            let item = JournalEnginePacket::ReadResp(ReadResp {
-                header: Some(header),
-                body: Some(body),
-            });
+            let item = JournalEnginePacket::ReadResp(
+                ReadResp {
+                    header: Some(header),
+                    body: Some(body),
+                },
+                ext,
+            );
             Ok(Some(item))
         }
         Err(e) => Err(Error::DecodeBodyError(
@@ -658,8 +682,12 @@ mod tests {
 
     use super::{JournalEnginePacket, JournalServerCodec};
     use crate::journal_server::journal_engine::{
-        ApiKey, ApiVersion, GetClusterMetadataReq, ReadReq, ReadReqBody, ReqHeader, RespHeader,
-        WriteReq, WriteReqBody, WriteResp, WriteRespBody,
+        ApiKey, ApiVersion, CreateShardReq, CreateShardReqBody, GetClusterMetadataReq, ReadReq,
+        ReadReqBody, ReadResp, ReadRespBody, ReadRespMessage, ReadRespSegmentMessage, ReqHeader,
+        RespHeader, WriteReq, WriteReqBody, WriteResp, WriteRespBody,
+    };
+    use crate::journal_server::journal_engine_ext::{
+        CreateShardReqBodyExt, ReadReqBodyExt, ReadRespBodyExt, ReadRespCompressedBatch,
     };
 
     #[test]
@@ -709,11 +737,65 @@ fn read_codec_test() {
             api_version: ApiVersion::V0.into(),
         };
 
-        let source = JournalEnginePacket::ReadReq(ReadReq {
-            header: Some(header),
-            body: Some(ReadReqBody::default()),
-        });
+        let mut codec = JournalServerCodec::new();
+        for ext in [
+            ReadReqBodyExt::default(),
+            ReadReqBodyExt {
+                compressed_batches: true,
+            },
+        ] {
+            let source = JournalEnginePacket::ReadReq(
+                ReadReq {
+                    header: Some(header.clone()),
+                    body: Some(ReadReqBody::default()),
+                },
+                ext,
+            );
+            let mut dst = bytes::BytesMut::new();
+            codec.encode(source.clone(), &mut dst).unwrap();
+            let target = codec.decode(&mut dst).unwrap().unwrap();
+            assert_eq!(source, target);
+        }
+    }
+
+    #[test]
+    fn read_resp_codec_test() {
+        let header = RespHeader {
+            api_key: ApiKey::Read.into(),
+            ..Default::default()
+        };
+        let body = ReadRespBody {
+            messages: vec![ReadRespSegmentMessage {
+                namespace: "n1".to_string(),
+                shard_name: "s1".to_string(),
+                messages: vec![ReadRespMessage {
+                    offset: 1,
+                    value: b"v1".to_vec(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let ext = ReadRespBodyExt {
+            compressed_batches: vec![ReadRespCompressedBatch {
+                namespace: "n1".to_string(),
+                shard_name: "s1".to_string(),
+                offset: 2,
+                codec: 1,
+                record_num: 2,
+                payload: vec![1, 2, 3],
+                min_offset: 2,
+                ..Default::default()
+            }],
+        };
 
+        let source = JournalEnginePacket::ReadResp(
+            ReadResp {
+                header: Some(header),
+                body: Some(body),
+            },
+            ext,
+        );
         let mut codec = JournalServerCodec::new();
         let mut dst = bytes::BytesMut::new();
         codec.encode(source.clone(), &mut dst).unwrap();
@@ -721,6 +803,40 @@ fn read_codec_test() {
         assert_eq!(source, target);
     }
 
+    #[test]
+    fn create_shard_codec_test() {
+        let header = ReqHeader {
+            api_key: ApiKey::CreateShard.into(),
+            api_version: ApiVersion::V0.into(),
+        };
+        let body = CreateShardReqBody {
+            namespace: "n1".to_string(),
+            shard_name: "s1".to_string(),
+            replica_num: 2,
+        };
+
+        let mut codec = JournalServerCodec::new();
+        for ext in [
+            CreateShardReqBodyExt::default(),
+            CreateShardReqBodyExt {
+                fsync_policy: br#"{"IntervalMs":100}"#.to_vec(),
+                compression: br#""Zstd""#.to_vec(),
+            },
+        ] {
+            let source = JournalEnginePacket::CreateShardReq(
+                CreateShardReq {
+                    header: Some(header.clone()),
+                    body: Some(body.clone()),
+                },
+                ext,
+            );
+            let mut dst = bytes::BytesMut::new();
+            codec.encode(source.clone(), &mut dst).unwrap();
+            let target = codec.decode(&mut dst).unwrap().unwrap();
+            assert_eq!(source, target);
+        }
+    }
+
     #[tokio::test]
     async fn storage_engine_frame_server() {
         let req_pkg = build_write_req();
@@ -25,10 +25,18 @@ pub mod journal_engine {
     tonic::include_proto!("journal.engine");
 }
 
+pub mod journal_engine_ext {
+    tonic::include_proto!("journal.engine.ext");
+}
+
 pub mod journal_inner {
     tonic::include_proto!("journal.inner");
 }
 
+pub mod journal_replica {
+    tonic::include_proto!("journal.replica");
+}
+
 pub mod journal_record {
     tonic::include_proto!("journal.record");
 }
@@ -18,11 +18,12 @@ pub fn len(auth: &Auth, properties: &Option<AuthProperties>) -> usize {
     // The Reason Code and Property Length can be omitted if the Reason Code is 0x00(Success)
     // and there are no properties. In this case the AUTH packet has a remaining length of 2.
     // <https://docs.oasis-open.org/mqtt/mqtt/v5.0/os/mqtt-v5.0-os.html#_Toc3901217>
-    if auth.reason.unwrap() == AuthReason::Success && properties.is_none() {
+    if is_short_form(auth, properties) {
         return 2; // Packet type + 0x00
     }
 
-    let mut len = 0;
+    // reason code
+    let mut len = 1;
     if let Some(p) = properties {
         let properties_len = properties::len(p);
         let properties_len_len = len_len(properties_len);
@@ -35,6 +36,10 @@ pub fn len(auth: &Auth, properties: &Option<AuthProperties>) -> usize {
     len
 }
 
+fn is_short_form(auth: &Auth, properties: &Option<AuthProperties>) -> bool {
+    auth.reason.unwrap_or(AuthReason::Success) == AuthReason::Success && properties.is_none()
+}
+
 pub fn write(
     auth: &Auth,
     properties: &Option<AuthProperties>,
@@ -43,13 +48,13 @@ pub fn write(
     let len = len(auth, properties);
     buffer.put_u8(0b1111_0000);
 
-    if len == 2 {
-        buffer.put_u8(0x00); // reason code Success (0x00)
+    if is_short_form(auth, properties) {
+        buffer.put_u8(0x00); // remaining length 0, the reason code is Success (0x00)
         return Ok(len);
     }
     let count = write_remaining_length(buffer, len)?;
 
-    buffer.put_u8(code(auth.reason.unwrap()));
+    buffer.put_u8(code(auth.reason.unwrap_or(AuthReason::Success)));
 
     if let Some(p) = &properties {
         properties::write(p, buffer)?;
@@ -91,6 +96,10 @@ pub fn read(
     let auth = Auth {
         reason: Some(reason(reason_code)?),
     };
+    // the Property Length can be omitted when there are no properties
+    if fixed_header.remaining_len == 1 {
+        return Ok((auth, None));
+    }
     let properties = properties::read(&mut bytes)?;
 
     Ok((auth, properties))
@@ -267,7 +276,7 @@ fn test_auth_v5() {
         let fixed_header: FixedHeader = parse_fixed_header(buffer.iter()).unwrap();
         assert_eq!(fixed_header.byte1, 0b1111_0000);
         assert_eq!(fixed_header.fixed_header_len, 2);
-        assert_eq!(fixed_header.remaining_len, 88);
+        assert_eq!(fixed_header.remaining_len, 89);
 
         // test the read function of pubrec packet and check the result of write function in MQTT v5
         let (x, y) = read(fixed_header, buffer.copy_to_bytes(buffer.len())).unwrap();
@@ -287,4 +296,43 @@ fn test_auth_v5() {
         println!("auth is {}", auth);
         println!("auth_properties is {}", auth_properties);
     }
+
+    #[test]
+    fn test_auth_v5_without_properties() {
+        use super::*;
+
+        // Success without properties uses the short form
+        let mut buffer = BytesMut::new();
+        let auth = Auth {
+            reason: Some(AuthReason::Success),
+        };
+        assert_eq!(write(&auth, &None, &mut buffer).unwrap(), 2);
+        let fixed_header: FixedHeader = parse_fixed_header(buffer.iter()).unwrap();
+        assert_eq!(fixed_header.remaining_len, 0);
+        let (x, y) = read(fixed_header, buffer.copy_to_bytes(buffer.len())).unwrap();
+        assert_eq!(x.reason.unwrap(), AuthReason::Success);
+        assert!(y.is_none());
+
+        // any other reason code is written with an empty property length
+        let mut buffer = BytesMut::new();
+        let auth = Auth {
+            reason: Some(AuthReason::ReAuthenticate),
+        };
+        write(&auth, &None, &mut buffer).unwrap();
+        let fixed_header: FixedHeader = parse_fixed_header(buffer.iter()).unwrap();
+        assert_eq!(fixed_header.remaining_len, 2);
+        let (x, y) = read(fixed_header, buffer.copy_to_bytes(buffer.len())).unwrap();
+        assert_eq!(x.reason.unwrap(), AuthReason::ReAuthenticate);
+        assert!(y.is_none());
+
+        // the property length can be omitted by the sender
+        let fixed_header = FixedHeader {
+            byte1: 0b1111_0000,
+            fixed_header_len: 2,
+            remaining_len: 1,
+        };
+        let (x, y) = read(fixed_header, Bytes::from_static(&[0xF0, 0x01, 0x18])).unwrap();
+        assert_eq!(x.reason.unwrap(), AuthReason::ContinueAuthentication);
+        assert!(y.is_none());
+    }
 }
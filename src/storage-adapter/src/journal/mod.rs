@@ -27,6 +27,7 @@
 
 pub mod offset;
 
+#[derive(Clone)]
 pub struct JournalStorageAdapter {
     cluster_name: String,
     client: JournalClient,
@@ -12,571 +12,49 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashMap, time::Duration};
-
-use axum::async_trait;
 use common_base::error::common::CommonError;
-use dashmap::DashMap;
-use futures::TryStreamExt;
-use metadata_struct::adapter::{read_config::ReadConfig, record::Record};
-use opendal::{services::S3, EntryMode, Operator};
-use tokio::{
-    select,
-    sync::{
-        broadcast,
-        mpsc::{self, Receiver},
-        oneshot,
-    },
-    time::{sleep, timeout},
-};
-
-use crate::storage::{ShardInfo, ShardOffset, StorageAdapter};
-
-#[derive(Debug)]
-#[allow(dead_code)]
-struct WriteThreadData {
-    namespace: String,
-    shard: String,
-    records: Vec<Record>,
-    resp_sx: oneshot::Sender<Result<Vec<u64>, CommonError>>, // thread response: offset or error
-}
-
-#[derive(Clone)]
-#[allow(dead_code)]
-struct ThreadWriteHandle {
-    data_sender: mpsc::Sender<WriteThreadData>,
-    stop_sender: broadcast::Sender<bool>,
-}
-
-#[allow(dead_code)]
-impl WriteThreadData {
-    fn new(
-        namespace: String,
-        shard: String,
-        records: Vec<Record>,
-        resp_sx: oneshot::Sender<Result<Vec<u64>, CommonError>>,
-    ) -> Self {
-        WriteThreadData {
-            namespace,
-            shard,
-            records,
-            resp_sx,
-        }
-    }
-}
-
-#[allow(dead_code)]
-struct MinIoStorageAdapter {
-    op: Operator,
-    write_handles: DashMap<String, ThreadWriteHandle>,
-}
-
-#[allow(dead_code)]
-impl MinIoStorageAdapter {
-    pub fn new(data_dir: impl AsRef<str>, bucket: impl AsRef<str>) -> Result<Self, CommonError> {
-        let builder = S3::default()
-            .root(data_dir.as_ref())
-            .bucket(bucket.as_ref())
-            .endpoint("http://127.0.0.1:9000")
-            .access_key_id("minioadmin")
-            .secret_access_key("minioadmin");
-        Ok(Self {
-            op: Operator::new(builder)?.finish(),
-            write_handles: DashMap::with_capacity(2),
-        })
-    }
-
-    #[inline(always)]
-    pub fn records_path(
-        namespace: impl AsRef<str>,
-        shard_name: impl AsRef<str>,
-        offset: u64,
-    ) -> String {
-        format!(
-            "records/{}/{}/record-{:020}",
-            namespace.as_ref(),
-            shard_name.as_ref(),
-            offset
-        )
-    }
-
-    #[inline(always)]
-    pub fn offsets_path(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
-        format!(
-            "offsets/{}-{}-offset",
-            namespace.as_ref(),
-            shard_name.as_ref()
-        )
-    }
-
-    #[inline(always)]
-    pub fn shard_info(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
-        format!("shard/{}-{}", namespace.as_ref(), shard_name.as_ref())
-    }
-
-    #[inline(always)]
-    pub fn tags_path(
-        namespace: impl AsRef<str>,
-        shard_name: impl AsRef<str>,
-        tag: impl AsRef<str>,
-        offset: u64,
-    ) -> String {
-        format!(
-            "tags/{}/{}/{}/record-{:020}",
-            namespace.as_ref(),
-            shard_name.as_ref(),
-            tag.as_ref(),
-            offset
-        )
-    }
-
-    #[inline(always)]
-    pub fn tags_path_prefix(
-        namespace: impl AsRef<str>,
-        shard_name: impl AsRef<str>,
-        tag: impl AsRef<str>,
-    ) -> String {
-        format!(
-            "tags/{}/{}/{}/",
-            namespace.as_ref(),
-            shard_name.as_ref(),
-            tag.as_ref()
-        )
-    }
-
-    #[inline(always)]
-    pub fn key_path(
-        namespace: impl AsRef<str>,
-        shard_name: impl AsRef<str>,
-        key: impl AsRef<str>,
-    ) -> String {
-        format!(
-            "keys/{}/{}/key-{}",
-            namespace.as_ref(),
-            shard_name.as_ref(),
-            key.as_ref()
-        )
-    }
-
-    #[inline(always)]
-    pub fn group_path(
-        group_name: impl AsRef<str>,
-        namespace: impl AsRef<str>,
-        shard_name: impl AsRef<str>,
-    ) -> String {
-        format!(
-            "groups/{}/{}-{}",
-            group_name.as_ref(),
-            namespace.as_ref(),
-            shard_name.as_ref()
-        )
-    }
-
-    #[inline(always)]
-    pub fn group_path_prefix(group_name: impl AsRef<str>) -> String {
-        format!("groups/{}/", group_name.as_ref())
-    }
-}
-
-impl MinIoStorageAdapter {
-    #[inline(always)]
-    fn write_handle_key(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
-        format!("{}-{}", namespace.as_ref(), shard_name.as_ref())
-    }
-
-    async fn handle_write_request(
-        &self,
-        namespace: String,
-        shard_name: String,
-        messages: Vec<Record>,
-    ) -> Result<Vec<u64>, CommonError> {
-        let write_handle = self.get_write_handle(&namespace, &shard_name).await;
-
-        let (resp_sx, resp_rx) = oneshot::channel();
-
-        let data = WriteThreadData::new(namespace, shard_name, messages, resp_sx);
-
-        write_handle.data_sender.send(data).await.map_err(|err| {
-            CommonError::CommonError(format!("Failed to send data to write thread: {}", err))
-        })?;
-
-        timeout(Duration::from_secs(3600), resp_rx)
-            .await
-            .map_err(|err| {
-                CommonError::CommonError(format!("Timeout while waiting for response: {}", err))
-            })?
-            .map_err(|err| {
-                CommonError::CommonError(format!("Failed to receive response: {}", err))
-            })?
-    }
-
-    async fn get_write_handle(
-        &self,
-        namespace: impl AsRef<str>,
-        shard_name: impl AsRef<str>,
-    ) -> ThreadWriteHandle {
-        let handle_key = Self::write_handle_key(namespace.as_ref(), shard_name.as_ref());
-
-        if !self.write_handles.contains_key(&handle_key) {
-            self.create_write_thread(namespace.as_ref(), shard_name.as_ref())
-                .await;
-        }
-
-        self.write_handles.get(&handle_key).unwrap().clone()
-    }
-
-    async fn get_all_write_handles(&self) -> Vec<ThreadWriteHandle> {
-        self.write_handles
-            .iter()
-            .map(|item| item.value().clone())
-            .collect()
-    }
-
-    async fn register_write_handle(
-        &self,
-        namespace: impl AsRef<str>,
-        shard_name: impl AsRef<str>,
-        handle: ThreadWriteHandle,
-    ) {
-        let handle_key = Self::write_handle_key(namespace, shard_name);
-        self.write_handles.insert(handle_key, handle);
-    }
-
-    async fn create_write_thread(&self, namespace: impl AsRef<str>, shard_name: impl AsRef<str>) {
-        let (data_sender, data_recv) = mpsc::channel::<WriteThreadData>(1000);
-        let (stop_sender, stop_recv) = broadcast::channel::<bool>(1);
-
-        Self::spawn_write_thread(self.op.clone(), stop_recv, data_recv).await;
-
-        let write_handle = ThreadWriteHandle {
-            data_sender,
-            stop_sender,
-        };
-
-        self.register_write_handle(namespace.as_ref(), shard_name.as_ref(), write_handle)
-            .await;
-    }
-
-    async fn spawn_write_thread(
-        op: Operator,
-        mut stop_recv: broadcast::Receiver<bool>,
-        mut data_recv: Receiver<WriteThreadData>,
-    ) {
-        tokio::spawn(async move {
-            loop {
-                select! {
-                    val = stop_recv.recv() => {
-                        if let Ok(flag) = val {
-                            if flag {
-                                break
-                            }
-                        }
-                    },
-                    val = data_recv.recv() => {
-                        if val.is_none() {
-                            sleep(Duration::from_millis(100)).await;
-                            continue
-                        }
-
-                        let packet = val.unwrap();  // unwrap is safe here since we checked for None before
-
-                        let res = Self::
-                            thread_batch_write(op.clone(), packet.namespace, packet.shard, packet.records)
-                            .await;
-
-                        packet.resp_sx.send(res).map_err(|_| {
-                            CommonError::CommonError("Failed to send response in write thread".to_string())
-                        })?;
-
-                    }
-                }
-            }
-
-            Ok::<(), CommonError>(())
-        });
-    }
-
-    async fn thread_batch_write(
-        op: Operator,
-        namespace: String,
-        shard_name: String,
-        messages: Vec<Record>,
-    ) -> Result<Vec<u64>, CommonError> {
-        let mut offsets = Vec::new();
-
-        let start_offset_bytes = op
-            .read(&Self::offsets_path(&namespace, &shard_name))
-            .await?
-            .to_vec();
-
-        let mut start_offset = serde_json::from_slice::<u64>(&start_offset_bytes)?;
-
-        for mut message in messages {
-            message.offset = Some(start_offset);
-            // write records
-            let record_path = Self::records_path(&namespace, &shard_name, start_offset);
-            op.write(&record_path, serde_json::to_vec(&message)?)
-                .await?;
-
-            // write key
-            let key_path = Self::key_path(&namespace, &shard_name, &message.key);
-            op.write(&key_path, serde_json::to_vec(&message)?).await?;
-
-            // write tags
-            for tag in message.tags.iter() {
-                let tag_path = Self::tags_path(&namespace, &shard_name, tag, start_offset);
-                op.write(&tag_path, serde_json::to_vec(&message)?).await?;
-            }
-
-            offsets.push(start_offset);
-            start_offset += 1;
-        }
-
-        op.write(
-            &Self::offsets_path(&namespace, &shard_name),
-            serde_json::to_vec(&start_offset)?,
-        )
-        .await?;
 
-        Ok(offsets)
+use crate::s3::{S3Config, S3StorageAdapter};
+
+// MinIO ignores the region, but the S3 signer requires one to be set
+const MINIO_REGION: &str = "us-east-1";
+
+/// MinIO speaks the S3 API, so a MinIO deployment is served by the S3 adapter with the same
+/// object layout.
+pub type MinIoStorageAdapter = S3StorageAdapter;
+
+impl S3StorageAdapter {
+    pub fn new_minio(
+        endpoint: impl AsRef<str>,
+        access_key_id: impl AsRef<str>,
+        secret_access_key: impl AsRef<str>,
+        data_dir: impl AsRef<str>,
+        bucket: impl AsRef<str>,
+    ) -> Result<Self, CommonError> {
+        S3StorageAdapter::new(minio_config(
+            endpoint,
+            access_key_id,
+            secret_access_key,
+            data_dir,
+            bucket,
+        ))
     }
 }
 
-#[async_trait]
-impl StorageAdapter for MinIoStorageAdapter {
-    async fn create_shard(&self, shard: ShardInfo) -> Result<(), CommonError> {
-        self.op
-            .write(
-                &Self::offsets_path(&shard.namespace, &shard.shard_name),
-                serde_json::to_vec(&0)?,
-            )
-            .await?;
-
-        self.op
-            .write(
-                &Self::shard_info(&shard.namespace, &shard.shard_name),
-                serde_json::to_vec(&shard)?,
-            )
-            .await?;
-
-        Ok(())
-    }
-
-    async fn list_shard(
-        &self,
-        _namespace: String,
-        _shard_name: String,
-    ) -> Result<Vec<ShardInfo>, CommonError> {
-        Ok(Vec::new())
-    }
-
-    async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
-        self.op
-            .remove_all(&Self::offsets_path(&namespace, &shard_name))
-            .await?;
-        Ok(())
-    }
-
-    async fn write(
-        &self,
-        namespace: String,
-        shard_name: String,
-        data: Record,
-    ) -> Result<u64, CommonError> {
-        let offsets = self
-            .handle_write_request(namespace, shard_name, vec![data])
-            .await?;
-
-        Ok(offsets[0])
-    }
-
-    async fn batch_write(
-        &self,
-        namespace: String,
-        shard_name: String,
-        data: Vec<Record>,
-    ) -> Result<Vec<u64>, CommonError> {
-        self.handle_write_request(namespace, shard_name, data).await
-    }
-
-    async fn read_by_offset(
-        &self,
-        namespace: String,
-        shard_name: String,
-        offset: u64,
-        read_config: ReadConfig,
-    ) -> Result<Vec<Record>, CommonError> {
-        let mut res = Vec::new();
-        let mut total_bytes = 0;
-
-        for i in offset..offset + read_config.max_record_num {
-            let path = Self::records_path(&namespace, &shard_name, i);
-            if !self.op.exists(&path).await? {
-                break;
-            }
-            let record_bytes = self.op.read(&path).await?.to_vec();
-            if record_bytes.len() + total_bytes > read_config.max_size as usize {
-                break;
-            }
-            total_bytes += record_bytes.len();
-            let record = serde_json::from_slice::<Record>(&record_bytes)?;
-            res.push(record);
-        }
-
-        Ok(res)
-    }
-
-    async fn read_by_tag(
-        &self,
-        namespace: String,
-        shard_name: String,
-        start_offset: u64,
-        tag: String,
-        read_config: ReadConfig,
-    ) -> Result<Vec<Record>, CommonError> {
-        let tags_path_prefix = Self::tags_path_prefix(&namespace, &shard_name, &tag);
-
-        let mut lister = self.op.lister_with(&tags_path_prefix).await?;
-
-        let mut records = Vec::new();
-        let mut bytes_read = 0;
-
-        while let Some(entry) = lister.try_next().await? {
-            match entry.metadata().mode() {
-                EntryMode::FILE => {
-                    if records.len() >= read_config.max_record_num as usize {
-                        break;
-                    }
-
-                    let path = entry.path();
-                    let record_bytes = self.op.read(path).await?.to_vec();
-
-                    if bytes_read + record_bytes.len() > read_config.max_size as usize {
-                        break;
-                    }
-
-                    let record = serde_json::from_slice::<Record>(&record_bytes)?;
-
-                    if record.offset.unwrap() < start_offset {
-                        continue;
-                    }
-
-                    records.push(record);
-                    bytes_read += record_bytes.len();
-                }
-                _ => continue,
-            }
-        }
-
-        Ok(records)
-    }
-
-    async fn read_by_key(
-        &self,
-        namespace: String,
-        shard_name: String,
-        offset: u64,
-        key: String,
-        read_config: ReadConfig,
-    ) -> Result<Vec<Record>, CommonError> {
-        if read_config.max_record_num == 0 {
-            return Ok(vec![]);
-        }
-
-        let record_bytes = self
-            .op
-            .read(&Self::key_path(&namespace, &shard_name, key))
-            .await?
-            .to_vec();
-
-        if record_bytes.len() < read_config.max_size as usize {
-            return Ok(vec![]);
-        }
-
-        let record = serde_json::from_slice::<Record>(&record_bytes)?;
-
-        if record.offset.unwrap() < offset {
-            return Ok(vec![]);
-        }
-
-        Ok(vec![record])
-    }
-
-    async fn get_offset_by_timestamp(
-        &self,
-        _namespace: String,
-        _shard_name: String,
-        _timestamp: u64,
-    ) -> Result<Option<ShardOffset>, CommonError> {
-        Ok(None)
-    }
-
-    async fn get_offset_by_group(
-        &self,
-        group_name: String,
-    ) -> Result<Vec<ShardOffset>, CommonError> {
-        if self
-            .op
-            .exists(&Self::group_path_prefix(&group_name))
-            .await?
-        {
-            let mut offsets = Vec::new();
-            let mut lister = self
-                .op
-                .lister_with(&Self::group_path_prefix(&group_name))
-                .await?;
-
-            while let Some(entry) = lister.try_next().await? {
-                match entry.metadata().mode() {
-                    EntryMode::FILE => {
-                        let path = entry.path();
-
-                        let offset_bytes = self.op.read(path).await?.to_vec();
-                        let offset = serde_json::from_slice::<u64>(&offset_bytes)?;
-                        offsets.push(ShardOffset {
-                            offset,
-                            ..Default::default()
-                        });
-                    }
-                    _ => continue,
-                }
-            }
-
-            Ok(offsets)
-        } else {
-            Ok(vec![])
-        }
-    }
-
-    async fn commit_offset(
-        &self,
-        group_name: String,
-        namespace: String,
-        offset: HashMap<String, u64>,
-    ) -> Result<(), CommonError> {
-        for (shard_name, offset) in offset {
-            self.op
-                .write(
-                    &Self::group_path(&group_name, &namespace, &shard_name),
-                    serde_json::to_vec(&offset)?,
-                )
-                .await?;
-        }
-
-        Ok(())
-    }
-
-    async fn close(&self) -> Result<(), CommonError> {
-        let write_handles = self.get_all_write_handles().await;
-
-        for handle in write_handles {
-            handle
-                .stop_sender
-                .send(true)
-                .map_err(CommonError::TokioBroadcastSendErrorBool)?;
-        }
-
-        Ok(())
+fn minio_config(
+    endpoint: impl AsRef<str>,
+    access_key_id: impl AsRef<str>,
+    secret_access_key: impl AsRef<str>,
+    data_dir: impl AsRef<str>,
+    bucket: impl AsRef<str>,
+) -> S3Config {
+    S3Config {
+        endpoint: endpoint.as_ref().to_owned(),
+        region: MINIO_REGION.to_owned(),
+        bucket: bucket.as_ref().to_owned(),
+        root: data_dir.as_ref().to_owned(),
+        access_key_id: access_key_id.as_ref().to_owned(),
+        secret_access_key: secret_access_key.as_ref().to_owned(),
     }
 }
 
@@ -591,15 +69,31 @@ mod tests {
         record::{Header, Record},
     };
 
-    use crate::{
-        minio::MinIoStorageAdapter,
-        storage::{ShardInfo, StorageAdapter},
-    };
+    use super::{minio_config, MinIoStorageAdapter, MINIO_REGION};
+    use crate::storage::{ShardInfo, StorageAdapter};
+
+    #[test]
+    fn minio_config_test() {
+        let config = minio_config("http://127.0.0.1:9000", "ak", "sk", "/tmp/minio", "test");
+        assert_eq!(config.endpoint, "http://127.0.0.1:9000");
+        assert_eq!(config.region, MINIO_REGION);
+        assert_eq!(config.bucket, "test");
+        assert_eq!(config.root, "/tmp/minio");
+        assert_eq!(config.access_key_id, "ak");
+        assert_eq!(config.secret_access_key, "sk");
+    }
 
     #[tokio::test]
     #[ignore]
     async fn stream_read_write() {
-        let storage_adapter = MinIoStorageAdapter::new("/tmp/minio", "test").unwrap();
+        let storage_adapter = MinIoStorageAdapter::new_minio(
+            "http://127.0.0.1:9000",
+            "minioadmin",
+            "minioadmin",
+            "/tmp/minio",
+            "test",
+        )
+        .unwrap();
         let namespace = unique_id();
         let shard_name = "test-11".to_string();
 
@@ -814,7 +308,16 @@ async fn stream_read_write() {
     #[tokio::test]
     #[ignore]
     async fn concurrency_test() {
-        let storage_adapter = Arc::new(MinIoStorageAdapter::new("/tmp/minio", "test").unwrap());
+        let storage_adapter = Arc::new(
+            MinIoStorageAdapter::new_minio(
+                "http://127.0.0.1:9000",
+                "minioadmin",
+                "minioadmin",
+                "/tmp/minio",
+                "test",
+            )
+            .unwrap(),
+        );
 
         // create one namespace with 10 shards
         let namespace = unique_id();
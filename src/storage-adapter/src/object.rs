@@ -0,0 +1,93 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers shared by the object store adapters (S3 and MinIO).
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+const PATH_SEGMENT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'.').remove(b'_').remove(b'-');
+
+/// Percent-encode a namespace, shard, key, tag or group name so it is a single path segment of
+/// an object key: `/`, `%` and any byte outside `[A-Za-z0-9._-]` are escaped.
+pub fn encode_path_segment(segment: &str) -> String {
+    let encoded = utf8_percent_encode(segment, PATH_SEGMENT_ENCODE_SET).to_string();
+    // "." and ".." would be resolved as relative paths by some stores
+    if encoded == "." || encoded == ".." {
+        return encoded.replace('.', "%2E");
+    }
+    encoded
+}
+
+/// the reverse of `encode_path_segment`, invalid escapes are kept as they are
+pub fn decode_path_segment(segment: &str) -> String {
+    percent_decode_str(segment).decode_utf8_lossy().to_string()
+}
+
+/// The name of the timestamp index object of a batch: the first offset of the batch and the
+/// largest timestamp of its records, both zero padded so the objects list in offset order.
+pub fn timestamp_index_name(first_offset: u64, max_timestamp: u64) -> String {
+    format!("{:020}-{:020}", first_offset, max_timestamp)
+}
+
+pub fn parse_timestamp_index_name(name: &str) -> Option<(u64, u64)> {
+    let (first_offset, max_timestamp) = name.split_once('-')?;
+    Some((first_offset.parse().ok()?, max_timestamp.parse().ok()?))
+}
+
+/// The first offset of the first batch holding a record whose timestamp is at least `timestamp`,
+/// so a lookup starts reading there instead of at the beginning of the shard. `batches` are
+/// `(first_offset, max_timestamp)` pairs sorted by offset.
+pub fn timestamp_batch_start(batches: &[(u64, u64)], timestamp: u64) -> Option<u64> {
+    batches
+        .iter()
+        .find(|(_, max_timestamp)| *max_timestamp >= timestamp)
+        .map(|(first_offset, _)| *first_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_path_segment, encode_path_segment, parse_timestamp_index_name,
+        timestamp_batch_start, timestamp_index_name,
+    };
+
+    #[test]
+    fn path_segment_test() {
+        assert_eq!(encode_path_segment("test-shard_1.a"), "test-shard_1.a");
+        assert_eq!(encode_path_segment("a/b"), "a%2Fb");
+        assert_eq!(encode_path_segment("50%"), "50%25");
+        assert_eq!(encode_path_segment(".."), "%2E%2E");
+        assert_eq!(encode_path_segment("主题"), "%E4%B8%BB%E9%A2%98");
+
+        for segment in ["a/b", "50%", "..", "主题", "x%2", "%", "%é", "plain"] {
+            assert_eq!(decode_path_segment(&encode_path_segment(segment)), segment);
+        }
+        assert_eq!(decode_path_segment("x%2"), "x%2");
+    }
+
+    #[test]
+    fn timestamp_index_test() {
+        let name = timestamp_index_name(12, 1700000000);
+        assert_eq!(name, "00000000000000000012-00000000001700000000");
+        assert_eq!(parse_timestamp_index_name(&name), Some((12, 1700000000)));
+        assert_eq!(parse_timestamp_index_name("record"), None);
+
+        let batches = vec![(0, 10), (5, 20), (8, 15), (12, 40)];
+        assert_eq!(timestamp_batch_start(&batches, 5), Some(0));
+        assert_eq!(timestamp_batch_start(&batches, 11), Some(5));
+        assert_eq!(timestamp_batch_start(&batches, 30), Some(12));
+        assert_eq!(timestamp_batch_start(&batches, 41), None);
+        assert_eq!(timestamp_batch_start(&[], 1), None);
+    }
+}
@@ -37,10 +37,11 @@
 
 use crate::storage::{ShardInfo, ShardOffset, StorageAdapter};
 
+#[derive(Clone)]
 pub struct PlacementStorageAdapter {
     client_pool: Arc<ClientPool>,
     addrs: Vec<String>,
-    write_handles: DashMap<String, ThreadWriteHandle>,
+    write_handles: Arc<DashMap<String, ThreadWriteHandle>>,
 }
 
 struct WriteThreadData {
@@ -77,7 +78,7 @@ pub fn new(client_pool: Arc<ClientPool>, addrs: Vec<String>) -> Self {
         PlacementStorageAdapter {
             client_pool,
             addrs,
-            write_handles: DashMap::with_capacity(2),
+            write_handles: Arc::new(DashMap::with_capacity(2)),
         }
     }
 
@@ -37,9 +37,10 @@ fn column_family_list() -> Vec<String> {
     vec![DB_COLUMN_FAMILY.to_string()]
 }
 
+#[derive(Clone)]
 pub struct RocksDBStorageAdapter {
     pub db: Arc<RocksDBEngine>,
-    write_handles: DashMap<String, ThreadWriteHandle>,
+    write_handles: Arc<DashMap<String, ThreadWriteHandle>>,
 }
 
 struct WriteThreadData {
@@ -79,7 +80,7 @@ pub fn new(db_path: impl AsRef<str>, max_open_files: i32) -> Self {
                 max_open_files,
                 column_family_list(),
             )),
-            write_handles: DashMap::with_capacity(2),
+            write_handles: Arc::new(DashMap::with_capacity(2)),
         }
     }
 
@@ -11,3 +11,887 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::async_trait;
+use common_base::error::common::CommonError;
+use dashmap::DashMap;
+use log::warn;
+use metadata_struct::adapter::{read_config::ReadConfig, record::Record};
+use opendal::{services::S3, EntryMode, ErrorKind, Operator};
+use tokio::{
+    select,
+    sync::{
+        broadcast,
+        mpsc::{self, Receiver},
+        oneshot,
+    },
+    time::{sleep, timeout},
+};
+
+use crate::object::{
+    decode_path_segment, encode_path_segment, parse_timestamp_index_name, timestamp_batch_start,
+    timestamp_index_name,
+};
+use crate::storage::{ShardInfo, ShardOffset, StorageAdapter};
+
+/// Connection parameters of an S3 compatible object store.
+#[derive(Debug, Clone, Default)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub root: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+struct WriteThreadData {
+    namespace: String,
+    shard: String,
+    records: Vec<Record>,
+    resp_sx: oneshot::Sender<Result<Vec<u64>, CommonError>>, // thread response: offset or error
+}
+
+#[derive(Clone)]
+struct ThreadWriteHandle {
+    data_sender: mpsc::Sender<WriteThreadData>,
+    stop_sender: broadcast::Sender<bool>,
+}
+
+impl WriteThreadData {
+    fn new(
+        namespace: String,
+        shard: String,
+        records: Vec<Record>,
+        resp_sx: oneshot::Sender<Result<Vec<u64>, CommonError>>,
+    ) -> Self {
+        WriteThreadData {
+            namespace,
+            shard,
+            records,
+            resp_sx,
+        }
+    }
+}
+
+/// Object layout, relative to the configured root:
+///
+/// - `shard/{namespace}/{shard}`: shard info
+/// - `offset/{namespace}/{shard}`: next offset to be allocated
+/// - `record/{namespace}/{shard}/{offset}`: record data
+/// - `key/{namespace}/{shard}/{key}`: offset of the latest record with the key
+/// - `tag/{namespace}/{shard}/{tag}/{offset}`: offset of a record with the tag
+/// - `timestamp/{namespace}/{shard}/{first offset}-{max timestamp}`: empty object per batch,
+///   listed to find the batch holding a timestamp without reading every record
+/// - `group/{group}/{namespace}/{shard}`: committed offset of a consumer group
+///
+/// Namespace, shard, key, tag and group names are percent-encoded into a single path segment.
+#[derive(Clone)]
+pub struct S3StorageAdapter {
+    op: Operator,
+    write_handles: Arc<DashMap<String, ThreadWriteHandle>>,
+}
+
+impl S3StorageAdapter {
+    pub fn new(config: S3Config) -> Result<Self, CommonError> {
+        let mut builder = S3::default()
+            .bucket(&config.bucket)
+            .region(&config.region)
+            .disable_config_load();
+
+        if !config.root.is_empty() {
+            builder = builder.root(&config.root);
+        }
+
+        if !config.endpoint.is_empty() {
+            builder = builder.endpoint(&config.endpoint);
+        }
+
+        if !config.access_key_id.is_empty() {
+            builder = builder
+                .access_key_id(&config.access_key_id)
+                .secret_access_key(&config.secret_access_key);
+        }
+
+        Ok(Self::from_operator(Operator::new(builder)?.finish()))
+    }
+
+    pub fn from_operator(op: Operator) -> Self {
+        S3StorageAdapter {
+            op,
+            write_handles: Arc::new(DashMap::with_capacity(2)),
+        }
+    }
+
+    #[inline(always)]
+    pub fn shard_info_path(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
+        format!(
+            "shard/{}/{}",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn shard_info_path_prefix(namespace: impl AsRef<str>) -> String {
+        if namespace.as_ref().is_empty() {
+            return "shard/".to_string();
+        }
+        format!("shard/{}/", encode_path_segment(namespace.as_ref()))
+    }
+
+    #[inline(always)]
+    pub fn offset_path(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
+        format!(
+            "offset/{}/{}",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn record_path(
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+        offset: u64,
+    ) -> String {
+        format!(
+            "record/{}/{}/{:020}",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref()),
+            offset
+        )
+    }
+
+    #[inline(always)]
+    pub fn record_path_prefix(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
+        format!(
+            "record/{}/{}/",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn key_path(
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+        key: impl AsRef<str>,
+    ) -> String {
+        format!(
+            "key/{}/{}/{}",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref()),
+            encode_path_segment(key.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn key_path_prefix(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
+        format!(
+            "key/{}/{}/",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn tag_path(
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+        tag: impl AsRef<str>,
+        offset: u64,
+    ) -> String {
+        format!(
+            "tag/{}/{}/{}/{:020}",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref()),
+            encode_path_segment(tag.as_ref()),
+            offset
+        )
+    }
+
+    #[inline(always)]
+    pub fn tag_path_prefix(
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+        tag: impl AsRef<str>,
+    ) -> String {
+        format!(
+            "tag/{}/{}/{}/",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref()),
+            encode_path_segment(tag.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn shard_tag_path_prefix(
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+    ) -> String {
+        format!(
+            "tag/{}/{}/",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn group_path(
+        group_name: impl AsRef<str>,
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+    ) -> String {
+        format!(
+            "group/{}/{}/{}",
+            encode_path_segment(group_name.as_ref()),
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn timestamp_index_path(
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+        first_offset: u64,
+        max_timestamp: u64,
+    ) -> String {
+        format!(
+            "{}{}",
+            Self::timestamp_index_path_prefix(namespace, shard_name),
+            timestamp_index_name(first_offset, max_timestamp)
+        )
+    }
+
+    #[inline(always)]
+    pub fn timestamp_index_path_prefix(
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+    ) -> String {
+        format!(
+            "timestamp/{}/{}/",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    #[inline(always)]
+    pub fn group_path_prefix(group_name: impl AsRef<str>) -> String {
+        format!("group/{}/", encode_path_segment(group_name.as_ref()))
+    }
+}
+
+impl S3StorageAdapter {
+    #[inline(always)]
+    fn write_handle_key(namespace: impl AsRef<str>, shard_name: impl AsRef<str>) -> String {
+        format!(
+            "{}/{}",
+            encode_path_segment(namespace.as_ref()),
+            encode_path_segment(shard_name.as_ref())
+        )
+    }
+
+    async fn read_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> Result<Option<T>, CommonError> {
+        match self.op.read(path).await {
+            Ok(data) => Ok(Some(serde_json::from_slice::<T>(&data.to_vec())?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn ensure_shard_exists(
+        &self,
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+    ) -> Result<u64, CommonError> {
+        match self
+            .read_json::<u64>(&Self::offset_path(namespace.as_ref(), shard_name.as_ref()))
+            .await?
+        {
+            Some(offset) => Ok(offset),
+            None => Err(CommonError::CommonError(format!(
+                "shard {} under namespace {} not exists",
+                shard_name.as_ref(),
+                namespace.as_ref()
+            ))),
+        }
+    }
+
+    async fn read_record(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        offset: u64,
+    ) -> Result<Option<Record>, CommonError> {
+        self.read_json::<Record>(&Self::record_path(namespace, shard_name, offset))
+            .await
+    }
+
+    async fn first_offset_since(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        start: u64,
+        end: u64,
+        timestamp: u64,
+    ) -> Result<Option<u64>, CommonError> {
+        for offset in start..end {
+            if let Some(record) = self.read_record(namespace, shard_name, offset).await? {
+                if record.timestamp >= timestamp {
+                    return Ok(Some(offset));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn handle_write_request(
+        &self,
+        namespace: String,
+        shard_name: String,
+        messages: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        self.ensure_shard_exists(&namespace, &shard_name).await?;
+
+        let write_handle = self.get_write_handle(&namespace, &shard_name);
+
+        let (resp_sx, resp_rx) = oneshot::channel();
+
+        let data = WriteThreadData::new(namespace, shard_name, messages, resp_sx);
+
+        write_handle.data_sender.send(data).await.map_err(|err| {
+            CommonError::CommonError(format!("Failed to send data to write thread: {}", err))
+        })?;
+
+        timeout(Duration::from_secs(3600), resp_rx)
+            .await
+            .map_err(|err| {
+                CommonError::CommonError(format!("Timeout while waiting for response: {}", err))
+            })?
+            .map_err(|err| {
+                CommonError::CommonError(format!("Failed to receive response: {}", err))
+            })?
+    }
+
+    fn get_write_handle(
+        &self,
+        namespace: impl AsRef<str>,
+        shard_name: impl AsRef<str>,
+    ) -> ThreadWriteHandle {
+        let handle_key = Self::write_handle_key(namespace.as_ref(), shard_name.as_ref());
+
+        // the entry lock makes sure concurrent writers of a shard share one write thread
+        self.write_handles
+            .entry(handle_key)
+            .or_insert_with(|| Self::create_write_thread(self.op.clone()))
+            .clone()
+    }
+
+    async fn get_all_write_handles(&self) -> Vec<ThreadWriteHandle> {
+        self.write_handles
+            .iter()
+            .map(|item| item.value().clone())
+            .collect()
+    }
+
+    fn create_write_thread(op: Operator) -> ThreadWriteHandle {
+        let (data_sender, data_recv) = mpsc::channel::<WriteThreadData>(1000);
+        let (stop_sender, stop_recv) = broadcast::channel::<bool>(1);
+
+        Self::spawn_write_thread(op, stop_recv, data_recv);
+
+        ThreadWriteHandle {
+            data_sender,
+            stop_sender,
+        }
+    }
+
+    fn spawn_write_thread(
+        op: Operator,
+        mut stop_recv: broadcast::Receiver<bool>,
+        mut data_recv: Receiver<WriteThreadData>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                select! {
+                    val = stop_recv.recv() => {
+                        if let Ok(flag) = val {
+                            if flag {
+                                break
+                            }
+                        }
+                    },
+                    val = data_recv.recv() => {
+                        if val.is_none() {
+                            sleep(Duration::from_millis(100)).await;
+                            continue
+                        }
+
+                        let packet = val.unwrap();  // unwrap is safe here since we checked for None before
+                        let shard = format!("{}/{}", packet.namespace, packet.shard);
+
+                        let res = Self::
+                            thread_batch_write(op.clone(), packet.namespace, packet.shard, packet.records)
+                            .await;
+
+                        // the caller timed out or went away, the thread keeps serving the shard
+                        if packet.resp_sx.send(res).is_err() {
+                            warn!("The writer of shard {} stopped waiting for the write response", shard);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    async fn thread_batch_write(
+        op: Operator,
+        namespace: String,
+        shard_name: String,
+        messages: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        let mut offsets = Vec::new();
+
+        let offset_path = Self::offset_path(&namespace, &shard_name);
+        let mut start_offset =
+            serde_json::from_slice::<u64>(&op.read(&offset_path).await?.to_vec())?;
+        let first_offset = start_offset;
+        let max_timestamp = messages.iter().map(|message| message.timestamp).max();
+
+        for mut message in messages {
+            message.offset = Some(start_offset);
+
+            op.write(
+                &Self::record_path(&namespace, &shard_name, start_offset),
+                serde_json::to_vec(&message)?,
+            )
+            .await?;
+
+            if !message.key.is_empty() {
+                op.write(
+                    &Self::key_path(&namespace, &shard_name, &message.key),
+                    serde_json::to_vec(&start_offset)?,
+                )
+                .await?;
+            }
+
+            for tag in message.tags.iter() {
+                op.write(
+                    &Self::tag_path(&namespace, &shard_name, tag, start_offset),
+                    serde_json::to_vec(&start_offset)?,
+                )
+                .await?;
+            }
+
+            offsets.push(start_offset);
+            start_offset += 1;
+        }
+
+        if let Some(max_timestamp) = max_timestamp {
+            op.write(
+                &Self::timestamp_index_path(&namespace, &shard_name, first_offset, max_timestamp),
+                Vec::<u8>::new(),
+            )
+            .await?;
+        }
+
+        // The next offset is only persisted after all records of the batch have been written,
+        // so a failed batch is simply overwritten by the next one.
+        op.write(&offset_path, serde_json::to_vec(&start_offset)?)
+            .await?;
+
+        Ok(offsets)
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for S3StorageAdapter {
+    async fn create_shard(&self, shard: ShardInfo) -> Result<(), CommonError> {
+        let offset_path = Self::offset_path(&shard.namespace, &shard.shard_name);
+        if self.op.exists(&offset_path).await? {
+            return Err(CommonError::CommonError(format!(
+                "shard {} under namespace {} already exists",
+                shard.shard_name, shard.namespace
+            )));
+        }
+
+        self.op
+            .write(
+                &Self::shard_info_path(&shard.namespace, &shard.shard_name),
+                serde_json::to_vec(&shard)?,
+            )
+            .await?;
+
+        self.op
+            .write(&offset_path, serde_json::to_vec(&0_u64)?)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_shard(
+        &self,
+        namespace: String,
+        shard_name: String,
+    ) -> Result<Vec<ShardInfo>, CommonError> {
+        if !namespace.is_empty() && !shard_name.is_empty() {
+            return Ok(self
+                .read_json::<ShardInfo>(&Self::shard_info_path(&namespace, &shard_name))
+                .await?
+                .into_iter()
+                .collect());
+        }
+
+        let entries = self
+            .op
+            .list_with(&Self::shard_info_path_prefix(&namespace))
+            .recursive(true)
+            .await?;
+
+        let mut res = Vec::new();
+        for entry in entries {
+            if entry.metadata().mode() != EntryMode::FILE {
+                continue;
+            }
+            if let Some(shard) = self.read_json::<ShardInfo>(entry.path()).await? {
+                res.push(shard);
+            }
+        }
+
+        Ok(res)
+    }
+
+    async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
+        self.ensure_shard_exists(&namespace, &shard_name).await?;
+
+        self.op
+            .remove_all(&Self::record_path_prefix(&namespace, &shard_name))
+            .await?;
+        self.op
+            .remove_all(&Self::key_path_prefix(&namespace, &shard_name))
+            .await?;
+        self.op
+            .remove_all(&Self::shard_tag_path_prefix(&namespace, &shard_name))
+            .await?;
+        self.op
+            .remove_all(&Self::timestamp_index_path_prefix(&namespace, &shard_name))
+            .await?;
+        self.op
+            .delete(&Self::shard_info_path(&namespace, &shard_name))
+            .await?;
+        self.op
+            .delete(&Self::offset_path(&namespace, &shard_name))
+            .await?;
+
+        if let Some(handle) = self
+            .write_handles
+            .remove(&Self::write_handle_key(&namespace, &shard_name))
+        {
+            let _ = handle.1.stop_sender.send(true);
+        }
+
+        Ok(())
+    }
+
+    async fn write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Record,
+    ) -> Result<u64, CommonError> {
+        let offsets = self
+            .handle_write_request(namespace, shard_name, vec![data])
+            .await?;
+
+        Ok(offsets[0])
+    }
+
+    async fn batch_write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        self.handle_write_request(namespace, shard_name, data).await
+    }
+
+    async fn read_by_offset(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let next_offset = self.ensure_shard_exists(&namespace, &shard_name).await?;
+
+        let mut records = Vec::new();
+        let mut total_size = 0;
+
+        let end_offset = offset
+            .saturating_add(read_config.max_record_num)
+            .min(next_offset);
+
+        for i in offset..end_offset {
+            let record = match self.read_record(&namespace, &shard_name, i).await? {
+                Some(record) => record,
+                None => break,
+            };
+
+            let record_bytes = record.data.len() as u64;
+            if total_size + record_bytes > read_config.max_size {
+                break;
+            }
+
+            total_size += record_bytes;
+            records.push(record);
+        }
+
+        Ok(records)
+    }
+
+    async fn read_by_tag(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        tag: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        self.ensure_shard_exists(&namespace, &shard_name).await?;
+
+        let entries = self
+            .op
+            .list(&Self::tag_path_prefix(&namespace, &shard_name, &tag))
+            .await?;
+
+        let mut offsets = Vec::new();
+        for entry in entries {
+            if entry.metadata().mode() != EntryMode::FILE {
+                continue;
+            }
+
+            // the object name is the zero padded offset of the record
+            let record_offset = entry.name().parse::<u64>()?;
+            if record_offset >= offset {
+                offsets.push(record_offset);
+            }
+        }
+        offsets.sort();
+
+        let mut records = Vec::new();
+        let mut total_size = 0;
+
+        for record_offset in offsets {
+            if records.len() >= read_config.max_record_num as usize {
+                break;
+            }
+
+            let record = match self
+                .read_record(&namespace, &shard_name, record_offset)
+                .await?
+            {
+                Some(record) => record,
+                None => continue,
+            };
+
+            let record_bytes = record.data.len() as u64;
+            if total_size + record_bytes > read_config.max_size {
+                break;
+            }
+
+            total_size += record_bytes;
+            records.push(record);
+        }
+
+        Ok(records)
+    }
+
+    async fn read_by_key(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        key: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        self.ensure_shard_exists(&namespace, &shard_name).await?;
+
+        if read_config.max_record_num == 0 {
+            return Ok(Vec::new());
+        }
+
+        let key_offset = match self
+            .read_json::<u64>(&Self::key_path(&namespace, &shard_name, &key))
+            .await?
+        {
+            Some(key_offset) if key_offset >= offset => key_offset,
+            _ => return Ok(Vec::new()),
+        };
+
+        match self
+            .read_record(&namespace, &shard_name, key_offset)
+            .await?
+        {
+            Some(record) if record.data.len() as u64 <= read_config.max_size => Ok(vec![record]),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    async fn get_offset_by_timestamp(
+        &self,
+        namespace: String,
+        shard_name: String,
+        timestamp: u64,
+    ) -> Result<Option<ShardOffset>, CommonError> {
+        let next_offset = self.ensure_shard_exists(&namespace, &shard_name).await?;
+
+        let mut batches = Vec::new();
+        let entries = self
+            .op
+            .list(&Self::timestamp_index_path_prefix(&namespace, &shard_name))
+            .await?;
+        for entry in entries {
+            if entry.metadata().mode() != EntryMode::FILE {
+                continue;
+            }
+            if let Some(batch) = parse_timestamp_index_name(entry.name()) {
+                batches.push(batch);
+            }
+        }
+        batches.sort();
+
+        let Some(start) = timestamp_batch_start(&batches, timestamp) else {
+            return Ok(None);
+        };
+        let offset = self
+            .first_offset_since(&namespace, &shard_name, start, next_offset, timestamp)
+            .await?;
+        Ok(offset.map(|offset| ShardOffset {
+            namespace,
+            shard_name,
+            offset,
+            ..Default::default()
+        }))
+    }
+
+    async fn get_offset_by_group(
+        &self,
+        group_name: String,
+    ) -> Result<Vec<ShardOffset>, CommonError> {
+        let group_prefix = Self::group_path_prefix(&group_name);
+
+        let entries = self.op.list_with(&group_prefix).recursive(true).await?;
+
+        let mut offsets = Vec::new();
+        for entry in entries {
+            if entry.metadata().mode() != EntryMode::FILE {
+                continue;
+            }
+
+            let offset = match self.read_json::<u64>(entry.path()).await? {
+                Some(offset) => offset,
+                None => continue,
+            };
+
+            let (namespace, shard_name) = entry
+                .path()
+                .trim_start_matches(&group_prefix)
+                .split_once('/')
+                .map(|(namespace, shard_name)| {
+                    (
+                        decode_path_segment(namespace),
+                        decode_path_segment(shard_name),
+                    )
+                })
+                .unwrap_or_default();
+
+            offsets.push(ShardOffset {
+                namespace,
+                shard_name,
+                offset,
+                ..Default::default()
+            });
+        }
+
+        Ok(offsets)
+    }
+
+    async fn commit_offset(
+        &self,
+        group_name: String,
+        namespace: String,
+        offset: HashMap<String, u64>,
+    ) -> Result<(), CommonError> {
+        for (shard_name, offset) in offset {
+            self.op
+                .write(
+                    &Self::group_path(&group_name, &namespace, &shard_name),
+                    serde_json::to_vec(&offset)?,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), CommonError> {
+        let write_handles = self.get_all_write_handles().await;
+
+        for handle in write_handles {
+            handle
+                .stop_sender
+                .send(true)
+                .map_err(CommonError::TokioBroadcastSendErrorBool)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opendal::services::Memory;
+    use opendal::Operator;
+
+    use super::S3StorageAdapter;
+
+    #[test]
+    fn path_layout_test() {
+        assert_eq!(
+            S3StorageAdapter::record_path("ns", "shard", 12),
+            "record/ns/shard/00000000000000000012"
+        );
+        assert_eq!(
+            S3StorageAdapter::tag_path("ns", "shard", "t1", 3),
+            "tag/ns/shard/t1/00000000000000000003"
+        );
+        assert_eq!(
+            S3StorageAdapter::group_path("g1", "ns", "shard"),
+            "group/g1/ns/shard"
+        );
+        assert_eq!(
+            S3StorageAdapter::key_path("ns", "a/b", "k 1"),
+            "key/ns/a%2Fb/k%201"
+        );
+        assert_eq!(
+            S3StorageAdapter::timestamp_index_path("ns", "shard", 5, 100),
+            "timestamp/ns/shard/00000000000000000005-00000000000000000100"
+        );
+        assert_eq!(S3StorageAdapter::shard_info_path_prefix(""), "shard/");
+        assert_eq!(S3StorageAdapter::shard_info_path_prefix("ns"), "shard/ns/");
+    }
+
+    #[tokio::test]
+    async fn from_operator_test() {
+        let op = Operator::new(Memory::default()).unwrap().finish();
+        let adapter = S3StorageAdapter::from_operator(op);
+        assert!(adapter.write_handles.is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common_base::tools::unique_id;
+    use common_base::utils::crc::calc_crc32;
+    use metadata_struct::adapter::read_config::ReadConfig;
+    use metadata_struct::adapter::record::Record;
+    use storage_adapter::s3::{S3Config, S3StorageAdapter};
+    use storage_adapter::storage::{ShardInfo, StorageAdapter};
+
+    use crate::s3::server::start_s3_server;
+
+    async fn build_adapter() -> S3StorageAdapter {
+        let endpoint = start_s3_server().await;
+        S3StorageAdapter::new(S3Config {
+            endpoint,
+            region: "us-east-1".to_string(),
+            bucket: "robustmq".to_string(),
+            root: "/mqtt".to_string(),
+            access_key_id: "robustmq".to_string(),
+            secret_access_key: "robustmq".to_string(),
+        })
+        .unwrap()
+    }
+
+    fn build_record(key: &str, tags: Vec<String>, timestamp: u64) -> Record {
+        let data = format!("data-{}", key).as_bytes().to_vec();
+        Record {
+            offset: None,
+            header: Vec::new(),
+            key: key.to_string(),
+            crc_num: calc_crc32(&data),
+            data,
+            tags,
+            timestamp,
+            delay_timestamp: 0,
+        }
+    }
+
+    fn read_all() -> ReadConfig {
+        ReadConfig {
+            max_record_num: u64::MAX,
+            max_size: u64::MAX,
+        }
+    }
+
+    async fn create_shard(adapter: &S3StorageAdapter, namespace: &str, shard_name: &str) {
+        adapter
+            .create_shard(ShardInfo {
+                namespace: namespace.to_string(),
+                shard_name: shard_name.to_string(),
+                replica_num: 1,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn shard_lifecycle_test() {
+        let adapter = build_adapter().await;
+        let namespace = unique_id();
+
+        create_shard(&adapter, &namespace, "s1").await;
+        create_shard(&adapter, &namespace, "s2").await;
+
+        // creating the same shard twice is rejected
+        assert!(adapter
+            .create_shard(ShardInfo {
+                namespace: namespace.clone(),
+                shard_name: "s1".to_string(),
+                replica_num: 1,
+            })
+            .await
+            .is_err());
+
+        let shards = adapter
+            .list_shard(namespace.clone(), "".to_string())
+            .await
+            .unwrap();
+        assert_eq!(shards.len(), 2);
+
+        let shards = adapter
+            .list_shard(namespace.clone(), "s1".to_string())
+            .await
+            .unwrap();
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].shard_name, "s1");
+
+        adapter
+            .write(
+                namespace.clone(),
+                "s1".to_string(),
+                build_record("k1", vec!["t1".to_string()], 10),
+            )
+            .await
+            .unwrap();
+
+        adapter
+            .delete_shard(namespace.clone(), "s1".to_string())
+            .await
+            .unwrap();
+
+        let shards = adapter
+            .list_shard(namespace.clone(), "".to_string())
+            .await
+            .unwrap();
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].shard_name, "s2");
+
+        // the records of a deleted shard are gone and writes are rejected
+        assert!(adapter
+            .read_by_offset(namespace.clone(), "s1".to_string(), 0, read_all())
+            .await
+            .is_err());
+        assert!(adapter
+            .write(
+                namespace.clone(),
+                "s1".to_string(),
+                build_record("k2", Vec::new(), 10)
+            )
+            .await
+            .is_err());
+
+        adapter.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_write_test() {
+        let adapter = build_adapter().await;
+        let namespace = unique_id();
+        let shard_name = "test".to_string();
+        create_shard(&adapter, &namespace, &shard_name).await;
+
+        let records = (0..10)
+            .map(|i| {
+                let tag = if i % 2 == 0 { "even" } else { "odd" };
+                build_record(&format!("key-{}", i), vec![tag.to_string()], 100 + i)
+            })
+            .collect::<Vec<_>>();
+
+        let offsets = adapter
+            .batch_write(namespace.clone(), shard_name.clone(), records)
+            .await
+            .unwrap();
+        assert_eq!(offsets, (0..10).collect::<Vec<u64>>());
+
+        let offset = adapter
+            .write(
+                namespace.clone(),
+                shard_name.clone(),
+                build_record("key-10", Vec::new(), 110),
+            )
+            .await
+            .unwrap();
+        assert_eq!(offset, 10);
+
+        // read by offset
+        let res = adapter
+            .read_by_offset(namespace.clone(), shard_name.clone(), 0, read_all())
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 11);
+        for (i, record) in res.iter().enumerate() {
+            assert_eq!(record.offset, Some(i as u64));
+            assert_eq!(record.key, format!("key-{}", i));
+            assert!(record.crc32_check());
+        }
+
+        let res = adapter
+            .read_by_offset(
+                namespace.clone(),
+                shard_name.clone(),
+                4,
+                ReadConfig {
+                    max_record_num: 3,
+                    max_size: u64::MAX,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            res.iter().map(|r| r.offset.unwrap()).collect::<Vec<_>>(),
+            vec![4, 5, 6]
+        );
+
+        let res = adapter
+            .read_by_offset(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                ReadConfig {
+                    max_record_num: u64::MAX,
+                    max_size: 20,
+                },
+            )
+            .await
+            .unwrap();
+        // each record carries 10 bytes of data
+        assert_eq!(res.len(), 2);
+
+        let res = adapter
+            .read_by_offset(namespace.clone(), shard_name.clone(), 11, read_all())
+            .await
+            .unwrap();
+        assert!(res.is_empty());
+
+        // read by tag
+        let res = adapter
+            .read_by_tag(
+                namespace.clone(),
+                shard_name.clone(),
+                3,
+                "even".to_string(),
+                read_all(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            res.iter().map(|r| r.offset.unwrap()).collect::<Vec<_>>(),
+            vec![4, 6, 8]
+        );
+
+        let res = adapter
+            .read_by_tag(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                "odd".to_string(),
+                ReadConfig {
+                    max_record_num: 2,
+                    max_size: u64::MAX,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            res.iter().map(|r| r.offset.unwrap()).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+
+        // read by key
+        let res = adapter
+            .read_by_key(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                "key-7".to_string(),
+                read_all(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].offset, Some(7));
+
+        let res = adapter
+            .read_by_key(
+                namespace.clone(),
+                shard_name.clone(),
+                8,
+                "key-7".to_string(),
+                read_all(),
+            )
+            .await
+            .unwrap();
+        assert!(res.is_empty());
+
+        let res = adapter
+            .read_by_key(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                "not-exist".to_string(),
+                read_all(),
+            )
+            .await
+            .unwrap();
+        assert!(res.is_empty());
+
+        // timestamp lookup
+        let res = adapter
+            .get_offset_by_timestamp(namespace.clone(), shard_name.clone(), 105)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.offset, 5);
+        assert_eq!(res.shard_name, shard_name);
+
+        let res = adapter
+            .get_offset_by_timestamp(namespace.clone(), shard_name.clone(), 1000)
+            .await
+            .unwrap();
+        assert!(res.is_none());
+
+        adapter.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn group_offset_test() {
+        let adapter = build_adapter().await;
+        let namespace = unique_id();
+        let group_name = unique_id();
+
+        let mut offsets = HashMap::new();
+        offsets.insert("s1".to_string(), 3);
+        offsets.insert("s2".to_string(), 5);
+        adapter
+            .commit_offset(group_name.clone(), namespace.clone(), offsets)
+            .await
+            .unwrap();
+
+        let mut res = adapter
+            .get_offset_by_group(group_name.clone())
+            .await
+            .unwrap();
+        res.sort_by(|a, b| a.shard_name.cmp(&b.shard_name));
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].namespace, namespace);
+        assert_eq!(res[0].shard_name, "s1");
+        assert_eq!(res[0].offset, 3);
+        assert_eq!(res[1].shard_name, "s2");
+        assert_eq!(res[1].offset, 5);
+
+        let mut offsets = HashMap::new();
+        offsets.insert("s1".to_string(), 4);
+        adapter
+            .commit_offset(group_name.clone(), namespace.clone(), offsets)
+            .await
+            .unwrap();
+
+        let res = adapter
+            .get_offset_by_group(group_name.clone())
+            .await
+            .unwrap();
+        let s1 = res.iter().find(|r| r.shard_name == "s1").unwrap();
+        assert_eq!(s1.offset, 4);
+
+        assert!(adapter
+            .get_offset_by_group(unique_id())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
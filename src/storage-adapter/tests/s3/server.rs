@@ -0,0 +1,239 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A minimal in-process stand-in for an S3 compatible object store.
+//!
+//! It implements only the subset of the S3 REST API used by the storage adapter:
+//! PutObject, GetObject (with `Range`), HeadObject, DeleteObject, DeleteObjects
+//! and ListObjectsV2. Requests are not authenticated.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use percent_encoding::percent_decode_str;
+use tokio::net::TcpListener;
+
+#[derive(Clone, Default)]
+pub struct S3MockState {
+    objects: Arc<RwLock<BTreeMap<String, Bytes>>>,
+}
+
+/// Starts the stand-in on a random local port and returns its endpoint.
+pub async fn start_s3_server() -> String {
+    let app = Router::new()
+        .fallback(handle_request)
+        .with_state(S3MockState::default());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    format!("http://{}", addr)
+}
+
+async fn handle_request(
+    State(state): State<S3MockState>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = percent_decode(uri.path().trim_start_matches('/'));
+    let (bucket, key) = match path.split_once('/') {
+        Some((bucket, key)) => (bucket.to_string(), key.to_string()),
+        None => (path.clone(), "".to_string()),
+    };
+    let query = parse_query(uri.query().unwrap_or_default());
+
+    if key.is_empty() {
+        return match method {
+            Method::GET => list_objects(&state, &bucket, &query),
+            Method::POST if query.contains_key("delete") => delete_objects(&state, &bucket, &body),
+            _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+        };
+    }
+
+    let object_key = format!("{}/{}", bucket, key);
+    match method {
+        Method::PUT => {
+            state.objects.write().unwrap().insert(object_key, body);
+            (StatusCode::OK, [(header::ETAG, "\"robustmq\"")]).into_response()
+        }
+        // Hyper drops the body of HEAD responses but keeps the Content-Length.
+        Method::GET | Method::HEAD => get_object(&state, &object_key, &headers),
+        Method::DELETE => {
+            state.objects.write().unwrap().remove(&object_key);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+    }
+}
+
+fn get_object(state: &S3MockState, object_key: &str, headers: &HeaderMap) -> Response {
+    let data = match state.objects.read().unwrap().get(object_key) {
+        Some(data) => data.clone(),
+        None => return not_found(object_key),
+    };
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_range(v, data.len()));
+
+    match range {
+        Some((start, end)) => {
+            let content_range = format!("bytes {}-{}/{}", start, end, data.len());
+            (
+                StatusCode::PARTIAL_CONTENT,
+                [
+                    (header::CONTENT_RANGE, content_range),
+                    (header::ETAG, "\"robustmq\"".to_string()),
+                ],
+                Body::from(data.slice(start..end + 1)),
+            )
+                .into_response()
+        }
+        None => (
+            StatusCode::OK,
+            [(header::ETAG, "\"robustmq\"")],
+            Body::from(data),
+        )
+            .into_response(),
+    }
+}
+
+fn list_objects(state: &S3MockState, bucket: &str, query: &HashMap<String, String>) -> Response {
+    let prefix = query.get("prefix").cloned().unwrap_or_default();
+    let delimiter = query.get("delimiter").cloned().unwrap_or_default();
+    let start_after = query.get("start-after").cloned().unwrap_or_default();
+
+    let bucket_prefix = format!("{}/", bucket);
+    let mut contents = Vec::new();
+    let mut common_prefixes = BTreeSet::new();
+
+    for (object_key, data) in state.objects.read().unwrap().iter() {
+        let key = match object_key.strip_prefix(&bucket_prefix) {
+            Some(key) => key,
+            None => continue,
+        };
+
+        if !key.starts_with(&prefix) || (!start_after.is_empty() && key <= start_after.as_str()) {
+            continue;
+        }
+
+        let rest = &key[prefix.len()..];
+        if !delimiter.is_empty() {
+            if let Some(idx) = rest.find(&delimiter) {
+                common_prefixes.insert(format!("{}{}", prefix, &rest[..idx + delimiter.len()]));
+                continue;
+            }
+        }
+
+        contents.push(format!(
+            "<Contents><Key>{}</Key><Size>{}</Size><LastModified>2024-01-01T00:00:00.000Z</LastModified><ETag>\"robustmq\"</ETag></Contents>",
+            key,
+            data.len()
+        ));
+    }
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><IsTruncated>false</IsTruncated>",
+    );
+    for content in contents {
+        xml.push_str(&content);
+    }
+    for common_prefix in common_prefixes {
+        xml.push_str(&format!(
+            "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+            common_prefix
+        ));
+    }
+    xml.push_str("</ListBucketResult>");
+
+    xml_response(StatusCode::OK, xml)
+}
+
+fn delete_objects(state: &S3MockState, bucket: &str, body: &Bytes) -> Response {
+    let request = String::from_utf8_lossy(body);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?><DeleteResult>");
+
+    let mut objects = state.objects.write().unwrap();
+    for part in request.split("<Key>").skip(1) {
+        let key = match part.split_once("</Key>") {
+            Some((key, _)) => key,
+            None => continue,
+        };
+        // S3 reports keys that did not exist as deleted as well.
+        objects.remove(&format!("{}/{}", bucket, key));
+        xml.push_str(&format!("<Deleted><Key>{}</Key></Deleted>", key));
+    }
+    xml.push_str("</DeleteResult>");
+
+    xml_response(StatusCode::OK, xml)
+}
+
+fn not_found(object_key: &str) -> Response {
+    xml_response(
+        StatusCode::NOT_FOUND,
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message><Resource>{}</Resource></Error>",
+            object_key
+        ),
+    )
+}
+
+fn xml_response(status: StatusCode, xml: String) -> Response {
+    (status, [(header::CONTENT_TYPE, "application/xml")], xml).into_response()
+}
+
+fn parse_range(value: &str, len: usize) -> Option<(usize, usize)> {
+    if len == 0 {
+        return None;
+    }
+
+    let (start, end) = value.strip_prefix("bytes=")?.split_once('-')?;
+    let start = start.parse::<usize>().ok()?;
+    let end = if end.is_empty() {
+        len - 1
+    } else {
+        end.parse::<usize>().ok()?.min(len - 1)
+    };
+
+    if start > end {
+        return None;
+    }
+    Some((start, end))
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (percent_decode(k), percent_decode(v)),
+            None => (percent_decode(pair), "".to_string()),
+        })
+        .collect()
+}
+
+fn percent_decode(input: &str) -> String {
+    percent_decode_str(input).decode_utf8_lossy().into_owned()
+}